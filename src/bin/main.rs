@@ -13,12 +13,28 @@
 // limitations under the License.
 
 use anyhow::{anyhow, Context, Result};
+use chrono::{NaiveDate, Utc};
+use clap::error::ErrorKind;
 use clap::{Arg, ArgAction, Command};
 use scyros::phases::{
-    download, duplicate_files, duplicate_ids, extract_benchmarks, filter_languages,
-    filter_metadata, forks, ids, languages, metadata, parse, pull_request,
+    check_keywords, classify, commits_head, diff_corpus, download, duplicate_files, duplicate_ids,
+    export_text, extract_benchmarks, extract_slices, fetch_files, filter_languages,
+    filter_metadata, forks, ids, import, keywords_report, languages, manifest, merge, metadata,
+    parse, pull_request, sample_functions, split, tokens, verify_languages,
 };
+use scyros::utils::config::{config_arg, Config};
+use scyros::utils::csv::Quoting;
+use scyros::utils::dataframes::OutputColumns;
+use scyros::utils::github_app::Auth;
+use scyros::utils::glob::{Globs, VENDORED_PATTERNS};
 use scyros::utils::logger::Logger;
+use scyros::utils::progress::{progress_arg, ProgressMode};
+use scyros::utils::report::report_arg;
+use scyros::utils::run_manifest::RunManifest;
+use scyros::utils::seed::{apply_master_seed, log_master_seed, master_seed_arg};
+use scyros::utils::shard::Shard;
+use scyros::utils::telemetry::telemetry_arg;
+use std::time::Duration;
 use tracing::{error, info};
 
 fn cli() -> Command {
@@ -26,6 +42,7 @@ fn cli() -> Command {
         .about("")
         .author("Andrea Gilot <andrea.gilot@it.uu.se>")
         .subcommand(ids::cli())
+        .subcommand(import::cli())
         .subcommand(duplicate_ids::cli())
         .subcommand(forks::cli())
         .subcommand(metadata::cli())
@@ -33,10 +50,24 @@ fn cli() -> Command {
         .subcommand(filter_metadata::cli())
         .subcommand(languages::cli())
         .subcommand(filter_languages::cli())
+        .subcommand(commits_head::cli())
         .subcommand(download::cli())
+        .subcommand(verify_languages::cli())
+        .subcommand(fetch_files::cli())
         .subcommand(duplicate_files::cli())
+        .subcommand(manifest::cli())
+        .subcommand(merge::cli())
+        .subcommand(diff_corpus::cli())
         .subcommand(parse::cli())
+        .subcommand(keywords_report::cli())
+        .subcommand(check_keywords::cli())
+        .subcommand(tokens::cli())
+        .subcommand(sample_functions::cli())
+        .subcommand(split::cli())
         .subcommand(extract_benchmarks::cli())
+        .subcommand(extract_slices::cli())
+        .subcommand(classify::cli())
+        .subcommand(export_text::cli())
         .arg(
             Arg::new("debug")
                 .long("debug")
@@ -50,11 +81,26 @@ fn cli() -> Command {
                 .help("Print version information.")
                 .action(ArgAction::SetTrue),
         )
+        .arg(config_arg())
+        .arg(master_seed_arg())
+        .arg(report_arg())
+        .arg(telemetry_arg())
+        .arg(progress_arg())
         .disable_version_flag(true)
 }
 
 fn main() {
-    let cli_args = cli().get_matches();
+    // Options missing from the command line are taken from the configuration file,
+    // and the default seeds are derived from the master seed if one is given.
+    let cli_args = Config::from_args(std::env::args_os())
+        .and_then(|config| config.apply(cli()))
+        .and_then(|cli| apply_master_seed(cli, std::env::args_os()))
+        .unwrap_or_else(|e| {
+            cli()
+                .error(ErrorKind::InvalidValue, format!("{e:#}"))
+                .exit()
+        })
+        .get_matches();
 
     // Calls to unwrap are safe because the arguments are required.
     let res: Result<()> =
@@ -73,25 +119,47 @@ fn main() {
                 format!("The subcommand {subcommand} is not available. Run the program with the --help flag to see the list of subcommands")).and_then
                 (
                     |cli_subargs| {
-                            if subcommand == ids::cli().get_name() {
+                            logger.start_run(RunManifest::new(subcommand, cli_subargs));
+                            logger.set_progress_mode(ProgressMode::parse(
+                                cli_subargs.get_one::<String>("progress").unwrap(),
+                            )?);
+                            log_master_seed(subcommand, cli_subargs);
+                            let res: Result<()> = if subcommand == ids::cli().get_name() {
                                 ids::run(
                                     cli_subargs.get_one::<String>("output").unwrap(),
-                                    cli_subargs.get_one::<String>("tokens").unwrap(),
+                                    &Auth::from_args(cli_subargs)?,
                                     *cli_subargs.get_one::<u64>("seed").unwrap(),
                                     *cli_subargs.get_one::<u32>("min").unwrap(),
                                     *cli_subargs.get_one::<u32>("max").unwrap(),
                                     cli_subargs.get_one::<usize>("number").copied(),
                                     cli_subargs.get_one::<String>("mode").unwrap(),
                                     cli_subargs.get_flag("force"),
+                                    cli_subargs.get_one::<u32>("resume-from").copied(),
+                                    cli_subargs.get_one::<usize>("resume-request").copied(),
+                                    &ids::IdFilters {
+                                        skip_forks: cli_subargs.get_flag("skip-forks"),
+                                        owner_type: cli_subargs.get_one::<String>("owner-type").cloned(),
+                                    },
+                                    cli_subargs.get_one::<String>("query").map(|query| ids::SearchQuery {
+                                        query: query.clone(),
+                                        created_from: *cli_subargs.get_one::<NaiveDate>("created-from").unwrap(),
+                                        created_to: cli_subargs.get_one::<NaiveDate>("created-to").copied().unwrap_or_else(|| Utc::now().date_naive()),
+                                    }).as_ref(),
                                     &logger
                                 )
                             } else if subcommand == duplicate_ids::cli().get_name() {
                                 duplicate_ids::run(
                                     cli_subargs.get_one::<String>("input").unwrap(),
                                     cli_subargs.get_one::<String>("output").map(|x| x.as_str()),
-                                    cli_subargs.get_one::<String>("column").unwrap(),
+                                    &cli_subargs
+                                        .get_many::<String>("column")
+                                        .unwrap()
+                                        .map(|x| x.as_str())
+                                        .collect::<Vec<_>>(),
+                                    cli_subargs.get_one::<String>("keep").unwrap(),
                                     cli_subargs.get_flag("force"),
                                     cli_subargs.get_flag("no-output"),
+                                    &OutputColumns::from_args(cli_subargs)?,
                                     &logger
                                 )
                             } else if subcommand == forks::cli().get_name() {
@@ -99,6 +167,7 @@ fn main() {
                                     cli_subargs.get_one::<String>("input").unwrap(),
                                     cli_subargs.get_one::<String>("output").map(|x| x.as_str()),
                                     cli_subargs.get_one::<String>("column").unwrap(),
+                                    cli_subargs.get_one::<String>("network").map(|x| x.as_str()),
                                     cli_subargs.get_flag("force"),
                                     cli_subargs.get_flag("no-output"),
                                     &logger
@@ -107,13 +176,21 @@ fn main() {
                                 metadata::run(
                                     cli_subargs.get_one::<String>("input").unwrap(),
                                     cli_subargs.get_one::<String>("output"),
-                                    cli_subargs.get_one::<String>("tokens").unwrap(),
+                                    &Auth::from_args(cli_subargs)?,
                                     cli_subargs.get_one::<String>("cache"),
                                     *cli_subargs.get_one::<u64>("seed").unwrap(),
                                     cli_subargs.get_flag("force"),
                                     cli_subargs.get_one::<String>("ids").unwrap(),
                                     cli_subargs.get_one::<String>("names").unwrap(),
                                     cli_subargs.get_one::<usize>("sub").copied(),
+                                    cli_subargs
+                                        .get_flag("refresh")
+                                        .then(|| *cli_subargs.get_one::<u64>("max-age").unwrap()),
+                                    cli_subargs.get_flag("contributors"),
+                                    &cli_subargs
+                                        .get_many::<String>("readme-keywords")
+                                        .map(|v| v.map(|s| s.as_str()).collect::<Vec<&str>>())
+                                        .unwrap_or_default(),
                                     &logger,
                                 )
                             } else if subcommand == filter_metadata::cli().get_name() {
@@ -122,23 +199,30 @@ fn main() {
                                     cli_subargs.get_one::<String>("output").map(|x| x.as_str()),
                                     cli_subargs.get_one::<u64>("size").unwrap().to_owned(),
                                     cli_subargs.get_one::<u32>("age").unwrap().to_owned(),
+                                    cli_subargs.get_one::<u32>("pushed-within-months").copied(),
+                                    *cli_subargs.get_one::<u32>("min-commits").unwrap(),
+                                    *cli_subargs.get_one::<u32>("min-readme-keywords").unwrap(),
+                                    *cli_subargs.get_one::<u32>("min-contributors").unwrap(),
                                     cli_subargs.get_flag("disabled"),
                                     cli_subargs.get_flag("non-code"),
                                     cli_subargs.get_flag("force"),
                                     cli_subargs.get_flag("no-output"),
+                                    &OutputColumns::from_args(cli_subargs)?,
                                     &logger,
                                 )
                             } else if subcommand == languages::cli().get_name() {
                                 languages::run(
                                     cli_subargs.get_one::<String>("input").unwrap(),
                                     cli_subargs.get_one::<String>("output").map(|x| x.as_str()),
-                                    cli_subargs.get_one::<String>("tokens").unwrap(),
+                                    &Auth::from_args(cli_subargs)?,
                                     cli_subargs.get_one::<String>("cache"),
                                     *cli_subargs.get_one::<u64>("seed").unwrap(),
                                     cli_subargs.get_flag("force"),
                                     cli_subargs.get_one::<String>("ids").unwrap(),
                                     cli_subargs.get_one::<String>("names").unwrap(),
                                     cli_subargs.get_one::<usize>("sub").copied(),
+                                    cli_subargs.get_one::<String>("commits").map(|x| x.as_str()),
+                                    *cli_subargs.get_one::<usize>("threads").unwrap(),
                                     &logger,
                                 )
                             } else if subcommand == filter_languages::cli().get_name() {
@@ -148,15 +232,23 @@ fn main() {
                                     cli_subargs.get_one::<String>("languages").unwrap(),
                                     cli_subargs.get_flag("force"),
                                     cli_subargs.get_flag("no-output"),
+                                    &OutputColumns::from_args(cli_subargs)?,
                                     &logger,
                                 )
                             } else if subcommand == download::cli().get_name() {
+                                let existing_corpus_logs: Option<Vec<&str>> = cli_subargs
+                                    .get_many::<String>("existing-corpus")
+                                    .map(|logs| logs.map(|s| s.as_str()).collect());
                                 download::run(
                                     cli_subargs.get_one::<String>("input").unwrap(),
                                     cli_subargs.get_one::<String>("projects").map(|x| x.as_str()),
                                     cli_subargs.get_one::<String>("files").map(|x| x.as_str()),
-                                    cli_subargs.get_one::<String>("dest").unwrap(),
-                                    cli_subargs.get_one::<String>("tokens").map(|x| x.as_str()),
+                                    &cli_subargs
+                                        .get_many::<String>("dest")
+                                        .unwrap()
+                                        .map(|s| s.as_str())
+                                        .collect::<Vec<&str>>(),
+                                    if cli_subargs.get_flag("skip") { None } else { Some(Auth::from_args(cli_subargs)?) }.as_ref(),
                                     &cli_subargs
                                         .get_many::<String>("keywords")
                                         .unwrap()
@@ -171,21 +263,62 @@ fn main() {
                                     &logger,
                                     *cli_subargs.get_one::<usize>("threads").unwrap(),
                                     cli_subargs.get_one::<String>("order").unwrap(),
+                                    Quoting::from_legacy_flag(cli_subargs.get_flag("legacy-csv")),
+                                    cli_subargs.get_flag("retry-errors"),
+                                    download::ProjectCaps {
+                                        max_files: cli_subargs.get_one::<usize>("max-files-per-project").copied(),
+                                        max_size: cli_subargs.get_one::<u64>("max-project-size").copied(),
+                                        truncate: cli_subargs.get_one::<String>("oversized").unwrap() == "truncate",
+                                    },
+                                    cli_subargs.get_one::<Shard>("shard").copied(),
+                                    cli_subargs.get_one::<String>("scratch").map(|x| x.as_str()),
+                                    cli_subargs.get_one::<String>("tree-log").map(|x| x.as_str()),
+                                    &Globs::new(
+                                        &cli_subargs
+                                            .get_many::<String>("exclude-paths")
+                                            .unwrap_or_default()
+                                            .map(|s| s.as_str())
+                                            .chain(if cli_subargs.get_flag("exclude-vendored") { VENDORED_PATTERNS.to_vec() } else { Vec::new() })
+                                            .collect::<Vec<&str>>(),
+                                    )?,
+                                    cli_subargs.get_flag("exclude-tests"),
+                                    cli_subargs.get_flag("exclude-generated"),
+                                    cli_subargs.get_one::<u64>("stats-interval").map(|s| Duration::from_secs(*s)),
+                                    cli_subargs.get_one::<u32>("max-requests-per-minute").copied(),
+                                    cli_subargs.get_one::<String>("balance").unwrap(),
+                                    cli_subargs.get_one::<usize>("match-positions").copied(),
+                                    cli_subargs.get_one::<usize>("split-subdirs").copied(),
+                                    download::ExternalContent {
+                                        lfs: cli_subargs.get_flag("resolve-lfs"),
+                                        submodules: cli_subargs.get_flag("resolve-submodules"),
+                                    },
+                                    existing_corpus_logs.as_deref().map(|logs| download::ExistingCorpus {
+                                        project_log: logs[0],
+                                        file_log: logs.get(1).copied(),
+                                    }),
                                 )
                             } else if subcommand == duplicate_files::cli().get_name() {
                                 duplicate_files::run(
                                     cli_subargs.get_one::<String>("input").unwrap(),
                                     cli_subargs.get_one::<String>("output").map(|x| x.as_str()),
                                     cli_subargs.get_one::<String>("map").map(|x| x.as_str()),
+                                    cli_subargs.get_one::<String>("clusters").map(|x| x.as_str()),
                                     cli_subargs.get_flag("force"),
                                     cli_subargs.get_one::<String>("similarity").unwrap(),
                                     *cli_subargs.get_one::<usize>("threads").unwrap(),
                                     cli_subargs.get_one::<String>("header").unwrap(),
+                                    cli_subargs.get_one::<Shard>("shard").copied(),
+                                    *cli_subargs.get_one::<u64>("memory-limit").unwrap(),
+                                    &OutputColumns::from_args(cli_subargs)?,
                                     &logger,
                                 )
                             } else if subcommand == parse::cli().get_name() {
                                 parse::run(
-                                    cli_subargs.get_one::<String>("input").unwrap(),
+                                    cli_subargs
+                                        .get_one::<String>("input")
+                                        .or(cli_subargs.get_one::<String>("input-dir"))
+                                        .unwrap(),
+                                    cli_subargs.contains_id("input-dir"),
                                     cli_subargs.get_one::<String>("output").map(|x| x.as_str()),
                                     cli_subargs.get_one::<String>("logs").map(|x| x.as_str()),
                                     &cli_subargs
@@ -202,8 +335,115 @@ fn main() {
                                     cli_subargs.get_one::<String>("failures").unwrap(),
                                     *cli_subargs.get_one::<usize>("threads").unwrap(),
                                     *cli_subargs.get_one::<u64>("seed").unwrap(),
+                                    cli_subargs.get_flag("group-by-project"),
                                     cli_subargs.get_flag("force"),
                                     cli_subargs.get_flag("ignore-comments"),
+                                    Quoting::from_legacy_flag(cli_subargs.get_flag("legacy-csv")),
+                                    cli_subargs.get_one::<String>("matches").map(|x| x.as_str()),
+                                    *cli_subargs.get_one::<usize>("context").unwrap(),
+                                    cli_subargs.get_one::<String>("per-keyword-counts").map(|x| x.as_str()),
+                                    cli_subargs.get_one::<String>("headers").unwrap(),
+                                    cli_subargs.get_one::<Shard>("shard").copied(),
+                                    cli_subargs.get_flag("exclude-tests"),
+                                    cli_subargs.get_flag("exclude-generated"),
+                                    cli_subargs.get_flag("compress"),
+                                    cli_subargs.get_flag("stats-only"),
+                                    parse::ComplexityFilter {
+                                        min_loc: *cli_subargs.get_one::<usize>("min-loc").unwrap(),
+                                        max_loc: cli_subargs.get_one::<usize>("max-loc").copied(),
+                                        min_loops: *cli_subargs.get_one::<usize>("min-loops").unwrap(),
+                                        min_conditionals: *cli_subargs.get_one::<usize>("min-conditionals").unwrap(),
+                                        keep_stats: cli_subargs.get_flag("filtered-stats"),
+                                    },
+                                    cli_subargs.get_one::<String>("emit-ast").map(|x| x.as_str()),
+                                    parse::TreeLimits {
+                                        max_depth: Some(*cli_subargs.get_one::<usize>("max-depth").unwrap()).filter(|max| *max > 0),
+                                        max_nodes: Some(*cli_subargs.get_one::<usize>("max-nodes").unwrap()).filter(|max| *max > 0),
+                                    },
+                                    cli_subargs.get_one::<String>("ids").unwrap(),
+                                    cli_subargs.get_one::<String>("names").unwrap(),
+                                    cli_subargs.get_one::<String>("languages").unwrap(),
+                                    cli_subargs.get_one::<String>("projects").unwrap(),
+                                    &logger,
+                                )
+                            } else if subcommand == check_keywords::cli().get_name() {
+                                check_keywords::run(
+                                    &cli_subargs
+                                        .get_many::<String>("keywords")
+                                        .unwrap()
+                                        .map(|s| s.as_str())
+                                        .collect::<Vec<&str>>(),
+                                    cli_subargs.get_flag("regex"),
+                                    cli_subargs.get_one::<String>("sample").map(|x| x.as_str()),
+                                    cli_subargs.get_one::<String>("lang").map(|x| x.as_str()),
+                                    &logger,
+                                )
+                            } else if subcommand == keywords_report::cli().get_name() {
+                                keywords_report::run(
+                                    cli_subargs.get_one::<String>("input").unwrap(),
+                                    cli_subargs.get_one::<String>("output").map(|x| x.as_str()),
+                                    cli_subargs
+                                        .get_many::<String>("columns")
+                                        .map(|v| v.map(|s| s.as_str()).collect::<Vec<&str>>()),
+                                    cli_subargs.get_flag("force"),
+                                    cli_subargs.get_flag("no-output"),
+                                    &logger,
+                                )
+                            } else if subcommand == verify_languages::cli().get_name() {
+                                verify_languages::run(
+                                    cli_subargs.get_one::<String>("input").unwrap(),
+                                    cli_subargs.get_one::<String>("files").unwrap(),
+                                    cli_subargs.get_one::<String>("projects").map(|x| x.as_str()),
+                                    cli_subargs
+                                        .get_many::<String>("languages")
+                                        .map(|v| v.map(|s| s.as_str()).collect::<Vec<&str>>()),
+                                    *cli_subargs.get_one::<u64>("min-bytes").unwrap(),
+                                    cli_subargs.get_one::<String>("output").map(|x| x.as_str()),
+                                    cli_subargs.get_flag("force"),
+                                    cli_subargs.get_flag("no-output"),
+                                    &logger,
+                                )
+                            }
+                            else if subcommand == merge::cli().get_name() {
+                                merge::run(
+                                    cli_subargs.get_one::<String>("input").unwrap(),
+                                    *cli_subargs.get_one::<usize>("shards").unwrap(),
+                                    cli_subargs.get_one::<String>("output").map(|x| x.as_str()),
+                                    cli_subargs.get_flag("force"),
+                                    &logger,
+                                )
+                            } else if subcommand == manifest::cli().get_name() {
+                                match cli_subargs.get_one::<String>("verify") {
+                                    Some(manifest_path) => manifest::verify(
+                                        manifest_path,
+                                        cli_subargs.get_one::<String>("output").map(|x| x.as_str()),
+                                        cli_subargs.get_flag("force"),
+                                        &logger,
+                                    ),
+                                    None => {
+                                        let dir = cli_subargs.get_one::<String>("dir");
+                                        manifest::run(
+                                            dir.or(cli_subargs.get_one::<String>("input")).unwrap(),
+                                            dir.is_some(),
+                                            cli_subargs.get_one::<String>("output").map(|x| x.as_str()),
+                                            cli_subargs.get_one::<String>("header").unwrap(),
+                                            cli_subargs.get_flag("force"),
+                                            &logger,
+                                        )
+                                    }
+                                }
+                            }
+                            else if subcommand == tokens::cli().get_name() {
+                                let dir = cli_subargs.get_one::<String>("dir");
+                                tokens::run(
+                                    dir.or(cli_subargs.get_one::<String>("input")).unwrap(),
+                                    dir.is_some(),
+                                    cli_subargs.get_one::<String>("output").map(|x| x.as_str()),
+                                    *cli_subargs.get_one::<usize>("ngrams").unwrap(),
+                                    *cli_subargs.get_one::<u64>("min-count").unwrap(),
+                                    cli_subargs.get_one::<String>("lexer").unwrap(),
+                                    cli_subargs.get_flag("force"),
+                                    cli_subargs.get_flag("no-output"),
                                     &logger,
                                 )
                             }
@@ -212,11 +452,54 @@ fn main() {
                                     cli_subargs.get_one::<String>("input").unwrap(),
                                     cli_subargs.get_one::<String>("output").map(|x| x.as_str()),
                                     cli_subargs.get_one::<String>("dest").unwrap(),
-                                    cli_subargs.get_one::<String>("tokens").unwrap(),
+                                    &Auth::from_args(cli_subargs)?,
                                     *cli_subargs.get_one::<u64>("seed").unwrap(),
                                     cli_subargs.get_flag("force"),
                                     *cli_subargs.get_one::<usize>("threads").unwrap(),
                                     *cli_subargs.get_one::<u64>("timeout").unwrap(),
+                                    Some(cli_subargs.get_one::<String>("dedup").unwrap().as_str()).filter(|d| *d != "none"),
+                                    cli_subargs.get_one::<String>("layout").unwrap(),
+                                    cli_subargs.get_one::<String>("functions-csv").map(|x| x.as_str()),
+                                    cli_subargs.get_one::<String>("scratch").map(|x| x.as_str()),
+                                    &cli_subargs
+                                        .get_many::<String>("retry-status")
+                                        .unwrap_or_default()
+                                        .map(|s| s.as_str())
+                                        .collect::<Vec<&str>>(),
+                                    cli_subargs
+                                        .get_flag("harness")
+                                        .then(|| *cli_subargs.get_one::<usize>("harness-array-size").unwrap()),
+                                    cli_subargs.get_one::<String>("lang").unwrap(),
+                                    &logger,
+                                )
+                            }
+                            else if subcommand == fetch_files::cli().get_name() {
+                                fetch_files::run(
+                                    cli_subargs.get_one::<String>("input").unwrap(),
+                                    cli_subargs.get_one::<String>("output").map(|x| x.as_str()),
+                                    cli_subargs.get_one::<String>("dest").unwrap(),
+                                    &Auth::from_args(cli_subargs)?,
+                                    cli_subargs.get_flag("force"),
+                                    &logger,
+                                )
+                            }
+                            else if subcommand == commits_head::cli().get_name() {
+                                commits_head::run(
+                                    cli_subargs.get_one::<String>("input").unwrap(),
+                                    cli_subargs.get_one::<String>("output").map(|x| x.as_str()),
+                                    &Auth::from_args(cli_subargs)?,
+                                    cli_subargs.get_flag("force"),
+                                    cli_subargs.get_one::<String>("ids").unwrap(),
+                                    cli_subargs.get_one::<String>("names").unwrap(),
+                                    &logger,
+                                )
+                            }
+                            else if subcommand == extract_slices::cli().get_name() {
+                                extract_slices::run(
+                                    cli_subargs.get_one::<String>("input").unwrap(),
+                                    cli_subargs.get_one::<String>("output").map(|x| x.as_str()),
+                                    cli_subargs.get_one::<String>("dest").unwrap(),
+                                    cli_subargs.get_flag("force"),
                                     &logger,
                                 )
                             }
@@ -224,19 +507,114 @@ fn main() {
                                 pull_request::run(
                                     cli_subargs.get_one::<String>("input").unwrap(),
                                     cli_subargs.get_one::<String>("output"),
-                                    cli_subargs.get_one::<String>("tokens").unwrap(),
+                                    &Auth::from_args(cli_subargs)?,
                                     *cli_subargs.get_one::<u64>("seed").unwrap(),
                                     cli_subargs.get_flag("force"),
                                     cli_subargs.get_one::<String>("ids").unwrap(),
                                     cli_subargs.get_one::<String>("names").unwrap(),
                                     cli_subargs.get_one::<String>("dest").unwrap(),
                                     cli_subargs.get_one::<usize>("sub").copied(),
+                                    &cli_subargs
+                                        .get_many::<String>("keywords")
+                                        .unwrap_or_default()
+                                        .map(|s| s.as_str())
+                                        .collect::<Vec<&str>>(),
+                                    cli_subargs.get_flag("regex"),
+                                    pull_request::PrSampling {
+                                        max_prs: cli_subargs.get_one::<usize>("max-prs").copied(),
+                                        random: cli_subargs.get_flag("random-prs"),
+                                        since: cli_subargs.get_one::<NaiveDate>("since").copied(),
+                                        until: cli_subargs.get_one::<NaiveDate>("until").copied(),
+                                    },
+                                    cli_subargs.get_flag("compress"),
+                                    &logger,
+                                )
+                            }
+                            else if subcommand == import::cli().get_name() {
+                                import::run(
+                                    cli_subargs.get_one::<String>("input").unwrap(),
+                                    cli_subargs.get_one::<String>("output").map(|x| x.as_str()),
+                                    cli_subargs.get_one::<String>("format").unwrap(),
+                                    &import::ColumnMapping {
+                                        id: cli_subargs.get_one::<String>("id-column").unwrap(),
+                                        name: cli_subargs.get_one::<String>("name-column").unwrap(),
+                                        commit: cli_subargs.get_one::<String>("commit-column").map(|x| x.as_str()),
+                                    },
+                                    cli_subargs.get_flag("force"),
+                                    &logger,
+                                )
+                            }
+                            else if subcommand == sample_functions::cli().get_name() {
+                                let languages: Option<Vec<&str>> = cli_subargs
+                                    .get_many::<String>("lang")
+                                    .map(|v| v.map(|s| s.as_str()).collect());
+                                sample_functions::run(
+                                    cli_subargs.get_one::<String>("input").unwrap(),
+                                    cli_subargs.get_one::<String>("output").map(|x| x.as_str()),
+                                    *cli_subargs.get_one::<usize>("per-language").unwrap(),
+                                    languages.as_deref(),
+                                    &sample_functions::FunctionFilters {
+                                        min_loc: cli_subargs.get_one::<u32>("min-loc").copied(),
+                                        max_loc: cli_subargs.get_one::<u32>("max-loc").copied(),
+                                        min_loops: *cli_subargs.get_one::<u32>("min-loops").unwrap(),
+                                        exclude_tests: cli_subargs.get_flag("exclude-tests"),
+                                    },
+                                    *cli_subargs.get_one::<u64>("seed").unwrap(),
+                                    cli_subargs.get_flag("force"),
+                                    &logger,
+                                )
+                            }
+                            else if subcommand == classify::cli().get_name() {
+                                classify::run(
+                                    cli_subargs.get_one::<String>("input").unwrap(),
+                                    cli_subargs.get_one::<String>("output").map(|x| x.as_str()),
+                                    cli_subargs.get_one::<String>("column").unwrap(),
+                                    cli_subargs.get_flag("force"),
+                                    &logger,
+                                )
+                            }
+                            else if subcommand == export_text::cli().get_name() {
+                                export_text::run(
+                                    cli_subargs.get_one::<String>("input").unwrap(),
+                                    cli_subargs.get_one::<String>("output").map(|x| x.as_str()),
+                                    cli_subargs.get_flag("strip-comments"),
+                                    cli_subargs.get_flag("force"),
+                                    &logger,
+                                )
+                            }
+                            else if subcommand == split::cli().get_name() {
+                                split::run(
+                                    cli_subargs.get_one::<String>("input").unwrap(),
+                                    cli_subargs.get_one::<String>("output").map(|x| x.as_str()),
+                                    cli_subargs.get_one::<String>("column").unwrap(),
+                                    ["train", "validation", "test"]
+                                        .map(|split| *cli_subargs.get_one::<f64>(split).unwrap()),
+                                    *cli_subargs.get_one::<u64>("seed").unwrap(),
+                                    cli_subargs.get_flag("force"),
+                                    &logger,
+                                )
+                            }
+                            else if subcommand == diff_corpus::cli().get_name() {
+                                diff_corpus::run(
+                                    cli_subargs.get_one::<String>("old").unwrap(),
+                                    cli_subargs.get_one::<String>("new").unwrap(),
+                                    cli_subargs.get_one::<String>("old-projects").map(|x| x.as_str()),
+                                    cli_subargs.get_one::<String>("new-projects").map(|x| x.as_str()),
+                                    cli_subargs.get_one::<String>("output").map(|x| x.as_str()),
+                                    *cli_subargs.get_one::<usize>("threads").unwrap(),
+                                    cli_subargs.get_flag("force"),
                                     &logger,
                                 )
                             }
                             else {
                                 Err(anyhow!("The subcommand {subcommand} is not available. Run the program with the --help flag to see the list of subcommands"))
-                            }
+                            };
+                            // The description of the run is written even if it failed, next to the partial outputs.
+                            let written: Result<()> = logger.finish_run(
+                                res.as_ref().err(),
+                                cli_subargs.get_one::<String>("report").map(|x| x.as_str()),
+                            );
+                            res.and(written)
                     }
                 )
         }
@@ -0,0 +1,356 @@
+// Copyright 2026 Andrea Gilot
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![doc = include_str!("../docs/check_keywords.md")]
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use clap::{Arg, ArgAction, Command};
+use json::JsonValue;
+use tracing::{error, info};
+
+use crate::utils::fs::*;
+use crate::utils::json::open_json_from_path;
+use crate::utils::logger::Logger;
+use crate::utils::regex::{shared_extension, KeywordFiles};
+
+/// Command line arguments parsing.
+pub fn cli() -> Command {
+    Command::new("check_keywords")
+        .alias("check-keywords")
+        .about("Validates keyword files and prints the regular expressions they produce for each language.")
+        .long_about(include_str!("../docs/check_keywords.md"))
+        .disable_version_flag(true)
+        .arg(
+            Arg::new("keywords")
+                .short('k')
+                .long("keywords")
+                .num_args(1..)
+                .action(ArgAction::Append)
+                .value_name("KEYWORDS_FILES.json")
+                .help("List of keyword files to validate, as given to the download and parse commands.")
+                .required(true),
+        )
+        .arg(
+            Arg::new("regex")
+                .long("regex")
+                .help("Whether to interpret the keywords as regular expressions. If not specified, the keywords are interpreted as whole words to match.")
+                .default_value("false")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("sample")
+                .long("sample")
+                .value_name("SAMPLE_FILE")
+                .help("Path to a source file in which the keywords are searched. The matches of each keyword file are printed.")
+                .required(false),
+        )
+        .arg(
+            Arg::new("lang")
+                .long("lang")
+                .value_name("LANGUAGE")
+                .help("Language of the sample file. By default, it is inferred from the extension of the file.")
+                .requires("sample")
+                .required(false),
+        )
+}
+
+/// Validates keyword files and prints the regular expressions of each language.
+/// If a sample file is given, prints the keywords of each file matching in the sample.
+///
+/// # Arguments
+///
+/// * `keywords_file_paths` - The paths to the keyword files.
+/// * `regex_syntax` - Whether to interpret the keywords as regular expressions.
+/// * `sample_path` - The optional path to a sample file in which the keywords are searched.
+/// * `sample_language` - The optional language of the sample file. Defaults to the language of its extension.
+/// * `logger` - The logger displaying the progress.
+///
+/// # Returns
+///
+/// An error if one of the keyword files is invalid.
+pub fn run(
+    keywords_file_paths: &[&str],
+    regex_syntax: bool,
+    sample_path: Option<&str>,
+    sample_language: Option<&str>,
+    logger: &Logger,
+) -> Result<()> {
+    logger.record_inputs(&[keywords_file_paths, sample_path.as_slice()].concat());
+
+    let mut problems: usize = 0;
+    for path in keywords_file_paths {
+        let file_problems: Vec<String> = check_keyword_file(path, regex_syntax)?;
+        for problem in &file_problems {
+            error!("{path}: {problem}");
+        }
+        problems += file_problems.len();
+    }
+    if problems > 0 {
+        bail!("{problems} problems found in the keyword files");
+    }
+
+    // Conflicts between files and invalid patterns are reported when the files are loaded together.
+    let keyword_files: KeywordFiles = KeywordFiles::new(regex_syntax)
+        .add_files(keywords_file_paths, false)
+        .with_context(|| "The keyword files cannot be used together")?;
+
+    info!(
+        "{} keyword files are valid: {} languages, {} extensions",
+        keyword_files.len(),
+        keyword_files.languages().len(),
+        keyword_files.extensions().len()
+    );
+    let regexes: HashMap<String, Vec<String>> = keyword_files.debug_regexes();
+    let mut languages: Vec<&String> = regexes.keys().collect();
+    languages.sort();
+    for language in languages {
+        info!("{language}:");
+        for (path, regex) in keyword_files.paths.iter().zip(&regexes[language]) {
+            info!("  {path}: {regex}");
+        }
+    }
+
+    if let Some(sample_path) = sample_path {
+        let language: String = match sample_language {
+            Some(language) => language.to_string(),
+            None => Path::new(sample_path)
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .and_then(|ext| keyword_files.extensions_to_language.get(ext))
+                .with_context(|| {
+                    format!("The language of {sample_path} cannot be inferred from its extension, use --lang")
+                })?
+                .clone(),
+        };
+        let matchers = keyword_files
+            .matchers
+            .get(&language)
+            .with_context(|| format!("No keyword file defines the language {language}"))?;
+        let sample: Vec<u8> = std::fs::read(check_path(sample_path)?)
+            .with_context(|| format!("Could not read {sample_path}"))?;
+
+        info!("Matches in {sample_path} ({language}):");
+        for (path, matcher) in keyword_files.paths.iter().zip(matchers) {
+            let matches: Vec<&[u8]> = matcher.matches(&sample);
+            let keywords: BTreeSet<String> = matches
+                .iter()
+                .map(|m| String::from_utf8_lossy(m).to_string())
+                .collect();
+            info!(
+                "  {path}: {} matches ({})",
+                matches.len(),
+                keywords.into_iter().collect::<Vec<_>>().join(", ")
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Checks the structure of a keyword file.
+///
+/// # Arguments
+///
+/// * `path` - The path to the keyword file.
+/// * `regex_syntax` - Whether the keywords are regular expressions, in which case they must compile.
+///
+/// # Returns
+///
+/// The problems found in the file, or an error if the file is not valid JSON.
+fn check_keyword_file(path: &str, regex_syntax: bool) -> Result<Vec<String>> {
+    let json: JsonValue = open_json_from_path(check_path(path)?.to_str().unwrap_or(path))?;
+    let mut problems: Vec<String> = Vec::new();
+
+    if !json.is_object() {
+        return Ok(vec!["the file must contain a JSON object".to_string()]);
+    }
+    for (key, _) in json.entries() {
+        if key != "languages" && key != "keywords" {
+            problems.push(format!("unknown field '{key}'"));
+        }
+    }
+    check_keywords(
+        &json["keywords"],
+        "global keywords",
+        regex_syntax,
+        &mut problems,
+    );
+
+    let languages: &JsonValue = &json["languages"];
+    if !languages.is_array() {
+        problems.push("the 'languages' field must be an array".to_string());
+        return Ok(problems);
+    }
+
+    let mut names: HashSet<&str> = HashSet::new();
+    let mut extensions: HashMap<&str, &str> = HashMap::new();
+    for (i, language) in languages.members().enumerate() {
+        let name: &str = match (language.as_str(), language["name"].as_str()) {
+            (Some(name), _) => name,
+            (None, Some(name)) if language.is_object() => name,
+            _ => {
+                problems.push(format!(
+                    "language {} must be a name or an object with a 'name' field",
+                    i + 1
+                ));
+                continue;
+            }
+        };
+        if !names.insert(name) {
+            problems.push(format!("language {name} is defined several times"));
+        }
+        for (key, _) in language.entries() {
+            if !["name", "extensions", "keywords"].contains(&key) {
+                problems.push(format!("unknown field '{key}' in language {name}"));
+            }
+        }
+
+        let language_extensions: &JsonValue = &language["extensions"];
+        if !language_extensions.is_null() && !language_extensions.is_array() {
+            problems.push(format!(
+                "the extensions of language {name} must be an array"
+            ));
+        }
+        for extension in language_extensions.members() {
+            match extension.as_str() {
+                Some(ext) if ext.is_empty() || ext.starts_with('.') => problems.push(format!(
+                    "extension '{ext}' of language {name} must be written without the period"
+                )),
+                Some(ext) => match extensions.insert(ext, name) {
+                    Some(other) if other != name && !shared_extension(ext, other, name) => problems
+                        .push(format!(
+                            "extension '{ext}' is associated with both {other} and {name}"
+                        )),
+                    _ => {}
+                },
+                None => problems.push(format!("the extensions of language {name} must be strings")),
+            }
+        }
+        check_keywords(
+            &language["keywords"],
+            &format!("keywords of language {name}"),
+            regex_syntax,
+            &mut problems,
+        );
+    }
+    Ok(problems)
+}
+
+/// Checks that a list of keywords is an array of non-empty strings, which are valid regular expressions if required.
+///
+/// # Arguments
+///
+/// * `keywords` - The JSON value of the list, null if the list is absent.
+/// * `description` - Description of the list used in the problems.
+/// * `regex_syntax` - Whether the keywords are regular expressions.
+/// * `problems` - The problems found so far.
+fn check_keywords(
+    keywords: &JsonValue,
+    description: &str,
+    regex_syntax: bool,
+    problems: &mut Vec<String>,
+) {
+    if keywords.is_null() {
+        return;
+    }
+    if !keywords.is_array() {
+        problems.push(format!("the {description} must be an array"));
+        return;
+    }
+    for keyword in keywords.members() {
+        match keyword.as_str() {
+            Some("") => problems.push(format!("the {description} contain an empty keyword")),
+            Some(keyword) if regex_syntax => {
+                if let Err(e) = regex::bytes::Regex::new(keyword) {
+                    problems.push(format!(
+                        "keyword '{keyword}' in the {description} is not a valid regular expression: {e}"
+                    ));
+                }
+            }
+            Some(_) => {}
+            None => problems.push(format!("the {description} must be strings")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::utils::logger::test_logger;
+
+    const TEST_DATA: &str = "tests/data/phases/check_keywords";
+
+    #[test]
+    fn valid_keyword_files() -> Result<()> {
+        for path in [
+            "tests/data/keywords/c_float.json",
+            "tests/data/keywords/fp_types.json",
+            "tests/data/keywords/scala_java.json",
+        ] {
+            assert_eq!(check_keyword_file(path, false)?, Vec::<String>::new());
+        }
+        run(
+            &[
+                "tests/data/keywords/c_float.json",
+                "tests/data/keywords/std_math.json",
+            ],
+            false,
+            Some("tests/data/phases/parse/matches.c"),
+            None,
+            test_logger(),
+        )
+    }
+
+    #[test]
+    fn invalid_keyword_file() -> Result<()> {
+        let path: String = format!("{TEST_DATA}/invalid.json");
+        // The message of the regular expression error depends on the version of the regex crate.
+        let mut problems: Vec<String> = check_keyword_file(&path, true)?
+            .into_iter()
+            .map(|p| p.split(": regex parse error").next().unwrap().to_string())
+            .collect();
+        problems.sort();
+        assert_eq!(
+            problems,
+            vec![
+                "extension '.h' of language c must be written without the period",
+                "extension 'c' is associated with both c and cpp",
+                "keyword '(float' in the keywords of language cpp is not a valid regular expression",
+                "language 3 must be a name or an object with a 'name' field",
+                "language c is defined several times",
+                "the global keywords must be strings",
+                "unknown field 'extension' in language c",
+                "unknown field 'version'",
+            ]
+        );
+        assert!(run(&[&path], true, None, None, test_logger()).is_err());
+
+        // Conflicts between files are only detected when they are loaded together.
+        assert!(run(
+            &[
+                "tests/data/keywords/c_float.json",
+                &format!("{TEST_DATA}/conflict.json")
+            ],
+            false,
+            None,
+            None,
+            test_logger()
+        )
+        .is_err());
+        Ok(())
+    }
+}
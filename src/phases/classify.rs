@@ -0,0 +1,374 @@
+// Copyright 2026 Andrea Gilot
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![doc = include_str!("../docs/classify.md")]
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::sync::LazyLock;
+
+use anyhow::{ensure, Context, Result};
+use clap::{Arg, ArgAction, Command};
+use polars::prelude::Schema;
+use regex::bytes::Regex;
+use tracing::{info, warn};
+
+use crate::utils::csv::{revert_placeholders, validate_columns, CSVFile, Quoting};
+use crate::utils::fs::*;
+use crate::utils::logger::{log_output_file, Logger};
+use crate::utils::progress::Progress;
+
+/// Category of the functions matching no pattern.
+const NO_CATEGORY: &str = "none";
+
+/// Heuristic category of numerical kernels.
+struct Category {
+    /// Name of the category, as written in the output file.
+    name: &'static str,
+    /// Keyword and call patterns of the category.
+    pattern: Regex,
+    /// Minimal number of matches of the pattern for a function to belong to the category.
+    min_matches: usize,
+}
+
+impl Category {
+    fn new(name: &'static str, pattern: &str, min_matches: usize) -> Self {
+        Self {
+            name,
+            pattern: Regex::new(pattern).unwrap(),
+            min_matches,
+        }
+    }
+}
+
+/// Categories of numerical kernels, in the order used to break ties between categories with as many matches.
+static CATEGORIES: LazyLock<[Category; 6]> = LazyLock::new(|| {
+    [
+        Category::new(
+            "linear_algebra",
+            concat!(
+                // BLAS, LAPACK and matrix library calls.
+                r"\b(?:cblas_\w+|lapacke?_\w+|[sdcz](?:gemm|gemv|symm|syrk|trmm|trsm|axpy|getrf|potrf|gesv)_?",
+                r"|gemm|gemv|matmul|dot|transpose|cholesky|lu_decomp\w*|eig\w*)\s*\(",
+                // Multiply-accumulate of two indexed operands, as in dot and matrix products.
+                r"|\+=\s*[\w.]+\s*\[[^;]*\]\s*\*\s*[\w.]+\s*\[",
+            ),
+            1,
+        ),
+        Category::new(
+            "reduction",
+            concat!(
+                // Accumulation of indexed values into a scalar.
+                r"\b[A-Za-z_]\w*\s*(?:\+|-|\*|\||&|\^)=\s*[^;\n]*\[",
+                // Running minimum or maximum.
+                r"|\b[A-Za-z_]\w*\s*=\s*(?:std::|Math\.)?f?(?:max|min)\s*\(",
+                // Reduction functions and OpenMP reduction clauses.
+                r"|\b(?:sum|prod|reduce|accumulate|fold)\s*\(|#\s*pragma\s+omp\b[^\n]*\breduction\s*\(",
+            ),
+            1,
+        ),
+        Category::new(
+            "stencil",
+            // Accesses to the neighbours of an element.
+            r"\[\s*[A-Za-z_]\w*\s*[+-]\s*[0-9]+\s*\]",
+            2,
+        ),
+        Category::new(
+            "transcendental",
+            concat!(
+                r"\b(?:a?sinh?|a?cosh?|a?tanh?|atan2|exp|exp2|expm1|log|log2|log10|log1p|pow|sqrt|cbrt|hypot",
+                r"|erfc?|tgamma|lgamma)[fl]?\s*\(",
+            ),
+            3,
+        ),
+        Category::new(
+            "rng",
+            concat!(
+                r"\b(?:s?rand|s?random|[delmnj]rand48|rand_r|randn|randint|nextDouble|nextFloat|nextInt|nextLong",
+                r"|nextGaussian|curand_\w+|gsl_rng_\w+|uniform)\s*\(",
+                r"|\b(?:mt19937(?:_64)?|minstd_rand0?|default_random_engine|random_device|\w+_distribution",
+                r"|Random|ThreadLocalRandom|SecureRandom)\b",
+            ),
+            1,
+        ),
+        Category::new(
+            "io",
+            concat!(
+                r"\b(?:printf|fprintf|vprintf|puts|fputs|putchar|fputc|getchar|fgetc|fgets|gets|getline|scanf|fscanf",
+                r"|fopen|fclose|fread|fwrite|fflush|fseek|ftell|rewind|read|write|open|close|println|print|readLine|input)\s*\(",
+                r"|\b(?:cout|cerr|clog|cin|ifstream|ofstream|fstream|BufferedReader|BufferedWriter|FileReader",
+                r"|FileWriter|PrintWriter|Scanner)\b|\bSystem\.(?:out|err|in)\b",
+            ),
+            1,
+        ),
+    ]
+});
+
+/// Line and block comments, removed before matching the patterns.
+static COMMENTS: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)/\*.*?\*/|//[^\n]*").unwrap());
+
+/// Command line arguments parsing.
+pub fn cli() -> Command {
+    Command::new("classify")
+        .about("Tags functions or benchmarks with heuristic categories of numerical kernels.")
+        .long_about(include_str!("../docs/classify.md"))
+        .disable_version_flag(true)
+        .arg(
+            Arg::new("input")
+                .short('i')
+                .long("input")
+                .value_name("INPUT_FILE.csv")
+                .help("Path to the csv file listing the functions to classify, such as the functions file produced by the parse command \
+                       or the output file of the extract_benchmarks command.")
+                .required(true),
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .value_name("OUTPUT_FILE.csv")
+                .help("Path to the output csv file. By default, the suffix '.categories.csv' is appended to the input path.")
+                .required(false),
+        )
+        .arg(
+            Arg::new("column")
+                .long("column")
+                .value_name("COLUMN")
+                .help("Column of the input file containing the paths to the code to classify, \
+                       e.g. 'path' for the functions file of the parse command or 'benchmark' for the output of extract_benchmarks.")
+                .default_value("path"),
+        )
+        .arg(
+            Arg::new("force")
+                .short('f')
+                .long("force")
+                .help("Override the output file if it already exists.")
+                .default_value("false")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+/// Tags every function of a CSV file with the categories of numerical kernels whose patterns occur in its code.
+/// Rows are copied unchanged, in the order of the input file, followed by the categories of the function.
+///
+/// # Arguments
+///
+/// * `input_path` - The path to the CSV file listing the functions.
+/// * `output_path` - The optional path to the output CSV file. Defaults to the input path with the suffix '.categories.csv'.
+/// * `column` - The column of the input file containing the paths to the code of the functions.
+/// * `force` - Whether to override the output file if it already exists.
+/// * `logger` - The logger displaying the progress.
+///
+/// # Returns
+///
+/// A result indicating success or failure of the operation.
+pub fn run(
+    input_path: &str,
+    output_path: Option<&str>,
+    column: &str,
+    force: bool,
+    logger: &Logger,
+) -> Result<()> {
+    let default_output_path: String = format!("{input_path}.categories.csv");
+    let output_path: &str = output_path.unwrap_or(&default_output_path);
+    logger.record_inputs(&[input_path]);
+    logger.record_outputs(&[output_path]);
+
+    check_path(input_path)?;
+    log_output_file(output_path, false, force)?;
+    validate_columns(input_path, &[column], &Schema::default())?;
+
+    let mut reader = csv::Reader::from_reader(open_file(input_path, FileMode::Read)?);
+    let header: Vec<String> = reader.headers()?.iter().map(|h| h.to_string()).collect();
+    for name in ["categories", "category"] {
+        ensure!(
+            !header.iter().any(|h| h == name),
+            "File {input_path} already contains column '{name}'"
+        );
+    }
+    let path_idx: usize = header.iter().position(|h| h == column).unwrap();
+    let rows: u64 = logger.run_task("Counting functions", || {
+        Ok(
+            csv::Reader::from_reader(open_file(input_path, FileMode::Read)?)
+                .byte_records()
+                .count() as u64,
+        )
+    })?;
+
+    let mut output_file: CSVFile = CSVFile::new(output_path, FileMode::Overwrite)?;
+    let mut output_header: Vec<&str> = header.iter().map(|h| h.as_str()).collect();
+    output_header.extend(["categories", "category"]);
+    output_file.write_header(&output_header)?;
+
+    let progress_bar: Progress = logger.progress_bar(Some(rows))?;
+
+    // Number of functions of every primary category.
+    let mut counts: BTreeMap<&str, u64> = BTreeMap::new();
+    let mut skipped: u64 = 0;
+    for (i, record) in reader.records().enumerate() {
+        progress_bar.inc(1);
+        let record = record.with_context(|| format!("Could not parse row {i} of {input_path}"))?;
+        let path: String = revert_placeholders(&record[path_idx]);
+
+        let categories: Option<Vec<&str>> = if path.is_empty() || path == "error" {
+            None
+        } else {
+            match load_file(&path, 1024 * 1024 * 1024) {
+                Ok(Ok(code)) => Some(categorize(&code)),
+                Ok(Err(size)) => {
+                    warn!("File {path} is too large ({size} bytes) and was skipped");
+                    None
+                }
+                Err(e) => {
+                    warn!("{e:#}");
+                    None
+                }
+            }
+        };
+        let (categories, category): (String, &str) = match categories {
+            Some(categories) => {
+                let category: &str = categories.first().copied().unwrap_or(NO_CATEGORY);
+                *counts.entry(category).or_default() += 1;
+                let mut all: Vec<&str> = categories;
+                all.sort_by_key(|name| CATEGORIES.iter().position(|c| c.name == *name));
+                (all.join(";"), category)
+            }
+            None => {
+                skipped += 1;
+                progress_bar.fail();
+                (String::new(), "")
+            }
+        };
+
+        writeln!(
+            output_file,
+            "{},{},{}",
+            record
+                .iter()
+                .map(|f| Quoting::Rfc4180.escape(f))
+                .collect::<Vec<_>>()
+                .join(","),
+            categories,
+            category
+        )?;
+    }
+    progress_bar.finish();
+
+    for (category, count) in &counts {
+        info!("  {category}: {count} functions");
+        logger.record_count(&format!("{category} functions"), *count);
+    }
+    if skipped > 0 {
+        info!("  {skipped} functions could not be read and were not classified.");
+        logger.record_count("functions skipped", skipped);
+    }
+    Ok(())
+}
+
+/// Returns the categories of a piece of code, comments excluded.
+///
+/// # Arguments
+///
+/// * `code` - The code to classify.
+///
+/// # Returns
+///
+/// The names of the categories whose patterns occur at least as often as required, by decreasing number of matches.
+/// Categories with as many matches are ordered as in [`CATEGORIES`], so that the first one is the primary category.
+fn categorize(code: &[u8]) -> Vec<&'static str> {
+    let code = COMMENTS.replace_all(code, &b" "[..]);
+    let mut matches: Vec<(usize, &'static str)> = CATEGORIES
+        .iter()
+        .map(|c| (c.pattern.find_iter(&code).count(), c))
+        .filter(|(n, c)| *n > 0 && *n >= c.min_matches)
+        .map(|(n, c)| (n, c.name))
+        .collect();
+    // The sort is stable, which keeps the order of the categories with as many matches.
+    matches.sort_by_key(|(n, _)| std::cmp::Reverse(*n));
+    matches.into_iter().map(|(_, name)| name).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::logger::test_logger;
+
+    const TEST_DATA: &str = "tests/data/phases/classify";
+
+    #[test]
+    fn categorize_test() {
+        let matmul = b"for (i = 0; i < n; i++)\n  for (j = 0; j < n; j++)\n    for (k = 0; k < n; k++)\n      c[i][j] += a[i][k] * b[k][j];";
+        assert_eq!(categorize(matmul), vec!["linear_algebra"]);
+
+        let dot = b"double s = 0; for (i = 0; i < n; i++) s += x[i] * y[i]; return s;";
+        assert_eq!(categorize(dot), vec!["linear_algebra", "reduction"]);
+
+        let sum = b"int s = 0; for (i = 0; i < n; i++) { s += a[i]; m = fmax(m, a[i]); }";
+        assert_eq!(categorize(sum), vec!["reduction"]);
+
+        let stencil = b"for (i = 1; i < n - 1; i++) b[i] = (a[i - 1] + a[i] + a[i + 1]) / 3;";
+        assert_eq!(categorize(stencil), vec!["stencil"]);
+
+        // A single call is not enough for the function to be transcendental-heavy.
+        assert!(categorize(b"return sqrt(x);").is_empty());
+        let gauss = b"return exp(-x * x / 2) / sqrt(2 * M_PI) * cosf(y) + log(z);";
+        assert_eq!(categorize(gauss), vec!["transcendental"]);
+
+        let monte_carlo = b"for (i = 0; i < n; i++) { x = rand(); y = rand(); } printf(\"%d\", x);";
+        assert_eq!(categorize(monte_carlo), vec!["rng", "io"]);
+
+        let java = b"System.out.println(new Random().nextInt(10));";
+        assert_eq!(categorize(java), vec!["rng", "io"]);
+
+        // Comments are ignored and identifiers only containing a keyword do not match.
+        let commented = b"// printf(\"%f\", sin(x));\n/* rand() */ int sinus(int random_seed) { return expected(); }";
+        assert!(categorize(commented).is_empty());
+    }
+
+    #[test]
+    fn classify_test() -> Result<()> {
+        let input_path: String = format!("{TEST_DATA}/functions.csv");
+        let output_path: &str = "target/tests/classify.csv";
+        run(&input_path, Some(output_path), "path", true, test_logger())?;
+
+        let mut reader = csv::Reader::from_reader(open_file(output_path, FileMode::Read)?);
+        assert_eq!(
+            reader.headers()?.iter().collect::<Vec<_>>(),
+            vec!["id", "path", "name", "categories", "category"]
+        );
+        let rows: Vec<Vec<String>> = reader
+            .records()
+            .map(|r| Ok(r?.iter().map(|f| f.to_string()).collect()))
+            .collect::<Result<_>>()?;
+        let categories: Vec<(&str, &str, &str)> = rows
+            .iter()
+            .map(|r| (r[2].as_str(), r[3].as_str(), r[4].as_str()))
+            .collect();
+        assert_eq!(
+            categories,
+            vec![
+                ("matmul", "linear_algebra;reduction", "linear_algebra"),
+                ("jacobi", "stencil", "stencil"),
+                ("dump", "io", "io"),
+                ("copy", "", NO_CATEGORY),
+                ("missing", "", ""),
+                ("failed, twice", "", ""),
+            ]
+        );
+
+        // Re-classifying the output file is rejected.
+        assert!(run(output_path, None, "path", true, test_logger()).is_err());
+        delete_file(output_path, false)
+    }
+}
@@ -0,0 +1,540 @@
+// Copyright 2026 Andrea Gilot
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![doc = include_str!("../docs/diff_corpus.md")]
+
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+
+use anyhow::{bail, Context, Result};
+use clap::{Arg, ArgAction, Command};
+use tracing::info;
+
+use crate::utils::csv::{revert_placeholders, CSVFile, Quoting};
+use crate::utils::fs::*;
+use crate::utils::logger::{log_output_file, Logger};
+use crate::utils::parallel::WorkerPool;
+use crate::utils::progress::Progress;
+
+/// Command line arguments parsing.
+pub fn cli() -> Command {
+    Command::new("diff_corpus")
+        .about("Compares the files of two versions of a corpus by content and reports the added, removed, moved and modified files.")
+        .long_about(include_str!("../docs/diff_corpus.md"))
+        .disable_version_flag(true)
+        .arg(
+            Arg::new("old")
+                .long("old")
+                .value_name("OLD_FILE_LOG.csv")
+                .help("Path to the file log of the old corpus, produced by the download command.")
+                .required(true),
+        )
+        .arg(
+            Arg::new("new")
+                .long("new")
+                .value_name("NEW_FILE_LOG.csv")
+                .help("Path to the file log of the new corpus, produced by the download command.")
+                .required(true),
+        )
+        .arg(
+            Arg::new("old-projects")
+                .long("old-projects")
+                .value_name("OLD_PROJECT_LOG.csv")
+                .help("Path to the project log of the old corpus, giving the directory of every repository. \
+                       By default, the suffix '.file_log.csv' of the old file log is replaced with '.project_log.csv'.")
+                .required(false),
+        )
+        .arg(
+            Arg::new("new-projects")
+                .long("new-projects")
+                .value_name("NEW_PROJECT_LOG.csv")
+                .help("Path to the project log of the new corpus, giving the directory of every repository. \
+                       By default, the suffix '.file_log.csv' of the new file log is replaced with '.project_log.csv'.")
+                .required(false),
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .value_name("OUTPUT_FILE.csv")
+                .help("Path to the output csv file storing the status of every file. \
+                       By default, the name of the output file is the same as the new file log with the suffix '.diff.csv'.")
+                .required(false),
+        )
+        .arg(
+            Arg::new("threads")
+                .short('n')
+                .help("Number of threads used to hash the files.")
+                .default_value("1")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("force")
+                .short('f')
+                .long("force")
+                .help("Override the output file if it already exists.")
+                .default_value("false")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+/// Status of a file of the new corpus with respect to the old corpus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FileStatus {
+    /// The file has the same path and content.
+    Unchanged,
+    /// The file has the same path and another content.
+    Modified,
+    /// The file has the same content at another path of the repository.
+    Moved,
+    /// The file is only in the new corpus.
+    Added,
+    /// The file is only in the old corpus.
+    Removed,
+}
+
+impl FileStatus {
+    /// All the statuses, in the order in which they are reported.
+    const ALL: [FileStatus; 5] = [
+        FileStatus::Unchanged,
+        FileStatus::Modified,
+        FileStatus::Moved,
+        FileStatus::Added,
+        FileStatus::Removed,
+    ];
+
+    /// Name of the status in the output file.
+    fn name(&self) -> &'static str {
+        match self {
+            FileStatus::Unchanged => "unchanged",
+            FileStatus::Modified => "modified",
+            FileStatus::Moved => "moved",
+            FileStatus::Added => "added",
+            FileStatus::Removed => "removed",
+        }
+    }
+}
+
+/// File of a corpus.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct CorpusFile {
+    /// ID of the repository of the file.
+    id: u32,
+    /// Path of the file relative to the directory of the repository, which identifies it across versions.
+    relative: String,
+    /// Path of the file in the corpus, as recorded in the file log.
+    name: String,
+    /// Blake3 hash of the content of the file, in hexadecimal.
+    hash: String,
+}
+
+/// Status of a file, with its version in the old and in the new corpus.
+#[derive(Debug, PartialEq, Eq)]
+struct FileDiff {
+    status: FileStatus,
+    old: Option<CorpusFile>,
+    new: Option<CorpusFile>,
+}
+
+impl FileDiff {
+    /// The newest version of the file.
+    fn file(&self) -> &CorpusFile {
+        self.new
+            .as_ref()
+            .or(self.old.as_ref())
+            .expect("A file is in one of the corpora")
+    }
+}
+
+/// Compares the files of two versions of a corpus by content.
+///
+/// # Arguments
+///
+/// * `old_path` - The path to the file log of the old corpus.
+/// * `new_path` - The path to the file log of the new corpus.
+/// * `old_projects_path` - The optional path to the project log of the old corpus. Defaults to the old file log with
+///   the suffix ".file_log.csv" replaced with ".project_log.csv".
+/// * `new_projects_path` - The optional path to the project log of the new corpus. Defaults to the new file log with
+///   the suffix ".file_log.csv" replaced with ".project_log.csv".
+/// * `output_path` - The optional path to the output CSV file. Defaults to the new file log with ".diff.csv" appended.
+/// * `threads` - The number of threads used to hash the files.
+/// * `force` - Whether to override the output file if it already exists.
+/// * `logger` - The logger displaying the progress.
+///
+/// # Returns
+///
+/// A result indicating success or failure of the operation.
+pub fn run(
+    old_path: &str,
+    new_path: &str,
+    old_projects_path: Option<&str>,
+    new_projects_path: Option<&str>,
+    output_path: Option<&str>,
+    threads: usize,
+    force: bool,
+    logger: &Logger,
+) -> Result<()> {
+    let old_projects_path: String = match old_projects_path {
+        Some(path) => path.to_string(),
+        None => default_project_log(old_path, "old")?,
+    };
+    let new_projects_path: String = match new_projects_path {
+        Some(path) => path.to_string(),
+        None => default_project_log(new_path, "new")?,
+    };
+    let default_output_path: String = format!("{new_path}.diff.csv");
+    let output_path: &str = output_path.unwrap_or(&default_output_path);
+    logger.record_inputs(&[old_path, &old_projects_path, new_path, &new_projects_path]);
+    logger.record_outputs(&[output_path]);
+
+    for path in [old_path, &old_projects_path, new_path, &new_projects_path] {
+        check_path(path)?;
+    }
+    log_output_file(output_path, false, force)?;
+
+    let (mut old_files, mut new_files): (Vec<CorpusFile>, Vec<CorpusFile>) =
+        logger.run_task("Loading file logs", || {
+            Ok((
+                read_files(old_path, &project_dirs(&old_projects_path)?)?,
+                read_files(new_path, &project_dirs(&new_projects_path)?)?,
+            ))
+        })?;
+
+    info!(
+        "  {} files in the old corpus, {} files in the new corpus.",
+        old_files.len(),
+        new_files.len()
+    );
+
+    hash_files(&mut old_files, &mut new_files, threads, logger)?;
+
+    let diffs: Vec<FileDiff> = diff(old_files, new_files);
+
+    let mut output: CSVFile = CSVFile::new(output_path, FileMode::Overwrite)?;
+    output.write_header(&["id", "status", "old_name", "new_name", "hash"])?;
+    for file_diff in &diffs {
+        writeln!(
+            output,
+            "{},{},{},{},{}",
+            file_diff.file().id,
+            file_diff.status.name(),
+            file_diff
+                .old
+                .as_ref()
+                .map_or_else(String::new, |f| Quoting::Rfc4180.escape(&f.name)),
+            file_diff
+                .new
+                .as_ref()
+                .map_or_else(String::new, |f| Quoting::Rfc4180.escape(&f.name)),
+            file_diff.file().hash
+        )?;
+    }
+
+    for status in FileStatus::ALL {
+        let count: usize = diffs.iter().filter(|d| d.status == status).count();
+        info!("  {count} files {}.", status.name());
+        logger.record_count(&format!("files {}", status.name()), count as u64);
+    }
+
+    Ok(())
+}
+
+/// Infers the path of the project log written by the download command next to a file log.
+///
+/// # Arguments
+///
+/// * `file_log` - The path to the file log.
+/// * `corpus` - The corpus of the file log, either "old" or "new", to name the option in the error message.
+fn default_project_log(file_log: &str, corpus: &str) -> Result<String> {
+    match file_log.strip_suffix(".file_log.csv") {
+        Some(prefix) => Ok(format!("{prefix}.project_log.csv")),
+        None => bail!(
+            "Could not infer the project log of {file_log}, which does not end with '.file_log.csv'. \
+             Specify it with --{corpus}-projects."
+        ),
+    }
+}
+
+/// Reads the directory of every repository from a project log.
+/// The rows of the directories recorded as separate projects with --split-subdirs are ignored.
+///
+/// # Arguments
+///
+/// * `path` - The path to the project log.
+///
+/// # Returns
+///
+/// The directory of every repository, indexed by its ID.
+fn project_dirs(path: &str) -> Result<HashMap<u32, String>> {
+    let mut reader = csv::Reader::from_reader(open_reader(path)?);
+    let (id_idx, path_idx): (usize, usize) = column_pair(path, reader.headers()?, "id", "path")?;
+    let mut dirs: HashMap<u32, String> = HashMap::new();
+    for (i, record) in reader.records().enumerate() {
+        let record = record.with_context(|| format!("Could not parse row {i} of {path}"))?;
+        if let Ok(id) = record[id_idx].parse::<u32>() {
+            dirs.insert(id, revert_placeholders(&record[path_idx]));
+        }
+    }
+    Ok(dirs)
+}
+
+/// Reads the files of a file log and their path relative to the directory of their repository.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file log.
+/// * `dirs` - The directory of every repository, indexed by its ID.
+///
+/// # Returns
+///
+/// The files of the file log, whose hashes are not computed yet.
+fn read_files(path: &str, dirs: &HashMap<u32, String>) -> Result<Vec<CorpusFile>> {
+    let mut reader = csv::Reader::from_reader(open_reader(path)?);
+    let (id_idx, name_idx): (usize, usize) = column_pair(path, reader.headers()?, "id", "name")?;
+    reader
+        .records()
+        .enumerate()
+        .map(|(i, record)| {
+            let record = record.with_context(|| format!("Could not parse row {i} of {path}"))?;
+            let id: u32 = record[id_idx]
+                .parse()
+                .with_context(|| format!("Invalid repository ID in row {i} of {path}"))?;
+            let name: String = revert_placeholders(&record[name_idx]);
+            let dir: &str = dirs
+                .get(&id)
+                .with_context(|| format!("Repository {id} of {path} is not in its project log"))?;
+            let relative: &str = name
+                .strip_prefix(dir)
+                .and_then(|relative| relative.strip_prefix('/'))
+                .with_context(|| {
+                    format!("File {name} is not in the directory {dir} of repository {id}")
+                })?;
+            Ok(CorpusFile {
+                id,
+                relative: relative.to_string(),
+                name: name.clone(),
+                hash: String::new(),
+            })
+        })
+        .collect()
+}
+
+/// Finds the indexes of two columns in the header of a CSV file.
+fn column_pair(
+    path: &str,
+    header: &csv::StringRecord,
+    first: &str,
+    second: &str,
+) -> Result<(usize, usize)> {
+    let idx = |column: &str| {
+        header
+            .iter()
+            .position(|h| h == column)
+            .with_context(|| format!("File {path} does not contain column '{column}'."))
+    };
+    Ok((idx(first)?, idx(second)?))
+}
+
+/// Computes the hashes of the files of both corpora in parallel.
+///
+/// # Arguments
+///
+/// * `old_files` - The files of the old corpus.
+/// * `new_files` - The files of the new corpus.
+/// * `threads` - The number of threads.
+/// * `logger` - The logger displaying the progress.
+fn hash_files(
+    old_files: &mut [CorpusFile],
+    new_files: &mut [CorpusFile],
+    threads: usize,
+    logger: &Logger,
+) -> Result<()> {
+    let progress_bar: Progress =
+        logger.progress_bar(Some((old_files.len() + new_files.len()) as u64))?;
+    let mut files: Vec<&mut CorpusFile> = old_files.iter_mut().chain(new_files).collect();
+    let names: Vec<String> = files.iter().map(|f| f.name.clone()).collect();
+    WorkerPool::new().run(
+        names.iter().enumerate(),
+        vec![(); threads.max(1)],
+        |_, (idx, name)| Ok(Some((idx, hash_file(name)?.1))),
+        |(idx, hash)| {
+            files[idx].hash = hash;
+            progress_bar.inc(1);
+            Ok(())
+        },
+    )?;
+    progress_bar.finish();
+    Ok(())
+}
+
+/// Compares the files of two versions of a corpus.
+/// Files with the same path in the same repository are unchanged or modified. The other files of a repository are
+/// moved if a file with the same content left its path, the files being paired in the order of their paths, and
+/// added or removed otherwise.
+///
+/// # Arguments
+///
+/// * `old_files` - The hashed files of the old corpus.
+/// * `new_files` - The hashed files of the new corpus.
+///
+/// # Returns
+///
+/// The status of every file, sorted by repository and path.
+fn diff(old_files: Vec<CorpusFile>, new_files: Vec<CorpusFile>) -> Vec<FileDiff> {
+    let mut old_by_path: HashMap<(u32, String), CorpusFile> = old_files
+        .into_iter()
+        .map(|f| ((f.id, f.relative.clone()), f))
+        .collect();
+    let mut diffs: Vec<FileDiff> = Vec::new();
+    let mut new_only: Vec<CorpusFile> = Vec::new();
+    for file in new_files {
+        match old_by_path.remove(&(file.id, file.relative.clone())) {
+            Some(old) => diffs.push(FileDiff {
+                status: if old.hash == file.hash {
+                    FileStatus::Unchanged
+                } else {
+                    FileStatus::Modified
+                },
+                old: Some(old),
+                new: Some(file),
+            }),
+            None => new_only.push(file),
+        }
+    }
+
+    let mut old_only: Vec<CorpusFile> = old_by_path.into_values().collect();
+    old_only.sort_by(|a, b| a.relative.cmp(&b.relative));
+    let mut old_by_content: HashMap<(u32, String), VecDeque<CorpusFile>> = HashMap::new();
+    for file in old_only {
+        old_by_content
+            .entry((file.id, file.hash.clone()))
+            .or_default()
+            .push_back(file);
+    }
+    new_only.sort_by(|a, b| a.relative.cmp(&b.relative));
+    for file in new_only {
+        let old: Option<CorpusFile> = old_by_content
+            .get_mut(&(file.id, file.hash.clone()))
+            .and_then(|files| files.pop_front());
+        diffs.push(FileDiff {
+            status: if old.is_some() {
+                FileStatus::Moved
+            } else {
+                FileStatus::Added
+            },
+            old,
+            new: Some(file),
+        });
+    }
+    diffs.extend(old_by_content.into_values().flatten().map(|old| FileDiff {
+        status: FileStatus::Removed,
+        old: Some(old),
+        new: None,
+    }));
+
+    diffs.sort_by(|a, b| (a.file().id, &a.file().relative).cmp(&(b.file().id, &b.file().relative)));
+    diffs
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::utils::logger::test_logger;
+
+    use super::*;
+
+    #[test]
+    fn diff_corpus_test() -> Result<()> {
+        let dir: &str = "target/tests/diff_corpus";
+        delete_dir(dir, true)?;
+        let old_files: [(&str, &str); 5] = [
+            ("a.c", "same"),
+            ("b.c", "before"),
+            ("src/c.c", "moved"),
+            ("d.c", "removed"),
+            ("e,f.c", "renamed"),
+        ];
+        let new_files: [(&str, &str); 5] = [
+            ("a.c", "same"),
+            ("b.c", "after"),
+            ("lib/c.c", "moved"),
+            ("f.c", "added"),
+            ("e_f.c", "renamed"),
+        ];
+        for (corpus, files) in [("old", old_files), ("new", new_files)] {
+            let project: String = format!("{dir}/{corpus}/0/1-{corpus}");
+            let mut file_log: String = String::from("id,name,language,loc\n");
+            for (name, content) in files {
+                write_file(format!("{project}/{name}"), content)?;
+                file_log.push_str(&format!(
+                    "1,{},c,1\n",
+                    Quoting::Rfc4180.escape(&format!("{project}/{name}"))
+                ));
+            }
+            write_file(format!("{dir}/{corpus}.file_log.csv"), file_log)?;
+            write_file(
+                format!("{dir}/{corpus}.project_log.csv"),
+                format!("id,path,name\n1,{project},a/b\n1/src,{project}/src,a/b\n"),
+            )?;
+        }
+
+        run(
+            &format!("{dir}/old.file_log.csv"),
+            &format!("{dir}/new.file_log.csv"),
+            None,
+            None,
+            None,
+            2,
+            false,
+            test_logger(),
+        )?;
+
+        let hash = |content: &str| blake3::hash(content.as_bytes()).to_hex().to_string();
+        let (old, new) = (format!("{dir}/old/0/1-old"), format!("{dir}/new/0/1-new"));
+        assert_eq!(
+            file_lines(format!("{dir}/new.file_log.csv.diff.csv"))?
+                .collect::<Result<Vec<String>, _>>()?,
+            [
+                "id,status,old_name,new_name,hash".to_string(),
+                format!("1,unchanged,{old}/a.c,{new}/a.c,{}", hash("same")),
+                format!("1,modified,{old}/b.c,{new}/b.c,{}", hash("after")),
+                format!("1,removed,{old}/d.c,,{}", hash("removed")),
+                format!("1,moved,\"{old}/e,f.c\",{new}/e_f.c,{}", hash("renamed")),
+                format!("1,added,,{new}/f.c,{}", hash("added")),
+                format!("1,moved,{old}/src/c.c,{new}/lib/c.c,{}", hash("moved")),
+            ]
+        );
+
+        assert_eq!(
+            run(
+                &format!("{dir}/old.file_log.csv"),
+                &format!("{dir}/new.csv"),
+                None,
+                None,
+                None,
+                1,
+                true,
+                test_logger(),
+            )
+            .unwrap_err()
+            .to_string(),
+            format!(
+                "Could not infer the project log of {dir}/new.csv, which does not end with '.file_log.csv'. \
+                 Specify it with --new-projects."
+            )
+        );
+
+        delete_dir(dir, false)
+    }
+}
@@ -15,20 +15,25 @@
 #![doc = include_str!("../docs/duplicate_files.md")]
 
 use std::collections::HashMap;
+use std::io::{BufWriter, Write};
 use std::iter::FromIterator;
+use std::path::Path;
 
-use anyhow::{anyhow, ensure, Context, Error, Result};
+use anyhow::{ensure, Context, Result};
 use blake3::Hash;
 use clap::{Arg, ArgAction, Command};
-use indicatif::ProgressBar;
 use polars::frame::DataFrame;
-use polars::prelude::{DataFrameJoinOps as _, DataType, Field, Schema};
-use tracing::info;
+use polars::prelude::{BooleanChunked, DataType, Field, Schema, StringChunked};
+use tracing::{info, warn};
 
+use crate::utils::csv::{revert_placeholders, validate_columns, CSVFile, Quoting};
 use crate::utils::dataframes::{self, *};
 use crate::utils::fs::*;
 use crate::utils::logger::{log_output_file, log_write_output, Logger};
+use crate::utils::parallel::WorkerPool;
+use crate::utils::progress::Progress;
 use crate::utils::regex::Matcher;
+use crate::utils::shard::{shard_arg, with_shard, Shard};
 
 /// Command line arguments parsing.
 pub fn cli() -> Command {
@@ -60,11 +65,18 @@ pub fn cli() -> Command {
                 .help("Path to the map csv file to store the mapping of clones to their originals.")
                 .required(false),
         )
+        .arg(
+            Arg::new("clusters")
+                .long("clusters")
+                .value_name("CLUSTERS_FILE.csv")
+                .help("Path to a csv file to store the cluster id, the cluster size and the representative of every file.")
+                .required(false),
+        )
         .arg(
             Arg::new("force")
                 .short('f')
                 .long("force")
-                .help("Override the output CSV file if it already exists.")
+                .help("Override the output CSV file if it already exists, and hash again the files hashed by an interrupted run.")
                 .default_value("false")
                 .action(ArgAction::SetTrue),
         )
@@ -88,6 +100,21 @@ pub fn cli() -> Command {
                 .help("Name of column storing file paths in the input CSV file.")
                 .default_value("name"),
         )
+        .arg(
+            Arg::new("memory-limit")
+                .long("memory-limit")
+                .value_name("SIZE")
+                .help("Memory available for the hashes of the files, e.g. 512M or 48G. \
+                       Beyond it, the hashes are split into partitions spilled to disk next to the output file and grouped one partition at a time.")
+                .default_value("4G")
+                .value_parser(parse_size),
+        )
+        .arg(shard_arg().long_help(
+            "Only process the K-th of N disjoint parts of the input, with K between 1 and N, and add the suffix .shard-K-of-N to the output files. \
+             The files are assigned to the shards by their number of words, which requires a 'words' column in the input file. \
+             Duplicates have the same number of words and are therefore always in the same shard, so that the outputs of the shards can be combined with the merge command.",
+        ))
+        .args(output_column_args())
 }
 
 /// Detects duplicate files in a dataset, returning only unique files.
@@ -97,10 +124,14 @@ pub fn cli() -> Command {
 /// * `input_path` - The path to the input CSV file storing the file paths.
 /// * `output_path` - The optional path to the output CSV file to store unique files metadata.
 /// * `map_path` - The optional path to the map CSV file to store the mapping of clones to their originals.
+/// * `clusters_path` - The optional path to the CSV file to store the cluster of every file.
 /// * `force` - Whether to override the output file if it already exists.
 /// * `similarity` - The similarity criterion for duplicate detection (exact match or invariant to token order and whitespaces).
 /// * `threads` - The number of threads to use.
 /// * `input_header` - The name of the column storing file paths in the input CSV file.
+/// * `shard` - The optional shard of the files to process. Files with the same number of words are in the same shard.
+/// * `memory_limit` - The memory available for the hashes of the files, in bytes, beyond which they are spilled to disk.
+/// * `output_columns` - The columns written to the output file and their new names.
 /// * `logger` - The logger displaying the progress.
 ///
 /// # Returns
@@ -110,204 +141,466 @@ pub fn run(
     input_path: &str,
     output_path: Option<&str>,
     map_path: Option<&str>,
+    clusters_path: Option<&str>,
     force: bool,
     similarity: &str,
     threads: usize,
     input_header: &str,
+    shard: Option<Shard>,
+    memory_limit: u64,
+    output_columns: &OutputColumns,
     logger: &Logger,
 ) -> Result<()> {
     let default_output_path: String = format!("{input_path}.unique.csv");
     let default_map_path: String = format!("{input_path}.duplicates_map.csv");
-    let output_path: &str = output_path.unwrap_or(&default_output_path);
-    let map_path: &str = map_path.unwrap_or(&default_map_path);
+    let output_path: String = with_shard(output_path.unwrap_or(&default_output_path), shard);
+    let output_path: &str = &output_path;
+    let map_path: String = with_shard(map_path.unwrap_or(&default_map_path), shard);
+    let map_path: &str = &map_path;
+    let clusters_path: Option<String> = clusters_path.map(|path| with_shard(path, shard));
+    let clusters_path: Option<&str> = clusters_path.as_deref();
+    logger.record_inputs(&[input_path]);
+    logger.record_outputs(&[output_path, map_path]);
+    if let Some(clusters_path) = clusters_path {
+        logger.record_outputs(&[clusters_path]);
+    }
 
     check_path(input_path)?;
     log_output_file(output_path, false, force)?;
+    if let Some(clusters_path) = clusters_path {
+        log_output_file(clusters_path, false, force)?;
+    }
 
-    let files: DataFrame = open_csv(
-        input_path,
-        Some(Schema::from_iter(vec![
-            Field::new(input_header.into(), DataType::String),
-            Field::new("extension".into(), DataType::String),
-            Field::new("loc".into(), DataType::UInt32),
-            Field::new("words".into(), DataType::UInt32),
-        ])),
-        None,
-    )?;
-
-    ensure!(
-        has_column(&files, input_header),
-        "File {input_path} does not contain column '{input_header}'."
-    );
+    let schema: Schema = Schema::from_iter(vec![
+        Field::new(input_header.into(), DataType::String),
+        Field::new("extension".into(), DataType::String),
+        Field::new("loc".into(), DataType::UInt32),
+        Field::new("words".into(), DataType::UInt32),
+    ]);
+    validate_columns(input_path, &[input_header], &schema)?;
+    let mut files: DataFrame = open_csv(input_path, Some(schema), None)?;
+
+    // Identical files, and files with the same bag of words, have the same number of words.
+    if let Some(shard) = shard {
+        ensure!(
+            has_column(&files, "words"),
+            "File {input_path} does not contain column 'words', which is required to split it into shards."
+        );
+        let mask: BooleanChunked = files
+            .column("words")?
+            .u32()?
+            .into_iter()
+            .map(|words| shard.contains(words.unwrap_or_default() as u64))
+            .collect();
+        files = files.filter(&mask)?;
+        info!("{} files selected in shard {shard}.", files.height());
+    }
 
     let file_count: usize = files.height();
 
     info!("{} files found.", file_count);
 
-    // Split the dataset into chunks for each thread.
-    let split_dataset: Vec<DataFrame> = files
-        .column(input_header)?
-        .clone()
-        .into_frame()
-        .with_row_index("idx".into(), None)?
-        .split_chunks_by_n(threads, true);
+    // The hashes are appended to a checkpoint file as they are computed, so that an interrupted run
+    // resumes after the files already hashed. The file is deleted once the output files are written.
+    let checkpoint_path: String = format!("{output_path}.{similarity}.hashes.csv");
+    if force {
+        delete_file(&checkpoint_path, true)?;
+    }
+    // Opening the checkpoint file removes the partial row left by an interrupted run.
+    let mut checkpoint: CSVFile = CSVFile::new(&checkpoint_path, FileMode::Append)?;
+    let previous_hashes: HashMap<String, Option<[u8; 32]>> = read_checkpoint(&checkpoint_path)?;
+    if !previous_hashes.is_empty() {
+        info!(
+            "Resuming from {checkpoint_path}: {} files already hashed.",
+            previous_hashes.len()
+        );
+    }
+    checkpoint.write_header(&CHECKPOINT_FILE_HEADERS)?;
+
+    // The hashes are grouped in partitions small enough to be sorted within the memory limit.
+    // Partitions left by an interrupted run are removed, their hashes are either in the checkpoint file or computed again.
+    let partitions_dir: String = format!("{output_path}.partitions");
+    delete_dir(&partitions_dir, true)?;
+    let mut partitions: HashPartitions =
+        HashPartitions::new(&partitions_dir, file_count, memory_limit);
+    if partitions.len() > 1 {
+        info!(
+            "Hashes split into {} partitions of at most {} bytes.",
+            partitions.len(),
+            memory_limit
+        );
+    }
+
+    // The files hashed by an interrupted run are not hashed again.
+    let names: Vec<&str> = dataframes::str(&files, input_header)?;
+    let mut resumed_files: usize = 0;
+    let mut resumed_big_files: usize = 0;
+    for (idx, name) in names.iter().enumerate() {
+        match previous_hashes.get(*name) {
+            Some(Some(hash)) => partitions.push(*hash, idx as u32)?,
+            Some(None) => resumed_big_files += 1,
+            None => continue,
+        }
+        resumed_files += 1;
+    }
 
     info!("Starting file processing...\n");
 
-    // Every thread comes with a sender channel.
-    // The sender channel is used to send information about the downloaded repository back to the main thread.
-    // The receiver channel is used by the main thread to collect and write the information to the log file.
-    let (tx, rx) =
-        crossbeam_channel::unbounded::<Option<Result<(u32, String, Option<Hash>), Error>>>();
-    crossbeam::thread::scope(|s| {
-        let mut ended_threads = 0;
-        for chunk in split_dataset {
-            let my_tx = tx.clone();
-            s.spawn(move |_| {
-                let word_matcher: Matcher = Matcher::words_matcher();
-                for (name, idx) in dataframes::str(&chunk, input_header)?
-                    .into_iter()
-                    .zip(dataframes::u32(&chunk, "idx")?.into_iter())
-                {
-                    // Revert the temporary replacements of special characters.
-                    let clean_name: String = name
-                        .replace("-was_comma-", ",")
-                        .replace("-was_quote-", "\"");
-                    match load_file(&clean_name, 1024 * 1024 * 1024) {
-                        Ok(Ok(file_content)) => {
-                            let hash = if similarity == "exact" {
-                                blake3::hash(&file_content)
-                            } else {
-                                blake3::hash(&word_matcher.bag_of_words(&file_content).serialize())
-                            };
-                            let _ = my_tx.send(Some(Ok((idx, name.to_owned(), Some(hash)))));
-                        }
-                        Ok(Err(_)) => {
-                            let _ = my_tx.send(Some(Ok((idx, name.to_owned(), None))));
-                        }
-                        Err(e) => {
-                            let _ = my_tx.send(Some(Err(e)));
-                        }
-                    }
+    let progress: Progress = logger.progress_bar(Some(file_count as u64))?;
+    progress.inc(resumed_files as u64);
+    let mut big_files: usize = resumed_big_files;
+
+    // The results are bounded so that the hashes waiting to be collected do not exceed the memory limit.
+    // The order in which the hashes are received is non-deterministic although the list of files is.
+    let matchers: Vec<Matcher> = (0..threads).map(|_| Matcher::words_matcher()).collect();
+    WorkerPool::new().bounded(CHANNEL_CAPACITY).run(
+        names
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| !previous_hashes.contains_key(**name)),
+        matchers,
+        |word_matcher, (idx, name)| {
+            // Revert the temporary replacements of special characters.
+            let clean_name: String = revert_placeholders(name);
+            let opt_hash: Option<Hash> = map_file(&clean_name, 1024 * 1024 * 1024)?
+                .ok()
+                .map(|file_content| content_hash(&file_content, similarity, word_matcher));
+            Ok(Some((idx as u32, opt_hash)))
+        },
+        |(idx, opt_hash)| {
+            writeln!(
+                checkpoint,
+                "{},{}",
+                Quoting::Rfc4180.escape(names[idx as usize]),
+                opt_hash
+                    .map(|hash| hash.to_hex().to_string())
+                    .unwrap_or_default()
+            )?;
+            match opt_hash {
+                None => {
+                    big_files += 1;
                 }
-                my_tx.send(None)?;
-                anyhow::Ok(())
-            });
-        }
+                Some(hash) => {
+                    partitions.push(*hash.as_bytes(), idx)?;
+                }
+            }
+            progress.inc(1);
+            Ok(())
+        },
+    )?;
+    progress.finish();
+    checkpoint.flush()?;
+    drop(checkpoint);
 
-        let progress = ProgressBar::new(file_count as u64);
-        progress.set_style(
-            indicatif::ProgressStyle::default_bar().template("{elapsed} {wide_bar} {percent}%")?,
-        );
+    let small_files = file_count - big_files;
+    let big_files_percentage = (big_files as f64 / file_count as f64) * 100.0;
 
-        let mut hash_map: HashMap<Hash, (u32, String, u32)> = std::collections::HashMap::new();
-        let mut clone_map: HashMap<String, String> = HashMap::new();
-        let mut big_files: usize = 0;
-
-        // Writes received messages to the log file.
-        // The order is therefore non-deterministic although the list of projects is.
-        while let Ok(msg_opt) = rx.recv() {
-            match msg_opt {
-                Some(msg) => {
-                    let (new_idx, new_name, opt_hash) = msg?;
-                    match opt_hash {
-                        None => {
-                            big_files += 1;
-                        }
-                        Some(hash) => {
-                            let (original_idx, original_name, count) = match hash_map.get(&hash) {
-                                Some((idx, orig_name, cnt)) => (*idx, orig_name.clone(), *cnt),
-                                None => (new_idx, new_name.to_string(), 0),
-                            };
-                            hash_map.insert(hash, (original_idx, original_name.clone(), count + 1));
-                            clone_map.insert(new_name, original_name);
-                            progress.inc(1);
-                        }
-                    }
-                }
-                None => {
-                    // When a None message is received, the sender thread is considered finished.
-                    // When all threads are finished, the main thread can exit.
-                    ended_threads += 1;
-                    if ended_threads == threads {
-                        break;
-                    }
+    info!(
+        "Ignored large files: {} / {:.2} %",
+        big_files, big_files_percentage
+    );
+    info!(
+        "Remaining files: {} / {:.2} %",
+        small_files,
+        100.0 - big_files_percentage
+    );
+
+    // Each group of duplicates is represented by its first file in the input, which gets the size of the group as count.
+    // The mapping of the files to their representative, and their clusters, are written group by group.
+    files.as_single_chunk();
+    let names: &StringChunked = files.column(input_header)?.str()?;
+    let name = |idx: u32| names.get(idx as usize).unwrap_or_default();
+    let mut counts: Vec<u32> = vec![0; file_count];
+    let mut unique_files: usize = 0;
+    logger.run_task(format!("Writing to {map_path}"), || {
+        let mut map_file: CSVFile = CSVFile::new(map_path, FileMode::Overwrite)?;
+        map_file.write_header(&["name", "original"])?;
+        let mut clusters_file: Option<CSVFile> = clusters_path
+            .map(|path| CSVFile::new(path, FileMode::Overwrite))
+            .transpose()?;
+        if let Some(clusters_file) = clusters_file.as_mut() {
+            clusters_file.write_header(&CLUSTERS_FILE_HEADERS)?;
+        }
+        partitions.groups(|group| {
+            let original: u32 = group.iter().map(|(_, idx)| *idx).min().unwrap_or_default();
+            counts[original as usize] = group.len() as u32;
+            unique_files += 1;
+            // The hash identifies the cluster across shards and runs.
+            let cluster_id: String = hex::encode(group[0].0);
+            for (_, idx) in group {
+                writeln!(
+                    map_file,
+                    "{},{}",
+                    Quoting::Rfc4180.escape(name(*idx)),
+                    Quoting::Rfc4180.escape(name(original))
+                )?;
+                if let Some(clusters_file) = clusters_file.as_mut() {
+                    writeln!(
+                        clusters_file,
+                        "{},{cluster_id},{},{}",
+                        Quoting::Rfc4180.escape(name(*idx)),
+                        group.len(),
+                        Quoting::Rfc4180.escape(name(original))
+                    )?;
                 }
             }
+            Ok(())
+        })?;
+        map_file.flush()?;
+        if let Some(mut clusters_file) = clusters_file {
+            clusters_file.flush()?;
         }
-        progress.finish();
+        Ok(())
+    })?;
+    delete_dir(&partitions_dir, true)?;
 
-        let small_files = file_count - big_files;
-        let big_files_percentage = (big_files as f64 / file_count as f64) * 100.0;
+    let unique_file_percentage = (unique_files as f64 / small_files as f64) * 100.0;
 
-        info!(
-            "Ignored large files: {} / {:.2} %",
-            big_files, big_files_percentage
-        );
-        info!(
-            "Remaining files: {} / {:.2} %",
-            small_files,
-            100.0 - big_files_percentage
-        );
+    info!(
+        "Unique files: {} / {:.2} %",
+        unique_files, unique_file_percentage
+    );
+    info!(
+        "Duplicate files: {} / {:.2} %",
+        small_files - unique_files,
+        100.0 - unique_file_percentage
+    );
 
-        let unique_files = hash_map.len();
-        let unique_file_percentage = (unique_files as f64 / small_files as f64) * 100.0;
+    let most_duplicated_file: u32 = *counts
+        .iter()
+        .max()
+        .with_context(|| "Empty column 'count'")?;
+    let most_duplicated_file_percentage =
+        (most_duplicated_file as f64 / small_files as f64) * 100.0;
 
-        info!(
-            "Unique files: {} / {:.2} %",
-            unique_files, unique_file_percentage
-        );
-        info!(
-            "Duplicate files: {} / {:.2} %",
-            small_files - unique_files,
-            100.0 - unique_file_percentage
-        );
+    info!(
+        "Most duplicated file: {} times / {:.2} %",
+        most_duplicated_file, most_duplicated_file_percentage
+    );
 
-        let clusters_column: (Vec<String>, Vec<u32>) =
-            hash_map.values().map(|v| (v.1.clone(), v.2)).unzip();
+    let mask: BooleanChunked = counts.iter().map(|count| *count > 0).collect();
+    files.with_column(polars::prelude::Column::new("count".into(), counts))?;
+    let output_df: DataFrame = files.filter(&mask)?;
 
-        let clusters = DataFrame::new(vec![
-            polars::prelude::Column::new("name".into(), clusters_column.0),
-            polars::prelude::Column::new("count".into(), clusters_column.1),
-        ])?;
+    let mut output_df: DataFrame = output_columns.apply(output_df)?;
+    log_write_output(logger, output_path, &mut output_df, false)?;
+    delete_file(&checkpoint_path, false)
+}
 
-        let map_columns: (Vec<String>, Vec<String>) = clone_map
-            .into_iter()
-            .map(|(k, v)| (k.to_string(), v.to_string()))
-            .unzip();
+/// Reads the hashes written to the checkpoint file by an interrupted run.
+///
+/// # Arguments
+///
+/// * `checkpoint_path` - The path to the checkpoint file, which may not exist.
+///
+/// # Returns
+///
+/// The hash of every file already processed, by path, or None for the files too large to be hashed.
+fn read_checkpoint(checkpoint_path: &str) -> Result<HashMap<String, Option<[u8; 32]>>> {
+    if !Path::new(checkpoint_path).exists() {
+        return Ok(HashMap::new());
+    }
+    let rows: Vec<(String, Option<[u8; 32]>)> = CSVFile::new(checkpoint_path, FileMode::Read)?
+        .extract(|line, record| {
+            let name: &str = record
+                .get(0)
+                .with_context(|| format!("Missing path at line {line} of {checkpoint_path}"))?;
+            let hash: Option<[u8; 32]> = match record.get(1).unwrap_or_default() {
+                "" => None,
+                hash => Some(
+                    hex::decode(hash)
+                        .ok()
+                        .and_then(|bytes| bytes.try_into().ok())
+                        .with_context(|| {
+                            format!("Invalid hash at line {line} of {checkpoint_path}")
+                        })?,
+                ),
+            };
+            Ok((name.to_string(), hash))
+        })?;
+    Ok(rows.into_iter().collect())
+}
 
-        let mut map_df = DataFrame::new(vec![
-            polars::prelude::Column::new("name".into(), map_columns.0),
-            polars::prelude::Column::new("original".into(), map_columns.1),
-        ])?;
+/// Columns of the checkpoint file storing the hashes computed so far.
+const CHECKPOINT_FILE_HEADERS: [&str; 2] = ["name", "hash"];
+
+/// Columns of the clusters file.
+const CLUSTERS_FILE_HEADERS: [&str; 4] = ["name", "cluster_id", "cluster_size", "representative"];
+
+/// A hash of a file and the index of the file in the input.
+type HashRecord = ([u8; 32], u32);
+
+/// Size in bytes of a record stored in memory or in a partition file.
+const RECORD_SIZE: usize = 36;
+
+/// Maximal number of hashes waiting to be collected by the main thread.
+const CHANNEL_CAPACITY: usize = 1 << 16;
+
+/// Maximal number of partitions, each of them having its own file.
+const MAX_PARTITIONS: usize = 4096;
+
+/// Hashes of files split by their value into partitions, which are spilled to files
+/// in a temporary directory when they do not fit in memory together.
+/// All the copies of a hash are in the same partition, so that each partition can be grouped independently.
+struct HashPartitions {
+    /// Directory of the partition files.
+    dir: String,
+    /// Records of each partition not yet written to its file.
+    buffers: Vec<Vec<HashRecord>>,
+    /// Number of records in the buffers.
+    buffered: usize,
+    /// Maximal number of records in the buffers.
+    capacity: usize,
+    /// Whether the partitions were written to files.
+    spilled: bool,
+}
 
-        let most_duplicated_file: u32 = *u32(&clusters, "count")?
-            .iter()
-            .max()
-            .with_context(|| "Empty column 'count'")?;
-        let most_duplicated_file_percentage =
-            (most_duplicated_file as f64 / small_files as f64) * 100.0;
+impl HashPartitions {
+    /// Creates empty partitions for a number of files such that each partition fits within the memory limit.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The directory of the partition files, created if the partitions are spilled.
+    /// * `file_count` - The number of files to hash.
+    /// * `memory_limit` - The memory available for the hashes, in bytes.
+    fn new(dir: &str, file_count: usize, memory_limit: u64) -> Self {
+        let capacity: usize = (memory_limit as usize / RECORD_SIZE).max(1);
+        let partitions: usize = file_count.div_ceil(capacity).clamp(1, MAX_PARTITIONS);
+        if file_count.div_ceil(capacity) > MAX_PARTITIONS {
+            warn!(
+                "The memory limit is too small for {file_count} files, hashes are split into {MAX_PARTITIONS} partitions."
+            );
+        }
+        Self {
+            dir: dir.to_string(),
+            buffers: vec![Vec::new(); partitions],
+            buffered: 0,
+            capacity,
+            spilled: false,
+        }
+    }
 
-        info!(
-            "Most duplicated file: {} times / {:.2} %",
-            most_duplicated_file, most_duplicated_file_percentage
-        );
+    /// Returns the number of partitions.
+    fn len(&self) -> usize {
+        self.buffers.len()
+    }
 
-        log_write_output(logger, map_path, &mut map_df, false)?;
+    /// Returns the path of the file of a partition.
+    fn path(&self, partition: usize) -> String {
+        format!("{}/{partition}.bin", self.dir)
+    }
 
-        let mut output_df = files.join(
-            &clusters,
-            ["name"],
-            ["name"],
-            polars::prelude::JoinType::Inner.into(),
-            None,
-        )?;
+    /// Adds the hash of a file to its partition, and writes all the partitions to their files when the memory limit is reached.
+    ///
+    /// # Arguments
+    ///
+    /// * `hash` - The hash of the file.
+    /// * `idx` - The index of the file in the input.
+    fn push(&mut self, hash: [u8; 32], idx: u32) -> Result<()> {
+        let prefix: [u8; 8] = hash[..8].try_into()?;
+        let partition: usize = (u64::from_le_bytes(prefix) % self.len() as u64) as usize;
+        self.buffers[partition].push((hash, idx));
+        self.buffered += 1;
+        if self.buffered >= self.capacity {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    /// Appends the buffered records of every partition to its file.
+    fn spill(&mut self) -> Result<()> {
+        if !self.spilled {
+            create_dir(&self.dir)?;
+            self.spilled = true;
+        }
+        for partition in 0..self.len() {
+            let records: Vec<HashRecord> = std::mem::take(&mut self.buffers[partition]);
+            if records.is_empty() {
+                continue;
+            }
+            let mut writer = BufWriter::new(open_file(self.path(partition), FileMode::Append)?);
+            for (hash, idx) in records {
+                writer.write_all(&hash)?;
+                writer.write_all(&idx.to_le_bytes())?;
+            }
+            writer.flush()?;
+        }
+        self.buffered = 0;
+        Ok(())
+    }
+
+    /// Calls a function on every group of files with the same hash, one partition at a time.
+    /// The records of a group are sorted by index.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The function called on each group.
+    fn groups(mut self, mut f: impl FnMut(&[HashRecord]) -> Result<()>) -> Result<()> {
+        if self.spilled {
+            self.spill()?;
+        }
+        for partition in 0..self.len() {
+            let mut records: Vec<HashRecord> = if self.spilled {
+                let path: String = self.path(partition);
+                if Path::new(&path).exists() {
+                    std::fs::read(&path)?
+                        .chunks_exact(RECORD_SIZE)
+                        .map(|record| {
+                            let hash: [u8; 32] = record[..32].try_into()?;
+                            let idx: [u8; 4] = record[32..].try_into()?;
+                            anyhow::Ok((hash, u32::from_le_bytes(idx)))
+                        })
+                        .collect::<Result<_>>()?
+                } else {
+                    Vec::new()
+                }
+            } else {
+                std::mem::take(&mut self.buffers[partition])
+            };
+            records.sort_unstable();
+            for group in records.chunk_by(|a, b| a.0 == b.0) {
+                f(group)?;
+            }
+        }
+        Ok(())
+    }
+}
 
-        log_write_output(logger, output_path, &mut output_df, false)
-    })
-    .map_err(|e| anyhow!("Error in child thread: {e:?}"))??;
+/// Parses a size in bytes, optionally followed by the binary unit K, M, G or T.
+///
+/// # Arguments
+///
+/// * `s` - The size, e.g. 512M or 48G.
+pub(crate) fn parse_size(s: &str) -> Result<u64, String> {
+    let s: &str = s.trim();
+    let (number, unit): (&str, u64) = match s.char_indices().last() {
+        Some((i, 'K' | 'k')) => (&s[..i], 1 << 10),
+        Some((i, 'M' | 'm')) => (&s[..i], 1 << 20),
+        Some((i, 'G' | 'g')) => (&s[..i], 1 << 30),
+        Some((i, 'T' | 't')) => (&s[..i], 1 << 40),
+        _ => (s, 1),
+    };
+    number
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(unit))
+        .filter(|n| *n > 0)
+        .ok_or_else(|| format!("invalid size '{s}', expected e.g. 512M or 48G"))
+}
 
-    Ok(())
+/// Hashes the content of a file according to a similarity criterion.
+///
+/// # Arguments
+///
+/// * `content` - The content of the file.
+/// * `similarity` - The similarity criterion, either "exact" or "bow" (invariant to token order and whitespaces).
+/// * `word_matcher` - The matcher splitting the content into words for the "bow" criterion.
+pub(crate) fn content_hash(content: &[u8], similarity: &str, word_matcher: &Matcher) -> Hash {
+    if similarity == "exact" {
+        blake3::hash(content)
+    } else {
+        blake3::hash(&word_matcher.bag_of_words(content).serialize())
+    }
 }
 
 #[cfg(test)]
@@ -321,19 +614,25 @@ mod tests {
 
     const TEST_DATA: &str = "tests/data/phases/duplicate_files/";
 
-    fn test_duplicate_files(input_path: &str, similarity: &str) -> Result<()> {
+    fn test_duplicate_files(input_path: &str, similarity: &str, memory_limit: u64) -> Result<()> {
         let default_output_path = format!("{input_path}.unique.csv");
         let default_map_path = format!("{input_path}.duplicates_map.csv");
+        let clusters_path = format!("{input_path}.clusters.csv");
         delete_file(&default_output_path, true)?;
         delete_file(&default_map_path, true)?;
+        delete_file(&clusters_path, true)?;
         run(
             input_path,
             None,
             None,
+            Some(&clusters_path),
             false,
             similarity,
             1,
             "name",
+            None,
+            memory_limit,
+            &OutputColumns::default(),
             test_logger(),
         )?;
 
@@ -345,6 +644,40 @@ mod tests {
         let sorted_output_df = output_df.sort(vec!["name"], SortMultipleOptions::new())?;
         assert_eq!(sorted_expected_df, sorted_output_df);
 
+        // Every file of the map is in the cluster of its original, whose size is its count.
+        let clusters_df =
+            open_csv(&clusters_path, None, None)?.sort(vec!["name"], SortMultipleOptions::new())?;
+        let map_df = open_csv(&default_map_path, None, None)?
+            .sort(vec!["name"], SortMultipleOptions::new())?;
+        assert_eq!(clusters_df.get_column_names(), CLUSTERS_FILE_HEADERS);
+        assert_eq!(clusters_df.column("name")?, map_df.column("name")?);
+        let representatives = dataframes::str(&clusters_df, "representative")?;
+        assert_eq!(representatives, dataframes::str(&map_df, "original")?);
+        let counts: std::collections::HashMap<&str, i64> = dataframes::str(&output_df, "name")?
+            .into_iter()
+            .zip(output_df.column("count")?.i64()?.into_no_null_iter())
+            .collect();
+        let mut cluster_ids: std::collections::HashMap<&str, &str> =
+            std::collections::HashMap::new();
+        for ((representative, size), cluster_id) in representatives
+            .iter()
+            .zip(
+                clusters_df
+                    .column("cluster_size")?
+                    .i64()?
+                    .into_no_null_iter(),
+            )
+            .zip(dataframes::str(&clusters_df, "cluster_id")?)
+        {
+            assert_eq!(counts[representative], size);
+            assert_eq!(
+                *cluster_ids.entry(representative).or_insert(cluster_id),
+                cluster_id
+            );
+        }
+        assert_eq!(cluster_ids.len(), output_df.height());
+        delete_file(&clusters_path, false)?;
+
         delete_file(&default_output_path, false)?;
 
         let expected_map = open_csv(&format!("{default_map_path}.expected"), None, None)?;
@@ -363,7 +696,137 @@ mod tests {
 
     #[test]
     fn exact_files() -> Result<()> {
-        test_duplicate_files(&format!("{TEST_DATA}/duplicate_files.csv"), "exact")?;
-        test_duplicate_files(&format!("{TEST_DATA}/duplicate_files_bow.csv"), "bow")
+        test_duplicate_files(
+            &format!("{TEST_DATA}/duplicate_files.csv"),
+            "exact",
+            1 << 30,
+        )?;
+        test_duplicate_files(
+            &format!("{TEST_DATA}/duplicate_files_bow.csv"),
+            "bow",
+            1 << 30,
+        )?;
+
+        // Room for two hashes, so that the hashes are spilled to disk.
+        let memory_limit: u64 = 2 * RECORD_SIZE as u64;
+        test_duplicate_files(
+            &format!("{TEST_DATA}/duplicate_files.csv"),
+            "exact",
+            memory_limit,
+        )?;
+        test_duplicate_files(
+            &format!("{TEST_DATA}/duplicate_files_bow.csv"),
+            "bow",
+            memory_limit,
+        )
+    }
+
+    #[test]
+    fn resume_test() -> Result<()> {
+        let dir = "target/tests/duplicate_files_resume";
+        let output_path = format!("{dir}/unique.csv");
+        let map_path = format!("{dir}/map.csv");
+        let checkpoint_path = format!("{output_path}.exact.hashes.csv");
+        delete_dir(dir, true)?;
+
+        // An interrupted run hashed foo.java like its clone, ignored c_float.json and was stopped while writing a row.
+        let file = |name: &str| format!("{TEST_DATA}files/{name}");
+        let clone_hash: Hash = blake3::hash(&std::fs::read(file("foo_clone.java"))?);
+        write_file(
+            &checkpoint_path,
+            format!(
+                "name,hash\n{},{clone_hash}\n{},\n{},0123",
+                file("foo.java"),
+                file("c_float.json"),
+                file("empty.c")
+            ),
+        )?;
+        // It also spilled the hash of the first file to a partition.
+        create_dir(format!("{output_path}.partitions"))?;
+        write_file(
+            format!("{output_path}.partitions/0.bin"),
+            [[0; 32].as_slice(), &0u32.to_le_bytes()].concat(),
+        )?;
+        run(
+            &format!("{TEST_DATA}/duplicate_files.csv"),
+            Some(&output_path),
+            Some(&map_path),
+            None,
+            false,
+            "exact",
+            2,
+            "name",
+            None,
+            // Room for two hashes, so that the hashes are spilled to disk.
+            2 * RECORD_SIZE as u64,
+            &OutputColumns::default(),
+            test_logger(),
+        )?;
+
+        let map_df = open_csv(&map_path, None, None)?;
+        // The partitions left by the interrupted run are not merged with the new ones.
+        let names: Vec<&str> = dataframes::str(&map_df, "name")?;
+        assert_eq!(
+            names.iter().collect::<std::collections::HashSet<_>>().len(),
+            names.len()
+        );
+        ensure!(!Path::new(&format!("{output_path}.partitions")).exists());
+        let originals: std::collections::HashMap<&str, &str> = dataframes::str(&map_df, "name")?
+            .into_iter()
+            .zip(dataframes::str(&map_df, "original")?)
+            .collect();
+        // The hashes of the checkpoint file are used instead of hashing the files again.
+        assert_eq!(originals[file("foo_clone.java").as_str()], file("foo.java"));
+        assert!(!originals.contains_key(file("c_float.json").as_str()));
+        assert_eq!(
+            originals[file("c_float.copy").as_str()],
+            file("c_float.copy")
+        );
+        assert_eq!(originals[file("empty.c").as_str()], file("empty.java"));
+        ensure!(!Path::new(&checkpoint_path).exists());
+
+        delete_dir(dir, false)
+    }
+
+    #[test]
+    fn hash_partitions_test() -> Result<()> {
+        let dir = "target/tests/hash_partitions";
+        delete_dir(dir, true)?;
+        let records: Vec<HashRecord> = (0..100)
+            .map(|idx| (*blake3::hash(&[(idx % 7) as u8]).as_bytes(), idx))
+            .collect();
+        let mut in_memory = HashPartitions::new(dir, records.len(), 1 << 20);
+        let mut spilled = HashPartitions::new(dir, records.len(), 10 * RECORD_SIZE as u64);
+        assert_eq!(in_memory.len(), 1);
+        assert_eq!(spilled.len(), 10);
+        for (hash, idx) in records.iter().rev() {
+            in_memory.push(*hash, *idx)?;
+            spilled.push(*hash, *idx)?;
+        }
+
+        let collect = |partitions: HashPartitions| -> Result<Vec<Vec<u32>>> {
+            let mut groups: Vec<Vec<u32>> = Vec::new();
+            partitions.groups(|group| {
+                groups.push(group.iter().map(|(_, idx)| *idx).collect());
+                Ok(())
+            })?;
+            groups.sort();
+            Ok(groups)
+        };
+        let expected: Vec<Vec<u32>> = (0..7).map(|r| (r..100).step_by(7).collect()).collect();
+        assert_eq!(collect(in_memory)?, expected);
+        assert_eq!(collect(spilled)?, expected);
+        delete_dir(dir, false)
+    }
+
+    #[test]
+    fn parse_size_test() {
+        assert_eq!(parse_size("512"), Ok(512));
+        assert_eq!(parse_size("4K"), Ok(4096));
+        assert_eq!(parse_size("48G"), Ok(48 << 30));
+        assert_eq!(parse_size("1t"), Ok(1 << 40));
+        assert!(parse_size("0").is_err());
+        assert!(parse_size("G").is_err());
+        assert!(parse_size("-1M").is_err());
     }
 }
@@ -13,11 +13,14 @@
 // limitations under the License.
 
 #![doc = include_str!("../docs/duplicate_ids.md")]
-use anyhow::Result;
+use anyhow::{bail, ensure, Context, Result};
 use clap::{Arg, ArgAction, Command};
-use polars::frame::DataFrame;
+use polars::frame::{DataFrame, UniqueKeepStrategy};
+use polars::prelude::{Schema, SortMultipleOptions};
 use tracing::info;
 
+use crate::utils::csv::validate_columns;
+use crate::utils::dataframes::{has_column, output_column_args, OutputColumns};
 use crate::utils::fs::*;
 use crate::utils::logger::log_write_output;
 use crate::utils::logger::{log_output_file, Logger};
@@ -25,7 +28,7 @@ use crate::utils::logger::{log_output_file, Logger};
 /// Command line arguments parsing.
 pub fn cli() -> Command {
     Command::new("duplicate_ids")
-        .about("Discards duplicates in a CSV file according to one of the columns (by default repositories ids).")
+        .about("Discards duplicates in a CSV file according to one or several columns (by default repositories ids).")
         .long_about(include_str!("../docs/duplicate_ids.md"))
         .disable_version_flag(true)
         .arg(
@@ -50,9 +53,20 @@ pub fn cli() -> Command {
                 .short('c')
                 .long("column")
                 .value_name("COLUMN_NAME")
-                .help("Name of the column to check for duplicates.")
+                .help("Name of the column to check for duplicates. \
+                       Can be repeated or given as a comma-separated list, in which case two rows are duplicates if they share the values of all the columns.")
+                .action(ArgAction::Append)
+                .value_delimiter(',')
                 .default_value("id"),
         )
+        .arg(
+            Arg::new("keep")
+                .long("keep")
+                .value_name("POLICY")
+                .help("Row kept among duplicates: 'first', 'last', or 'max:COLUMN_NAME' to keep the row with the largest value in the given column, \
+                       such as the latest commit timestamp. Ties are resolved by keeping the first row.")
+                .default_value("first"),
+        )
         .arg(
             Arg::new("force")
                 .short('f')
@@ -68,6 +82,34 @@ pub fn cli() -> Command {
                 .action(ArgAction::SetTrue)
                 .conflicts_with_all(vec!["output", "force"]),
         )
+        .args(output_column_args())
+}
+
+/// Row kept among a set of duplicates.
+#[derive(Debug, PartialEq)]
+enum KeepPolicy<'a> {
+    /// First row in the file.
+    First,
+    /// Last row in the file.
+    Last,
+    /// Row with the largest value in a column.
+    Max(&'a str),
+}
+
+impl<'a> KeepPolicy<'a> {
+    /// Parses a policy of the form 'first', 'last' or 'max:COLUMN_NAME'.
+    fn parse(policy: &'a str) -> Result<Self> {
+        match policy {
+            "first" => Ok(KeepPolicy::First),
+            "last" => Ok(KeepPolicy::Last),
+            _ => match policy.strip_prefix("max:") {
+                Some(column) if !column.is_empty() => Ok(KeepPolicy::Max(column)),
+                _ => bail!(
+                    "Invalid keep policy '{policy}', expected 'first', 'last' or 'max:COLUMN_NAME'"
+                ),
+            },
+        }
+    }
 }
 
 /// Discards duplicate entries from a CSV file.
@@ -76,9 +118,11 @@ pub fn cli() -> Command {
 ///
 /// * `input_path` - The path to the input CSV file.
 /// * `output_path` - The optional path to the output CSV file. Defaults to the input path with ".unique.csv" appended.
-/// * `column` - The name of the column to check for duplicates.
+/// * `columns` - The names of the columns whose values identify duplicates.
+/// * `keep` - The policy choosing the row kept among duplicates: 'first', 'last' or 'max:COLUMN_NAME'.
 /// * `force` - Whether to override the output file if it already exists.
 /// * `no_output` - Whether to skip writing the output file.
+/// * `output_columns` - The columns written to the output file and their new names.
 /// * `logger` - The logger displaying the progress.
 ///
 /// # Returns
@@ -87,29 +131,31 @@ pub fn cli() -> Command {
 pub fn run(
     input_path: &str,
     output_path: Option<&str>,
-    column: &str,
+    columns: &[&str],
+    keep: &str,
     force: bool,
     no_output: bool,
+    output_columns: &OutputColumns,
     logger: &Logger,
 ) -> Result<()> {
+    let keep: KeepPolicy = KeepPolicy::parse(keep)?;
     let default_output_path = format!("{input_path}.unique.csv");
     let output_path = output_path.unwrap_or(&default_output_path);
+    logger.record_inputs(&[input_path]);
+    if !no_output {
+        logger.record_outputs(&[output_path]);
+    }
 
     check_path(input_path)?;
     log_output_file(output_path, no_output, force)?;
 
+    validate_columns(input_path, columns, &Schema::default())?;
     let mut ids: DataFrame = open_csv(input_path, None, None)?;
     let ids_count: usize = ids.height();
 
     info!("{} entries found in the file.", ids_count);
 
-    // Keeping first occurrence of each id.
-    // Unique stable is used to ensure reproducibility.
-    ids = ids.unique_stable(
-        Some(&[column.to_string()]),
-        polars::frame::UniqueKeepStrategy::First,
-        None,
-    )?;
+    ids = unique_rows(ids, columns, &keep)?;
     let unique_ids_count: usize = ids.height();
     let unique_ids_percentage: f64 = (unique_ids_count as f64 / ids_count as f64) * 100.0;
 
@@ -123,9 +169,51 @@ pub fn run(
         100.0 - unique_ids_percentage
     );
 
+    let mut ids: DataFrame = output_columns.apply(ids)?;
     log_write_output(logger, output_path, &mut ids, no_output)
 }
 
+/// Keeps a single row among the rows sharing the same values in the given columns.
+/// Unique stable is used to ensure reproducibility: the kept rows stay in the order of the input file.
+///
+/// # Arguments
+///
+/// * `df` - The rows to deduplicate.
+/// * `columns` - The names of the columns whose values identify duplicates.
+/// * `keep` - The policy choosing the row kept among duplicates.
+///
+/// # Returns
+///
+/// The deduplicated rows.
+fn unique_rows(df: DataFrame, columns: &[&str], keep: &KeepPolicy) -> Result<DataFrame> {
+    let subset: Vec<String> = columns.iter().map(|c| c.to_string()).collect();
+    match keep {
+        KeepPolicy::First => {
+            Ok(df.unique_stable(Some(&subset), UniqueKeepStrategy::First, None)?)
+        }
+        KeepPolicy::Last => Ok(df.unique_stable(Some(&subset), UniqueKeepStrategy::Last, None)?),
+        KeepPolicy::Max(column) => {
+            ensure!(
+                has_column(&df, column),
+                "Input file does not contain column '{column}'."
+            );
+            // The first row of each group in the sorted order has the largest value.
+            df.with_row_index("row_index".into(), None)?
+                .sort(
+                    [*column],
+                    SortMultipleOptions::default()
+                        .with_order_descending(true)
+                        .with_nulls_last(true)
+                        .with_maintain_order(true),
+                )?
+                .unique_stable(Some(&subset), UniqueKeepStrategy::First, None)?
+                .sort(["row_index"], SortMultipleOptions::default())?
+                .drop("row_index")
+                .with_context(|| format!("Could not keep the rows with the largest '{column}'"))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -140,7 +228,16 @@ mod tests {
         let default_output_path = format!("{input_path}.unique.csv");
 
         delete_file(&default_output_path, true)?;
-        run(&input_path, None, "id", false, false, test_logger())?;
+        run(
+            &input_path,
+            None,
+            &["id"],
+            "first",
+            false,
+            false,
+            &OutputColumns::default(),
+            test_logger(),
+        )?;
 
         let expected_output_path = format!("{default_output_path}.expected");
         let expected_df = open_csv(&expected_output_path, None, None)?;
@@ -150,4 +247,45 @@ mod tests {
 
         delete_file(&default_output_path, false)
     }
+
+    #[test]
+    fn test_composite_keys() -> Result<()> {
+        let input_path = format!("{TEST_DATA}/commits.csv");
+        for (keep, suffix) in [("last", "last"), ("max:latest_commit", "max")] {
+            let output_path = format!("{input_path}.{suffix}.csv");
+
+            delete_file(&output_path, true)?;
+            run(
+                &input_path,
+                Some(&output_path),
+                &["id", "name"],
+                keep,
+                false,
+                false,
+                &OutputColumns::default(),
+                test_logger(),
+            )?;
+
+            let expected_df = open_csv(&format!("{output_path}.expected"), None, None)?;
+            let output_df = open_csv(&output_path, None, None)?;
+            assert_eq!(expected_df, output_df);
+
+            delete_file(&output_path, false)?;
+        }
+
+        assert!(KeepPolicy::parse("max:").is_err());
+        assert!(KeepPolicy::parse("min:id").is_err());
+        assert!(run(
+            &input_path,
+            None,
+            &["id", "missing"],
+            "first",
+            false,
+            true,
+            &OutputColumns::default(),
+            test_logger()
+        )
+        .is_err());
+        Ok(())
+    }
 }
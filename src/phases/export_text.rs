@@ -0,0 +1,324 @@
+// Copyright 2026 Andrea Gilot
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![doc = include_str!("../docs/export_text.md")]
+
+use std::collections::HashMap;
+use std::io::{BufWriter, Write};
+
+use anyhow::{Context, Result};
+use clap::{Arg, ArgAction, Command};
+use polars::prelude::Schema;
+use tracing::{info, warn};
+use tree_sitter::{Node, Parser};
+
+use crate::phases::parse::{language_to_grammar, Grammar};
+use crate::utils::csv::{revert_placeholders, validate_columns};
+use crate::utils::fs::*;
+use crate::utils::logger::{log_output_file, Logger};
+use crate::utils::progress::Progress;
+
+/// Columns of the functions file copied to the output file.
+const COLUMNS: [&str; 4] = ["id", "path", "name", "language"];
+
+/// Command line arguments parsing.
+pub fn cli() -> Command {
+    Command::new("export_text")
+        .about("Exports the code of the functions extracted by the parse command into a single JSON Lines file.")
+        .long_about(include_str!("../docs/export_text.md"))
+        .disable_version_flag(true)
+        .arg(
+            Arg::new("input")
+                .short('i')
+                .long("input")
+                .value_name("FUNCTIONS_FILE.csv")
+                .help("Path to the csv file listing the functions produced by the parse command.")
+                .required(true),
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .value_name("OUTPUT_FILE.jsonl")
+                .help("Path to the output JSON Lines file. By default, the suffix '.jsonl' is appended to the input path.")
+                .required(false),
+        )
+        .arg(
+            Arg::new("strip-comments")
+                .long("strip-comments")
+                .help("Remove the comments from the code of the functions. \
+                       The code of the functions written in a language without grammar is exported unchanged.")
+                .default_value("false")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("force")
+                .short('f')
+                .long("force")
+                .help("Override the output file if it already exists.")
+                .default_value("false")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+/// Writes the code of every function of the functions file as a JSON object on its own line.
+///
+/// # Arguments
+///
+/// * `input_path` - The path to the functions file produced by the parse command.
+/// * `output_path` - The optional path to the output file. Defaults to the input path with ".jsonl" appended.
+/// * `strip_comments` - Whether to remove the comments from the code of the functions.
+/// * `force` - Whether to override the output file if it already exists.
+/// * `logger` - The logger displaying the progress.
+///
+/// # Returns
+///
+/// A result indicating success or failure of the operation.
+pub fn run(
+    input_path: &str,
+    output_path: Option<&str>,
+    strip_comments: bool,
+    force: bool,
+    logger: &Logger,
+) -> Result<()> {
+    let default_output_path: String = format!("{input_path}.jsonl");
+    let output_path: &str = output_path.unwrap_or(&default_output_path);
+    logger.record_inputs(&[input_path]);
+    logger.record_outputs(&[output_path]);
+
+    check_path(input_path)?;
+    log_output_file(output_path, false, force)?;
+    validate_columns(input_path, &COLUMNS, &Schema::default())?;
+
+    let mut reader = csv::Reader::from_reader(open_file(input_path, FileMode::Read)?);
+    let header: Vec<String> = reader.headers()?.iter().map(|h| h.to_string()).collect();
+    // Safe unwrap: the columns were validated above.
+    let [id_idx, path_idx, name_idx, language_idx] =
+        COLUMNS.map(|c| header.iter().position(|h| h == c).unwrap());
+    let rows: u64 = logger.run_task("Counting functions", || {
+        Ok(
+            csv::Reader::from_reader(open_file(input_path, FileMode::Read)?)
+                .byte_records()
+                .count() as u64,
+        )
+    })?;
+
+    let mut output_file = BufWriter::new(open_file(output_path, FileMode::Overwrite)?);
+
+    let progress_bar: Progress = logger.progress_bar(Some(rows))?;
+
+    let mut parser: Parser = Parser::new();
+    let mut grammars: HashMap<String, Option<Grammar>> = HashMap::new();
+    let mut exported: u64 = 0;
+    let mut skipped: u64 = 0;
+    for (i, record) in reader.records().enumerate() {
+        progress_bar.inc(1);
+        let record = record.with_context(|| format!("Could not parse row {i} of {input_path}"))?;
+        let path: String = revert_placeholders(&record[path_idx]);
+        if path.is_empty() || path == "error" {
+            skipped += 1;
+            progress_bar.fail();
+            continue;
+        }
+
+        let code: Vec<u8> = match load_file(&path, 1024 * 1024 * 1024) {
+            Ok(Ok(code)) => code,
+            Ok(Err(size)) => {
+                warn!("File {path} is too large ({size} bytes) and was skipped");
+                skipped += 1;
+                progress_bar.fail();
+                continue;
+            }
+            Err(e) => {
+                warn!("{e:#}");
+                skipped += 1;
+                progress_bar.fail();
+                continue;
+            }
+        };
+        let language: &str = &record[language_idx];
+
+        let code: Vec<u8> = if strip_comments {
+            let grammar: &Option<Grammar> = grammars
+                .entry(language.to_string())
+                .or_insert_with(|| language_to_grammar(language));
+            match grammar {
+                Some(g) => {
+                    parser.set_language(&g.lang)?;
+                    match parser.parse(&code, None) {
+                        Some(tree) => remove_comments(&code, tree.root_node(), g),
+                        None => {
+                            warn!("Could not parse file {path}, its comments were kept");
+                            code
+                        }
+                    }
+                }
+                None => code,
+            }
+        } else {
+            code
+        };
+
+        let id: &str = &record[id_idx];
+        let function = json::object! {
+            id: id.parse::<u64>().with_context(|| format!("Invalid id {id} in row {i} of {input_path}"))?,
+            path: path,
+            name: revert_placeholders(&record[name_idx]),
+            language: language,
+            code: String::from_utf8_lossy(&code).as_ref(),
+        };
+        writeln!(output_file, "{}", function.dump())?;
+        exported += 1;
+    }
+    progress_bar.finish();
+    output_file.flush()?;
+
+    info!("  {exported} functions exported.");
+    logger.record_count("functions exported", exported);
+    if skipped > 0 {
+        info!("  {skipped} functions could not be read and were skipped.");
+        logger.record_count("functions skipped", skipped);
+    }
+    Ok(())
+}
+
+/// Removes the comments from a piece of code.
+/// The lines holding nothing but comments are removed entirely, as are the spaces before a comment ending a line,
+/// and a comment between two pieces of code is replaced with a single space.
+///
+/// # Arguments
+///
+/// * `code` - The code whose comments are removed.
+/// * `root` - The root of the syntax tree of the code.
+/// * `grammar` - The grammar of the language of the code.
+fn remove_comments(code: &[u8], root: Node, grammar: &Grammar) -> Vec<u8> {
+    // Byte ranges of the comments, in order of appearance.
+    let mut comments: Vec<(usize, usize)> = Vec::new();
+    let mut call_stack: Vec<Node> = vec![root];
+    while let Some(node) = call_stack.pop() {
+        if grammar.comment_nodes.contains(node.kind()) {
+            comments.push((node.start_byte(), node.end_byte()));
+        } else {
+            call_stack.extend(node.children(&mut node.walk()));
+        }
+    }
+    comments.sort_unstable();
+
+    let is_blank = |b: &u8| *b == b' ' || *b == b'\t' || *b == b'\r';
+    let mut stripped: Vec<u8> = Vec::with_capacity(code.len());
+    let mut cursor: usize = 0;
+    for (start, end) in comments {
+        // Spaces around the comment on the same line.
+        let before: usize = start
+            - code[cursor..start]
+                .iter()
+                .rev()
+                .take_while(|b| is_blank(b))
+                .count();
+        let after: usize = end + code[end..].iter().take_while(|b| is_blank(b)).count();
+        let line_start: bool = before == 0 || code[before - 1] == b'\n';
+        let line_end: bool = after == code.len() || code[after] == b'\n';
+        stripped.extend_from_slice(&code[cursor..before]);
+        cursor = if line_start && line_end {
+            (after + 1).min(code.len())
+        } else if line_end {
+            after
+        } else {
+            stripped.push(b' ');
+            after
+        };
+    }
+    stripped.extend_from_slice(&code[cursor..]);
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::logger::test_logger;
+
+    const TEST_DATA: &str = "tests/data/phases/export_text";
+
+    fn strip(code: &str, language: &str) -> String {
+        let grammar: Grammar = language_to_grammar(language).unwrap();
+        let mut parser: Parser = Parser::new();
+        parser.set_language(&grammar.lang).unwrap();
+        let tree = parser.parse(code, None).unwrap();
+        String::from_utf8(remove_comments(code.as_bytes(), tree.root_node(), &grammar)).unwrap()
+    }
+
+    #[test]
+    fn remove_comments_test() {
+        assert_eq!(
+            strip(
+                "int f(int a) {\n  // Doubles a.\n  return a /* twice */ * 2; // done\n}\n",
+                "c"
+            ),
+            "int f(int a) {\n  return a * 2;\n}\n"
+        );
+        // Strings looking like comments are kept.
+        assert_eq!(
+            strip(
+                "def f():\n    # Comment\n    return \"# not a comment\"\n",
+                "python"
+            ),
+            "def f():\n    return \"# not a comment\"\n"
+        );
+        assert_eq!(strip("int f();", "c"), "int f();");
+    }
+
+    #[test]
+    fn export_text_test() -> Result<()> {
+        let input_path: String = format!("{TEST_DATA}/functions.csv");
+        let output_path: String = format!("{input_path}.jsonl");
+
+        let exported = |strip_comments: bool| -> Result<Vec<json::JsonValue>> {
+            run(&input_path, None, strip_comments, true, test_logger())?;
+            file_lines(&output_path)?
+                .map(|line| Ok(json::parse(&line?)?))
+                .collect()
+        };
+
+        let functions: Vec<json::JsonValue> = exported(false)?;
+        // The row whose function could not be extracted is skipped.
+        assert_eq!(functions.len(), 2);
+        assert_eq!(functions[0]["id"], 1);
+        assert_eq!(
+            functions[0]["path"],
+            format!("{TEST_DATA}/src/add.c.functions/1-1")
+        );
+        assert_eq!(functions[0]["name"], "add");
+        assert_eq!(functions[0]["language"], "c");
+        assert_eq!(
+            functions[0]["code"],
+            "/* Adds \"two\" numbers. */\nint add(int a, int b) {\n\treturn a + b; // sum\n}\n"
+        );
+        // Compressed files are decompressed.
+        assert_eq!(functions[1]["id"], 2);
+        assert_eq!(functions[1]["name"], "square");
+        assert_eq!(
+            functions[1]["code"],
+            "def square(x):\n    # Squares x.\n    return x * x\n"
+        );
+
+        let functions: Vec<json::JsonValue> = exported(true)?;
+        assert_eq!(
+            functions[0]["code"],
+            "int add(int a, int b) {\n\treturn a + b;\n}\n"
+        );
+        assert_eq!(functions[1]["code"], "def square(x):\n    return x * x\n");
+
+        delete_file(&output_path, false)
+    }
+}
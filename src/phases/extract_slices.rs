@@ -0,0 +1,1002 @@
+// Copyright 2026 Andrea Gilot
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![doc = include_str!("../docs/extract_slices.md")]
+
+use std::collections::{HashMap, HashSet};
+use std::io::Write as _;
+use std::iter::FromIterator as _;
+use std::ops::Range;
+use std::path::Path;
+
+use anyhow::{bail, ensure, Context, Result};
+use clap::{Arg, ArgAction, Command};
+use polars::frame::DataFrame;
+use polars::prelude::{AnyValue, DataType, Field, Schema};
+use regex::Regex;
+use tracing::{info, warn};
+use tree_sitter::{Language, Node, Parser};
+use walkdir::WalkDir;
+
+use crate::utils::csv::*;
+use crate::utils::dataframes;
+use crate::utils::fs::*;
+use crate::utils::logger::Logger;
+use crate::utils::progress::Progress;
+
+/// Maximum number of declarations a slice can contain before giving up.
+const MAX_UNITS: usize = 1000;
+
+/// Command line arguments parsing.
+pub fn cli() -> Command {
+    Command::new("extract_slices")
+        .about("(Experimental) Extract single-file slices of Java, Kotlin and Scala functions with their project-local dependencies.")
+        .long_about(include_str!("../docs/extract_slices.md"))
+        .disable_version_flag(true)
+        .arg(
+            Arg::new("input")
+                .short('i')
+                .long("input")
+                .value_name("INPUT_FILE.csv")
+                .help("Path to the csv file storing the functions statistics produced by the parse command.")
+                .required(true),
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .value_name("OUTPUT_FILE.csv")
+                .help("Path to the output csv file storing the extracted slices. \
+                       By default, the name of the output file is the same as the input file with the suffix '.slices.csv'.")
+                .required(false),
+        )
+        .arg(
+            Arg::new("dest")
+                .short('d')
+                .long("dest")
+                .aliases(["target", "destination"])
+                .value_name("DESTINATION")
+                .help("Directory where the slices will be stored.")
+                .required(true),
+        )
+        .arg(
+            Arg::new("force")
+                .short('f')
+                .long("force")
+                .help("Overwrite the output file if it already exists.")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+/// Entry point of the program.
+///
+/// # Arguments
+///
+/// * `input_path` - Path to the functions statistics produced by the parse command.
+/// * `output_path` - Path to the output csv file. If not specified, the input file name will be used with ".slices.csv" appended.
+/// * `target` - Directory where the slices are written.
+/// * `force` - Whether to overwrite the output file if it already exists.
+/// * `logger` - The logger to use to display information about the progress of the program.
+pub fn run(
+    input_path: &str,
+    output_path: Option<&str>,
+    target: &str,
+    force: bool,
+    logger: &Logger,
+) -> Result<()> {
+    let input_file: DataFrame = logger.run_task("Loading input file", || {
+        let schema: Schema = Schema::from_iter(vec![
+            Field::new("id".into(), DataType::UInt32),
+            Field::new("path".into(), DataType::String),
+            Field::new("name".into(), DataType::String),
+            Field::new("position".into(), DataType::String),
+            Field::new("language".into(), DataType::String),
+        ]);
+        let columns: Vec<&str> = vec!["id", "path", "name", "position", "language"];
+        validate_columns(input_path, &columns, &schema)?;
+        open_csv(input_path, Some(schema), Some(columns))
+    })?;
+
+    let function_suffix = Regex::new(r"\.functions/\d+-\d+(\.zst)?$")?;
+
+    // Functions written in a supported language, grouped by project and language
+    // so that every project is indexed only once.
+    let mut functions: Vec<SliceRequest> = Vec::new();
+    for idx in 0..input_file.height() {
+        let row = input_file.get_row(idx)?.0;
+        match (&row[0], &row[1], &row[2], &row[3], &row[4]) {
+            (
+                AnyValue::UInt32(id),
+                AnyValue::String(path),
+                AnyValue::String(name),
+                AnyValue::String(position),
+                AnyValue::String(language),
+            ) => {
+                let language: String = language.to_lowercase();
+                if slice_grammar(&language).is_some() {
+                    let path: String = revert_placeholders(path);
+                    let source: String = function_suffix.replace(&path, "").to_string();
+                    functions.push(SliceRequest {
+                        id: *id,
+                        project: project_root(&source),
+                        position: parse_position(position)
+                            .with_context(|| format!("Invalid position in row {idx}"))?,
+                        path,
+                        source,
+                        name: name.to_string(),
+                        language,
+                    });
+                }
+            }
+            _ => bail!("Could not parse row {idx} in the input file"),
+        }
+    }
+    functions.sort_by(|a, b| (&a.project, &a.language).cmp(&(&b.project, &b.language)));
+
+    info!("{} Java, Kotlin or Scala functions found.", functions.len());
+
+    let default_output_path: String = format!("{input_path}.slices.csv");
+    let output_path: &str = output_path.unwrap_or(&default_output_path);
+    logger.record_inputs(&[input_path]);
+    logger.record_outputs(&[output_path]);
+
+    // Load the previous results.
+    let previous_results: HashSet<String> = if force || !Path::new(output_path).exists() {
+        HashSet::new()
+    } else {
+        logger.run_task("Resuming progress", || {
+            let output_df: DataFrame = open_csv(
+                output_path,
+                Some(Schema::from_iter(vec![Field::new(
+                    "path".into(),
+                    DataType::String,
+                )])),
+                Some(vec!["path"]),
+            )?;
+            Ok(dataframes::str(&output_df, "path")?
+                .into_iter()
+                .map(|p| p.to_string())
+                .collect())
+        })?
+    };
+
+    if !previous_results.is_empty() {
+        info!(
+            "Resuming from {} previously sliced functions",
+            previous_results.len()
+        );
+    }
+
+    let mut output_file = CSVFile::new(
+        output_path,
+        if force {
+            FileMode::Overwrite
+        } else {
+            FileMode::Append
+        },
+    )?;
+
+    output_file.write_header(&[
+        "id",
+        "path",
+        "name",
+        "language",
+        "slice",
+        "declarations",
+        "status",
+    ])?;
+
+    create_dir(target)?;
+
+    let progress_bar: Progress = logger.progress_bar(Some(functions.len() as u64))?;
+
+    let mut index: Option<ProjectIndex> = None;
+
+    for function in functions {
+        if !previous_results.contains(&function.path) {
+            // Safe unwrap: unsupported languages were filtered out above
+            let grammar: SliceGrammar = slice_grammar(&function.language).unwrap();
+
+            if index
+                .as_ref()
+                .is_none_or(|i| i.root != function.project || i.language != function.language)
+            {
+                index = Some(ProjectIndex::new(
+                    &function.project,
+                    &function.language,
+                    &grammar,
+                )?);
+            }
+            // Safe unwrap: the index was built above
+            let project: &ProjectIndex = index.as_ref().unwrap();
+
+            let slice_path: String = format!(
+                "{}/{}-{}-{}-{}.{}",
+                target,
+                function.id,
+                function
+                    .name
+                    .chars()
+                    .map(|c| if c.is_alphanumeric() { c } else { '_' })
+                    .collect::<String>(),
+                function.position.0,
+                function.position.1,
+                grammar.extension
+            );
+
+            let (slice, declarations, status): (String, usize, &str) =
+                match extract_slice(project, &grammar, &function.source, function.position) {
+                    Ok(Some((code, declarations))) => {
+                        write_file(&slice_path, code)?;
+                        (slice_path, declarations, "ok")
+                    }
+                    Ok(None) => (String::new(), 0, "not-found"),
+                    Err(e) => {
+                        warn!(
+                            "Could not extract slice for function {} in file {}:\n {}",
+                            function.name, function.source, e
+                        );
+                        (String::new(), 0, "error")
+                    }
+                };
+
+            writeln!(
+                &mut output_file,
+                "{},{},{},{},{},{},{}",
+                function.id,
+                Quoting::default().escape(&function.path),
+                Quoting::default().escape(&function.name),
+                function.language,
+                Quoting::default().escape(&slice),
+                declarations,
+                status
+            )?;
+        }
+        progress_bar.inc(1);
+    }
+    progress_bar.finish();
+    Ok(())
+}
+
+/// A function of the parse output to slice.
+struct SliceRequest {
+    /// Id of the project.
+    id: u32,
+    /// Path to the function file written by the parse command.
+    path: String,
+    /// Path to the source file containing the function.
+    source: String,
+    /// Root directory of the project.
+    project: String,
+    /// Name of the function.
+    name: String,
+    /// Line and column of the function in the source file, starting at 1.
+    position: (usize, usize),
+    /// Language of the function.
+    language: String,
+}
+
+/// Returns the root directory of the project containing a file.
+/// Projects downloaded by the download command are stored in directories named `{id}-{commit}`;
+/// if the file is not in such a directory, its parent directory is used instead.
+fn project_root(source: &str) -> String {
+    // Safe unwrap: the regex is valid
+    let downloaded_project = Regex::new(r"^(.*?[0-9]+-[0-9a-fA-F]{40})/").unwrap();
+    match downloaded_project.captures(source) {
+        Some(c) => c[1].to_string(),
+        None => Path::new(source)
+            .parent()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|| ".".to_string()),
+    }
+}
+
+/// Parses a position of the form `line:column`.
+fn parse_position(position: &str) -> Option<(usize, usize)> {
+    let (row, col) = position.split_once(':')?;
+    Some((row.parse().ok()?, col.parse().ok()?))
+}
+
+/// Node kinds used to slice the source code of a language.
+struct SliceGrammar {
+    /// The programming language the grammar belongs to.
+    lang: Language,
+    /// Extensions of the source files of the language.
+    extensions: HashSet<&'static str>,
+    /// Extension of the emitted slices.
+    extension: &'static str,
+    /// Nodes representing classes, objects, interfaces or traits.
+    type_nodes: HashSet<&'static str>,
+    /// Nodes representing the body of a type.
+    body_nodes: HashSet<&'static str>,
+    /// Bodies whose members can be emitted separately. Types with other bodies are always emitted entirely.
+    sliced_body_nodes: HashSet<&'static str>,
+    /// Nodes representing methods or constructors.
+    method_nodes: HashSet<&'static str>,
+    /// Nodes representing fields, kept in every emitted type.
+    field_nodes: HashSet<&'static str>,
+    /// Nodes representing imports.
+    import_nodes: HashSet<&'static str>,
+    /// Nodes representing package declarations.
+    package_nodes: HashSet<&'static str>,
+    /// Nodes representing identifiers.
+    identifier_nodes: HashSet<&'static str>,
+    /// Whether the `public` modifier must be removed from top-level types so that they can share a file.
+    strip_public: bool,
+}
+
+/// Returns the slice grammar for the Java programming language.
+fn java_grammar() -> SliceGrammar {
+    SliceGrammar {
+        lang: tree_sitter_java::LANGUAGE.into(),
+        extensions: vec!["java"].into_iter().collect(),
+        extension: "java",
+        type_nodes: vec![
+            "class_declaration",
+            "interface_declaration",
+            "enum_declaration",
+            "record_declaration",
+            "annotation_type_declaration",
+        ]
+        .into_iter()
+        .collect(),
+        body_nodes: vec![
+            "class_body",
+            "interface_body",
+            "enum_body",
+            "annotation_type_body",
+        ]
+        .into_iter()
+        .collect(),
+        sliced_body_nodes: vec!["class_body", "interface_body"].into_iter().collect(),
+        method_nodes: vec![
+            "method_declaration",
+            "constructor_declaration",
+            "compact_constructor_declaration",
+        ]
+        .into_iter()
+        .collect(),
+        field_nodes: vec!["field_declaration", "constant_declaration"]
+            .into_iter()
+            .collect(),
+        import_nodes: vec!["import_declaration"].into_iter().collect(),
+        package_nodes: vec!["package_declaration"].into_iter().collect(),
+        identifier_nodes: vec!["identifier", "type_identifier"].into_iter().collect(),
+        strip_public: true,
+    }
+}
+
+/// Returns the slice grammar for the Scala programming language.
+fn scala_grammar() -> SliceGrammar {
+    SliceGrammar {
+        lang: tree_sitter_scala::LANGUAGE.into(),
+        extensions: vec!["scala", "sc"].into_iter().collect(),
+        extension: "scala",
+        type_nodes: vec![
+            "class_definition",
+            "object_definition",
+            "trait_definition",
+            "enum_definition",
+        ]
+        .into_iter()
+        .collect(),
+        body_nodes: vec!["template_body", "enum_body"].into_iter().collect(),
+        sliced_body_nodes: vec!["template_body"].into_iter().collect(),
+        method_nodes: vec!["function_definition", "function_declaration"]
+            .into_iter()
+            .collect(),
+        field_nodes: vec![
+            "val_definition",
+            "var_definition",
+            "val_declaration",
+            "var_declaration",
+        ]
+        .into_iter()
+        .collect(),
+        import_nodes: vec!["import_declaration"].into_iter().collect(),
+        package_nodes: vec!["package_clause"].into_iter().collect(),
+        identifier_nodes: vec!["identifier", "type_identifier"].into_iter().collect(),
+        strip_public: false,
+    }
+}
+
+/// Returns the slice grammar for the Kotlin programming language.
+fn kotlin_grammar() -> SliceGrammar {
+    SliceGrammar {
+        lang: tree_sitter_kotlin_ng::LANGUAGE.into(),
+        extensions: vec!["kt", "kts"].into_iter().collect(),
+        extension: "kt",
+        type_nodes: vec![
+            "class_declaration",
+            "object_declaration",
+            "companion_object",
+        ]
+        .into_iter()
+        .collect(),
+        body_nodes: vec!["class_body", "enum_class_body"].into_iter().collect(),
+        sliced_body_nodes: vec!["class_body"].into_iter().collect(),
+        method_nodes: vec!["function_declaration", "secondary_constructor"]
+            .into_iter()
+            .collect(),
+        field_nodes: vec!["property_declaration"].into_iter().collect(),
+        import_nodes: vec!["import"].into_iter().collect(),
+        package_nodes: vec!["package_header"].into_iter().collect(),
+        identifier_nodes: vec!["identifier"].into_iter().collect(),
+        strip_public: false,
+    }
+}
+
+/// Returns the slice grammar corresponding to the given language,
+/// or `None` if slicing is not supported for this language.
+fn slice_grammar(language: &str) -> Option<SliceGrammar> {
+    match language {
+        "java" => Some(java_grammar()),
+        "scala" => Some(scala_grammar()),
+        "kotlin" => Some(kotlin_grammar()),
+        _ => None,
+    }
+}
+
+/// A source file of a project.
+struct SourceFile {
+    /// Path to the file.
+    path: String,
+    /// Content of the file.
+    source: Vec<u8>,
+    /// Identifiers of the file with their start byte, sorted by start byte.
+    identifiers: Vec<(usize, String)>,
+    /// Byte ranges of the imports of the file.
+    imports: Vec<Range<usize>>,
+}
+
+/// A type or method declared in a project.
+struct Declaration {
+    /// Index of the file declaring it.
+    file: usize,
+    /// Byte range of the declaration.
+    range: Range<usize>,
+    /// Index of the innermost type enclosing the declaration.
+    parent: Option<usize>,
+}
+
+/// A type declared in a project.
+struct TypeDeclaration {
+    /// Location of the type.
+    decl: Declaration,
+    /// Byte range of the name of the type.
+    name: Option<Range<usize>>,
+    /// Byte range of the header of the type, up to and including the opening brace of its body.
+    /// `None` if the type cannot be sliced, in which case it is always emitted entirely.
+    header: Option<Range<usize>>,
+    /// Byte ranges of the fields of the type.
+    fields: Vec<Range<usize>>,
+}
+
+/// Types and methods declared in a project, indexed by name.
+struct ProjectIndex {
+    /// Root directory of the project.
+    root: String,
+    /// Language of the indexed files.
+    language: String,
+    /// Source files of the project.
+    files: Vec<SourceFile>,
+    /// Types declared in the project.
+    types: Vec<TypeDeclaration>,
+    /// Methods declared in the project with their start position.
+    methods: Vec<(Declaration, (usize, usize))>,
+    /// Indices of the types of a given name.
+    types_by_name: HashMap<String, Vec<usize>>,
+    /// Indices of the methods of a given name.
+    methods_by_name: HashMap<String, Vec<usize>>,
+    /// Packages declared in the project.
+    packages: HashSet<String>,
+}
+
+impl ProjectIndex {
+    /// Parses all the source files of a project written in a given language.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - Root directory of the project.
+    /// * `language` - Language of the files to index.
+    /// * `grammar` - The grammar of the language.
+    fn new(root: &str, language: &str, grammar: &SliceGrammar) -> Result<Self> {
+        let mut parser: Parser = Parser::new();
+        parser.set_language(&grammar.lang)?;
+
+        let mut index = Self {
+            root: root.to_string(),
+            language: language.to_string(),
+            files: Vec::new(),
+            types: Vec::new(),
+            methods: Vec::new(),
+            types_by_name: HashMap::new(),
+            methods_by_name: HashMap::new(),
+            packages: HashSet::new(),
+        };
+
+        for entry in WalkDir::new(root)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .filter(|e| {
+                e.path()
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| grammar.extensions.contains(ext))
+            })
+        {
+            if let Ok(source) = load_file(entry.path(), 1024 * 1024 * 1024)? {
+                index.add_file(
+                    &entry.path().to_string_lossy(),
+                    source,
+                    grammar,
+                    &mut parser,
+                )?;
+            }
+        }
+        Ok(index)
+    }
+
+    /// Parses a source file and indexes its declarations.
+    fn add_file(
+        &mut self,
+        path: &str,
+        source: Vec<u8>,
+        grammar: &SliceGrammar,
+        parser: &mut Parser,
+    ) -> Result<()> {
+        let tree = parser
+            .parse(&source, None)
+            .with_context(|| format!("Failed to parse file {path}"))?;
+        let file: usize = self.files.len();
+        let text = |n: &Node| String::from_utf8_lossy(&source[n.byte_range()]).to_string();
+
+        let mut identifiers: Vec<(usize, String)> = Vec::new();
+        let mut imports: Vec<Range<usize>> = Vec::new();
+
+        // Simulating call stack, every node comes with its innermost enclosing type
+        let mut call_stack: Vec<(Node, Option<usize>)> = vec![(tree.root_node(), None)];
+        let mut cursor = tree.walk();
+
+        while let Some((node, parent)) = call_stack.pop() {
+            let kind: &str = node.kind();
+            let mut child_parent: Option<usize> = parent;
+
+            // Keywords can share the kind of a node, e.g. 'import' in Kotlin
+            if !node.is_named() {
+                continue;
+            }
+
+            if grammar.identifier_nodes.contains(kind) {
+                identifiers.push((node.start_byte(), text(&node)));
+            } else if grammar.import_nodes.contains(kind) {
+                imports.push(node.byte_range());
+            } else if grammar.package_nodes.contains(kind) {
+                self.packages.insert(qualified_name(&text(&node)));
+            } else if grammar.type_nodes.contains(kind) {
+                let body: Option<Node> = node
+                    .children(&mut node.walk())
+                    .find(|c| grammar.body_nodes.contains(c.kind()));
+                let sliced_body: Option<Node> =
+                    body.filter(|b| grammar.sliced_body_nodes.contains(b.kind()));
+                let name: Option<Node> = node.child_by_field_name("name");
+                let type_idx: usize = self.types.len();
+                if let Some(n) = name {
+                    self.types_by_name
+                        .entry(text(&n))
+                        .or_default()
+                        .push(type_idx);
+                }
+                self.types.push(TypeDeclaration {
+                    decl: Declaration {
+                        file,
+                        range: node.byte_range(),
+                        parent,
+                    },
+                    name: name.map(|n| n.byte_range()),
+                    header: sliced_body.map(|b| node.start_byte()..b.start_byte() + 1),
+                    fields: sliced_body
+                        .map(|b| {
+                            b.named_children(&mut b.walk())
+                                .filter(|c| grammar.field_nodes.contains(c.kind()))
+                                .map(|c| c.byte_range())
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                });
+                child_parent = Some(type_idx);
+            } else if grammar.method_nodes.contains(kind) {
+                if let Some(n) = node.child_by_field_name("name") {
+                    self.methods_by_name
+                        .entry(text(&n))
+                        .or_default()
+                        .push(self.methods.len());
+                }
+                self.methods.push((
+                    Declaration {
+                        file,
+                        range: node.byte_range(),
+                        parent,
+                    },
+                    (
+                        node.start_position().row + 1,
+                        node.start_position().column + 1,
+                    ),
+                ));
+            }
+
+            for c in node.children(&mut cursor) {
+                call_stack.push((c, child_parent));
+            }
+        }
+
+        identifiers.sort();
+        self.files.push(SourceFile {
+            path: path.to_string(),
+            source,
+            identifiers,
+            imports,
+        });
+        Ok(())
+    }
+
+    /// Returns the outermost type enclosing a type, or the type itself if it is a top-level type.
+    fn top_level_type(&self, mut ty: usize) -> usize {
+        while let Some(parent) = self.types[ty].decl.parent {
+            ty = parent;
+        }
+        ty
+    }
+
+    /// Returns the names referenced in a byte range of a file.
+    fn identifiers_in(&self, file: usize, range: &Range<usize>) -> Vec<&str> {
+        let identifiers = &self.files[file].identifiers;
+        let start: usize = identifiers.partition_point(|(b, _)| *b < range.start);
+        identifiers[start..]
+            .iter()
+            .take_while(|(b, _)| *b < range.end)
+            .map(|(_, name)| name.as_str())
+            .collect()
+    }
+
+    /// Returns the source code of a byte range of a file.
+    fn code(&self, file: usize, range: &Range<usize>) -> &[u8] {
+        &self.files[file].source[range.clone()]
+    }
+}
+
+/// Keeps the package path of a package or import statement.
+fn qualified_name(statement: &str) -> String {
+    statement
+        .trim()
+        .trim_start_matches("package")
+        .trim_start_matches("import")
+        .trim()
+        .trim_start_matches("static ")
+        .trim_end_matches(';')
+        .trim()
+        .to_string()
+}
+
+/// A piece of code emitted in a slice.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Unit {
+    /// A member of the type enclosing the sliced function, identified by its byte range.
+    Member(Range<usize>),
+    /// A top-level type or function, identified by its file and byte range.
+    TopLevel(usize, Range<usize>),
+}
+
+/// Extracts the slice of a function, i.e., the function with the declarations it transitively references.
+/// The type enclosing the function is emitted with its fields and the members that are referenced only,
+/// while the other project-local types are emitted entirely.
+///
+/// # Arguments
+///
+/// * `project` - The index of the project containing the function.
+/// * `grammar` - The grammar of the language.
+/// * `source` - Path to the file containing the function.
+/// * `position` - Line and column of the function in the file.
+///
+/// # Returns
+///
+/// The source code of the slice and the number of emitted declarations, or `None` if the function could not be found.
+fn extract_slice(
+    project: &ProjectIndex,
+    grammar: &SliceGrammar,
+    source: &str,
+    position: (usize, usize),
+) -> Result<Option<(Vec<u8>, usize)>> {
+    let root_method: Option<&Declaration> = project
+        .methods
+        .iter()
+        .find(|(d, p)| *p == position && project.files[d.file].path == source)
+        .map(|(d, _)| d);
+
+    let root_method: &Declaration = match root_method {
+        Some(m) => m,
+        None => return Ok(None),
+    };
+
+    // The top-level type enclosing the function, if it can be sliced.
+    let sliced_type: Option<usize> = root_method
+        .parent
+        .map(|p| project.top_level_type(p))
+        .filter(|t| project.types[*t].header.is_some());
+
+    // Returns the unit containing a declaration.
+    let unit_of = |decl: &Declaration, ty: Option<usize>| -> Option<Unit> {
+        let mut child: (Range<usize>, Option<usize>) = (decl.range.clone(), ty);
+        let mut parent: Option<usize> = decl.parent;
+        while let Some(p) = parent {
+            if Some(p) == sliced_type {
+                return Some(Unit::Member(child.0));
+            }
+            child = (project.types[p].decl.range.clone(), Some(p));
+            parent = project.types[p].decl.parent;
+        }
+        match child.1 {
+            Some(t) if Some(t) == sliced_type => None,
+            Some(t) => Some(Unit::TopLevel(
+                project.types[t].decl.file,
+                project.types[t].decl.range.clone(),
+            )),
+            None => Some(Unit::TopLevel(decl.file, decl.range.clone())),
+        }
+    };
+
+    let mut units: Vec<Unit> = Vec::new();
+    let mut visited: HashSet<Unit> = HashSet::new();
+    let mut worklist: Vec<(usize, Range<usize>)> = Vec::new();
+
+    if let Some(t) = sliced_type {
+        let ty: &TypeDeclaration = &project.types[t];
+        // Safe unwrap: sliced types have a header
+        worklist.push((ty.decl.file, ty.header.clone().unwrap()));
+        for field in &ty.fields {
+            worklist.push((ty.decl.file, field.clone()));
+        }
+    }
+
+    // Safe unwrap: the root method belongs to the sliced type or is a top-level unit
+    let root_unit: Unit = unit_of(root_method, None).unwrap();
+    visited.insert(root_unit.clone());
+    units.push(root_unit);
+    worklist.push((root_method.file, root_method.range.clone()));
+
+    while let Some((file, range)) = worklist.pop() {
+        ensure!(
+            units.len() <= MAX_UNITS,
+            "The slice contains more than {MAX_UNITS} declarations"
+        );
+        for name in project.identifiers_in(file, &range) {
+            let methods = project
+                .methods_by_name
+                .get(name)
+                .into_iter()
+                .flatten()
+                .map(|m| (&project.methods[*m].0, None));
+            let types = project
+                .types_by_name
+                .get(name)
+                .into_iter()
+                .flatten()
+                .map(|t| (&project.types[*t].decl, Some(*t)));
+
+            for (decl, ty) in methods.chain(types) {
+                if let Some(unit) = unit_of(decl, ty) {
+                    if visited.insert(unit.clone()) {
+                        worklist.push(match &unit {
+                            Unit::Member(r) => {
+                                (project.types[sliced_type.unwrap()].decl.file, r.clone())
+                            }
+                            Unit::TopLevel(f, r) => (*f, r.clone()),
+                        });
+                        units.push(unit);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(Some(emit_slice(project, grammar, sliced_type, units)))
+}
+
+/// Writes the source code of a slice.
+///
+/// # Arguments
+///
+/// * `project` - The index of the project.
+/// * `grammar` - The grammar of the language.
+/// * `sliced_type` - The type enclosing the sliced function, if any.
+/// * `units` - The units to emit.
+///
+/// # Returns
+///
+/// The source code of the slice and the number of emitted units.
+fn emit_slice(
+    project: &ProjectIndex,
+    grammar: &SliceGrammar,
+    sliced_type: Option<usize>,
+    units: Vec<Unit>,
+) -> (Vec<u8>, usize) {
+    let sliced_file: Option<usize> = sliced_type.map(|t| project.types[t].decl.file);
+
+    let location = |u: &Unit| -> (usize, Range<usize>) {
+        match u {
+            // Safe unwrap: members only exist if there is a sliced type
+            Unit::Member(r) => (sliced_file.unwrap(), r.clone()),
+            Unit::TopLevel(f, r) => (*f, r.clone()),
+        }
+    };
+
+    // Units nested in other units (e.g. local functions) are already emitted with their parent.
+    let mut kept: Vec<(usize, Range<usize>, bool)> = units
+        .iter()
+        .map(|u| {
+            let (f, r) = location(u);
+            (f, r, matches!(u, Unit::Member(_)))
+        })
+        .filter(|(f, r, _)| {
+            !units.iter().any(|o| {
+                let (of, or) = location(o);
+                of == *f && or != *r && or.start <= r.start && r.end <= or.end
+            })
+        })
+        .collect();
+    kept.sort_by_key(|(f, r, _)| (*f, r.start));
+
+    let mut files: Vec<usize> = kept.iter().map(|(f, _, _)| *f).collect();
+    files.extend(sliced_file);
+    files.sort();
+    files.dedup();
+
+    let mut out: Vec<u8> = Vec::new();
+
+    // Imports of the involved files, except imports of project-local packages.
+    let mut imports: HashSet<String> = HashSet::new();
+    for f in &files {
+        for import in &project.files[*f].imports {
+            let text: String = String::from_utf8_lossy(project.code(*f, import)).to_string();
+            let path: String = qualified_name(&text);
+            let is_local: bool = project
+                .packages
+                .iter()
+                .any(|p| path.starts_with(&format!("{p}.")));
+            if !is_local && imports.insert(path) {
+                out.extend_from_slice(text.trim().as_bytes());
+                out.push(b'\n');
+            }
+        }
+    }
+    if !imports.is_empty() {
+        out.push(b'\n');
+    }
+
+    // Source code of a top-level type or function, without its public modifier if needed.
+    let top_level_code = |f: usize, range: &Range<usize>, header_only: bool| -> Vec<u8> {
+        let ty: Option<&TypeDeclaration> = project
+            .types
+            .iter()
+            .find(|t| t.decl.file == f && t.decl.range == *range);
+        let end: usize = match (header_only, ty.and_then(|t| t.header.clone())) {
+            (true, Some(h)) => h.end,
+            _ => range.end,
+        };
+        let code: &[u8] = project.code(f, &(range.start..end));
+        match ty.and_then(|t| t.name.clone()) {
+            Some(name) if grammar.strip_public => {
+                let (modifiers, rest) = code.split_at(name.start - range.start);
+                let modifiers: String =
+                    String::from_utf8_lossy(modifiers).replacen("public ", "", 1);
+                [modifiers.as_bytes(), rest].concat()
+            }
+            _ => code.to_vec(),
+        }
+    };
+
+    let mut emitted: usize = 0;
+
+    if let Some(t) = sliced_type {
+        let ty: &TypeDeclaration = &project.types[t];
+        let f: usize = ty.decl.file;
+        out.extend(top_level_code(f, &ty.decl.range, true));
+        out.push(b'\n');
+
+        let mut members: Vec<Range<usize>> = ty.fields.clone();
+        members.extend(
+            kept.iter()
+                .filter(|(_, _, is_member)| *is_member)
+                .map(|(_, r, _)| r.clone()),
+        );
+        members.sort_by_key(|r| r.start);
+        members.dedup();
+
+        for member in members {
+            // Keep the indentation of the member.
+            let source: &[u8] = &project.files[f].source;
+            let line_start: usize = source[..member.start]
+                .iter()
+                .rposition(|c| *c == b'\n')
+                .map_or(0, |i| i + 1);
+            let indent: &[u8] = &source[line_start..member.start];
+            if indent.iter().all(|c| c.is_ascii_whitespace()) {
+                out.extend_from_slice(indent);
+            }
+            out.extend_from_slice(project.code(f, &member));
+            out.extend_from_slice(b"\n");
+        }
+        out.extend_from_slice(b"}\n");
+        emitted += 1;
+    }
+
+    for (f, r, _) in kept.iter().filter(|(_, _, is_member)| !*is_member) {
+        out.push(b'\n');
+        out.extend(top_level_code(*f, r, false));
+        out.push(b'\n');
+        emitted += 1;
+    }
+
+    emitted += kept.iter().filter(|(_, _, is_member)| *is_member).count();
+
+    (out, emitted)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::utils::logger::test_logger;
+
+    const TEST_DATA: &str = "tests/data/phases/extract_slices";
+
+    #[test]
+    fn extract_slices_test() -> Result<()> {
+        let input_path: String = format!("{TEST_DATA}/functions.csv");
+        let output_path: String = format!("{input_path}.slices.csv");
+        let target: String = "target/tests/extract_slices".to_string();
+
+        delete_dir(&target, true)?;
+        run(&input_path, None, &target, true, test_logger())?;
+
+        let output_df: DataFrame = open_csv(&output_path, None, None)?;
+        let expected_df: DataFrame = open_csv(&format!("{output_path}.expected"), None, None)?;
+        assert_eq!(expected_df, output_df);
+
+        for slice in dataframes::str(&output_df, "slice")? {
+            if !slice.is_empty() {
+                let file_name: &str = slice.rsplit('/').next().unwrap_or_default();
+                assert_eq!(
+                    std::fs::read_to_string(slice)?,
+                    std::fs::read_to_string(format!("{TEST_DATA}/expected/{file_name}"))?,
+                    "Unexpected slice {slice}"
+                );
+            }
+        }
+
+        delete_file(&output_path, false)?;
+        delete_dir(&target, false)
+    }
+
+    #[test]
+    fn project_root_test() {
+        assert_eq!(
+            project_root("data/0/12-0123456789abcdef0123456789abcdef01234567/src/A.java"),
+            "data/0/12-0123456789abcdef0123456789abcdef01234567"
+        );
+        assert_eq!(project_root("data/src/A.java"), "data/src");
+    }
+}
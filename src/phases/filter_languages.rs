@@ -21,12 +21,15 @@ use std::vec;
 use anyhow::{Context, Result};
 use clap::{Arg, ArgAction, Command};
 use polars::frame::DataFrame;
-use polars::prelude::{col, lit, DataType, Field, IdxCa, IntoLazy, Schema};
+use polars::prelude::{col, lit, Column, DataType, Field, IdxCa, IntoLazy, Schema};
 use tracing::info;
 
+use crate::utils::csv::validate_columns;
+use crate::utils::dataframes::{self, output_column_args, OutputColumns};
+use crate::utils::fs::*;
+use crate::utils::language::normalize_language;
 use crate::utils::logger::{log_output_file, log_write_output, Logger};
 use crate::utils::regex::KeywordFiles;
-use crate::utils::{dataframes, fs::*};
 
 /// Command line arguments parsing.
 pub fn cli() -> Command {
@@ -77,6 +80,7 @@ pub fn cli() -> Command {
                 .action(ArgAction::SetTrue)
                 .conflicts_with_all(vec!["output", "force"]),
         )
+        .args(output_column_args())
 }
 
 /// Entrypoint of the program
@@ -88,6 +92,7 @@ pub fn cli() -> Command {
 /// * `languages_path` - The path to the JSON file storing the languages to keep.
 /// * `force` - Whether to override the output file if it already exists.
 /// * `no_output` - Whether to write the output file.
+/// * `output_columns` - The columns written to the output file and their new names.
 /// * `logger` - The logger displaying the progress.
 ///
 /// # Returns
@@ -99,10 +104,15 @@ pub fn run(
     languages_path: &str,
     force: bool,
     no_output: bool,
+    output_columns: &OutputColumns,
     logger: &Logger,
 ) -> Result<()> {
     let default_output_path = format!("{input_path}.filtered_lang.csv");
     let output_path = output_path.unwrap_or(&default_output_path);
+    logger.record_inputs(&[input_path, languages_path]);
+    if !no_output {
+        logger.record_outputs(&[output_path]);
+    }
 
     check_path(input_path)?;
 
@@ -116,16 +126,14 @@ pub fn run(
         .cloned()
         .collect();
 
-    let mut projects: DataFrame = open_csv(
-        input_path,
-        Some(Schema::from_iter(vec![
-            Field::new("id".into(), DataType::UInt32),
-            Field::new("name".into(), DataType::String),
-            Field::new("languages".into(), DataType::String),
-            Field::new("latest_commit".into(), DataType::String),
-        ])),
-        None,
-    )?;
+    let schema: Schema = Schema::from_iter(vec![
+        Field::new("id".into(), DataType::UInt32),
+        Field::new("name".into(), DataType::String),
+        Field::new("languages".into(), DataType::String),
+        Field::new("latest_commit".into(), DataType::String),
+    ]);
+    validate_columns(input_path, &["name", "languages"], &schema)?;
+    let mut projects: DataFrame = open_csv(input_path, Some(schema), None)?;
     let projects_count = projects.height();
 
     info!("{} projects found in the file", projects_count);
@@ -163,7 +171,7 @@ pub fn run(
         .into_iter()
         .filter_map(|(idx, m)| {
             Some(Some(idx as u32))
-                .filter(|_| m.keys().any(|k| languages.contains(&k.to_lowercase())))
+                .filter(|_| m.keys().any(|k| languages.contains(&normalize_language(k))))
         })
         .collect::<IdxCa>();
 
@@ -186,10 +194,76 @@ pub fn run(
         retained_projects_count, retained_projects_percentage
     );
 
+    // The share of every language kept is added, so that the projects can be thresholded by fraction downstream.
+    let mut languages: Vec<String> = languages.into_iter().collect();
+    languages.sort();
+    let shares: Vec<Vec<f64>> = dataframes::str(&projects, "languages")?
+        .into_iter()
+        .map(|map| language_percentages(&parse_map(map), &languages))
+        .collect();
+    for (i, language) in languages.iter().enumerate() {
+        projects.with_column(Column::new(
+            format!("pct_{language}").into(),
+            shares.iter().map(|s| s[i]).collect::<Vec<f64>>(),
+        ))?;
+    }
+    projects.with_column(Column::new(
+        "pct_selected".into(),
+        shares
+            .iter()
+            .map(|s| round_percentage(s.iter().sum()))
+            .collect::<Vec<f64>>(),
+    ))?;
+
     // Writes the result to the output CSV file
+    let mut projects: DataFrame = output_columns.apply(projects)?;
     log_write_output(logger, output_path, &mut projects, no_output)
 }
 
+/// Computes the percentages of the bytes of a project written in some languages.
+///
+/// # Arguments
+///
+/// * `sizes` - The number of bytes of every language of the project, as recorded by the 'languages' command.
+/// * `languages` - The canonical names of the languages, as given by [`normalize_language`].
+///
+/// # Returns
+///
+/// The percentage of the bytes of the project written in every language, rounded to two decimals, or 0 if the
+/// project has no code.
+fn language_percentages(sizes: &HashMap<&str, &str>, languages: &[String]) -> Vec<f64> {
+    let sizes: Vec<(String, u64)> = sizes
+        .iter()
+        .map(|(language, size)| {
+            (
+                normalize_language(language),
+                size.trim().parse().unwrap_or(0),
+            )
+        })
+        .collect();
+    let total: u64 = sizes.iter().map(|(_, size)| size).sum();
+    languages
+        .iter()
+        .map(|language| {
+            let bytes: u64 = sizes
+                .iter()
+                .filter(|(l, _)| l == language)
+                .map(|(_, size)| size)
+                .sum();
+            if total == 0 {
+                0.0
+            } else {
+                round_percentage(bytes as f64 * 100.0 / total as f64)
+            }
+        })
+        .collect()
+}
+
+/// Rounds a percentage to two decimals.
+fn round_percentage(percentage: f64) -> f64 {
+    (percentage * 100.0).round() / 100.0
+}
+
 fn parse_map(map: &str) -> HashMap<&str, &str> {
     map.split(';')
         .filter_map(|pair| {
@@ -237,6 +311,29 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_language_percentages() {
+        let sizes: HashMap<&str, &str> = parse_map("Java:600;CSS:100;HTML:200;Scala:100");
+        let languages: Vec<String> = vec!["java".to_string(), "kotlin".to_string()];
+        assert_eq!(language_percentages(&sizes, &languages), vec![60.0, 0.0]);
+        assert_eq!(
+            language_percentages(&parse_map("Java:1;C:2"), &languages),
+            vec![33.33, 0.0]
+        );
+        assert_eq!(
+            language_percentages(&HashMap::new(), &languages),
+            vec![0.0, 0.0]
+        );
+        // Aliases of a language are counted together.
+        assert_eq!(
+            language_percentages(
+                &parse_map("C++:1;Cpp:1;C:2"),
+                &["c".to_string(), "c++".to_string()]
+            ),
+            vec![50.0, 50.0]
+        );
+    }
+
     const TEST_DATA: &str = "tests/data/phases/filter_languages";
 
     #[test]
@@ -252,6 +349,7 @@ mod tests {
             language_path,
             false,
             false,
+            &OutputColumns::default(),
             test_logger(),
         )?;
 
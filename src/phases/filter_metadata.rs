@@ -16,12 +16,14 @@ use std::iter::FromIterator;
 use std::vec;
 
 use anyhow::{ensure, Context, Result};
+use chrono::{Months, Utc};
 use clap::{value_parser, Arg, ArgAction, Command};
 use polars::frame::DataFrame;
-use polars::prelude::{col, lit, DataType, Field, IntoLazy, Schema};
+use polars::prelude::{col, lit, DataType, Expr, Field, IntoLazy, Schema};
 use tracing::info;
 
-use crate::utils::dataframes;
+use crate::utils::csv::validate_columns;
+use crate::utils::dataframes::{self, output_column_args, OutputColumns};
 use crate::utils::fs::*;
 use crate::utils::logger::{log_output_file, log_write_output, Logger};
 
@@ -85,6 +87,46 @@ pub fn cli() -> Command {
                 .required(false)
                 .default_value("0"),
         )
+        .arg(
+            Arg::new("pushed-within-months")
+                .long("pushed-within-months")
+                .value_name("MONTHS")
+                .help("Discard projects whose last push is older than the given number of months, relative to the current date.")
+                .value_parser(value_parser!(u32))
+                .required(false),
+        )
+        .arg(
+            Arg::new("min-commits")
+                .long("min-commits")
+                .value_name("COMMITS")
+                .help("The threshold for the number of commits of the project under which it is discarded. \
+                       The input file must then contain a column named 'commits'.")
+                .value_parser(value_parser!(u32))
+                .required(false)
+                .default_value("0"),
+        )
+        .arg(
+            Arg::new("min-readme-keywords")
+                .long("min-readme-keywords")
+                .value_name("MATCHES")
+                .help("The threshold for the number of keyword matches in the README of the project under which it is discarded. \
+                       The matches are summed over the columns starting with 'readme_', \
+                       which the metadata command records with --readme-keywords.")
+                .value_parser(value_parser!(u32))
+                .required(false)
+                .default_value("0"),
+        )
+        .arg(
+            Arg::new("min-contributors")
+                .long("min-contributors")
+                .value_name("CONTRIBUTORS")
+                .help("The threshold for the number of contributors of the project under which it is discarded. \
+                       The input file must then contain a column named 'contributors', which the metadata command records with --contributors. \
+                       Projects whose number of contributors is unknown are discarded.")
+                .value_parser(value_parser!(u32))
+                .required(false)
+                .default_value("0"),
+        )
         .arg(
             Arg::new("non-code")
                 .long("non-code")
@@ -102,6 +144,7 @@ pub fn cli() -> Command {
                 .action(ArgAction::SetTrue)
                 .conflicts_with_all(vec!["output", "force"]),
         )
+        .args(output_column_args())
 }
 
 /// Filters out projects that are below provided thresholds for some characteristics.
@@ -112,10 +155,15 @@ pub fn cli() -> Command {
 /// * `output_path` - The optional path to the output CSV file. Defaults to the input path with ".unique.csv" appended.
 /// * `size` - The threshold for the size of the project under which it is discarded. (in kB)
 /// * `age` - The threshold for the age (in days) of the project under which it is discarded. If `None`, no filtering is applied.
+/// * `pushed_within` - The number of months before the current date in which the last push must have happened. If `None`, no filtering is applied.
+/// * `min_commits` - The threshold for the number of commits of the project under which it is discarded.
+/// * `min_readme_keywords` - The threshold for the number of keyword matches in the README of the project under which it is discarded.
+/// * `min_contributors` - The threshold for the number of contributors of the project under which it is discarded.
 /// * `disabled` - Whether to discard disabled projects.
 /// * `force` - Whether to override the output file if it already exists.
 /// * `non_code` - Whether to discard projects that do not contain code (e.g., documentation only).
 /// * `no_output` - Whether to write the output file.
+/// * `output_columns` - The columns written to the output file and their new names.
 /// * `logger` - The logger displaying the progress.
 ///
 /// # Returns
@@ -126,45 +174,80 @@ pub fn run(
     output_path: Option<&str>,
     size: u64,
     age: u32,
+    pushed_within: Option<u32>,
+    min_commits: u32,
+    min_readme_keywords: u32,
+    min_contributors: u32,
     disabled: bool,
     non_code: bool,
     force: bool,
     no_output: bool,
+    output_columns: &OutputColumns,
     logger: &Logger,
 ) -> Result<()> {
     let default_output_path = format!("{input_path}.filtered.csv");
     let output_path = output_path.unwrap_or(&default_output_path);
+    logger.record_inputs(&[input_path]);
+    if !no_output {
+        logger.record_outputs(&[output_path]);
+    }
 
     check_path(input_path)?;
 
     // Checks if the output file already exists
     log_output_file(output_path, no_output, force)?;
 
-    let mut projects: DataFrame = open_csv(
+    let schema: Schema = Schema::from_iter(vec![
+        Field::new("id".into(), DataType::UInt32),
+        Field::new("name".into(), DataType::String),
+        Field::new("language".into(), DataType::String),
+        Field::new("created".into(), DataType::UInt64),
+        Field::new("pushed".into(), DataType::UInt64),
+        // Field::new("updated".into(), DataType::UInt64),
+        // Field::new("fork".into(), DataType::UInt32),
+        Field::new("disabled".into(), DataType::UInt32),
+        // Field::new("archived".into(), DataType::UInt32),
+        // Field::new("stars".into(), DataType::UInt32),
+        // Field::new("forks".into(), DataType::UInt32),
+        // Field::new("issues".into(), DataType::UInt32),
+        // Field::new("has_issues".into(), DataType::UInt32),
+        // Field::new("watchers_count".into(), DataType::UInt32),
+        // Field::new("subscribers".into(), DataType::UInt32),
+        Field::new("size".into(), DataType::UInt64),
+        // Field::new("license".into(), DataType::String),
+        Field::new("commits".into(), DataType::UInt32),
+        Field::new("contributors".into(), DataType::UInt32),
+    ]);
+    validate_columns(
         input_path,
-        Some(Schema::from_iter(vec![
-            Field::new("id".into(), DataType::UInt32),
-            Field::new("name".into(), DataType::String),
-            Field::new("language".into(), DataType::String),
-            Field::new("created".into(), DataType::UInt64),
-            Field::new("pushed".into(), DataType::UInt64),
-            // Field::new("updated".into(), DataType::UInt64),
-            // Field::new("fork".into(), DataType::UInt32),
-            Field::new("disabled".into(), DataType::UInt32),
-            // Field::new("archived".into(), DataType::UInt32),
-            // Field::new("stars".into(), DataType::UInt32),
-            // Field::new("forks".into(), DataType::UInt32),
-            // Field::new("issues".into(), DataType::UInt32),
-            // Field::new("has_issues".into(), DataType::UInt32),
-            // Field::new("watchers_count".into(), DataType::UInt32),
-            // Field::new("subscribers".into(), DataType::UInt32),
-            Field::new("size".into(), DataType::UInt64),
-            // Field::new("license".into(), DataType::String),
-        ])),
-        None,
+        &[
+            "id", "name", "language", "created", "pushed", "disabled", "size",
+        ],
+        &schema,
     )?;
+    let mut projects: DataFrame = open_csv(input_path, Some(schema), None)?;
 
-    ensure!(dataframes::has_columns(&projects, ["id", "name", "language", "created", "pushed", "disabled", "size"]), "Input file must contain the following columns: id, name, language, created, pushed, disabled, size");
+    ensure!(
+        min_commits == 0 || dataframes::has_column(&projects, "commits"),
+        "Input file must contain a column named 'commits' to filter projects by number of commits"
+    );
+
+    ensure!(
+        min_contributors == 0 || dataframes::has_column(&projects, "contributors"),
+        "Input file must contain a column named 'contributors' to filter projects by number of contributors"
+    );
+
+    // Keyword counts of the READMEs recorded by the metadata command with --readme-keywords.
+    let readme_columns: Vec<String> = projects
+        .get_column_names()
+        .into_iter()
+        .filter(|c| c.starts_with("readme_"))
+        .map(|c| c.to_string())
+        .collect();
+    ensure!(
+        min_readme_keywords == 0 || !readme_columns.is_empty(),
+        "Input file must contain columns starting with 'readme_' to filter projects by keyword matches in their README"
+    );
 
     let projects_count = projects.height();
 
@@ -174,7 +257,6 @@ pub fn run(
         .lazy()
         .filter(col("name").str().starts_with(lit("http/2 ")).not())
         .with_column((col("pushed") - col("created")).alias("age"))
-        .drop(vec!["created", "pushed"])
         .with_column(
             (col("age") / lit(60 * 60 * 24))
                 .cast(DataType::UInt32)
@@ -279,6 +361,102 @@ pub fn run(
         100.0 - age_filter_percentage
     );
 
+    let pushed_mask = match pushed_within {
+        Some(months) => {
+            let threshold: i64 = Utc::now()
+                .checked_sub_months(Months::new(months))
+                .with_context(|| format!("Invalid number of months: {months}"))?
+                .timestamp();
+            let pushed_mask = col("pushed").gt_eq(lit(threshold.max(0) as u64));
+            let pushed_filter_count: usize = count_matching(&projects, pushed_mask.clone())?;
+            let pushed_filter_percentage =
+                (pushed_filter_count as f64 / reachable_projects_count as f64) * 100.0;
+
+            info!(
+                "\nProjects pushed within {} months: {} / {:.2} %",
+                months, pushed_filter_count, pushed_filter_percentage
+            );
+            info!(
+                "Projects not pushed within {} months: {} / {:.2} %",
+                months,
+                reachable_projects_count - pushed_filter_count,
+                100.0 - pushed_filter_percentage
+            );
+            pushed_mask
+        }
+        None => lit(true),
+    };
+
+    let commits_mask = if min_commits > 0 {
+        let commits_mask = col("commits").gt_eq(lit(min_commits));
+        let commits_filter_count: usize = count_matching(&projects, commits_mask.clone())?;
+        let commits_filter_percentage =
+            (commits_filter_count as f64 / reachable_projects_count as f64) * 100.0;
+
+        info!(
+            "\nProjects with ≥ {} commits: {} / {:.2} %",
+            min_commits, commits_filter_count, commits_filter_percentage
+        );
+        info!(
+            "Projects with < {} commits: {} / {:.2} %",
+            min_commits,
+            reachable_projects_count - commits_filter_count,
+            100.0 - commits_filter_percentage
+        );
+        commits_mask
+    } else {
+        lit(true)
+    };
+
+    let readme_mask = if min_readme_keywords > 0 {
+        // Safe unwrap: there is at least one column of README keyword counts.
+        let readme_matches: Expr = readme_columns
+            .iter()
+            .map(|c| col(c.as_str()).cast(DataType::UInt64))
+            .reduce(|a, b| a + b)
+            .unwrap();
+        let readme_mask = readme_matches.gt_eq(lit(min_readme_keywords as u64));
+        let readme_filter_count: usize = count_matching(&projects, readme_mask.clone())?;
+        let readme_filter_percentage =
+            (readme_filter_count as f64 / reachable_projects_count as f64) * 100.0;
+
+        info!(
+            "\nProjects with ≥ {} keyword matches in their README: {} / {:.2} %",
+            min_readme_keywords, readme_filter_count, readme_filter_percentage
+        );
+        info!(
+            "Projects with < {} keyword matches in their README: {} / {:.2} %",
+            min_readme_keywords,
+            reachable_projects_count - readme_filter_count,
+            100.0 - readme_filter_percentage
+        );
+        readme_mask
+    } else {
+        lit(true)
+    };
+
+    let contributors_mask = if min_contributors > 0 {
+        let contributors_mask = col("contributors").gt_eq(lit(min_contributors));
+        let contributors_filter_count: usize =
+            count_matching(&projects, contributors_mask.clone())?;
+        let contributors_filter_percentage =
+            (contributors_filter_count as f64 / reachable_projects_count as f64) * 100.0;
+
+        info!(
+            "\nProjects with ≥ {} contributors: {} / {:.2} %",
+            min_contributors, contributors_filter_count, contributors_filter_percentage
+        );
+        info!(
+            "Projects with < {} contributors or an unknown number of contributors: {} / {:.2} %",
+            min_contributors,
+            reachable_projects_count - contributors_filter_count,
+            100.0 - contributors_filter_percentage
+        );
+        contributors_mask
+    } else {
+        lit(true)
+    };
+
     let disabled_mask = if disabled {
         col("disabled").eq(lit(0))
     } else {
@@ -315,7 +493,16 @@ pub fn run(
 
     projects = projects
         .lazy()
-        .filter(loc_mask.and(age_mask).and(disabled_mask))
+        .filter(
+            loc_mask
+                .and(age_mask)
+                .and(pushed_mask)
+                .and(commits_mask)
+                .and(readme_mask)
+                .and(contributors_mask)
+                .and(disabled_mask),
+        )
+        .drop(vec!["created", "pushed"])
         .collect()
         .with_context(|| "Could not filter projects")?;
 
@@ -341,9 +528,26 @@ pub fn run(
     );
 
     // Writes the result to the output CSV file
+    let mut projects: DataFrame = output_columns.apply(projects)?;
     log_write_output(logger, output_path, &mut projects, no_output)
 }
 
+/// Counts the projects satisfying a condition.
+///
+/// # Arguments
+///
+/// * `projects` - The projects.
+/// * `mask` - The condition.
+fn count_matching(projects: &DataFrame, mask: Expr) -> Result<usize> {
+    Ok(projects
+        .clone()
+        .lazy()
+        .filter(mask)
+        .collect()
+        .with_context(|| "Could not filter projects")?
+        .height())
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -365,10 +569,15 @@ mod tests {
             None,
             500,
             3,
+            None,
+            0,
+            0,
+            0,
             true,
             true,
             true,
             false,
+            &OutputColumns::default(),
             test_logger(),
         )?;
 
@@ -383,4 +592,162 @@ mod tests {
 
         delete_file(&default_output_path, false)
     }
+
+    #[test]
+    fn test_filter_activity() -> Result<()> {
+        let input_path = format!("{TEST_DATA}/activity.csv");
+        let default_output_path = format!("{input_path}.filtered.csv");
+
+        delete_file(&default_output_path, true)?;
+        run(
+            &input_path,
+            None,
+            0,
+            0,
+            Some(24),
+            50,
+            0,
+            0,
+            false,
+            false,
+            true,
+            false,
+            &OutputColumns::default(),
+            test_logger(),
+        )?;
+
+        let expected_df = open_csv(&format!("{default_output_path}.expected"), None, None)?;
+        let output_df = open_csv(&default_output_path, None, None)?;
+        assert_eq!(expected_df, output_df);
+
+        delete_file(&default_output_path, false)?;
+
+        // The number of commits is required to filter by number of commits.
+        assert!(run(
+            &format!("{TEST_DATA}/filter_metadata.csv"),
+            None,
+            0,
+            0,
+            None,
+            50,
+            0,
+            0,
+            false,
+            false,
+            false,
+            true,
+            &OutputColumns::default(),
+            test_logger(),
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_readme() -> Result<()> {
+        let input_path = format!("{TEST_DATA}/readme.csv");
+        let default_output_path = format!("{input_path}.filtered.csv");
+
+        // The matches of the keyword files are summed.
+        run(
+            &input_path,
+            None,
+            0,
+            0,
+            None,
+            0,
+            2,
+            0,
+            false,
+            false,
+            true,
+            false,
+            &OutputColumns::default(),
+            test_logger(),
+        )?;
+        let output_df = open_csv(
+            &default_output_path,
+            Some(Schema::from_iter(vec![Field::new(
+                "id".into(),
+                DataType::UInt32,
+            )])),
+            None,
+        )?;
+        assert_eq!(dataframes::u32(&output_df, "id")?, vec![1, 3]);
+        delete_file(&default_output_path, false)?;
+
+        // The keyword counts of the READMEs are required to filter by keyword matches.
+        assert!(run(
+            &format!("{TEST_DATA}/activity.csv"),
+            None,
+            0,
+            0,
+            None,
+            0,
+            1,
+            0,
+            false,
+            false,
+            false,
+            true,
+            &OutputColumns::default(),
+            test_logger(),
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_contributors() -> Result<()> {
+        let input_path = format!("{TEST_DATA}/contributors.csv");
+        let default_output_path = format!("{input_path}.filtered.csv");
+
+        // The projects whose number of contributors is unknown are discarded.
+        run(
+            &input_path,
+            None,
+            0,
+            0,
+            None,
+            0,
+            0,
+            3,
+            false,
+            false,
+            true,
+            false,
+            &OutputColumns::default(),
+            test_logger(),
+        )?;
+        let output_df = open_csv(
+            &default_output_path,
+            Some(Schema::from_iter(vec![Field::new(
+                "id".into(),
+                DataType::UInt32,
+            )])),
+            None,
+        )?;
+        assert_eq!(dataframes::u32(&output_df, "id")?, vec![1, 4]);
+        delete_file(&default_output_path, false)?;
+
+        // The number of contributors is required to filter by number of contributors.
+        assert!(run(
+            &format!("{TEST_DATA}/readme.csv"),
+            None,
+            0,
+            0,
+            None,
+            0,
+            0,
+            1,
+            false,
+            false,
+            false,
+            true,
+            &OutputColumns::default(),
+            test_logger(),
+        )
+        .is_err());
+        Ok(())
+    }
 }
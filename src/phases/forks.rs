@@ -16,12 +16,16 @@
 
 use std::iter::FromIterator;
 
-use anyhow::{Context, Result};
+use anyhow::{ensure, Context, Result};
 use clap::{Arg, ArgAction, Command};
 use polars::frame::DataFrame;
-use polars::prelude::{col, lit, DataType, Field, IntoLazy, Schema};
+use polars::prelude::{
+    col, lit, when, DataType, Field, IntoLazy, Schema, SortMultipleOptions, UniqueKeepStrategy,
+};
 use tracing::info;
 
+use crate::utils::csv::validate_columns;
+use crate::utils::dataframes::has_columns;
 use crate::utils::fs::*;
 use crate::utils::logger::{log_output_file, log_write_output, Logger};
 
@@ -54,6 +58,17 @@ pub fn cli() -> Command {
                 .help("Name of the column storing whether projects are forks.")
                 .default_value("fork"),
         )
+        .arg(
+            Arg::new("network")
+                .long("network")
+                .value_name("REPRESENTATIVE")
+                .help("Keep a single project per fork network instead of discarding the projects flagged as forks. \
+                       The input file must contain the columns 'source' and 'stars' collected by the metadata command.\n\
+                       source: keep the root project of the network if it is in the file, otherwise its most starred member\n\
+                       stars: keep the most starred member of the network")
+                .value_parser(["source", "stars"])
+                .required(false),
+        )
         .arg(
             Arg::new("force")
                 .short('f')
@@ -79,6 +94,8 @@ pub fn cli() -> Command {
 ///
 /// * `input_path` - The path to the input CSV file.
 /// * `output_path` - The optional path to the output CSV file. Defaults to the input path with ".non-forks.csv" appended.
+/// * `forks` - The name of the column storing whether projects are forks.
+/// * `network` - If set, keeps one project per fork network instead of discarding forks, chosen by "source" or "stars".
 /// * `force` - Whether to override the output file if it already exists.
 /// * `no_output` - Whether to skip writing the output file.
 /// * `logger` - The logger displaying the progress.
@@ -90,12 +107,17 @@ pub fn run(
     input_path: &str,
     output_path: Option<&str>,
     forks: &str,
+    network: Option<&str>,
     force: bool,
     no_output: bool,
     logger: &Logger,
 ) -> Result<()> {
     let default_output_path = format!("{input_path}.non-forks.csv");
     let output_path = output_path.unwrap_or(&default_output_path);
+    logger.record_inputs(&[input_path, forks]);
+    if !no_output {
+        logger.record_outputs(&[output_path]);
+    }
 
     // Checks if the input file exists
     check_path(input_path)?;
@@ -104,18 +126,34 @@ pub fn run(
     log_output_file(output_path, no_output, force)?;
 
     // Reads the CSV file into a DataFrame
-    let mut projects: DataFrame = open_csv(
-        input_path,
-        Some(Schema::from_iter(vec![Field::new(
-            forks.into(),
-            DataType::UInt32,
-        )])),
-        None,
-    )?;
+    let schema: Schema = Schema::from_iter(vec![Field::new(forks.into(), DataType::UInt32)]);
+    let required: Vec<&str> = match network {
+        Some(_) => vec!["id", "source", "stars"],
+        None => vec![forks],
+    };
+    validate_columns(input_path, &required, &schema)?;
+    let mut projects: DataFrame = open_csv(input_path, Some(schema), None)?;
     let projects_count = projects.height();
 
     info!("{} entries found in the file.", projects_count);
 
+    if let Some(representative) = network {
+        projects = fork_network_representatives(projects, representative == "source")?;
+
+        let networks_count = projects.height();
+        info!(
+            "Fork networks: {} / {:.2} %",
+            networks_count,
+            (networks_count as f64 / projects_count as f64) * 100.0
+        );
+        info!(
+            "Other members of a fork network: {}",
+            projects_count - networks_count
+        );
+
+        return log_write_output(logger, output_path, &mut projects, no_output);
+    }
+
     // Filter out forked projects
     projects = projects
         .lazy()
@@ -141,6 +179,51 @@ pub fn run(
     log_write_output(logger, output_path, &mut projects, no_output)
 }
 
+/// Keeps a single project per fork network, in the order of the input.
+/// A fork network contains a root project and all the projects whose source is this root project.
+///
+/// # Arguments
+///
+/// * `projects` - The projects, with the columns 'id', 'source' and 'stars'.
+/// * `prefer_source` - Whether to keep the root project of the network when it is present.
+///   Otherwise, or if it is absent, the most starred project is kept, with ties broken by the smallest id.
+///
+/// # Returns
+///
+/// The representatives of the fork networks.
+fn fork_network_representatives(projects: DataFrame, prefer_source: bool) -> Result<DataFrame> {
+    ensure!(
+        has_columns(&projects, ["id", "source", "stars"]),
+        "Input file must contain the columns id, source and stars to deduplicate fork networks"
+    );
+
+    let sorted: DataFrame = projects
+        .lazy()
+        .with_row_index("row_index", None)
+        .with_column(
+            when(col("source").eq(lit(0)))
+                .then(col("id"))
+                .otherwise(col("source"))
+                .alias("network"),
+        )
+        .with_column((col("id").eq(col("network")).and(lit(prefer_source))).alias("is_source"))
+        .sort_by_exprs(
+            vec![col("is_source"), col("stars"), col("id")],
+            SortMultipleOptions::default().with_order_descending_multi([true, true, false]),
+        )
+        .collect()
+        .with_context(|| "Could not sort fork networks")?;
+
+    // The first project of each network in the sorted order is its representative.
+    sorted
+        .unique_stable(Some(&["network".into()]), UniqueKeepStrategy::First, None)?
+        .lazy()
+        .sort(["row_index"], SortMultipleOptions::default())
+        .drop(vec!["row_index", "network", "is_source"])
+        .collect()
+        .with_context(|| "Could not deduplicate fork networks")
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -154,7 +237,7 @@ mod tests {
         let default_output_path = format!("{input_path}.non-forks.csv");
 
         delete_file(&default_output_path, true)?;
-        run(input_path, None, "fork", false, false, test_logger())?;
+        run(input_path, None, "fork", None, false, false, test_logger())?;
 
         let expected_df = open_csv(&format!("{default_output_path}.expected"), None, None)?;
 
@@ -167,4 +250,41 @@ mod tests {
 
         delete_file(&default_output_path, false)
     }
+
+    #[test]
+    fn fork_networks() -> Result<()> {
+        let input_path = "tests/data/phases/forks/networks.csv";
+
+        for representative in ["source", "stars"] {
+            let output_path = format!("{input_path}.{representative}.csv");
+            delete_file(&output_path, true)?;
+            run(
+                input_path,
+                Some(&output_path),
+                "fork",
+                Some(representative),
+                false,
+                false,
+                test_logger(),
+            )?;
+
+            let expected_df = open_csv(&format!("{output_path}.expected"), None, None)?;
+            let output_df = open_csv(&output_path, None, None)?;
+            assert_eq!(expected_df, output_df);
+
+            delete_file(&output_path, false)?;
+        }
+
+        assert!(run(
+            "tests/data/phases/forks/forks.csv",
+            None,
+            "fork",
+            Some("stars"),
+            false,
+            true,
+            test_logger()
+        )
+        .is_err());
+        Ok(())
+    }
 }
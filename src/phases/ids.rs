@@ -14,28 +14,31 @@
 
 #![doc = include_str!("../docs/ids.md")]
 
-use anyhow::{anyhow, bail, Context, Result};
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use chrono::{Days, NaiveDate, Utc};
 use clap::ArgAction;
 use clap::{Arg, Command};
-use indicatif::ProgressBar;
 use json::JsonValue;
 use polars::prelude::DataFrame;
 use polars::prelude::DataType;
 use polars::prelude::Field;
 use polars::prelude::Schema;
+use std::collections::HashSet;
 use std::fmt::Write as _;
 use std::io::Write;
 use std::iter::FromIterator as _;
 use std::path::Path;
-use tracing::info;
+use tracing::{info, warn};
 
 use crate::utils::csv::*;
 use crate::utils::dataframes;
 use crate::utils::fs::*;
 use crate::utils::github::*;
 use crate::utils::github_api::Github;
+use crate::utils::github_app::{auth_args, Auth};
 use crate::utils::json::*;
 use crate::utils::logger::{log_seed, Logger};
+use crate::utils::progress::Progress;
 use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
 
@@ -60,9 +63,10 @@ pub fn cli() -> Command {
                 .long("tokens")
                 .value_name("TOKENS_FILE.csv")
                 .help("Path to the file containing the GitHub tokens to use for querrying GitHub REST API.\n\
-                       It must be a valid CSV file, with a column named 'token' where every entry is a valid GitHub token.")
-                .required(true)
+                       It must be a valid CSV file, with a column named 'token' where every entry is a valid GitHub token.\n\
+                       Without tokens, or with 'none', the requests are not authenticated and limited to 60 per hour.")
         )
+        .args(auth_args())
         .arg(
             Arg::new("seed")
                 .short('s')
@@ -102,9 +106,32 @@ pub fn cli() -> Command {
             Arg::new("mode")
             .long("mode")
             .value_name("MODE")
-            .help("Sampling mode. 'linear' to sample ids in sequential order, 'random' to sample ids at random.")
+            .help("Sampling mode. 'linear' to sample ids in sequential order, 'random' to sample ids at random, \
+                   'search' to collect the repositories matching a query of the search API.")
             .default_value("random")
-            .value_parser(["linear", "random"]),
+            .value_parser(["linear", "random", "search"]),
+        )
+        .arg(
+            Arg::new("query")
+                .long("query")
+                .value_name("QUERY")
+                .help("In search mode, query of the repository search API, e.g. 'language:c stars:>100 pushed:>2026-01-01'.")
+                .required_if_eq("mode", "search"),
+        )
+        .arg(
+            Arg::new("created-from")
+                .long("created-from")
+                .value_name("YYYY-MM-DD")
+                .help("In search mode, creation date of the oldest repositories to collect.")
+                .default_value("2008-01-01")
+                .value_parser(parse_date),
+        )
+        .arg(
+            Arg::new("created-to")
+                .long("created-to")
+                .value_name("YYYY-MM-DD")
+                .help("In search mode, creation date of the most recent repositories to collect. Defaults to today.")
+                .value_parser(parse_date),
         )
         .arg(
             Arg::new("force")
@@ -114,6 +141,36 @@ pub fn cli() -> Command {
                 .default_value("false")
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("skip-forks")
+                .long("skip-forks")
+                .help("Do not record the repositories that are forks.")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("owner-type")
+                .long("owner-type")
+                .value_name("TYPE")
+                .help("Only record the repositories owned by users or by organizations.")
+                .value_parser(["User", "Organization"]),
+        )
+        .arg(
+            Arg::new("resume-from")
+                .long("resume-from")
+                .value_name("ID")
+                .help("In linear mode, resume the sampling from this id instead of the id recorded by the previous run.")
+                .conflicts_with("force")
+                .value_parser(clap::value_parser!(u32))
+        )
+        .arg(
+            Arg::new("resume-request")
+                .long("resume-request")
+                .value_name("REQUEST_NUMBER")
+                .help("In random mode, resume the sampling at this request number instead of the one recorded by the previous run. \
+                       The random ids of the previous requests are generated and discarded.")
+                .conflicts_with("force")
+                .value_parser(clap::value_parser!(usize))
+        )
 }
 
 /// Main function
@@ -121,33 +178,84 @@ pub fn cli() -> Command {
 /// # Arguments
 ///
 /// * `output_path` - Path to the output CSV file.
-/// * `tokens` - Path to the file containing GitHub tokens.
+/// * `auth` - The credentials used to query the GitHub API.
 /// * `seed` - Random seed used to generate the random ids.
 /// * `min_id` - Minimum id to sample.
 /// * `max_id` - Maximum id to sample.
 /// * `n` - Number of ids to sample. If not defined, the program runs indefinitely.
-/// * `mode` - Sampling mode. 'linear' to sample ids in sequential order, 'random' to sample ids at random.
+/// * `mode` - Sampling mode. 'linear' to sample ids in sequential order, 'random' to sample ids at random,
+///   'search' to collect the repositories matching a search query.
 /// * `force` - If true, overwrite the output file, append otherwise.
+/// * `resume_from` - In linear mode, the id to resume the sampling from, overriding the progress of the previous run.
+/// * `resume_request` - In random mode, the request number to resume the sampling at, overriding the progress of the previous run.
+/// * `filters` - The filters the repositories must pass to be recorded.
+/// * `search` - The query and the creation dates of the repositories collected in search mode.
 /// * `logger` - Logger printing to standard output.
 ///
 pub fn run(
     output_path: &str,
-    tokens: &str,
+    auth: &Auth,
     seed: u64,
     min_id: u32,
     max_id: u32,
     n: Option<usize>,
     mode: &str,
     force: bool,
+    resume_from: Option<u32>,
+    resume_request: Option<usize>,
+    filters: &IdFilters,
+    search: Option<&SearchQuery>,
     logger: &Logger,
 ) -> Result<()> {
-    // Check if the token file is valid.
-    logger.log_tokens(tokens)?;
+    ensure!(
+        (mode == "search") == search.is_some(),
+        "A search query must be given in search mode, and only in search mode"
+    );
+    ensure!(
+        resume_from.is_none() || mode == "linear",
+        "--resume-from is only supported in linear mode"
+    );
+    ensure!(
+        resume_request.is_none() || mode == "random",
+        "--resume-request is only supported in random mode"
+    );
+
+    // Check if the credentials are valid.
+    logger.log_auth(auth)?;
+    logger.record_outputs(&[output_path]);
+
+    let mut state: SamplingState = SamplingState {
+        mode: mode.to_string(),
+        seed,
+        min_id,
+        max_id,
+        requests: 0,
+        last_id: min_id,
+        query: search.map(|search| search.query.clone()),
+        searched_until: None,
+    };
+    let state_path: String = SamplingState::path(output_path);
+    let saved_state: Option<SamplingState> = if force {
+        None
+    } else {
+        SamplingState::load(&state_path)?.filter(|saved| saved.continues(&state))
+    };
 
-    // Load the previous results if the file exists.
+    // Output files of earlier versions do not record when the projects were collected.
+    if !force && Path::new(output_path).exists() {
+        add_missing_column(output_path, "collected_at", "0")?;
+    }
+
+    // Load the previous results from the state file, or from the output file if it exists.
     let (mut last_id, mut requests): (u32, usize) = if force {
         info!("Overwriting previous results");
         (min_id, 0)
+    } else if let Some(saved) = &saved_state {
+        info!(
+            "Resuming from {state_path}: {} requests already sent.",
+            saved.requests
+        );
+        (saved.last_id, saved.requests)
     } else if Path::new(output_path).exists() {
         let input_df: DataFrame = logger.run_task("Loading previous results", || {
             open_csv(
@@ -157,6 +265,7 @@ pub fn run(
                     Field::new("name".into(), DataType::String),
                     Field::new("fork".into(), DataType::UInt32),
                     Field::new("request_number".into(), DataType::UInt32),
+                    Field::new("collected_at".into(), DataType::Int64),
                 ])),
                 Some(ProjectInfo::header().to_vec()),
             )
@@ -177,6 +286,14 @@ pub fn run(
         info!("No previous data found");
         (min_id, 0)
     };
+    if let Some(id) = resume_from {
+        info!("Resuming from id {id}");
+        last_id = id;
+    }
+    if let Some(request) = resume_request {
+        info!("Resuming at request {request}");
+        requests = request;
+    }
 
     match n {
         Some(n) => info!("Sampling {} ids...", n),
@@ -198,18 +315,26 @@ pub fn run(
     output_file.write_header(ProjectInfo::header())?;
 
     // Initialize Github client.
-    let gh = Github::new(tokens);
+    let gh = Github::from_auth(auth);
 
     // Create a progress bar if the number of ids to sample is known or a spinner if not.
-    let progress_bar: ProgressBar = match n {
-        Some(n) => ProgressBar::new(n as u64),
-        None => ProgressBar::new_spinner(),
-    };
+    let progress_bar: Progress = logger.progress_bar(n.map(|n| n as u64))?;
 
-    if n.is_some() {
-        progress_bar.set_style(
-            indicatif::ProgressStyle::default_bar().template("{elapsed} {wide_bar} {percent}%")?,
-        )
+    if let Some(search) = search {
+        state.requests = requests;
+        state.searched_until = saved_state.and_then(|saved| saved.searched_until);
+        return search_ids(
+            &gh,
+            search,
+            filters,
+            n,
+            output_path,
+            &mut output_file,
+            &mut state,
+            &state_path,
+            &progress_bar,
+            logger,
+        );
     }
 
     // If the program was interrupted, the rng will be in the same state as before.
@@ -268,7 +393,7 @@ pub fn run(
                 // Number of repositories in the response.
                 let response_size = repos.len();
 
-                // Skipped null responses
+                // Skipped null responses and repositories filtered out
                 let mut skipped: usize = 0;
 
                 // If the response is an array, process each repository.
@@ -278,8 +403,13 @@ pub fn run(
                     } else {
                         let project_info: ProjectInfo = ProjectInfo::parse_json(repo, ())?;
                         last_id = project_info.id as u32;
-                        // Write the row in the CSV file.
-                        writeln!(&mut builder, "{}", project_info.to_csv(requests))?;
+                        if filters.keeps(repo)? {
+                            // Write the row in the CSV file.
+                            writeln!(&mut builder, "{}", project_info.to_csv(requests))?;
+                        } else {
+                            logger.record_count("repositories filtered out", 1);
+                            skipped += 1;
+                        }
                     }
                 }
 
@@ -310,11 +440,274 @@ pub fn run(
         }
 
         requests += 1;
+
+        // The progress is saved after every request, so that the sampling resumes after the last request sent.
+        // The ids are written first, so that the state never claims ids missing from the output file.
+        output_file
+            .flush()
+            .with_context(|| format!("Could not write to file {output_path}"))?;
+        state.requests = requests;
+        state.last_id = last_id;
+        state.save(&state_path)?;
     }
 
     Ok(())
 }
 
+/// Maximal number of results of a query of the search API.
+const MAX_SEARCH_RESULTS: usize = 1000;
+
+/// Query of the search mode.
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    /// Query of the repository search API.
+    pub query: String,
+    /// Creation date of the oldest repositories to collect.
+    pub created_from: NaiveDate,
+    /// Creation date of the most recent repositories to collect.
+    pub created_to: NaiveDate,
+}
+
+/// Parses a date of the form YYYY-MM-DD.
+pub fn parse_date(date: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .with_context(|| format!("Invalid date {date}, expected YYYY-MM-DD"))
+}
+
+/// Collects the repositories matching a query of the search API.
+///
+/// The search API returns at most 1000 results per query. The range of creation dates is therefore sliced,
+/// in chronological order, into ranges of days matching at most 1000 repositories each, by halving the range
+/// until it is small enough. The repositories of a slice are written once the slice is complete, and the end of
+/// the last complete slice is saved in the state file, so that an interrupted run resumes after it.
+/// Repositories already in the output file are not written again.
+///
+/// # Arguments
+///
+/// * `gh` - The GitHub client.
+/// * `search` - The query and the range of creation dates.
+/// * `filters` - The filters the repositories must pass to be recorded.
+/// * `n` - Number of ids to collect. If not defined, all the matching repositories are collected.
+/// * `output_path` - The path of the output file.
+/// * `output_file` - The output file.
+/// * `state` - The progress of the sampling, saved after every slice.
+/// * `state_path` - The path of the state file.
+/// * `progress_bar` - The progress bar to advance.
+/// * `logger` - The logger recording the counts of the run.
+fn search_ids(
+    gh: &Github,
+    search: &SearchQuery,
+    filters: &IdFilters,
+    n: Option<usize>,
+    output_path: &str,
+    output_file: &mut CSVFile,
+    state: &mut SamplingState,
+    state_path: &str,
+    progress_bar: &Progress,
+    logger: &Logger,
+) -> Result<()> {
+    let mut known_ids: HashSet<u32> = if Path::new(output_path).exists() {
+        CSVFile::new(output_path, FileMode::Read)?
+            .column::<u32>(0)?
+            .into_iter()
+            .collect()
+    } else {
+        HashSet::new()
+    };
+    let mut remaining: Option<usize> = n;
+    let mut from: NaiveDate = match state.searched_until {
+        Some(until) => {
+            info!("Resuming the search after {until}");
+            until + Days::new(1)
+        }
+        None => search.created_from,
+    };
+
+    while from <= search.created_to && remaining.is_none_or(|r| r > 0) {
+        // Halve the slice until it matches few enough repositories to be listed entirely.
+        let mut to: NaiveDate = search.created_to;
+        let (mut page, mut next): (JsonValue, Option<String>) = loop {
+            let url: reqwest::Url = reqwest::Url::parse_with_params(
+                "https://api.github.com/search/repositories",
+                &[
+                    ("q", format!("{} created:{from}..{to}", search.query)),
+                    ("per_page", "100".to_string()),
+                ],
+            )?;
+            state.requests += 1;
+            let (page, next) = gh.request_page(url.as_str()).with_context(|| {
+                format!("Could not search repositories created in {from}..{to}")
+            })?;
+            let total: usize = get_field::<u64>(&page, "total_count")? as usize;
+            if total <= MAX_SEARCH_RESULTS || from == to {
+                if total > MAX_SEARCH_RESULTS {
+                    warn!("{total} repositories created on {from} match the query, only the first {MAX_SEARCH_RESULTS} are collected");
+                }
+                break (page, next);
+            }
+            to = from + Days::new((to - from).num_days() as u64 / 2);
+        };
+
+        let mut builder: String = String::new();
+        loop {
+            for repo in page["items"].members() {
+                if remaining == Some(0) {
+                    break;
+                }
+                let project_info: ProjectInfo = ProjectInfo::parse_json(repo, ())?;
+                if !known_ids.insert(project_info.id as u32) {
+                    continue;
+                }
+                if filters.keeps(repo)? {
+                    writeln!(&mut builder, "{}", project_info.to_csv(state.requests - 1))?;
+                    remaining = remaining.map(|r| r - 1);
+                    match remaining {
+                        Some(_) => progress_bar.inc(1),
+                        None => progress_bar.tick(),
+                    }
+                } else {
+                    logger.record_count("repositories filtered out", 1);
+                }
+            }
+            match next.take() {
+                Some(url) if remaining != Some(0) => {
+                    state.requests += 1;
+                    (page, next) = gh
+                        .request_page(&url)
+                        .with_context(|| format!("Could not request {url}"))?;
+                }
+                _ => break,
+            }
+        }
+        write!(output_file, "{builder}")
+            .with_context(|| format!("Could not write to file {output_path}"))?;
+        output_file
+            .flush()
+            .with_context(|| format!("Could not write to file {output_path}"))?;
+
+        // A slice interrupted by the number of ids is collected again by the next run.
+        if remaining != Some(0) {
+            state.searched_until = Some(to);
+        }
+        state.save(state_path)?;
+        from = to + Days::new(1);
+    }
+    Ok(())
+}
+
+/// Filters applied to the repositories returned by the list endpoint or the search API before they are recorded.
+/// The repositories of the list endpoint do not include their size, their language or their number of stars,
+/// which are only filtered after the `metadata` subcommand, or with the qualifiers of the query in search mode.
+#[derive(Debug, Clone, Default)]
+pub struct IdFilters {
+    /// Whether to skip the forks.
+    pub skip_forks: bool,
+    /// The type of the owners of the recorded repositories ("User" or "Organization"), if restricted.
+    pub owner_type: Option<String>,
+}
+
+impl IdFilters {
+    /// Checks whether a repository of the list endpoint passes the filters.
+    ///
+    /// # Arguments
+    ///
+    /// * `repo` - The JSON object describing the repository.
+    fn keeps(&self, repo: &JsonValue) -> Result<bool> {
+        if self.skip_forks && get_field::<bool>(repo, "fork")? {
+            return Ok(false);
+        }
+        if let Some(owner_type) = &self.owner_type {
+            if &get_field::<String>(&repo["owner"], "type")? != owner_type {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Progress of the sampling, saved in a sidecar file of the output file so that an interrupted run
+/// resumes independently of the content of the output file.
+#[derive(Debug, PartialEq)]
+struct SamplingState {
+    /// Sampling mode.
+    mode: String,
+    /// Seed of the random ids.
+    seed: u64,
+    /// Minimum id to sample.
+    min_id: u32,
+    /// Maximum id to sample.
+    max_id: u32,
+    /// Number of requests sent, which is also the number of random ids generated.
+    requests: usize,
+    /// Last id sampled.
+    last_id: u32,
+    /// In search mode, the query of the search API.
+    query: Option<String>,
+    /// In search mode, the creation date of the most recent repositories of the slices already collected.
+    searched_until: Option<NaiveDate>,
+}
+
+impl SamplingState {
+    /// Returns the path of the state file of an output file.
+    fn path(output_path: &str) -> String {
+        format!("{output_path}.state.json")
+    }
+
+    /// Loads the state saved by a previous run, if any.
+    fn load(path: &str) -> Result<Option<Self>> {
+        if !Path::new(path).exists() {
+            return Ok(None);
+        }
+        let value: JsonValue = open_json_from_path(path)?;
+        let field = |name: &str| {
+            value[name]
+                .as_u64()
+                .with_context(|| format!("Missing field {name} in state file {path}"))
+        };
+        Ok(Some(Self {
+            mode: value["mode"]
+                .as_str()
+                .with_context(|| format!("Missing field mode in state file {path}"))?
+                .to_string(),
+            seed: field("seed")?,
+            min_id: field("min_id")? as u32,
+            max_id: field("max_id")? as u32,
+            requests: field("requests")? as usize,
+            last_id: field("last_id")? as u32,
+            query: value["query"].as_str().map(String::from),
+            searched_until: value["searched_until"]
+                .as_str()
+                .map(parse_date)
+                .transpose()?,
+        }))
+    }
+
+    /// Saves the state.
+    fn save(&self, path: &str) -> Result<()> {
+        let value: JsonValue = json::object! {
+            "mode": self.mode.as_str(),
+            "seed": self.seed,
+            "min_id": self.min_id,
+            "max_id": self.max_id,
+            "requests": self.requests,
+            "last_id": self.last_id,
+            "query": self.query.as_deref(),
+            "searched_until": self.searched_until.map(|date| date.to_string()),
+        };
+        write_file(path, value.pretty(2))
+    }
+
+    /// Checks whether a run with the parameters of `other` continues the sampling of this state.
+    /// Otherwise, the state is ignored, since the ids of the previous requests would be different.
+    fn continues(&self, other: &Self) -> bool {
+        self.mode == other.mode
+            && self.min_id == other.min_id
+            && self.max_id == other.max_id
+            && (self.mode != "random" || self.seed == other.seed)
+            && self.query == other.query
+    }
+}
+
 /// Information about a GitHub project.
 struct ProjectInfo {
     /// Project ID.
@@ -330,11 +723,18 @@ impl ToCSV for ProjectInfo {
     type Key = usize;
 
     fn header() -> &'static [&'static str] {
-        &["id", "name", "fork", "request_number"]
+        &["id", "name", "fork", "request_number", "collected_at"]
     }
 
     fn to_csv(&self, request_number: Self::Key) -> String {
-        format!("{},{},{},{}", self.id, self.name, self.fork, request_number)
+        format!(
+            "{},{},{},{},{}",
+            self.id,
+            self.name,
+            self.fork,
+            request_number,
+            Utc::now().timestamp()
+        )
     }
 }
 impl Default for ProjectInfo {
@@ -368,8 +768,6 @@ impl FromGitHub for ProjectInfo {
 
 #[cfg(test)]
 mod tests {
-    use std::fs;
-
     use super::*;
     use crate::utils::logger::test_logger;
 
@@ -377,85 +775,181 @@ mod tests {
     const TOKENS: &str = "ghtokens.csv";
     const SEED: u64 = 113722657;
 
+    /// Loads the ids sampled in an output file, without the time of collection which differs between runs.
+    fn sampled_ids(path: &str) -> Result<DataFrame> {
+        Ok(open_csv(path, None, None)?.drop("collected_at")?)
+    }
+
     #[test]
     fn test_random_ids() -> Result<()> {
         let id_half = format!("{TEST_DATA}/id_random_1.csv");
         let id_full = format!("{TEST_DATA}/id_random_2.csv");
         let id_force = format!("{TEST_DATA}/id_random_3.csv");
 
-        delete_file(&id_half, true)?;
-        delete_file(&id_full, true)?;
-        delete_file(&id_force, true)?;
+        for path in [&id_half, &id_full, &id_force] {
+            delete_file(path, true)?;
+            delete_file(SamplingState::path(path), true)?;
+        }
 
         run(
             &id_half,
-            TOKENS,
+            &Auth::Tokens(TOKENS.to_string()),
             SEED,
             0,
             871212690,
             Some(280),
             "random",
             false,
+            None,
+            None,
+            &IdFilters::default(),
+            None,
             test_logger(),
         )?;
 
         run(
             &id_half,
-            TOKENS,
+            &Auth::Tokens(TOKENS.to_string()),
             SEED,
             0,
             871212690,
             Some(280),
             "random",
             false,
+            None,
+            None,
+            &IdFilters::default(),
+            None,
             test_logger(),
         )?;
 
         run(
             &id_full,
-            TOKENS,
+            &Auth::Tokens(TOKENS.to_string()),
             SEED,
             0,
             871212690,
             Some(500),
             "random",
             false,
+            None,
+            None,
+            &IdFilters::default(),
+            None,
             test_logger(),
         )?;
 
         run(
             &id_force,
-            TOKENS,
+            &Auth::Tokens(TOKENS.to_string()),
             SEED,
             0,
             871212690,
             Some(1000),
             "random",
             true,
+            None,
+            None,
+            &IdFilters::default(),
+            None,
             test_logger(),
         )?;
 
         run(
             &id_half,
-            TOKENS,
+            &Auth::Tokens(TOKENS.to_string()),
             SEED,
             0,
             871212690,
             Some(500),
             "random",
             true,
+            None,
+            None,
+            &IdFilters::default(),
+            None,
             test_logger(),
         )?;
 
-        assert_eq!(fs::read_to_string(&id_half)?, fs::read_to_string(&id_full)?);
-        assert_ne!(
-            fs::read_to_string(&id_half)?,
-            fs::read_to_string(&id_force)?
-        );
+        assert_eq!(sampled_ids(&id_half)?, sampled_ids(&id_full)?);
+        assert_ne!(sampled_ids(&id_half)?, sampled_ids(&id_force)?);
+
+        for path in [&id_half, &id_full, &id_force] {
+            delete_file(path, false)?;
+            delete_file(SamplingState::path(path), false)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn sampling_state_test() -> Result<()> {
+        let path: String = SamplingState::path("target/tests/ids_state.csv");
+        delete_file(&path, true)?;
+        assert_eq!(SamplingState::load(&path)?, None);
+
+        let state = |mode: &str, seed: u64, requests: usize| SamplingState {
+            mode: mode.to_string(),
+            seed,
+            min_id: 0,
+            max_id: 1000,
+            requests,
+            last_id: 42,
+            query: None,
+            searched_until: None,
+        };
+        state("random", SEED, 7).save(&path)?;
+        let saved: SamplingState = SamplingState::load(&path)?.context("State not saved")?;
+        assert_eq!(saved, state("random", SEED, 7));
 
-        delete_file(&id_half, false)?;
-        delete_file(&id_full, false)?;
-        delete_file(&id_force, false)
+        assert!(saved.continues(&state("random", SEED, 0)));
+        assert!(!saved.continues(&state("random", SEED + 1, 0)));
+        assert!(!saved.continues(&state("linear", SEED, 0)));
+        // The seed does not change the ids sampled in linear mode.
+        assert!(state("linear", SEED, 7).continues(&state("linear", SEED + 1, 0)));
+
+        let search = |query: &str| -> Result<SamplingState> {
+            Ok(SamplingState {
+                query: Some(query.to_string()),
+                searched_until: Some(parse_date("2024-02-29")?),
+                ..state("search", SEED, 3)
+            })
+        };
+        search("language:c")?.save(&path)?;
+        let saved: SamplingState = SamplingState::load(&path)?.context("State not saved")?;
+        assert_eq!(saved, search("language:c")?);
+        assert!(saved.continues(&search("language:c")?));
+        assert!(!saved.continues(&search("language:go")?));
+        assert!(parse_date("2024-02-30").is_err());
+
+        delete_file(&path, false)
+    }
+
+    #[test]
+    fn id_filters_test() -> Result<()> {
+        let repo = |fork: bool, owner_type: &str| {
+            json::object! {
+                "id": 1,
+                "full_name": "owner/repo",
+                "fork": fork,
+                "owner": { "login": "owner", "type": owner_type },
+            }
+        };
+        assert!(IdFilters::default().keeps(&repo(true, "User"))?);
+
+        let skip_forks: IdFilters = IdFilters {
+            skip_forks: true,
+            owner_type: None,
+        };
+        assert!(skip_forks.keeps(&repo(false, "User"))?);
+        assert!(!skip_forks.keeps(&repo(true, "User"))?);
+
+        let organizations: IdFilters = IdFilters {
+            skip_forks: false,
+            owner_type: Some("Organization".to_string()),
+        };
+        assert!(organizations.keeps(&repo(true, "Organization"))?);
+        assert!(!organizations.keeps(&repo(false, "User"))?);
+        Ok(())
     }
 
     #[test]
@@ -464,78 +958,98 @@ mod tests {
         let id_full = format!("{TEST_DATA}/id_linear_2.csv");
         let id_force = format!("{TEST_DATA}/id_linear_3.csv");
 
-        delete_file(&id_half, true)?;
-        delete_file(&id_full, true)?;
-        delete_file(&id_force, true)?;
+        for path in [&id_half, &id_full, &id_force] {
+            delete_file(path, true)?;
+            delete_file(SamplingState::path(path), true)?;
+        }
 
         run(
             &id_half,
-            TOKENS,
+            &Auth::Tokens(TOKENS.to_string()),
             SEED,
             0,
             871212690,
             Some(280),
             "linear",
             false,
+            None,
+            None,
+            &IdFilters::default(),
+            None,
             test_logger(),
         )?;
 
         run(
             &id_half,
-            TOKENS,
+            &Auth::Tokens(TOKENS.to_string()),
             SEED,
             0,
             871212690,
             Some(280),
             "linear",
             false,
+            None,
+            None,
+            &IdFilters::default(),
+            None,
             test_logger(),
         )?;
 
         run(
             &id_full,
-            TOKENS,
+            &Auth::Tokens(TOKENS.to_string()),
             SEED,
             0,
             871212690,
             Some(500),
             "linear",
             false,
+            None,
+            None,
+            &IdFilters::default(),
+            None,
             test_logger(),
         )?;
 
         run(
             &id_force,
-            TOKENS,
+            &Auth::Tokens(TOKENS.to_string()),
             SEED,
             0,
             871212690,
             Some(1000),
             "linear",
             true,
+            None,
+            None,
+            &IdFilters::default(),
+            None,
             test_logger(),
         )?;
 
         run(
             &id_half,
-            TOKENS,
+            &Auth::Tokens(TOKENS.to_string()),
             SEED,
             0,
             871212690,
             Some(500),
             "linear",
             true,
+            None,
+            None,
+            &IdFilters::default(),
+            None,
             test_logger(),
         )?;
 
-        assert_eq!(fs::read_to_string(&id_half)?, fs::read_to_string(&id_full)?);
-        assert_ne!(
-            fs::read_to_string(&id_half)?,
-            fs::read_to_string(&id_force)?
-        );
+        assert_eq!(sampled_ids(&id_half)?, sampled_ids(&id_full)?);
+        assert_ne!(sampled_ids(&id_half)?, sampled_ids(&id_force)?);
 
-        delete_file(&id_half, false)?;
-        delete_file(&id_full, false)?;
-        delete_file(&id_force, false)
+        for path in [&id_half, &id_full, &id_force] {
+            delete_file(path, false)?;
+            delete_file(SamplingState::path(path), false)?;
+        }
+        Ok(())
     }
 }
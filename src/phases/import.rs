@@ -0,0 +1,420 @@
+// Copyright 2026 Andrea Gilot
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![doc = include_str!("../docs/import.md")]
+
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader, Write};
+
+use anyhow::{bail, Context, Result};
+use clap::{Arg, ArgAction, Command};
+use json::JsonValue;
+use tracing::{debug, info};
+
+use crate::utils::csv::{CSVFile, Quoting};
+use crate::utils::fs::*;
+use crate::utils::logger::{log_output_file, Logger};
+
+/// Command line arguments parsing.
+pub fn cli() -> Command {
+    Command::new("import")
+        .about("Converts a list of projects obtained outside of scyros, such as a GH Archive or BigQuery export, into an input file of the pipeline.")
+        .long_about(include_str!("../docs/import.md"))
+        .disable_version_flag(true)
+        .arg(
+            Arg::new("input")
+                .short('i')
+                .long("input")
+                .value_name("INPUT_FILE")
+                .help("Path to the list of projects, a CSV file with a header or a JSON Lines file with one JSON object per line.")
+                .required(true),
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .value_name("OUTPUT_FILE.csv")
+                .help("Path to the output csv file. By default, the suffix '.projects.csv' is appended to the input path.")
+                .required(false),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .value_name("FORMAT")
+                .help("Format of the input file. With 'auto', files with the .jsonl, .ndjson and .json extensions are read as JSON Lines \
+                       and the other files as CSV.")
+                .default_value("auto")
+                .value_parser(["auto", "csv", "jsonl"]),
+        )
+        .arg(
+            Arg::new("id-column")
+                .long("id-column")
+                .value_name("COLUMN")
+                .help("Column or, in JSON Lines files, dot-separated path of the field storing the id of the project, e.g. 'repo_id' or 'repo.id'.")
+                .default_value("id"),
+        )
+        .arg(
+            Arg::new("name-column")
+                .long("name-column")
+                .value_name("COLUMN")
+                .help("Column or path of the field storing the full name of the project (owner/repository) or its GitHub URL, e.g. 'repo_name' or 'repo.name'.")
+                .default_value("name"),
+        )
+        .arg(
+            Arg::new("commit-column")
+                .long("commit-column")
+                .value_name("COLUMN")
+                .help("Column or path of the field storing the hash of the commit to download. \
+                       If not given, the output has no latest_commit column and must be completed with the commits_head command.")
+                .required(false),
+        )
+        .arg(
+            Arg::new("force")
+                .short('f')
+                .long("force")
+                .help("Override the output file if it already exists.")
+                .default_value("false")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+/// The columns of the input file mapped to the columns of the output file.
+pub struct ColumnMapping<'a> {
+    /// Column of the ids of the projects.
+    pub id: &'a str,
+    /// Column of the full names of the projects.
+    pub name: &'a str,
+    /// Column of the commits of the projects, if any.
+    pub commit: Option<&'a str>,
+}
+
+/// Converts a list of projects into an input file of the pipeline.
+/// Rows with a missing or invalid field are skipped, and only the first row of every project id is kept.
+///
+/// # Arguments
+///
+/// * `input_path` - The path to the list of projects.
+/// * `output_path` - The optional path to the output CSV file. Defaults to the input path with the suffix '.projects.csv'.
+/// * `format` - The format of the input file: "csv", "jsonl" or "auto".
+/// * `columns` - The columns of the input file storing the ids, names and commits of the projects.
+/// * `force` - Whether to override the output file if it already exists.
+/// * `logger` - The logger displaying the progress.
+///
+/// # Returns
+///
+/// A result indicating success or failure of the operation.
+pub fn run(
+    input_path: &str,
+    output_path: Option<&str>,
+    format: &str,
+    columns: &ColumnMapping,
+    force: bool,
+    logger: &Logger,
+) -> Result<()> {
+    let default_output_path: String = format!("{input_path}.projects.csv");
+    let output_path: &str = output_path.unwrap_or(&default_output_path);
+    logger.record_inputs(&[input_path]);
+    logger.record_outputs(&[output_path]);
+
+    check_path(input_path)?;
+    log_output_file(output_path, false, force)?;
+
+    let jsonl: bool = match format {
+        "jsonl" => true,
+        "csv" => false,
+        _ => [".jsonl", ".ndjson", ".json"]
+            .iter()
+            .any(|ext| input_path.to_lowercase().ends_with(ext)),
+    };
+    let records: Vec<Vec<Option<String>>> = logger.run_task("Loading input file", || {
+        let fields: Vec<&str> = [columns.id, columns.name]
+            .into_iter()
+            .chain(columns.commit)
+            .collect();
+        if jsonl {
+            read_jsonl(input_path, &fields)
+        } else {
+            read_csv(input_path, &fields)
+        }
+    })?;
+    info!("{} rows found.", records.len());
+    logger.record_count("rows read", records.len() as u64);
+
+    let mut output_file: CSVFile = CSVFile::new(output_path, FileMode::Overwrite)?;
+    output_file.write_header(if columns.commit.is_some() {
+        &["id", "name", "latest_commit"]
+    } else {
+        &["id", "name"]
+    })?;
+
+    let mut seen: HashSet<u32> = HashSet::new();
+    let mut duplicates: u64 = 0;
+    for (i, record) in records.iter().enumerate() {
+        let project: Option<(u32, String, Option<String>)> =
+            parse_project(record, columns.commit.is_some());
+        match project {
+            Some((id, name, commit)) => {
+                if !seen.insert(id) {
+                    duplicates += 1;
+                    continue;
+                }
+                write!(output_file, "{id},{}", Quoting::Rfc4180.escape(&name))?;
+                if let Some(commit) = commit {
+                    write!(output_file, ",{commit}")?;
+                }
+                writeln!(output_file)?;
+            }
+            None => {
+                logger.record_error("invalid row");
+                debug!("Row {} of {input_path} skipped: {record:?}", i + 1);
+            }
+        }
+    }
+
+    logger.record_count("projects imported", seen.len() as u64);
+    logger.record_count("duplicate rows", duplicates);
+    info!(
+        "{} projects imported, {} duplicate rows and {} invalid rows skipped.",
+        seen.len(),
+        duplicates,
+        records.len() - seen.len() - duplicates as usize
+    );
+    Ok(())
+}
+
+/// Validates the fields of a row of the input file.
+///
+/// # Arguments
+///
+/// * `record` - The id, the name and, if `has_commit` is set, the commit of the project.
+/// * `has_commit` - Whether the row has a commit.
+///
+/// # Returns
+///
+/// The id, the normalized name and the lowercase commit of the project, or None if a field is missing or invalid.
+fn parse_project(
+    record: &[Option<String>],
+    has_commit: bool,
+) -> Option<(u32, String, Option<String>)> {
+    let id: u32 = record[0].as_deref()?.trim().parse().ok()?;
+    let name: String = normalize_name(record[1].as_deref()?)?;
+    let commit: Option<String> = if has_commit {
+        let commit: &str = record[2].as_deref()?.trim();
+        if commit.len() != 40 || !commit.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        Some(commit.to_lowercase())
+    } else {
+        None
+    };
+    Some((id, name, commit))
+}
+
+/// Reads the given columns of a CSV file with a header.
+///
+/// # Arguments
+///
+/// * `input_path` - The path to the CSV file.
+/// * `columns` - The names of the columns to read.
+///
+/// # Returns
+///
+/// The values of the columns of every row, None for empty fields.
+fn read_csv(input_path: &str, columns: &[&str]) -> Result<Vec<Vec<Option<String>>>> {
+    let mut reader = csv::Reader::from_reader(open_file(input_path, FileMode::Read)?);
+    let header: Vec<String> = reader.headers()?.iter().map(|h| h.to_string()).collect();
+    let indices: Vec<usize> = columns
+        .iter()
+        .map(|column| {
+            header
+                .iter()
+                .position(|h| h == column)
+                .with_context(|| format!("File {input_path} does not contain column '{column}'."))
+        })
+        .collect::<Result<_>>()?;
+
+    reader
+        .records()
+        .enumerate()
+        .map(|(i, record)| {
+            let record =
+                record.with_context(|| format!("Could not parse row {i} of {input_path}"))?;
+            Ok(indices
+                .iter()
+                .map(|idx| record.get(*idx).filter(|f| !f.is_empty()).map(String::from))
+                .collect())
+        })
+        .collect()
+}
+
+/// Reads the given fields of a JSON Lines file.
+///
+/// # Arguments
+///
+/// * `input_path` - The path to the JSON Lines file.
+/// * `fields` - The dot-separated paths of the fields to read, e.g. 'repo.id'.
+///
+/// # Returns
+///
+/// The values of the fields of every object, None for missing or null fields. Numbers are converted to strings.
+fn read_jsonl(input_path: &str, fields: &[&str]) -> Result<Vec<Vec<Option<String>>>> {
+    let mut records: Vec<Vec<Option<String>>> = Vec::new();
+    for (i, line) in BufReader::new(open_file(input_path, FileMode::Read)?)
+        .lines()
+        .enumerate()
+    {
+        let line: String = line.with_context(|| format!("Could not read {input_path}"))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let object: JsonValue = json::parse(&line)
+            .with_context(|| format!("Could not parse line {} of {input_path}", i + 1))?;
+        if !object.is_object() {
+            bail!("Line {} of {input_path} is not a JSON object", i + 1);
+        }
+        records.push(
+            fields
+                .iter()
+                .map(|field| {
+                    let value: &JsonValue = field.split('.').fold(&object, |v, key| &v[key]);
+                    if value.is_string() {
+                        value.as_str().map(String::from)
+                    } else if value.is_number() {
+                        Some(value.dump())
+                    } else {
+                        None
+                    }
+                })
+                .collect(),
+        );
+    }
+    Ok(records)
+}
+
+/// Normalizes the full name of a project, given as owner/repository or as a GitHub URL.
+///
+/// # Arguments
+///
+/// * `name` - The name or URL of the project.
+///
+/// # Returns
+///
+/// The full name of the project, or None if it is not of the form owner/repository.
+fn normalize_name(name: &str) -> Option<String> {
+    let name: &str = name.trim();
+    let name: &str = ["https://", "http://"]
+        .iter()
+        .find_map(|scheme| name.strip_prefix(scheme))
+        .unwrap_or(name);
+    let name: &str = name.strip_prefix("www.").unwrap_or(name);
+    let name: &str = ["github.com/", "api.github.com/repos/"]
+        .iter()
+        .find_map(|host| name.strip_prefix(host))
+        .unwrap_or(name);
+    let name: &str = name.trim_end_matches('/');
+    let name: &str = name.strip_suffix(".git").unwrap_or(name);
+    match name.split_once('/') {
+        Some((owner, repo)) if !owner.is_empty() && !repo.is_empty() && !repo.contains('/') => {
+            Some(name.to_string())
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::logger::test_logger;
+
+    const TEST_DATA: &str = "tests/data/phases/import";
+
+    #[test]
+    fn import_test() -> Result<()> {
+        let output_path: &str = "target/tests/import.projects.csv";
+
+        // GH Archive events, with the project of every event and no commit.
+        run(
+            &format!("{TEST_DATA}/events.jsonl"),
+            Some(output_path),
+            "auto",
+            &ColumnMapping {
+                id: "repo.id",
+                name: "repo.url",
+                commit: None,
+            },
+            true,
+            test_logger(),
+        )?;
+        assert_eq!(
+            std::fs::read_to_string(output_path)?,
+            "id,name\n1296269,octocat/Hello-World\n28688495,gilot/scyros\n"
+        );
+
+        // BigQuery export with other column names.
+        run(
+            &format!("{TEST_DATA}/bigquery.csv"),
+            Some(output_path),
+            "auto",
+            &ColumnMapping {
+                id: "repo_id",
+                name: "repo_name",
+                commit: Some("head_sha"),
+            },
+            true,
+            test_logger(),
+        )?;
+        assert_eq!(
+            std::fs::read_to_string(output_path)?,
+            "id,name,latest_commit\n\
+             1296269,octocat/Hello-World,7fd1a60b01f91b314f59955a4e4d4e80d8edf11d\n\
+             28688495,gilot/scyros,553c2077f0edc3d5dc5d17262f6aa498e69d6f8e\n"
+        );
+
+        assert!(run(
+            &format!("{TEST_DATA}/bigquery.csv"),
+            Some(output_path),
+            "csv",
+            &ColumnMapping {
+                id: "id",
+                name: "repo_name",
+                commit: None,
+            },
+            true,
+            test_logger(),
+        )
+        .is_err());
+
+        delete_file(output_path, false)
+    }
+
+    #[test]
+    fn normalize_name_test() {
+        assert_eq!(normalize_name("owner/repo"), Some("owner/repo".to_string()));
+        assert_eq!(
+            normalize_name("https://github.com/owner/repo.git"),
+            Some("owner/repo".to_string())
+        );
+        assert_eq!(
+            normalize_name(" github.com/owner/repo/ "),
+            Some("owner/repo".to_string())
+        );
+        assert_eq!(
+            normalize_name("https://api.github.com/repos/owner/repo"),
+            Some("owner/repo".to_string())
+        );
+        assert_eq!(normalize_name("owner"), None);
+        assert_eq!(normalize_name("owner/repo/tree/main"), None);
+        assert_eq!(normalize_name("/repo"), None);
+    }
+}
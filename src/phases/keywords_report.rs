@@ -0,0 +1,338 @@
+// Copyright 2026 Andrea Gilot
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![doc = include_str!("../docs/keywords_report.md")]
+
+use anyhow::{ensure, Context, Result};
+use clap::{Arg, ArgAction, Command};
+use polars::frame::DataFrame;
+use polars::prelude::{Column, DataType};
+use tracing::info;
+
+use crate::utils::dataframes::has_columns;
+use crate::utils::fs::*;
+use crate::utils::logger::{log_output_file, log_write_output, Logger};
+
+/// Maximum number of keyword columns, the output file has one row per combination of columns.
+const MAX_KEYWORD_COLUMNS: usize = 16;
+
+/// Command line arguments parsing.
+pub fn cli() -> Command {
+    Command::new("keywords_report")
+        .about("Counts the functions matching each combination of keyword files")
+        .long_about(include_str!("../docs/keywords_report.md"))
+        .disable_version_flag(true)
+        .arg(
+            Arg::new("input")
+                .short('i')
+                .long("input")
+                .value_name("INPUT_FILE.csv")
+                .help("Path to the csv file storing the functions statistics produced by the parse command.")
+                .required(true),
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .value_name("OUTPUT_FILE.csv")
+                .help("Path to the output csv file storing the contingency table. \
+                       By default, the name of the output file is the same as the input file with the suffix '.keywords_report.csv'.")
+                .required(false),
+        )
+        .arg(
+            Arg::new("columns")
+                .long("columns")
+                .value_name("COLUMN_NAMES")
+                .help("Names of the columns storing the number of matches of each keyword file. \
+                       By default, all the columns between 'words' and 'loop_statements' are used.")
+                .num_args(1..)
+                .required(false),
+        )
+        .arg(
+            Arg::new("force")
+                .short('f')
+                .long("force")
+                .help("Override the output file if it already exists.")
+                .default_value("false")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("no-output")
+                .long("no-output")
+                .help("Does not write the output file. Prints statistics only.")
+                .default_value("false")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(vec!["output", "force"]),
+        )
+}
+
+/// Counts the functions matching each combination of keyword files.
+///
+/// # Arguments
+///
+/// * `input_path` - The path to the functions statistics produced by the parse command.
+/// * `output_path` - The optional path to the output CSV file. Defaults to the input path with ".keywords_report.csv" appended.
+/// * `columns` - The names of the keyword columns. If not specified, the columns between 'words' and 'loop_statements' are used.
+/// * `force` - Whether to override the output file if it already exists.
+/// * `no_output` - Whether to skip writing the output file.
+/// * `logger` - The logger displaying the progress.
+///
+/// # Returns
+///
+/// A result indicating success or failure of the operation.
+pub fn run(
+    input_path: &str,
+    output_path: Option<&str>,
+    columns: Option<Vec<&str>>,
+    force: bool,
+    no_output: bool,
+    logger: &Logger,
+) -> Result<()> {
+    let default_output_path = format!("{input_path}.keywords_report.csv");
+    let output_path = output_path.unwrap_or(&default_output_path);
+    logger.record_inputs(&[input_path]);
+    if !no_output {
+        logger.record_outputs(&[output_path]);
+    }
+
+    // Checks if the input file exists
+    check_path(input_path)?;
+
+    // Checks if the output file already exists
+    log_output_file(output_path, no_output, force)?;
+
+    let functions: DataFrame =
+        logger.run_task("Loading input file", || open_csv(input_path, None, None))?;
+
+    let keyword_columns: Vec<String> = match columns {
+        Some(cols) => {
+            ensure!(
+                has_columns(&functions, cols.iter().copied()),
+                "The input file does not contain all the columns {}",
+                cols.join(", ")
+            );
+            cols.into_iter().map(|c| c.to_string()).collect()
+        }
+        None => default_keyword_columns(&functions)?,
+    };
+
+    ensure!(
+        !keyword_columns.is_empty(),
+        "No keyword column found in {input_path}"
+    );
+    ensure!(
+        keyword_columns.len() <= MAX_KEYWORD_COLUMNS,
+        "At most {MAX_KEYWORD_COLUMNS} keyword columns are supported, {} were given",
+        keyword_columns.len()
+    );
+
+    info!(
+        "{} functions and {} keyword files found in the file.",
+        functions.height(),
+        keyword_columns.len()
+    );
+
+    let counts: Vec<u32> = logger.run_task("Counting keyword combinations", || {
+        count_combinations(&functions, &keyword_columns)
+    })?;
+
+    for (combination, count) in counts.iter().enumerate().filter(|(_, c)| **c > 0) {
+        let names: Vec<&str> = keyword_columns
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| combination >> i & 1 == 1)
+            .map(|(_, c)| c.as_str())
+            .collect();
+        info!(
+            "{}: {} / {:.2} %",
+            if names.is_empty() {
+                "No keyword file".to_string()
+            } else {
+                names.join(" & ")
+            },
+            count,
+            percentage(*count, functions.height())
+        );
+    }
+
+    let mut report: DataFrame = DataFrame::new(
+        keyword_columns
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                Column::new(
+                    name.into(),
+                    (0..counts.len())
+                        .map(|combination| (combination >> i & 1) as u32)
+                        .collect::<Vec<u32>>(),
+                )
+            })
+            .chain([
+                Column::new("functions".into(), &counts),
+                Column::new(
+                    "percentage".into(),
+                    counts
+                        .iter()
+                        .map(|c| percentage(*c, functions.height()))
+                        .collect::<Vec<f64>>(),
+                ),
+            ])
+            .collect(),
+    )?;
+
+    log_write_output(logger, output_path, &mut report, no_output)
+}
+
+/// Returns the keyword columns of a file produced by the parse command,
+/// i.e., the columns located between 'words' and 'loop_statements'.
+///
+/// # Arguments
+///
+/// * `functions` - The functions statistics produced by the parse command.
+fn default_keyword_columns(functions: &DataFrame) -> Result<Vec<String>> {
+    let names: Vec<&str> = functions
+        .get_column_names()
+        .iter()
+        .map(|s| s.as_str())
+        .collect();
+    let start: usize = names
+        .iter()
+        .position(|c| *c == "words")
+        .context("Column 'words' not found, use --columns to select the keyword columns")?;
+    let end: usize = names.iter().position(|c| *c == "loop_statements").context(
+        "Column 'loop_statements' not found, use --columns to select the keyword columns",
+    )?;
+    Ok(names
+        .get(start + 1..end)
+        .unwrap_or_default()
+        .iter()
+        .map(|c| c.to_string())
+        .collect())
+}
+
+/// Counts the number of functions matching each combination of keyword columns.
+/// A function matches a keyword column if the column has a positive value.
+///
+/// # Arguments
+///
+/// * `functions` - The functions statistics.
+/// * `keyword_columns` - The names of the keyword columns.
+///
+/// # Returns
+///
+/// A vector indexed by combination, where the i-th bit of the index is set if the i-th column is part of the combination.
+fn count_combinations(functions: &DataFrame, keyword_columns: &[String]) -> Result<Vec<u32>> {
+    let matches: Vec<Vec<bool>> = keyword_columns
+        .iter()
+        .map(|c| {
+            Ok(functions
+                .column(c)?
+                .cast(&DataType::Int64)
+                .with_context(|| format!("Column {c} does not contain match counts"))?
+                .i64()?
+                .into_iter()
+                .map(|m| m.unwrap_or_default() > 0)
+                .collect())
+        })
+        .collect::<Result<_>>()?;
+
+    let mut counts: Vec<u32> = vec![0; 1 << keyword_columns.len()];
+    for row in 0..functions.height() {
+        let combination: usize = matches
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| m[row])
+            .fold(0, |acc, (i, _)| acc | 1 << i);
+        counts[combination] += 1;
+    }
+    Ok(counts)
+}
+
+/// Computes a percentage, returning 0 if the total is 0.
+fn percentage(count: u32, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 / total as f64 * 100.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::utils::logger::test_logger;
+    use anyhow::ensure;
+
+    const TEST_DATA: &str = "tests/data/phases/keywords_report";
+
+    #[test]
+    fn report_default_columns() -> Result<()> {
+        let input_path = format!("{TEST_DATA}/functions.csv");
+        let default_output_path = format!("{input_path}.keywords_report.csv");
+
+        delete_file(&default_output_path, true)?;
+        run(&input_path, None, None, false, false, test_logger())?;
+
+        let expected_df = open_csv(&format!("{default_output_path}.expected"), None, None)?;
+        let output_df = open_csv(&default_output_path, None, None)?;
+
+        ensure!(
+            expected_df.equals(&output_df),
+            "Keyword report does not match expected result."
+        );
+
+        delete_file(&default_output_path, false)
+    }
+
+    #[test]
+    fn report_selected_columns() -> Result<()> {
+        let input_path = format!("{TEST_DATA}/functions.csv");
+        let output_path = format!("{TEST_DATA}/functions.csv.selected.csv");
+
+        delete_file(&output_path, true)?;
+        run(
+            &input_path,
+            Some(&output_path),
+            Some(vec![
+                "tests/data/keywords/fp_types.json",
+                "tests/data/keywords/fp_transcendental.json",
+            ]),
+            false,
+            false,
+            test_logger(),
+        )?;
+
+        let expected_df = open_csv(&format!("{output_path}.expected"), None, None)?;
+        let output_df = open_csv(&output_path, None, None)?;
+
+        ensure!(
+            expected_df.equals(&output_df),
+            "Keyword report does not match expected result."
+        );
+
+        ensure!(run(
+            &input_path,
+            Some(&output_path),
+            Some(vec!["not_a_column"]),
+            true,
+            false,
+            test_logger(),
+        )
+        .is_err());
+
+        delete_file(&output_path, false)
+    }
+}
@@ -0,0 +1,298 @@
+// Copyright 2026 Andrea Gilot
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![doc = include_str!("../docs/manifest.md")]
+
+use std::iter::FromIterator as _;
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use clap::{Arg, ArgAction, Command};
+use polars::frame::DataFrame;
+use polars::prelude::{Column, DataType, Field, Schema};
+use tracing::{info, warn};
+use walkdir::WalkDir;
+
+use crate::utils::csv::{revert_placeholders, validate_columns};
+use crate::utils::dataframes;
+use crate::utils::fs::*;
+use crate::utils::logger::{log_output_file, log_write_output, Logger};
+use crate::utils::progress::Progress;
+
+/// Command line arguments parsing.
+pub fn cli() -> Command {
+    Command::new("manifest")
+        .about("Computes the size and hash of the files of a corpus, or verifies a corpus against an existing manifest.")
+        .long_about(include_str!("../docs/manifest.md"))
+        .disable_version_flag(true)
+        .arg(
+            Arg::new("input")
+                .short('i')
+                .long("input")
+                .value_name("INPUT_FILE.csv")
+                .help("Path to the csv file listing the files of the corpus, such as the file log produced by the download command.")
+                .required_unless_present_any(["dir", "verify"])
+                .conflicts_with_all(["dir", "verify"]),
+        )
+        .arg(
+            Arg::new("dir")
+                .long("dir")
+                .value_name("DIRECTORY")
+                .help("Directory containing the corpus, instead of a list of files. Every file of the directory is included in the manifest.")
+                .conflicts_with("verify"),
+        )
+        .arg(
+            Arg::new("verify")
+                .long("verify")
+                .value_name("MANIFEST.csv")
+                .help("Path to a manifest produced by a previous run. The files it lists are checked against their size and hash."),
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .value_name("OUTPUT_FILE.csv")
+                .help("Path to the output csv file. By default, the name of the output file is the same as the input file or directory \
+                       with the suffix '.manifest.csv', or the same as the verified manifest with the suffix '.verify.csv'.")
+                .required(false),
+        )
+        .arg(
+            Arg::new("header")
+                .long("header")
+                .help("Name of column storing file paths in the input CSV file.")
+                .default_value("name"),
+        )
+        .arg(
+            Arg::new("force")
+                .short('f')
+                .long("force")
+                .help("Override the output CSV file if it already exists.")
+                .default_value("false")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+/// Writes the manifest of a corpus.
+///
+/// # Arguments
+///
+/// * `input_path` - The path to the csv file listing the files, or to the directory containing the corpus if `dir` is true.
+/// * `dir` - Whether the input is a directory.
+/// * `output_path` - The optional path to the manifest. Defaults to the input path with ".manifest.csv" appended.
+/// * `input_header` - The name of the column storing file paths in the input CSV file.
+/// * `force` - Whether to override the output file if it already exists.
+/// * `logger` - The logger displaying the progress.
+///
+/// # Returns
+///
+/// A result indicating success or failure of the operation.
+pub fn run(
+    input_path: &str,
+    dir: bool,
+    output_path: Option<&str>,
+    input_header: &str,
+    force: bool,
+    logger: &Logger,
+) -> Result<()> {
+    let default_output_path: String = format!("{}.manifest.csv", input_path.trim_end_matches('/'));
+    let output_path: &str = output_path.unwrap_or(&default_output_path);
+    logger.record_inputs(&[input_path]);
+    logger.record_outputs(&[output_path]);
+
+    check_path(input_path)?;
+    log_output_file(output_path, false, force)?;
+
+    let mut paths: Vec<String> = logger.run_task("Loading files", || {
+        if dir {
+            Ok(WalkDir::new(input_path)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_file())
+                .map(|e| e.path().to_string_lossy().to_string())
+                .collect())
+        } else {
+            let schema: Schema =
+                Schema::from_iter(vec![Field::new(input_header.into(), DataType::String)]);
+            validate_columns(input_path, &[input_header], &schema)?;
+            let files: DataFrame = open_csv(input_path, Some(schema), None)?;
+            Ok(dataframes::str(&files, input_header)?
+                .into_iter()
+                .map(revert_placeholders)
+                .collect())
+        }
+    })?;
+    paths.sort();
+    paths.dedup();
+
+    info!("{} files found.", paths.len());
+
+    let progress_bar: Progress = logger.progress_bar(Some(paths.len() as u64))?;
+
+    let mut sizes: Vec<u64> = Vec::with_capacity(paths.len());
+    let mut hashes: Vec<String> = Vec::with_capacity(paths.len());
+    for path in &paths {
+        let (size, hash) = hash_file(path)?;
+        sizes.push(size);
+        hashes.push(hash);
+        progress_bar.inc(1);
+    }
+    progress_bar.finish();
+
+    info!("Total size: {} bytes", sizes.iter().copied().sum::<u64>());
+
+    let mut manifest: DataFrame = DataFrame::new(vec![
+        Column::new("path".into(), paths),
+        Column::new("size".into(), sizes),
+        Column::new("blake3".into(), hashes),
+    ])?;
+
+    log_write_output(logger, output_path, &mut manifest, false)
+}
+
+/// Checks the files of a corpus against a manifest.
+/// Writes the status of every file and fails if at least one file does not match the manifest.
+///
+/// # Arguments
+///
+/// * `manifest_path` - The path to the manifest.
+/// * `output_path` - The optional path to the report. Defaults to the manifest path with ".verify.csv" appended.
+/// * `force` - Whether to override the output file if it already exists.
+/// * `logger` - The logger displaying the progress.
+///
+/// # Returns
+///
+/// A result indicating whether the corpus matches the manifest.
+pub fn verify(
+    manifest_path: &str,
+    output_path: Option<&str>,
+    force: bool,
+    logger: &Logger,
+) -> Result<()> {
+    let default_output_path: String = format!("{manifest_path}.verify.csv");
+    let output_path: &str = output_path.unwrap_or(&default_output_path);
+    logger.record_inputs(&[manifest_path]);
+    logger.record_outputs(&[output_path]);
+
+    check_path(manifest_path)?;
+    log_output_file(output_path, false, force)?;
+
+    let manifest: DataFrame = logger.run_task("Loading manifest", || {
+        let schema: Schema = Schema::from_iter(vec![
+            Field::new("path".into(), DataType::String),
+            Field::new("size".into(), DataType::UInt64),
+            Field::new("blake3".into(), DataType::String),
+        ]);
+        let columns: Vec<&str> = vec!["path", "size", "blake3"];
+        validate_columns(manifest_path, &columns, &schema)?;
+        open_csv(manifest_path, Some(schema), Some(columns))
+    })?;
+
+    let paths: Vec<&str> = dataframes::str(&manifest, "path")?;
+    let sizes: Vec<Option<u64>> = manifest.column("size")?.u64()?.into_iter().collect();
+    let hashes: Vec<&str> = dataframes::str(&manifest, "blake3")?;
+
+    info!("{} files found in the manifest.", paths.len());
+
+    let progress_bar: Progress = logger.progress_bar(Some(paths.len() as u64))?;
+
+    let mut statuses: Vec<&str> = Vec::with_capacity(paths.len());
+    for ((path, expected_size), expected_hash) in paths.iter().zip(&sizes).zip(&hashes) {
+        let status: &str = if !Path::new(path).is_file() {
+            "missing"
+        } else {
+            let (size, hash) = hash_file(path)?;
+            if Some(size) == *expected_size && hash == *expected_hash {
+                "ok"
+            } else {
+                "modified"
+            }
+        };
+        if status != "ok" {
+            warn!("File {path} is {status}");
+        }
+        statuses.push(status);
+        progress_bar.inc(1);
+    }
+    progress_bar.finish();
+
+    let count = |s: &str| statuses.iter().filter(|status| **status == s).count();
+    let (missing, modified) = (count("missing"), count("modified"));
+    info!(
+        "Verified files: {} ok, {} modified, {} missing",
+        count("ok"),
+        modified,
+        missing
+    );
+
+    let mut report: DataFrame = DataFrame::new(vec![
+        Column::new("path".into(), paths),
+        Column::new("status".into(), statuses),
+    ])?;
+
+    log_write_output(logger, output_path, &mut report, false)?;
+
+    if missing + modified > 0 {
+        bail!("{modified} files were modified and {missing} files are missing, see {output_path}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::utils::logger::test_logger;
+
+    const TEST_DATA: &str = "tests/data/phases/manifest";
+
+    #[test]
+    fn manifest_and_verify() -> Result<()> {
+        let input_path: String = format!("{TEST_DATA}/files.csv");
+        let manifest_path: String = format!("{input_path}.manifest.csv");
+        let report_path: String = format!("{manifest_path}.verify.csv");
+
+        delete_file(&manifest_path, true)?;
+        delete_file(&report_path, true)?;
+        run(&input_path, false, None, "name", false, test_logger())?;
+
+        let expected_df = open_csv(&format!("{manifest_path}.expected"), None, None)?;
+        let output_df = open_csv(&manifest_path, None, None)?;
+        assert_eq!(expected_df, output_df);
+
+        verify(&manifest_path, None, false, test_logger())?;
+        assert!(
+            dataframes::str(&open_csv(&report_path, None, None)?, "status")?
+                .into_iter()
+                .all(|s| s == "ok")
+        );
+
+        delete_file(&manifest_path, false)?;
+        delete_file(&report_path, false)
+    }
+
+    #[test]
+    fn verify_altered_corpus() -> Result<()> {
+        let manifest_path: String = format!("{TEST_DATA}/altered.manifest.csv");
+        let report_path: String = format!("{manifest_path}.verify.csv");
+
+        delete_file(&report_path, true)?;
+        assert!(verify(&manifest_path, None, false, test_logger()).is_err());
+
+        let expected_df = open_csv(&format!("{report_path}.expected"), None, None)?;
+        let output_df = open_csv(&report_path, None, None)?;
+        assert_eq!(expected_df, output_df);
+
+        delete_file(&report_path, false)
+    }
+}
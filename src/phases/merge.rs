@@ -0,0 +1,193 @@
+// Copyright 2026 Andrea Gilot
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![doc = include_str!("../docs/merge.md")]
+
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+use anyhow::{bail, ensure, Context, Result};
+use clap::{Arg, ArgAction, Command};
+use tracing::info;
+
+use crate::utils::csv::CSVFile;
+use crate::utils::fs::*;
+use crate::utils::logger::{log_output_file, Logger};
+use crate::utils::shard::shard_path;
+
+/// Command line arguments parsing.
+pub fn cli() -> Command {
+    Command::new("merge")
+        .about("Combines the output files written by the shards of a command run with --shard.")
+        .long_about(include_str!("../docs/merge.md"))
+        .disable_version_flag(true)
+        .arg(
+            Arg::new("input")
+                .short('i')
+                .long("input")
+                .value_name("OUTPUT_FILE.csv")
+                .help("Path of the output file without the shard suffix, as given to or chosen by the command run with --shard. \
+                       The files OUTPUT_FILE.shard-K-of-N.csv are merged for every K between 1 and N.")
+                .required(true),
+        )
+        .arg(
+            Arg::new("shards")
+                .short('n')
+                .long("shards")
+                .value_name("N")
+                .help("Number of shards the input was split into.")
+                .required(true)
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .value_name("MERGED_FILE.csv")
+                .help("Path to the merged csv file. By default, the path given with --input is used.")
+                .required(false),
+        )
+        .arg(
+            Arg::new("force")
+                .short('f')
+                .long("force")
+                .help("Override the output file if it already exists.")
+                .default_value("false")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+/// Concatenates the CSV files written by the shards of a command into a single file.
+/// All shard files must exist and have the same header, which is written once.
+/// The rows are copied unchanged, in the order of the shards.
+///
+/// # Arguments
+///
+/// * `input_path` - The path of the output file of the command without the shard suffix.
+/// * `shards` - The number of shards.
+/// * `output_path` - The optional path to the merged file. Defaults to the input path.
+/// * `force` - Whether to override the output file if it already exists.
+/// * `logger` - The logger displaying the progress.
+///
+/// # Returns
+///
+/// A result indicating success or failure of the operation.
+pub fn run(
+    input_path: &str,
+    shards: usize,
+    output_path: Option<&str>,
+    force: bool,
+    logger: &Logger,
+) -> Result<()> {
+    ensure!(shards > 0, "The number of shards must be positive");
+    let output_path: &str = output_path.unwrap_or(input_path);
+    let shard_paths: Vec<String> = (1..=shards)
+        .map(|k| shard_path(input_path, k, shards))
+        .collect();
+    logger.record_inputs(&shard_paths.iter().map(|p| p.as_str()).collect::<Vec<_>>());
+    logger.record_outputs(&[output_path]);
+
+    let missing: Vec<&str> = shard_paths
+        .iter()
+        .filter(|p| !Path::new(p).is_file())
+        .map(|p| p.as_str())
+        .collect();
+    if !missing.is_empty() {
+        bail!(
+            "{} of {shards} shard files are missing: {}",
+            missing.len(),
+            missing.join(", ")
+        );
+    }
+    log_output_file(output_path, false, force)?;
+
+    let mut output: CSVFile = CSVFile::new(output_path, FileMode::Overwrite)?;
+    let mut header: Option<String> = None;
+    let mut rows: usize = 0;
+
+    for path in &shard_paths {
+        let shard_rows: usize = logger.run_task(format!("Merging {path}"), || {
+            let mut reader = BufReader::new(open_file(path, FileMode::Read)?);
+            let mut shard_header: String = String::new();
+            reader
+                .read_line(&mut shard_header)
+                .with_context(|| format!("Could not read the header of {path}"))?;
+            let shard_header: &str = shard_header.trim_end_matches(['\r', '\n']);
+            match &header {
+                None => {
+                    output.write_header(&[shard_header])?;
+                    header = Some(shard_header.to_string());
+                }
+                Some(header) => ensure!(
+                    header == shard_header,
+                    "The header of {path} differs from the header of {}",
+                    shard_paths[0]
+                ),
+            }
+
+            let mut shard_rows: usize = 0;
+            let mut line: Vec<u8> = Vec::new();
+            while reader.read_until(b'\n', &mut line)? > 0 {
+                if !line.ends_with(b"\n") {
+                    line.push(b'\n');
+                }
+                output.write_all(&line)?;
+                shard_rows += 1;
+                line.clear();
+            }
+            Ok(shard_rows)
+        })?;
+        rows += shard_rows;
+    }
+    output.flush()?;
+
+    info!("{shards} shards merged into {output_path} ({rows} lines).");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::utils::logger::test_logger;
+
+    const TEST_DATA: &str = "tests/data/phases/merge";
+
+    #[test]
+    fn merge_test() -> Result<()> {
+        let input_path: String = format!("{TEST_DATA}/functions.csv");
+        let output_path: String = format!("{TEST_DATA}/functions.merged.csv");
+
+        run(&input_path, 2, Some(&output_path), true, test_logger())?;
+        let merged: String = std::fs::read_to_string(&output_path)?;
+        delete_file(&output_path, false)?;
+        assert_eq!(
+            merged,
+            std::fs::read_to_string(format!("{input_path}.expected"))?
+        );
+
+        // A shard is missing.
+        assert!(run(&input_path, 3, Some(&output_path), true, test_logger()).is_err());
+        // The headers of the shards differ.
+        assert!(run(
+            &format!("{TEST_DATA}/headers.csv"),
+            2,
+            Some(&output_path),
+            true,
+            test_logger()
+        )
+        .is_err());
+        delete_file(&output_path, true)
+    }
+}
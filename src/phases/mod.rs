@@ -12,15 +12,30 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod check_keywords;
+pub mod classify;
+pub mod commits_head;
+pub mod diff_corpus;
 pub mod download;
 pub mod duplicate_files;
 pub mod duplicate_ids;
+pub mod export_text;
 pub mod extract_benchmarks;
+pub mod extract_slices;
+pub mod fetch_files;
 pub mod filter_languages;
 pub mod filter_metadata;
 pub mod forks;
 pub mod ids;
+pub mod import;
+pub mod keywords_report;
 pub mod languages;
+pub mod manifest;
+pub mod merge;
 pub mod metadata;
 pub mod parse;
 pub mod pull_request;
+pub mod sample_functions;
+pub mod split;
+pub mod tokens;
+pub mod verify_languages;
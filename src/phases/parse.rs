@@ -15,7 +15,7 @@
 #![doc = include_str!("../docs/parse.md")]
 use clap::ArgAction;
 use clap::{Arg, Command};
-use indicatif::ProgressBar;
+use json::JsonValue;
 use polars::prelude::*;
 use rand::rngs::StdRng;
 use rand::seq::SliceRandom as _;
@@ -23,15 +23,29 @@ use rand::SeedableRng;
 
 use anyhow::{anyhow, bail, ensure, Context, Error, Result};
 use std::iter::FromIterator as _;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
 use std::vec;
-use std::{collections::HashSet, fmt::Write, io::Write as IOWrite, sync::Mutex};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    io::Write as IOWrite,
+};
 use tracing::info;
 use tree_sitter::{Language, Node, Parser, Tree};
+use walkdir::WalkDir;
 
 use crate::utils::fs::*;
+use crate::utils::generated_code::is_generated;
+use crate::utils::language::normalize_language;
+use crate::utils::parallel::WorkerPool;
+use crate::utils::progress::Progress;
 use crate::utils::regex::*;
+use crate::utils::shard::{shard_arg, with_shard, Shard};
+use crate::utils::test_code::{has_test_imports, is_test_file_name};
 use crate::utils::{
     csv::*,
+    dataframes,
     logger::{log_output_file, log_seed, Logger},
 };
 
@@ -48,7 +62,16 @@ pub fn cli() -> Command {
                 .value_name("INPUT_FILE.csv")
                 .help("Path to the input csv file to use. It must be a valid CSV file where the first column is the path to the file and the \
                        second column is the extension of the file. Other columns are ignored.")
-                .required(true)
+                .required_unless_present("input-dir")
+        )
+        .arg(
+            Arg::new("input-dir")
+                .long("input-dir")
+                .value_name("DIR")
+                .help("Parse the files of a directory tree instead of an input csv file. Every top-level directory is a project and \
+                       the languages of the files are inferred from their extensions with the keyword files. \
+                       The list of files is written to DIR.files.csv.")
+                .conflicts_with("input"),
         )
         .arg(
             Arg::new("output")
@@ -101,7 +124,7 @@ pub fn cli() -> Command {
                 .num_args(1..)
                 .action(ArgAction::Append)
                 .value_name("LANGUAGES")
-                .help("List of languages to parse. The supported languages are C, C++, C#, Fortran, Go, Java, Python and Typescript.")
+                .help("List of languages to parse. The supported languages are C, C++, C#, Fortran, Go, Java, Kotlin, Python, Rust, Scala and Typescript.")
                 .required(false)
         )
         .arg(
@@ -128,6 +151,14 @@ pub fn cli() -> Command {
                 .default_value("8155495201244430235")
                 .value_parser(clap::value_parser!(u64)),
         )
+        .arg(
+            Arg::new("group-by-project")
+                .long("group-by-project")
+                .help("Shuffle the projects instead of the files, and process the files of each project one after the other, \
+                       in the order of the input file. Improves the locality of the file accesses and reports the number of \
+                       projects processed.")
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("failures")
             .long("failures")
@@ -147,13 +178,183 @@ pub fn cli() -> Command {
             .default_value("false")
             .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("matches")
+            .long("matches")
+            .value_name("MATCHES_FILE.csv")
+            .help("Path to an output csv file storing every keyword match in the retained functions with its line and the surrounding code, \
+                   for manual inspection. Not written if not specified.")
+            .required(false),
+        )
+        .arg(
+            Arg::new("context")
+            .long("context")
+            .value_name("LINES")
+            .help("Number of lines before and after the matched line stored with every match in the file given with --matches.")
+            .default_value("0")
+            .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("per-keyword-counts")
+            .long("per-keyword-counts")
+            .value_name("KEYWORD_COUNTS_FILE.csv")
+            .help("Path to an output csv file storing the number of matches of every keyword in each retained function, \
+                   rather than of every keyword file. Not written if not specified.")
+            .required(false),
+        )
+        .arg(
+            Arg::new("legacy-csv")
+            .long("legacy-csv")
+            .help("Replace commas and quotes in paths and names with the -was_comma- and -was_quote- placeholders instead of quoting the fields. \
+                   Useful to stay compatible with datasets produced by earlier versions.")
+            .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("headers")
+            .long("headers")
+            .value_name("LANGUAGE")
+            .help("Language of the .h headers of C and C++ files.\n\
+            c: parse them as C\n\
+            c++: parse them as C++\n\
+            auto: as C in projects with C sources only, as C++ in projects with C++ sources only, and otherwise \
+            (header-only libraries or mixed projects) according to C++ constructs found in the header.\n\
+            Headers with the .hpp, .hh, .hxx and .h++ extensions are always parsed as C++.")
+            .default_value("auto")
+            .value_parser(["c", "c++", "auto"]),
+        )
+        .arg(
+            Arg::new("exclude-tests")
+                .long("exclude-tests")
+                .help("Do not extract the functions of test files. Test files are labelled by the is_test column of the input file if \
+                       it has one, as in the file log of the download command, and otherwise detected by their names (e.g. '*_test.go') \
+                       or their imports of test frameworks (e.g. JUnit or pytest).")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("exclude-generated")
+                .long("exclude-generated")
+                .help("Do not extract the functions of the files that are likely generated. They are labelled by the likely_generated \
+                       column of the input file if it has one, as in the file log of the download command, and otherwise detected by \
+                       markers such as 'DO NOT EDIT' at their beginning, lines longer than 1000 bytes or a low entropy of their identifiers.")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("compress")
+                .long("compress")
+                .help("Write the files of the extracted functions compressed with zstd, with the .zst extension. \
+                       Commands reading the function files, such as duplicate_files, decompress them transparently.")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("stats-only")
+                .long("stats-only")
+                .help("Only write the statistics of the functions, without creating the .functions directories and the files of \
+                       the functions. The path column still records where each function would have been written.")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["compress", "emit-ast"]),
+        )
+        .arg(
+            Arg::new("max-depth")
+                .long("max-depth")
+                .value_name("DEPTH")
+                .help("Maximal depth of the syntax tree of a file. Deeper files, e.g. pathological generated files, are not analysed \
+                       and are logged with 'too_complex' as parse error. 0 for no limit.")
+                .default_value("1000")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("max-nodes")
+                .long("max-nodes")
+                .value_name("NODES")
+                .help("Maximal number of nodes of the syntax tree of a file. Larger files are not analysed \
+                       and are logged with 'too_complex' as parse error. 0 for no limit.")
+                .default_value("1000000")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("emit-ast")
+                .long("emit-ast")
+                .value_name("FORMAT")
+                .help("Write the syntax tree of every extracted function next to its code, with the .ast extension.\n\
+                       sexp: the s-expression of tree-sitter\n\
+                       json: the named nodes with their kind, field and byte range in the code, with the .ast.json extension")
+                .value_parser(["sexp", "json"]),
+        )
+        .arg(
+            Arg::new("min-loc")
+                .long("min-loc")
+                .value_name("LINES")
+                .help("Minimal number of lines of code, without comments, of the functions matching the keywords that are written out.")
+                .default_value("0")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("max-loc")
+                .long("max-loc")
+                .value_name("LINES")
+                .help("Maximal number of lines of code, without comments, of the functions matching the keywords that are written out.")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("min-loops")
+                .long("min-loops")
+                .value_name("N")
+                .help("Minimal number of loops of the functions matching the keywords that are written out.")
+                .default_value("0")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("min-conditionals")
+                .long("min-conditionals")
+                .value_name("N")
+                .help("Minimal number of conditional statements of the functions matching the keywords that are written out.")
+                .default_value("0")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("filtered-stats")
+                .long("filtered-stats")
+                .help("Still write the statistics of the functions matching the keywords that are not written out because of \
+                       --min-loc, --max-loc, --min-loops or --min-conditionals, with 'filtered' in place of their path.")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("ids")
+                .long("ids")
+                .help("Name of the column containing the ids of the projects.")
+                .value_name("COLUMN_NAME")
+                .default_value("id"),
+        )
+        .arg(
+            Arg::new("names")
+                .long("names")
+                .help("Name of the column containing the paths of the files.")
+                .value_name("COLUMN_NAME")
+                .default_value("name"),
+        )
+        .arg(
+            Arg::new("languages")
+                .long("languages")
+                .help("Name of the column containing the languages of the files.")
+                .value_name("COLUMN_NAME")
+                .default_value("language"),
+        )
+        .arg(
+            Arg::new("projects")
+                .long("projects")
+                .help("Name of the column containing the names of the projects, copied to the outputs if the input file has it.")
+                .value_name("COLUMN_NAME")
+                .default_value("project"),
+        )
+        .arg(shard_arg())
 }
 
 /// Entry point of the program
 ///
 /// # Arguments
 ///
-/// * `input_path` - Path to the input csv file to use.
+/// * `input_path` - Path to the input csv file to use, or to the directory tree to list if `input_dir` is set.
+/// * `input_dir` - Whether to parse the files of the directory tree at `input_path`, with the projects as top-level directories.
 /// * `output_path` - Path to the output csv file storing the functions statistics.
 /// * `logs_path` - Path to the output csv file storing the files statistics.
 /// * `keywords_file_paths` - Paths to the files containing the list of extensions and keywords to use.
@@ -165,11 +366,31 @@ pub fn cli() -> Command {
 ///   * `skip-function`: replace the function statistics with an error row in the output file.
 /// * `threads` - The number of threads to use.
 /// * `seed` - The seed used to shuffle the input file.
+/// * `group_by_project` - Whether to shuffle the projects instead of the files and process the files of each project contiguously.
 /// * `force` - Whether to override the output file if it already exists.
 /// * `ignore_comments` - Whether to ignore comments when extracting functions.
+/// * `quoting` - How to escape paths and names in the output files.
+/// * `matches_path` - Optional path to the output csv file storing the keyword matches of the retained functions.
+/// * `context` - The number of lines before and after the matched line stored with every match.
+/// * `keyword_counts_path` - Optional path to the output csv file storing the number of matches of every keyword in each
+///   retained function.
+/// * `headers` - The language of the .h headers of C and C++ files: "c", "c++" or "auto".
+/// * `shard` - The optional shard of the shuffled files to process.
+/// * `exclude_tests` - Whether to skip the test files, which are then logged without functions.
+/// * `exclude_generated` - Whether to skip the files that are likely generated, which are then logged without functions.
+/// * `compress` - Whether to compress the files of the extracted functions with zstd.
+/// * `stats_only` - Whether to only write the statistics of the functions, without writing their files.
+/// * `complexity` - The complexity criteria of the functions matching the keywords that are written out.
+/// * `emit_ast` - The format of the syntax trees written next to the extracted functions, "sexp" or "json", if any.
+/// * `limits` - The limits on the syntax trees of the files, above which the files are not analysed.
+/// * `ids` - The name of the column containing the ids of the projects in the input file.
+/// * `names` - The name of the column containing the paths of the files in the input file.
+/// * `language_column` - The name of the column containing the languages of the files in the input file.
+/// * `projects` - The name of the optional column containing the names of the projects in the input file.
 /// * `logger` - The logger to use to display information about the progress of the program.
 pub fn run(
     input_path: &str,
+    input_dir: bool,
     output_path: Option<&str>,
     logs_path: Option<&str>,
     keywords_file_paths: &[&str],
@@ -178,8 +399,26 @@ pub fn run(
     fail_policy: &str,
     threads: usize,
     seed: u64,
+    group_by_project: bool,
     force: bool,
     ignore_comments: bool,
+    quoting: Quoting,
+    matches_path: Option<&str>,
+    context: usize,
+    keyword_counts_path: Option<&str>,
+    headers: &str,
+    shard: Option<Shard>,
+    exclude_tests: bool,
+    exclude_generated: bool,
+    compress: bool,
+    stats_only: bool,
+    complexity: ComplexityFilter,
+    emit_ast: Option<&str>,
+    limits: TreeLimits,
+    ids: &str,
+    names: &str,
+    language_column: &str,
+    projects: &str,
     logger: &Logger,
 ) -> Result<()> {
     let supported_languages: HashSet<&'static str> = vec![
@@ -193,15 +432,17 @@ pub fn run(
         "go",
         "scala",
         "rust",
+        "kotlin",
     ]
     .into_iter()
     .collect::<HashSet<_>>();
 
-    let languages: Vec<&str> = match opt_languages {
+    let languages: Vec<String> = match opt_languages {
         Some(l) => {
+            let l: Vec<String> = l.into_iter().map(normalize_language).collect();
             for lang in l.iter() {
                 ensure!(
-                    supported_languages.contains(lang),
+                    supported_languages.contains(lang.as_str()),
                     "Unsupported language: {lang}"
                 );
             }
@@ -209,41 +450,169 @@ pub fn run(
         }
         None => {
             info!("No language specified, using all supported languages");
-            supported_languages.into_iter().collect()
+            supported_languages.into_iter().map(String::from).collect()
         }
     };
 
     info!("Selected languages: {}", languages.join(", "));
 
-    let languages_series = Series::new(
-        "language_filter".into(),
-        languages
-            .iter()
-            .map(|x| x.to_string())
-            .collect::<Vec<String>>(),
-    );
+    let mut language_filter: Vec<String> = languages.clone();
+    // The language of unresolved headers is only known once they are read, they are parsed if C or C++ is selected.
+    if languages.iter().any(|l| l == "c" || l == "c++") {
+        language_filter.push(UNRESOLVED_HEADER.to_string());
+    }
+    let languages_series = Series::new("language_filter".into(), language_filter);
+
+    let keyword_files: KeywordFiles = logger.run_task("Loading keywords", || {
+        KeywordFiles::new(regex_syntax).add_files(keywords_file_paths, true)
+    })?;
 
-    let default_output_path: String = format!("{input_path}.functions.csv");
-    let output_path: &str = output_path.unwrap_or(&default_output_path);
+    // A directory tree is listed in a csv file, which is then parsed as any other input file.
+    let dir_path: &str = input_path.trim_end_matches('/');
+    let listing_path: String = format!("{dir_path}.files.csv");
+    let (input_path, ids, names, language_column, projects): (&str, &str, &str, &str, &str) =
+        if input_dir {
+            (&listing_path, "id", "name", "language", "project")
+        } else {
+            (input_path, ids, names, language_column, projects)
+        };
+    // The outputs of a directory tree are named after the directory.
+    let output_base: &str = if input_dir { dir_path } else { input_path };
+
+    let default_output_path: String = format!("{output_base}.functions.csv");
+    let output_path: String = with_shard(output_path.unwrap_or(&default_output_path), shard);
+    let output_path: &str = &output_path;
     log_output_file(output_path, false, force)?;
 
-    let default_logs_path: String = format!("{input_path}.function_logs.csv");
-    let logs_path: &str = logs_path.unwrap_or(&default_logs_path);
+    let default_logs_path: String = format!("{output_base}.function_logs.csv");
+    let logs_path: String = with_shard(logs_path.unwrap_or(&default_logs_path), shard);
+    let logs_path: &str = &logs_path;
+    let matches_path: Option<String> = matches_path.map(|path| with_shard(path, shard));
+    let matches_path: Option<&str> = matches_path.as_deref();
+    let keyword_counts_path: Option<String> =
+        keyword_counts_path.map(|path| with_shard(path, shard));
+    let keyword_counts_path: Option<&str> = keyword_counts_path.as_deref();
+    if input_dir {
+        logger.record_inputs(&[&[dir_path], keywords_file_paths].concat());
+        logger.record_outputs(
+            &[
+                &[input_path, output_path, logs_path],
+                matches_path.as_slice(),
+                keyword_counts_path.as_slice(),
+            ]
+            .concat(),
+        );
+        log_output_file(input_path, false, force)?;
+    } else {
+        logger.record_inputs(&[&[input_path], keywords_file_paths].concat());
+        logger.record_outputs(
+            &[
+                &[output_path, logs_path],
+                matches_path.as_slice(),
+                keyword_counts_path.as_slice(),
+            ]
+            .concat(),
+        );
+    }
 
     log_output_file(logs_path, false, force)?;
+    for path in matches_path.iter().chain(&keyword_counts_path) {
+        log_output_file(path, false, force)?;
+    }
+    if input_dir {
+        let n_files: usize = logger.run_task("Listing the files of the directory tree", || {
+            list_directory(dir_path, &keyword_files, input_path, quoting)
+        })?;
+        info!("  {n_files} files with the extensions of the keyword files found in {dir_path}");
+    }
+    if let Some(dir) = archive_keyword_files(keywords_file_paths, output_path, true)? {
+        info!("  Keyword files archived in {dir}.");
+    }
 
-    let mut input_file = open_csv(
+    let header: String = file_lines(input_path)?
+        .next()
+        .transpose()?
+        .unwrap_or_default();
+    let header_columns: Vec<&str> = header
+        .split(',')
+        .map(|column| column.trim().trim_matches('"'))
+        .collect();
+    ensure!(
+        [ids, names, language_column]
+            .iter()
+            .all(|column| header_columns.contains(column)),
+        "File {input_path} must contain the columns '{ids}', '{names}' and '{language_column}'"
+    );
+    // The test files are labelled by the download command, relative to their projects, if it is the source of the input file.
+    let has_test_labels: bool = header_columns.contains(&"is_test");
+    // The commit of the files is also recorded by the download command, and copied to the outputs for provenance.
+    let has_commits: bool = header_columns.contains(&"commit");
+    // And so are the files that are likely generated.
+    let has_generated_labels: bool = header_columns.contains(&"likely_generated");
+    // The names of the projects are copied to the outputs as well, so that they do not need to be joined back.
+    let has_projects: bool =
+        header_columns.contains(&projects) && ![ids, names, language_column].contains(&projects);
+    let mut input_columns: Vec<&str> = vec![ids, names, language_column];
+    let mut input_schema: Vec<Field> = vec![
+        Field::new(ids.into(), DataType::UInt32),
+        Field::new(names.into(), DataType::String),
+        Field::new(language_column.into(), DataType::String),
+    ];
+    let mut column_names: Vec<&str> = vec!["id", "name", "language"];
+    if has_test_labels {
+        input_columns.push("is_test");
+        input_schema.push(Field::new("is_test".into(), DataType::Boolean));
+        column_names.push("is_test");
+    }
+    if has_generated_labels {
+        input_columns.push("likely_generated");
+        input_schema.push(Field::new("likely_generated".into(), DataType::Boolean));
+        column_names.push("likely_generated");
+    }
+    if has_projects {
+        input_columns.push(projects);
+        input_schema.push(Field::new(projects.into(), DataType::String));
+        column_names.push("project");
+    }
+    if has_commits {
+        input_columns.push("commit");
+        input_schema.push(Field::new("commit".into(), DataType::String));
+        column_names.push("commit");
+    }
+    let input_file = open_csv(
         input_path,
-        Some(Schema::from_iter(vec![
-            Field::new("id".into(), DataType::UInt32),
-            Field::new("name".into(), DataType::String),
-            Field::new("language".into(), DataType::String),
-        ])),
-        Some(vec!["id", "name", "language"]),
+        Some(Schema::from_iter(input_schema)),
+        Some(input_columns.clone()),
     )?;
+    // The columns are renamed and ordered as in the file log of the download command.
+    let mut input_file: DataFrame = input_file.select(input_columns.clone())?;
+    input_file.set_column_names(column_names)?;
 
     let n_files_before = input_file.height();
 
+    // Aliases of the languages, such as 'Cpp' or 'Python3', are resolved to the names of the grammars.
+    let normalized_languages: Vec<String> = dataframes::str(&input_file, "language")?
+        .into_iter()
+        .map(normalize_language)
+        .collect();
+    let resolved_languages: Vec<String> = resolve_header_languages(
+        &dataframes::u32(&input_file, "id")?,
+        &dataframes::str(&input_file, "name")?,
+        &normalized_languages
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<&str>>(),
+        headers,
+    );
+    let n_unresolved: usize = resolved_languages
+        .iter()
+        .filter(|l| *l == UNRESOLVED_HEADER)
+        .count();
+    if n_unresolved > 0 {
+        info!("  {n_unresolved} .h headers will be parsed as C or C++ according to their content");
+    }
+    input_file.with_column(Column::new("language".into(), resolved_languages))?;
+
     info!(
         "  {} files found in the input file, filtering by selected languages",
         n_files_before
@@ -269,36 +638,71 @@ pub fn run(
 
     log_seed(seed);
 
-    let mut shuffled_idx = (0..input_file.height()).collect::<Vec<usize>>();
+    let project_ids: Vec<u32> = dataframes::u32(&input_file, "id")?;
 
     // Load the ids from the input file in random order.
-    logger.run_task("Loading files in random order", || {
-        let mut rng: StdRng = SeedableRng::seed_from_u64(seed);
-        shuffled_idx.shuffle(&mut rng);
-        Ok(())
+    let shuffled_idx: Vec<usize> = logger.run_task("Loading files in random order", || {
+        Ok(processing_order(
+            &project_ids,
+            seed,
+            group_by_project,
+            shard,
+        ))
     })?;
+    if let Some(shard) = shard {
+        info!("  {} files selected in shard {shard}", shuffled_idx.len());
+    }
+    let n_selected: usize = shuffled_idx.len();
 
+    // Number of files left to process in every project, to report the projects processed.
+    let mut remaining_files: HashMap<u32, usize> = HashMap::new();
+    if group_by_project {
+        for idx in &shuffled_idx {
+            *remaining_files.entry(project_ids[*idx]).or_default() += 1;
+        }
+    }
+    let n_projects: usize = remaining_files.len();
+
+    let is_test_idx: Option<usize> = input_file.get_column_index("is_test");
+    let generated_idx: Option<usize> = input_file.get_column_index("likely_generated");
+    let project_idx: Option<usize> = input_file.get_column_index("project");
+    let commit_idx: Option<usize> = input_file.get_column_index("commit");
     let shuffled_rows = shuffled_idx.into_iter().map(|idx| {
         let row = input_file.get_row(idx).unwrap().0;
+        let is_test: Option<bool> = match is_test_idx.and_then(|i| row.get(i)) {
+            Some(AnyValue::Boolean(is_test)) => Some(*is_test),
+            _ => None,
+        };
+        // The files of projects downloaded by earlier versions are not labelled.
+        let generated: Option<bool> = match generated_idx.and_then(|i| row.get(i)) {
+            Some(AnyValue::Boolean(generated)) => Some(*generated),
+            _ => None,
+        };
+        // The name of the project and the commit are appended to the rows. The commit is empty for the files of
+        // projects downloaded by earlier versions.
+        let provenance: String = [project_idx, commit_idx]
+            .into_iter()
+            .flatten()
+            .map(|i| match row.get(i) {
+                Some(AnyValue::String(field)) => {
+                    format!(",{}", quoting.escape(&revert_placeholders(field)))
+                }
+                _ => ",".to_string(),
+            })
+            .collect();
         match (row[0].clone(), row[1].clone(), row[2].clone()) {
             (AnyValue::UInt32(id), AnyValue::String(path), AnyValue::String(lang)) => Ok((
                 id,
-                path.replace("-was_comma-", ",")
-                    .replace("-was_quote-", "\""),
+                revert_placeholders(path),
                 lang,
+                is_test,
+                generated,
+                provenance,
             )),
             _ => Err(idx),
         }
     });
 
-    // Number of columns in the output file.
-    const OUTPUT_COLS: usize = 18;
-    const LOGS_COLS: usize = 7;
-
-    let keyword_files: KeywordFiles = logger.run_task("Loading keywords", || {
-        KeywordFiles::new(regex_syntax).add_files(keywords_file_paths, true)
-    })?;
-
     let keyword_match_headers: String = keyword_files.paths.join(",");
 
     let word_counter: Matcher = Matcher::words_matcher();
@@ -307,10 +711,12 @@ pub fn run(
     let mut output_file = CSVFile::new(output_path, FileMode::Overwrite)?;
 
     // Write the header.
-    let header: [&str; OUTPUT_COLS] = [
+    let mut header: Vec<&str> = [
         "id",
         "path",
         "name",
+        "qualified_name",
+        "scope",
         "position",
         "language",
         "loc",
@@ -325,15 +731,25 @@ pub fn run(
         "params",
         "param_kw_match",
         "return_kw_match",
+        "comment_lines",
+        "has_doc",
         "parse_error",
-    ];
+        "is_test",
+    ]
+    .to_vec();
+    if has_projects {
+        header.push("project");
+    }
+    if has_commits {
+        header.push("commit");
+    }
 
     output_file.write_header(&header)?;
 
     let mut logs_file = CSVFile::new(logs_path, FileMode::Overwrite)?;
 
     // Write the header.
-    let logs_header: [&str; LOGS_COLS] = [
+    let mut logs_header: Vec<&str> = [
         "id",
         "name",
         "language",
@@ -341,98 +757,225 @@ pub fn run(
         "functions_with_kw",
         &keyword_match_headers,
         "parse_error",
-    ];
+        "is_test",
+    ]
+    .to_vec();
+    if has_projects {
+        logs_header.push("project");
+    }
+    if has_commits {
+        logs_header.push("commit");
+    }
 
     logs_file.write_header(&logs_header)?;
 
-    let iter = Mutex::new(shuffled_rows.into_iter());
-
-    // Every thread comes with a sender channel.
-    // The sender channel is used to send information about the extracted functions back to the main thread.
-    // The receiver channel is used by the main thread to collect and write the information to the log file.
-    let (tx, rx) =
-        crossbeam_channel::unbounded::<Option<Result<(String, Option<String>), Error>>>();
-
-    crossbeam::thread::scope(|s| {
-        for _ in 0..threads {
-            s.spawn(|_| {
-                let my_tx = tx.clone();
-                // The main loop of the thread.
-                // Download the repositories until the iterator is empty.
-                loop {
-                    // Lock the repository iterator and retrieve the next item.
-                    let next_item: Option<Result<(u32, String, &str), usize>> = {
-                        let mut iter_guard = iter.lock().unwrap();
-                        iter_guard.next()
-                    };
+    let mut matches_file: Option<CSVFile> = match matches_path {
+        Some(path) => {
+            let mut file = CSVFile::new(path, FileMode::Overwrite)?;
+            file.write_header(&["id", "path", "keyword_file", "keyword", "line", "context"])?;
+            Some(file)
+        }
+        None => None,
+    };
+    let mut keyword_counts_file: Option<CSVFile> = match keyword_counts_path {
+        Some(path) => {
+            let mut file = CSVFile::new(path, FileMode::Overwrite)?;
+            file.write_header(&["id", "path", "keyword_file", "keyword", "count"])?;
+            Some(file)
+        }
+        None => None,
+    };
+    // Matches are only searched for if they are written.
+    let context: Option<usize> = matches_path.map(|_| context);
 
-                    match next_item {
-                        Some(row) => match row {
-                            Ok((project_id, file_name, language)) => match analyze_file(
-                                project_id,
-                                &file_name,
-                                language,
-                                &keyword_files,
-                                fail_policy,
-                                ignore_comments,
-                                &word_counter,
-                            ) {
-                                Ok(s) => {
-                                    my_tx.send(Some(Ok(s))).unwrap();
-                                }
-                                Err(e) => {
-                                    my_tx.send(Some(Err(e))).unwrap();
-                                    break;
-                                }
-                            },
-                            Err(row_nr) => {
-                                let _ =
-                                    my_tx.send(Some(Err(anyhow!("Could not parse row {row_nr}"))));
-                            }
-                        },
-                        None => {
-                            // When the iterator is empty, sends a None message to the main thread to signal the end of the thread.
-                            my_tx.send(None).unwrap();
-                            break;
-                        }
-                    }
+    let progress: Progress = logger.progress_bar(Some(n_selected as u64))?;
+    let mut processed_projects: usize = 0;
+    if group_by_project {
+        progress.set_message(format!("0/{n_projects} projects"));
+    }
+
+    // Parsers are created once per language and reused for all the files of a thread.
+    let parsers: Vec<ParserCache> = (0..threads).map(|_| ParserCache::default()).collect();
+    WorkerPool::new().run(
+        shuffled_rows.into_iter(),
+        parsers,
+        |parsers, row| {
+            let (project_id, file_name, language, is_test, generated, provenance) =
+                row.map_err(|row_nr| anyhow!("Could not parse row {row_nr}"))?;
+            let (output, log, matches, too_complex) = analyze_file(
+                project_id,
+                &file_name,
+                language,
+                is_test,
+                generated,
+                &provenance,
+                exclude_tests,
+                exclude_generated,
+                &keyword_files,
+                fail_policy,
+                ignore_comments,
+                compress,
+                stats_only,
+                &complexity,
+                emit_ast,
+                limits,
+                quoting,
+                context,
+                keyword_counts_path.is_some(),
+                &word_counter,
+                parsers,
+            )?;
+            Ok(Some((project_id, output, log, matches, too_complex)))
+        },
+        // Writes the results to the output files.
+        // The order is therefore non-deterministic although the list of files is.
+        |(project_id, output, opt_log, matches, too_complex)| {
+            logger.record_count("files processed", 1);
+            logger.record_count("functions extracted", output.matches('\n').count() as u64);
+            if too_complex {
+                logger.record_count("files too complex", 1);
+            }
+            if opt_log.is_none() {
+                progress.fail();
+                logger.record_count("files skipped", 1);
+                logger.record_error("parse error");
+            }
+            write!(&mut output_file, "{output}")?;
+            if let Some(log) = opt_log {
+                writeln!(&mut logs_file, "{log}")?;
+            }
+            if let Some(matches_file) = matches_file.as_mut() {
+                write!(matches_file, "{}", matches.matches)?;
+            }
+            if let Some(keyword_counts_file) = keyword_counts_file.as_mut() {
+                write!(keyword_counts_file, "{}", matches.keyword_counts)?;
+            }
+            progress.inc(1);
+            if let Some(remaining) = remaining_files.get_mut(&project_id) {
+                *remaining -= 1;
+                if *remaining == 0 {
+                    processed_projects += 1;
+                    logger.record_count("projects processed", 1);
+                    progress.set_message(format!("{processed_projects}/{n_projects} projects"));
                 }
-            });
-        }
+            }
+            Ok(())
+        },
+    )?;
+    progress.finish();
+    Ok(())
+}
 
-        let mut ended_threads = 0;
+/// Returns the positions of the files of the input file in the order in which they are processed.
+/// The files are shuffled with the seed, or with `group_by_project` the projects are shuffled and the files of each
+/// project follow each other in the order of the input file.
+/// The shards partition the same shuffled list, of files or of projects, so that their union is the list processed without shards.
+///
+/// # Arguments
+///
+/// * `project_ids` - The ids of the projects of the files, in the order of the input file.
+/// * `seed` - The seed used to shuffle the files or the projects.
+/// * `group_by_project` - Whether to shuffle the projects instead of the files.
+/// * `shard` - The optional shard of the shuffled files or projects to process.
+fn processing_order(
+    project_ids: &[u32],
+    seed: u64,
+    group_by_project: bool,
+    shard: Option<Shard>,
+) -> Vec<usize> {
+    let mut rng: StdRng = SeedableRng::seed_from_u64(seed);
+    if !group_by_project {
+        let mut shuffled_idx: Vec<usize> = (0..project_ids.len()).collect();
+        shuffled_idx.shuffle(&mut rng);
+        return match shard {
+            Some(shard) => shard.select(shuffled_idx),
+            None => shuffled_idx,
+        };
+    }
 
-        let progress = ProgressBar::new(n_files as u64);
-        progress.set_style(
-            indicatif::ProgressStyle::default_bar().template("{elapsed} {wide_bar} {percent}%")?,
-        );
+    // Files of every project, projects being ordered by first appearance in the input file.
+    let mut projects: Vec<Vec<usize>> = Vec::new();
+    let mut positions: HashMap<u32, usize> = HashMap::new();
+    for (idx, id) in project_ids.iter().enumerate() {
+        let position: usize = *positions.entry(*id).or_insert_with(|| {
+            projects.push(Vec::new());
+            projects.len() - 1
+        });
+        projects[position].push(idx);
+    }
+    projects.shuffle(&mut rng);
+    if let Some(shard) = shard {
+        projects = shard.select(projects);
+    }
+    projects.into_iter().flatten().collect()
+}
 
-        // Writes received messages to the log file.
-        // The order is therefore non-deterministic although the list of projects is.
-        while let Ok(msg) = rx.recv() {
-            match msg {
-                Some(msg_content) => {
-                    let (output, opt_log) = msg_content?;
-                    write!(&mut output_file, "{output}")?;
-                    if let Some(log) = opt_log {
-                        writeln!(&mut logs_file, "{log}")?;
-                    }
-                    progress.inc(1);
-                }
-                None => {
-                    // When a None message is received, the sender thread is considered finished.
-                    // When all threads are finished, the main thread can exit.
-                    ended_threads += 1;
-                    if ended_threads == threads {
-                        break;
-                    }
-                }
+/// Lists the files of a directory tree whose extension is associated with a language in the keyword files,
+/// in a csv file with the columns 'id', 'project', 'name' and 'language' that can be given to [`run`].
+///
+/// Every top-level directory is a project. If all of them are named after an id, as the directories of the
+/// repositories downloaded by id, the names are the ids of the projects. Otherwise, the projects are numbered in
+/// alphabetical order of their names. Files directly in the root directory and symbolic links are ignored.
+///
+/// # Arguments
+///
+/// * `dir_path` - The path to the root of the directory tree.
+/// * `keyword_files` - The keyword files mapping the extensions to the languages.
+/// * `listing_path` - The path to the csv file to write.
+/// * `quoting` - How to escape the paths and names in the csv file.
+///
+/// # Returns
+///
+/// The number of files listed.
+fn list_directory(
+    dir_path: &str,
+    keyword_files: &KeywordFiles,
+    listing_path: &str,
+    quoting: Quoting,
+) -> Result<usize> {
+    let mut projects: Vec<(String, PathBuf)> = std::fs::read_dir(dir_path)
+        .with_context(|| format!("Could not read directory {dir_path}"))?
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|e| (e.file_name().to_string_lossy().to_string(), e.path()))
+        .collect();
+    projects.sort();
+    let ids: Vec<u32> = match projects
+        .iter()
+        .map(|(name, _)| name.parse::<u32>().ok())
+        .collect::<Option<Vec<u32>>>()
+    {
+        Some(ids) => ids,
+        None => (0..projects.len() as u32).collect(),
+    };
+
+    let mut listing = CSVFile::new(listing_path, FileMode::Overwrite)?;
+    listing.write_header(&["id", "project", "name", "language"])?;
+    let mut n_files: usize = 0;
+    for (id, (project, path)) in ids.iter().zip(&projects) {
+        for entry in WalkDir::new(path)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|e| e.file_type().is_file())
+        {
+            let language: Option<&String> = entry.path().extension().and_then(|ext| {
+                keyword_files
+                    .extensions_to_language
+                    .get(&*ext.to_string_lossy())
+            });
+            if let Some(language) = language {
+                writeln!(
+                    listing,
+                    "{id},{},{},{language}",
+                    quoting.escape(project),
+                    quoting.escape(&entry.path().to_string_lossy())
+                )?;
+                n_files += 1;
             }
         }
-        progress.finish();
-        Ok(())
-    })
-    .map_err(|e| anyhow!("Error in thread pool: {e:?}"))?
+    }
+    Ok(n_files)
 }
 
 /// Analyze a file and extract the functions whose body contains one of the provided keywords.
@@ -443,10 +986,25 @@ pub fn run(
 /// * `project_id` - The id of the project to which the file belongs.
 /// * `path` - The path to the file to analyze.
 /// * `language` - The language of the file.
+/// * `is_test` - Whether the file is a test file, if known from the input file. Otherwise, it is detected from its name and imports.
+/// * `generated` - Whether the file is likely generated, if known from the input file. Otherwise, it is detected from its content.
+/// * `provenance_fields` - The name of the project and the commit of the file, each preceded by a comma if the input file has
+///   the column, appended to the output rows.
+/// * `exclude_tests` - Whether to skip the file if it is a test file.
+/// * `exclude_generated` - Whether to skip the file if it is likely generated.
 /// * `keywords_files` - The files containing the list of keywords to search for in the functions.
 /// * `fail_policy` - The policy to apply when a parse error is encountered.
 /// * `ignore_comments` - Whether to ignore comments when extracting functions, in addition to ignoring them during keyword matching.
+/// * `compress` - Whether to compress the files of the extracted functions with zstd.
+/// * `stats_only` - Whether to only compute the statistics of the functions, without writing their files.
+/// * `complexity` - The complexity criteria of the functions that are written out.
+/// * `emit_ast` - The format of the syntax trees written next to the extracted functions, if any.
+/// * `limits` - The limits on the syntax tree of the file, above which it is not analysed.
+/// * `quoting` - How to escape paths and names in the output rows.
+/// * `context` - The number of lines of context of the keyword matches, or None if matches are not collected.
+/// * `keyword_counts` - Whether to count the matches of every keyword in the retained functions.
 /// * `word_counter` - The matcher to use to count the words in the functions.
+/// * `parsers` - The parsers of the thread, reused across files.
 /// # Returns
 ///
 /// A string containing the statistics of the functions in the file. Specifically:
@@ -459,53 +1017,126 @@ pub fn run(
 /// * The number of conditional statements.
 /// * The maximum conditional nesting level.
 ///
+/// The statistics of the file and the rows of the keyword matches and counts are also returned, as well as whether the
+/// file was not analysed because its syntax tree exceeds the limits.
 fn analyze_file(
     project_id: u32,
     path: &str,
     language: &str,
+    is_test: Option<bool>,
+    generated: Option<bool>,
+    provenance_fields: &str,
+    exclude_tests: bool,
+    exclude_generated: bool,
     keywords_files: &KeywordFiles,
     fail_policy: &str,
     ignore_comments: bool,
+    compress: bool,
+    stats_only: bool,
+    complexity: &ComplexityFilter,
+    emit_ast: Option<&str>,
+    limits: TreeLimits,
+    quoting: Quoting,
+    context: Option<usize>,
+    keyword_counts: bool,
     word_counter: &Matcher,
-) -> Result<(String, Option<String>)> {
-    let grammar = language_to_grammar(language)
-        .with_context(|| format!("Unsupported language: {language}"))?;
-    // Initializes the parser
-    let mut parser: Parser = Parser::new();
-    parser.set_language(&grammar.lang)?;
+    parsers: &mut ParserCache,
+) -> Result<(String, Option<String>, MatchRows, bool)> {
     match load_file(path, 1024 * 1024 * 1024)? {
         Ok(source_code) => {
+            let language: &str = if language == UNRESOLVED_HEADER {
+                header_language(&source_code)
+            } else {
+                language
+            };
+            let is_test: bool = is_test.unwrap_or_else(|| {
+                is_test_file_name(Path::new(path)) || has_test_imports(&source_code)
+            });
+            if (is_test && exclude_tests)
+                || (exclude_generated && generated.unwrap_or_else(|| is_generated(&source_code)))
+            {
+                return Ok((
+                    String::new(),
+                    Some(format!(
+                        "{},{},{},0,0,{},none,{}{provenance_fields}",
+                        project_id,
+                        quoting.escape(path),
+                        language,
+                        vec!["0"; keywords_files.paths.len()].join(","),
+                        if is_test { 1 } else { 0 },
+                    )),
+                    MatchRows::default(),
+                    false,
+                ));
+            }
+            let (grammar, parser) = parsers.get(language)?;
             // Creates a folder to store the functions of the file
             let target_folder: String = format!("{path}.functions");
-            create_dir(&target_folder)?;
+            if !stats_only {
+                create_dir(&target_folder)?;
+            }
 
             // Parses the source code of the file
             let tree: Tree = parser
                 .parse(&source_code, None)
                 .with_context(|| format!("Failed to parse file {path}"))?;
 
+            // Pathological files, e.g. generated ones, would take minutes to analyse.
+            if limits.exceeded_by(&tree.root_node()) {
+                return Ok((
+                    String::new(),
+                    Some(
+                        file_error_row(
+                            project_id,
+                            path,
+                            language,
+                            keywords_files,
+                            "too_complex",
+                            is_test,
+                            quoting,
+                        ) + provenance_fields,
+                    ),
+                    MatchRows::default(),
+                    true,
+                ));
+            }
+
             let file_has_parse_error: bool = tree.root_node().has_error();
 
             if file_has_parse_error && fail_policy == "skip-file" {
-                Ok((String::new(), None))
+                Ok((String::new(), None, MatchRows::default(), false))
             } else if file_has_parse_error && fail_policy == "abort" {
                 bail!("Parse error in file {path}")
             } else {
                 let root: Node<'_> = tree.root_node();
-                let (output, total_functions, functions_with_kw, functions_with_specific_kw) =
-                    extract_functions(
-                        project_id,
-                        &root,
-                        &target_folder,
-                        language,
-                        &grammar,
-                        &source_code,
-                        keywords_files,
-                        fail_policy,
-                        ignore_comments,
-                        word_counter,
-                        &mut parser,
-                    )?;
+                let (
+                    output,
+                    total_functions,
+                    functions_with_kw,
+                    functions_with_specific_kw,
+                    matches,
+                ) = extract_functions(
+                    project_id,
+                    &root,
+                    &target_folder,
+                    language,
+                    grammar,
+                    &source_code,
+                    keywords_files,
+                    fail_policy,
+                    ignore_comments,
+                    compress,
+                    stats_only,
+                    complexity,
+                    emit_ast,
+                    quoting,
+                    context,
+                    keyword_counts,
+                    word_counter,
+                    parser,
+                    is_test,
+                    provenance_fields,
+                )?;
 
                 let error_position: String = if file_has_parse_error {
                     position_to_string(find_first_error_position(&root))
@@ -516,10 +1147,9 @@ fn analyze_file(
                 Ok((
                     output,
                     Some(format!(
-                        "{},{},{},{},{},{},{}",
+                        "{},{},{},{},{},{},{},{}{provenance_fields}",
                         project_id,
-                        path.replace(",", "-was_comma-")
-                            .replace("\"", "-was_quote-"),
+                        quoting.escape(path),
                         language,
                         total_functions,
                         functions_with_kw,
@@ -529,7 +1159,10 @@ fn analyze_file(
                             .collect::<Vec<String>>()
                             .join(","),
                         error_position,
+                        if is_test { 1 } else { 0 },
                     )),
+                    matches,
+                    false,
                 ))
             }
         }
@@ -537,13 +1170,19 @@ fn analyze_file(
         // If the file is too large, return an error row
         Err(_) => Ok((
             String::new(),
-            Some(file_error_row(
-                project_id,
-                path,
-                language,
-                keywords_files,
-                "none",
-            )),
+            Some(
+                file_error_row(
+                    project_id,
+                    path,
+                    language,
+                    keywords_files,
+                    "none",
+                    is_test.unwrap_or_else(|| is_test_file_name(Path::new(path))),
+                    quoting,
+                ) + provenance_fields,
+            ),
+            MatchRows::default(),
+            false,
         )),
     }
 }
@@ -554,12 +1193,13 @@ fn file_error_row(
     language: &str,
     keyword_files: &KeywordFiles,
     parse_error: &str,
+    is_test: bool,
+    quoting: Quoting,
 ) -> String {
     format!(
-        "{},{},{},-1,-1,{},{}",
+        "{},{},{},-1,-1,{},{},{}",
         project_id,
-        path.replace(",", "-was_comma-")
-            .replace("\"", "-was_quote-"),
+        quoting.escape(path),
         language,
         keyword_files
             .paths
@@ -568,6 +1208,7 @@ fn file_error_row(
             .collect::<Vec<String>>()
             .join(","),
         parse_error,
+        if is_test { 1 } else { 0 },
     )
 }
 
@@ -587,12 +1228,22 @@ fn file_error_row(
 /// * `keyword_files` - The keyword files containing the keywords to search for in the functions.
 /// * `fail_policy` - The policy to apply when a parse error is encountered.
 /// * `ignore_comments` - Whether to ignore comments when extracting functions, in addition to ignoring them during keyword matching.
+/// * `compress` - Whether to compress the files of the extracted functions with zstd.
+/// * `stats_only` - Whether to only compute the statistics of the functions, without writing their files.
+/// * `complexity` - The complexity criteria of the functions that are written out.
+/// * `emit_ast` - The format of the syntax trees written next to the extracted functions, if any.
+/// * `quoting` - How to escape paths and names in the output rows.
+/// * `context` - The number of lines of context of the keyword matches, or None if matches are not collected.
+/// * `keyword_counts` - Whether to count the matches of every keyword in the retained functions.
 /// * `word_counter` - The matcher to use to count the words in the functions.
 /// * `parser` - The parser to use to parse the functions.
+/// * `is_test` - Whether the source file is a test file.
+/// * `provenance_fields` - The name of the project and the commit of the source file, each preceded by a comma if known, appended to the rows.
 ///
 /// # Returns
 ///
-/// A tuple containing the statistics of the functions in the file and the function number after processing the file node
+/// A tuple containing the statistics of the functions in the file, the function number after processing the file node
+/// and the rows of the keyword matches and counts of the retained functions.
 ///
 fn extract_functions(
     project_id: u32,
@@ -604,11 +1255,25 @@ fn extract_functions(
     keyword_files: &KeywordFiles,
     fail_policy: &str,
     ignore_comments: bool,
+    compress: bool,
+    stats_only: bool,
+    complexity: &ComplexityFilter,
+    emit_ast: Option<&str>,
+    quoting: Quoting,
+    context: Option<usize>,
+    keyword_counts: bool,
     word_counter: &Matcher,
     parser: &mut Parser,
-) -> Result<(String, usize, usize, Vec<usize>), Error> {
+    is_test: bool,
+    provenance_fields: &str,
+) -> Result<(String, usize, usize, Vec<usize>, MatchRows), Error> {
     // Initializes the builder to store the statistics of the functions in the file
     let mut builder: String = String::new();
+    let mut match_rows: MatchRows = MatchRows::default();
+    let source_lines: Vec<&[u8]> = match context {
+        Some(_) => source.split(|b| *b == b'\n').collect(),
+        None => Vec::new(),
+    };
     let mut functions: usize = 0;
     let mut functions_with_kw: usize = 0;
     let mut functions_with_specific_kw: Vec<usize> = vec![0; keyword_files.paths.len()];
@@ -668,103 +1333,172 @@ fn extract_functions(
                     keyword_files.count_matches_in_text(language, function_code);
 
                 if matches.iter().any(|x| *x > 0) {
-                    let function_path: String = format!(
-                        "{}/{}-{}",
-                        target_folder, function_position.0, function_position.1
-                    );
-
-                    std::fs::write(
-                        &function_path,
-                        if ignore_comments {
-                            function_code_with_strings
-                        } else {
-                            function_source_code
-                        },
-                    )?;
-
                     // Count the number of loops, conditionals and parameters if the function
                     let (loops, loop_nesting) = count_nodes_of_kind(&node, &grammar.loop_nodes);
                     let (conditionals, conditional_nesting) =
                         count_nodes_of_kind(&node, &grammar.cond_nodes);
-                    let (calls, calls_nesting) =
-                        count_nodes_of_kind(&node, &grammar.function_call_nodes);
-
-                    let params_vec: Vec<Node<'_>> =
-                        find_first_node_of_kind(&node, &grammar.param_seq_nodes, true);
-
-                    let mut name: String = String::from_utf8_lossy(
-                        find_first_field(&node, grammar.name_field)
-                            .map(|n| node_source_code(&n, source))
-                            .unwrap_or(b""),
-                    )
-                    .to_string();
-                    if let Some(idx) = name.find('(') {
-                        name.truncate(idx);
+                    let loc: usize = count_text_lines(function_code_with_strings);
+
+                    // Functions that are not complex enough are not written out.
+                    let kept: bool = complexity.keeps(loc, loops, conditionals);
+                    let function_path: String = if kept {
+                        format!(
+                            "{}/{}-{}{}",
+                            target_folder,
+                            function_position.0,
+                            function_position.1,
+                            if compress { ".zst" } else { "" }
+                        )
+                    } else {
+                        "filtered".to_string()
+                    };
+
+                    if kept && !stats_only {
+                        let function_code: &[u8] = if ignore_comments {
+                            function_code_with_strings
+                        } else {
+                            function_source_code
+                        };
+                        if compress {
+                            write_compressed_file(&function_path, function_code)?;
+                        } else {
+                            std::fs::write(&function_path, function_code)?;
+                        }
+                        if let Some(format) = emit_ast {
+                            let ast_path: String = format!(
+                                "{}/{}-{}.ast{}{}",
+                                target_folder,
+                                function_position.0,
+                                function_position.1,
+                                if format == "json" { ".json" } else { "" },
+                                if compress { ".zst" } else { "" }
+                            );
+                            let ast: String = match format {
+                                "json" => ast_to_json(&node, node.start_byte()).to_string(),
+                                _ => node.to_sexp(),
+                            };
+                            if compress {
+                                write_compressed_file(&ast_path, ast)?;
+                            } else {
+                                std::fs::write(&ast_path, ast)?;
+                            }
+                        }
                     }
-                    name = name.chars().filter(|c| !c.is_whitespace()).collect();
+                    // The statistics of the functions that are not written out are only kept on demand.
+                    if kept || complexity.keep_stats {
+                        let (calls, calls_nesting) =
+                            count_nodes_of_kind(&node, &grammar.function_call_nodes);
+
+                        let params_vec: Vec<Node<'_>> =
+                            find_first_node_of_kind(&node, &grammar.param_seq_nodes, true);
+
+                        let name: String = function_name(&node, grammar, source);
+                        let qualified_name: String = qualified_name(&node, &name, grammar, source);
+                        let scope: &str = enclosing_scope(&node, grammar);
+
+                        let mut n_param: usize = 0;
+                        let mut param_match: usize = 0;
+                        for params in params_vec {
+                            let matches = match grammar.param_type_field {
+                                Some(field) => {
+                                    // Safe unwrap: whole source code was read as utf8 before
+                                    // Safe unwrap: the pattern is already checked above
+                                    find_fields(&params, field)
+                                        .into_iter()
+                                        .map(|x| node_source_code(&x, source))
+                                        .filter(|x| keyword_files.has_matches_in_text(language, x))
+                                        .count()
+                                }
+                                None => 0,
+                            };
+
+                            n_param += count_nodes_of_kind(&params, &grammar.param_nodes).0;
+                            param_match += matches;
+                        }
 
-                    let mut n_param: usize = 0;
-                    let mut param_match: usize = 0;
-                    for params in params_vec {
-                        let matches = match grammar.param_type_field {
+                        let return_type_match = match grammar.return_type_field {
                             Some(field) => {
                                 // Safe unwrap: whole source code was read as utf8 before
                                 // Safe unwrap: the pattern is already checked above
-                                find_fields(&params, field)
-                                    .into_iter()
+                                find_first_field(&node, field)
                                     .map(|x| node_source_code(&x, source))
                                     .filter(|x| keyword_files.has_matches_in_text(language, x))
-                                    .count()
+                                    .map(|_| 1)
+                                    .unwrap_or(0)
                             }
                             None => 0,
                         };
 
-                        n_param += count_nodes_of_kind(&params, &grammar.param_nodes).0;
-                        param_match += matches;
-                    }
-
-                    let return_type_match = match grammar.return_type_field {
-                        Some(field) => {
-                            // Safe unwrap: whole source code was read as utf8 before
-                            // Safe unwrap: the pattern is already checked above
-                            find_first_field(&node, field)
-                                .map(|x| node_source_code(&x, source))
-                                .filter(|x| keyword_files.has_matches_in_text(language, x))
-                                .map(|_| 1)
-                                .unwrap_or(0)
+                        let comment_lines: usize =
+                            count_comment_lines(&node, &grammar.comment_nodes);
+                        let has_doc: bool = has_doc_comment(&node, grammar);
+
+                        writeln!(
+                            &mut builder,
+                            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}{provenance_fields}",
+                            project_id,
+                            quoting.escape(&function_path),
+                            quoting.escape(&name),
+                            quoting.escape(&qualified_name),
+                            scope,
+                            position_to_string(Some(function_position)),
+                            language,
+                            loc,
+                            word_counter.count_matches_in_text(function_code_with_strings),
+                            matches
+                                .iter()
+                                .map(|x| x.to_string())
+                                .collect::<Vec<String>>()
+                                .join(","),
+                            loops,
+                            loop_nesting,
+                            conditionals,
+                            conditional_nesting,
+                            calls,
+                            calls_nesting,
+                            n_param,
+                            param_match,
+                            return_type_match,
+                            comment_lines,
+                            if has_doc { 1 } else { 0 },
+                            error_position,
+                            if is_test { 1 } else { 0 },
+                        )?;
+                        if let Some(context) = context.filter(|_| kept) {
+                            write_keyword_matches(
+                                &mut match_rows.matches,
+                                project_id,
+                                &function_path,
+                                &node,
+                                grammar,
+                                source,
+                                &source_lines,
+                                language,
+                                keyword_files,
+                                context,
+                                quoting,
+                            )?;
                         }
-                        None => 0,
-                    };
-
-                    writeln!(
-                        &mut builder,
-                        "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
-                        project_id,
-                        &function_path
-                            .replace(",", "-was_comma-")
-                            .replace("\"", "-was_quote-"),
-                        name.replace(",", "-was_comma-")
-                            .replace("\"", "-was_quote-"),
-                        position_to_string(Some(function_position)),
-                        language,
-                        count_text_lines(function_code_with_strings),
-                        word_counter.count_matches_in_text(function_code_with_strings),
-                        matches
-                            .iter()
-                            .map(|x| x.to_string())
-                            .collect::<Vec<String>>()
-                            .join(","),
-                        loops,
-                        loop_nesting,
-                        conditionals,
-                        conditional_nesting,
-                        calls,
-                        calls_nesting,
-                        n_param,
-                        param_match,
-                        return_type_match,
-                        error_position,
-                    )?;
+                        if keyword_counts && kept {
+                            if let Some(matchers) = keyword_files.matchers.get(language) {
+                                for (matcher, keyword_file) in
+                                    matchers.iter().zip(&keyword_files.paths)
+                                {
+                                    for (keyword, count) in matcher.keyword_counts(function_code) {
+                                        writeln!(
+                                            &mut match_rows.keyword_counts,
+                                            "{},{},{},{},{}",
+                                            project_id,
+                                            quoting.escape(&function_path),
+                                            quoting.escape(keyword_file),
+                                            Quoting::Rfc4180.escape(keyword),
+                                            count
+                                        )?;
+                                    }
+                                }
+                            }
+                        }
+                    }
                     functions_with_kw += 1;
                     for (i, m) in matches.iter().enumerate() {
                         if *m > 0 {
@@ -790,31 +1524,253 @@ fn extract_functions(
         functions,
         functions_with_kw,
         functions_with_specific_kw,
+        match_rows,
     ))
 }
 
-/// Returns the source code of a node in the parse tree
+/// Returns the named nodes of a syntax tree as JSON, as in its s-expression, with their kind, their field in their
+/// parent if any, their byte range and their children.
 ///
 /// # Arguments
 ///
-/// * `n` - The node to extract the source code from.
-/// * `source` - The source code of the whole file.
-fn node_source_code<'a>(n: &Node, source: &'a [u8]) -> &'a [u8] {
-    &source[n.start_byte()..n.end_byte()]
+/// * `node` - The root of the tree.
+/// * `offset` - The byte subtracted from the positions of the nodes, e.g. the start of the root so that the ranges
+///   are relative to its code.
+fn ast_to_json(node: &Node, offset: usize) -> JsonValue {
+    let mut value: JsonValue = json::object! {
+        "kind": node.kind(),
+        "start": node.start_byte() - offset,
+        "end": node.end_byte() - offset,
+    };
+    let mut children: Vec<JsonValue> = Vec::new();
+    let mut cursor = node.walk();
+    if cursor.goto_first_child() {
+        loop {
+            let child: Node = cursor.node();
+            if child.is_named() {
+                let mut child_value: JsonValue = ast_to_json(&child, offset);
+                if let Some(field) = cursor.field_name() {
+                    child_value["field"] = field.into();
+                }
+                children.push(child_value);
+            }
+            if !cursor.goto_next_sibling() {
+                break;
+            }
+        }
+    }
+    if !children.is_empty() {
+        value["children"] = children.into();
+    }
+    value
 }
 
-/// Grammar of a programming language.
-struct Grammar {
-    /// The programming language the grammar belongs to.
-    lang: Language,
+/// Writes a row for every keyword match in a function, with the line of the match and the surrounding lines.
+/// As for the keyword counts, comments and string literals are not searched,
+/// but they are blanked out instead of being removed so that the lines of the matches are preserved.
+///
+/// # Arguments
+///
+/// * `builder` - The string the rows are written to.
+/// * `project_id` - The id of the project to which the file belongs.
+/// * `function_path` - The path to the extracted function file.
+/// * `node` - The node of the function.
+/// * `grammar` - The grammar of the language.
+/// * `source` - The source code of the whole file.
+/// * `source_lines` - The lines of the source code of the whole file.
+/// * `language` - The language of the source file.
+/// * `keyword_files` - The keyword files containing the keywords to search for.
+/// * `context` - The number of lines before and after the matched line to write.
+/// * `quoting` - How to escape the path in the rows. The code is always quoted as specified by RFC 4180.
+fn write_keyword_matches(
+    builder: &mut String,
+    project_id: u32,
+    function_path: &str,
+    node: &Node,
+    grammar: &Grammar,
+    source: &[u8],
+    source_lines: &[&[u8]],
+    language: &str,
+    keyword_files: &KeywordFiles,
+    context: usize,
+    quoting: Quoting,
+) -> Result<()> {
+    let Some(matchers) = keyword_files.matchers.get(language) else {
+        return Ok(());
+    };
+    let mut code: Vec<u8> = node_source_code(node, source).to_vec();
+    for n in find_kind(node, &grammar.comment_nodes)
+        .into_iter()
+        .chain(find_kind(node, &grammar.string_literal_nodes))
+    {
+        for b in &mut code[n.start_byte() - node.start_byte()..n.end_byte() - node.start_byte()] {
+            if *b != b'\n' {
+                *b = b' ';
+            }
+        }
+    }
 
-    /// Nodes representing comments.
-    comment_nodes: HashSet<&'static str>,
+    for (matcher, keyword_file) in matchers.iter().zip(&keyword_files.paths) {
+        for range in matcher.match_ranges(&code) {
+            // Row of the match in the file, starting from 0.
+            let row: usize = node.start_position().row
+                + code[..range.start].iter().filter(|b| **b == b'\n').count();
+            let lines: Vec<String> = source_lines
+                [row.saturating_sub(context)..(row + context + 1).min(source_lines.len())]
+                .iter()
+                .map(|l| String::from_utf8_lossy(l.strip_suffix(b"\r").unwrap_or(l)).to_string())
+                .collect();
+            writeln!(
+                builder,
+                "{},{},{},{},{},{}",
+                project_id,
+                quoting.escape(function_path),
+                quoting.escape(keyword_file),
+                Quoting::Rfc4180.escape(&String::from_utf8_lossy(&code[range])),
+                row + 1,
+                Quoting::Rfc4180.escape(&lines.join("\n")),
+            )?;
+        }
+    }
+    Ok(())
+}
 
-    /// Nodes representing string literals.
-    string_literal_nodes: HashSet<&'static str>,
+/// Complexity criteria that the functions matching the keywords must satisfy to be written out.
+#[derive(Clone, Copy, Default)]
+pub struct ComplexityFilter {
+    /// Minimal number of lines of code.
+    pub min_loc: usize,
+    /// Maximal number of lines of code.
+    pub max_loc: Option<usize>,
+    /// Minimal number of loops.
+    pub min_loops: usize,
+    /// Minimal number of conditional statements.
+    pub min_conditionals: usize,
+    /// Whether the statistics of the functions that do not satisfy the criteria are still written,
+    /// with 'filtered' in place of their path.
+    pub keep_stats: bool,
+}
 
-    /// Nodes representing loops.
+impl ComplexityFilter {
+    /// Whether a function satisfies the criteria.
+    ///
+    /// # Arguments
+    ///
+    /// * `loc` - The number of lines of code of the function.
+    /// * `loops` - The number of loops of the function.
+    /// * `conditionals` - The number of conditional statements of the function.
+    fn keeps(&self, loc: usize, loops: usize, conditionals: usize) -> bool {
+        loc >= self.min_loc
+            && self.max_loc.is_none_or(|max| loc <= max)
+            && loops >= self.min_loops
+            && conditionals >= self.min_conditionals
+    }
+}
+
+/// Limits on the syntax tree of a file, above which the file is not analysed.
+#[derive(Clone, Copy, Default)]
+pub struct TreeLimits {
+    /// Maximal depth of the tree, the root being at depth 0.
+    pub max_depth: Option<usize>,
+    /// Maximal number of nodes of the tree.
+    pub max_nodes: Option<usize>,
+}
+
+impl TreeLimits {
+    /// Checks whether a syntax tree is deeper or has more nodes than the limits.
+    /// The tree is walked without recursion and the walk stops as soon as a limit is exceeded.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The root of the tree.
+    fn exceeded_by(&self, root: &Node) -> bool {
+        if self.max_depth.is_none() && self.max_nodes.is_none() {
+            return false;
+        }
+        let mut cursor = root.walk();
+        let mut depth: usize = 0;
+        let mut nodes: usize = 1;
+        loop {
+            if cursor.goto_first_child() {
+                depth += 1;
+            } else {
+                while !cursor.goto_next_sibling() {
+                    if !cursor.goto_parent() {
+                        return false;
+                    }
+                    depth -= 1;
+                }
+            }
+            nodes += 1;
+            if self.max_depth.is_some_and(|max| depth > max)
+                || self.max_nodes.is_some_and(|max| nodes > max)
+            {
+                return true;
+            }
+        }
+    }
+}
+
+/// Rows describing the keyword matches of the retained functions of a file.
+#[derive(Default)]
+struct MatchRows {
+    /// The rows of the file given with --matches.
+    matches: String,
+    /// The rows of the file given with --per-keyword-counts.
+    keyword_counts: String,
+}
+
+/// Grammars and parsers of the languages analyzed by a thread.
+/// They are created the first time a file of their language is analyzed and reused for the next files.
+#[derive(Default)]
+struct ParserCache {
+    parsers: HashMap<String, (Grammar, Parser)>,
+}
+
+impl ParserCache {
+    /// Returns the grammar and the parser of a language, creating them if needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `language` - The language of the file to parse.
+    fn get(&mut self, language: &str) -> Result<(&Grammar, &mut Parser)> {
+        if !self.parsers.contains_key(language) {
+            let grammar: Grammar = language_to_grammar(language)
+                .with_context(|| format!("Unsupported language: {language}"))?;
+            let mut parser: Parser = Parser::new();
+            parser.set_language(&grammar.lang)?;
+            self.parsers.insert(language.to_string(), (grammar, parser));
+        }
+        // Safe unwrap: the parser was inserted above.
+        let (grammar, parser) = self.parsers.get_mut(language).unwrap();
+        // Clears the state left by the parsing of the previous file.
+        parser.reset();
+        Ok((grammar, parser))
+    }
+}
+
+/// Returns the source code of a node in the parse tree
+///
+/// # Arguments
+///
+/// * `n` - The node to extract the source code from.
+/// * `source` - The source code of the whole file.
+fn node_source_code<'a>(n: &Node, source: &'a [u8]) -> &'a [u8] {
+    &source[n.start_byte()..n.end_byte()]
+}
+
+/// Grammar of a programming language.
+pub(crate) struct Grammar {
+    /// The programming language the grammar belongs to.
+    pub(crate) lang: Language,
+
+    /// Nodes representing comments.
+    pub(crate) comment_nodes: HashSet<&'static str>,
+
+    /// Nodes representing string literals.
+    string_literal_nodes: HashSet<&'static str>,
+
+    /// Nodes representing loops.
     loop_nodes: HashSet<&'static str>,
 
     /// Nodes representing conditional statements.
@@ -840,6 +1796,16 @@ struct Grammar {
 
     /// The field name of the function or method name.
     name_field: &'static str,
+
+    /// Nodes enclosing functions whose name is part of the qualified name of the functions, such as classes,
+    /// namespaces and modules, with the field name of their name and the kind of declaration they are.
+    scope_nodes: HashMap<&'static str, (&'static str, &'static str)>,
+
+    /// The separator of the names of the scopes in a qualified name.
+    scope_separator: &'static str,
+
+    /// Whether a string literal at the start of the function body is a docstring.
+    docstrings: bool,
 }
 
 /// Returns the grammar for the C programming language.
@@ -861,6 +1827,9 @@ fn c_grammar() -> Grammar {
         param_type_field: Some("type"),
         return_type_field: Some("type"),
         name_field: "declarator",
+        scope_nodes: HashMap::new(),
+        scope_separator: "::",
+        docstrings: false,
     }
 }
 
@@ -887,6 +1856,15 @@ fn cpp_grammar() -> Grammar {
         param_type_field: Some("type"),
         return_type_field: Some("type"),
         name_field: "declarator",
+        scope_nodes: [
+            ("namespace_definition", ("name", "namespace")),
+            ("class_specifier", ("name", "class")),
+            ("struct_specifier", ("name", "struct")),
+        ]
+        .into_iter()
+        .collect(),
+        scope_separator: "::",
+        docstrings: false,
     }
 }
 
@@ -921,6 +1899,17 @@ fn cs_grammar() -> Grammar {
         param_type_field: Some("type"),
         return_type_field: Some("returns"),
         name_field: "name",
+        scope_nodes: [
+            ("namespace_declaration", ("name", "namespace")),
+            ("class_declaration", ("name", "class")),
+            ("struct_declaration", ("name", "struct")),
+            ("interface_declaration", ("name", "interface")),
+            ("record_declaration", ("name", "record")),
+        ]
+        .into_iter()
+        .collect(),
+        scope_separator: ".",
+        docstrings: false,
     }
 }
 
@@ -953,6 +1942,17 @@ fn ts_grammar() -> Grammar {
         param_type_field: Some("type"),
         return_type_field: Some("return_type"),
         name_field: "name",
+        scope_nodes: [
+            ("internal_module", ("name", "namespace")),
+            ("module", ("name", "module")),
+            ("class_declaration", ("name", "class")),
+            ("abstract_class_declaration", ("name", "class")),
+            ("class", ("name", "class")),
+        ]
+        .into_iter()
+        .collect(),
+        scope_separator: ".",
+        docstrings: false,
     }
 }
 
@@ -983,6 +1983,9 @@ fn go_grammar() -> Grammar {
         param_type_field: Some("type"),
         return_type_field: Some("result"),
         name_field: "name",
+        scope_nodes: HashMap::new(),
+        scope_separator: ".",
+        docstrings: false,
     }
 }
 
@@ -1014,6 +2017,16 @@ fn java_grammar() -> Grammar {
         param_type_field: Some("type"),
         return_type_field: Some("type"),
         name_field: "name",
+        scope_nodes: [
+            ("class_declaration", ("name", "class")),
+            ("interface_declaration", ("name", "interface")),
+            ("enum_declaration", ("name", "enum")),
+            ("record_declaration", ("name", "record")),
+        ]
+        .into_iter()
+        .collect(),
+        scope_separator: ".",
+        docstrings: false,
     }
 }
 
@@ -1036,6 +2049,16 @@ fn scala_grammar() -> Grammar {
         param_type_field: Some("type"),
         return_type_field: Some("return_type"),
         name_field: "name",
+        scope_nodes: [
+            ("package_clause", ("name", "package")),
+            ("class_definition", ("name", "class")),
+            ("object_definition", ("name", "object")),
+            ("trait_definition", ("name", "trait")),
+        ]
+        .into_iter()
+        .collect(),
+        scope_separator: ".",
+        docstrings: false,
     }
 }
 
@@ -1072,6 +2095,9 @@ fn fortran_grammar() -> Grammar {
         param_type_field: None,
         return_type_field: None,
         name_field: "name",
+        scope_nodes: HashMap::new(),
+        scope_separator: ".",
+        docstrings: false,
     }
 }
 
@@ -1094,6 +2120,11 @@ fn python_grammar() -> Grammar {
         param_type_field: None,
         return_type_field: None,
         name_field: "name",
+        scope_nodes: [("class_definition", ("name", "class"))]
+            .into_iter()
+            .collect(),
+        scope_separator: ".",
+        docstrings: true,
     }
 }
 
@@ -1122,7 +2153,161 @@ fn rust_grammar() -> Grammar {
         param_type_field: Some("type"),
         return_type_field: Some("return_type"),
         name_field: "name",
+        scope_nodes: [
+            ("mod_item", ("name", "module")),
+            ("impl_item", ("type", "impl")),
+            ("trait_item", ("name", "trait")),
+        ]
+        .into_iter()
+        .collect(),
+        scope_separator: "::",
+        docstrings: false,
+    }
+}
+
+/// Returns the grammar for the Kotlin programming language.
+fn kotlin_grammar() -> Grammar {
+    Grammar {
+        lang: tree_sitter_kotlin_ng::LANGUAGE.into(),
+        comment_nodes: vec!["line_comment", "block_comment"].into_iter().collect(),
+        string_literal_nodes: vec!["string_literal", "multiline_string_literal"]
+            .into_iter()
+            .collect(),
+        loop_nodes: vec!["for_statement", "while_statement", "do_while_statement"]
+            .into_iter()
+            .collect(),
+        cond_nodes: vec!["if_expression", "when_expression"]
+            .into_iter()
+            .collect(),
+        function_nodes: vec!["function_declaration", "anonymous_function"]
+            .into_iter()
+            .collect(),
+        function_call_nodes: vec!["call_expression"].into_iter().collect(),
+        param_seq_nodes: vec!["function_value_parameters"].into_iter().collect(),
+        param_nodes: vec!["parameter"].into_iter().collect(),
+        param_type_field: None,
+        return_type_field: None,
+        name_field: "name",
+        scope_nodes: [
+            ("class_declaration", ("name", "class")),
+            ("object_declaration", ("name", "object")),
+        ]
+        .into_iter()
+        .collect(),
+        scope_separator: ".",
+        docstrings: false,
+    }
+}
+
+/// Language of the .h headers whose language could not be resolved from their project.
+/// It is detected from their content when they are parsed.
+const UNRESOLVED_HEADER: &str = "c/c++";
+
+/// Extensions of the headers only used by C++.
+const CPP_HEADER_EXTENSIONS: [&str; 4] = ["hpp", "hh", "hxx", "h++"];
+
+/// Extensions of the C++ source files.
+const CPP_SOURCE_EXTENSIONS: [&str; 4] = ["cpp", "cc", "cxx", "c++"];
+
+/// Constructs of C++ that are not valid C, used to detect the language of headers.
+static CPP_CONSTRUCTS: LazyLock<regex::bytes::Regex> = LazyLock::new(|| {
+    regex::bytes::Regex::new(
+        r"\bnamespace\s+\w*\s*\{|\btemplate\s*<|\bclass\s+\w+\s*[:{]|\b(public|private|protected)\s*:|\bstd::|\b(constexpr|nullptr)\b|#\s*include\s*<[a-z_]+>",
+    )
+    .unwrap()
+});
+
+/// Returns the lowercase extension of a path.
+fn extension(path: &str) -> Option<String> {
+    Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+}
+
+/// Returns the language of a header from its content: C++ if it contains constructs that are not valid C, and C otherwise.
+fn header_language(code: &[u8]) -> &'static str {
+    if CPP_CONSTRUCTS.is_match(code) {
+        "c++"
+    } else {
+        "c"
+    }
+}
+
+/// Returns the language of a file from its extension, for files listed without their language such as the files of a
+/// directory. C++ extensions are C++, the language of .h headers is detected from their content, and other extensions
+/// are resolved by [`normalize_language`], e.g. `py` is Python.
+///
+/// # Arguments
+///
+/// * `ext` - The extension of the file.
+/// * `code` - The content of the file.
+pub(crate) fn extension_language(ext: &str, code: &[u8]) -> String {
+    let ext: String = ext.to_ascii_lowercase();
+    if CPP_SOURCE_EXTENSIONS.contains(&ext.as_str())
+        || CPP_HEADER_EXTENSIONS.contains(&ext.as_str())
+    {
+        "c++".to_string()
+    } else if ext == "h" {
+        header_language(code).to_string()
+    } else {
+        normalize_language(&ext)
+    }
+}
+
+/// Resolves the language of the C and C++ headers of the input files.
+/// Headers with a C++ extension are C++. The language of .h headers is given by the policy:
+/// * `c` or `c++`: the headers are in this language.
+/// * `auto`: the headers are C in projects with C sources only and C++ in projects with C++ sources only.
+///   In other projects, such as header-only libraries, their language is [`UNRESOLVED_HEADER`] and detected from their content.
+///
+/// The languages of other files are unchanged.
+///
+/// # Arguments
+///
+/// * `ids` - The project ids of the files.
+/// * `paths` - The paths of the files.
+/// * `languages` - The languages of the files in the input.
+/// * `policy` - The language of the .h headers: "c", "c++" or "auto".
+fn resolve_header_languages(
+    ids: &[u32],
+    paths: &[&str],
+    languages: &[&str],
+    policy: &str,
+) -> Vec<String> {
+    // Whether each project has C sources and C++ sources.
+    let mut sources: HashMap<u32, (bool, bool)> = HashMap::new();
+    for ((id, path), language) in ids.iter().zip(paths).zip(languages) {
+        let ext: Option<String> = extension(path);
+        let (c, cpp) = sources.entry(*id).or_default();
+        match ext.as_deref() {
+            Some("c") => *c = true,
+            Some(ext) if CPP_SOURCE_EXTENSIONS.contains(&ext) => *cpp = true,
+            _ if *language == "c++" && !matches!(ext.as_deref(), Some("h")) => *cpp = true,
+            _ => (),
+        }
     }
+
+    ids.iter()
+        .zip(paths)
+        .zip(languages)
+        .map(|((id, path), language)| {
+            if *language != "c" && *language != "c++" {
+                return language.to_string();
+            }
+            match extension(path).as_deref() {
+                Some(ext) if CPP_HEADER_EXTENSIONS.contains(&ext) => "c++".to_string(),
+                Some("h") => match (policy, sources.get(id)) {
+                    ("c", _) => "c".to_string(),
+                    ("c++", _) => "c++".to_string(),
+                    (_, Some((true, false))) => "c".to_string(),
+                    (_, Some((false, true))) => "c++".to_string(),
+                    _ => UNRESOLVED_HEADER.to_string(),
+                },
+                _ => language.to_string(),
+            }
+        })
+        .collect()
 }
 
 /// Returns the grammar corresponding to the given language.
@@ -1134,7 +2319,7 @@ fn rust_grammar() -> Grammar {
 /// # Returns
 ///
 /// The grammar corresponding to the language or `None` if the language is not supported.
-fn language_to_grammar(lang: &str) -> Option<Grammar> {
+pub(crate) fn language_to_grammar(lang: &str) -> Option<Grammar> {
     match lang.to_lowercase().as_str() {
         "c" => Some(c_grammar()),
         "c++" => Some(cpp_grammar()),
@@ -1146,6 +2331,7 @@ fn language_to_grammar(lang: &str) -> Option<Grammar> {
         "go" => Some(go_grammar()),
         "scala" => Some(scala_grammar()),
         "rust" => Some(rust_grammar()),
+        "kotlin" => Some(kotlin_grammar()),
         _ => None,
     }
 }
@@ -1387,6 +2573,119 @@ fn remove_kind_from_source(source: &[u8], root: &Node, kinds: &HashSet<&str>) ->
     new_source
 }
 
+/// Counts the number of source lines spanned by comments in a tree.
+/// A line holding several comments, or a comment next to code, is counted once.
+///
+/// # Arguments
+///
+/// * `root` - The root node of the tree.
+/// * `comment_nodes` - The kinds of nodes representing comments.
+fn count_comment_lines(root: &Node, comment_nodes: &HashSet<&str>) -> usize {
+    find_kind(root, comment_nodes)
+        .into_iter()
+        .flat_map(|n| n.start_position().row..=n.end_position().row)
+        .collect::<HashSet<usize>>()
+        .len()
+}
+
+/// Returns the name of a function, without its parameters and whitespace, or an empty string if it is anonymous.
+///
+/// # Arguments
+///
+/// * `function` - The node of the function.
+/// * `grammar` - The grammar of the language.
+/// * `source` - The source code of the whole file.
+fn function_name(function: &Node, grammar: &Grammar, source: &[u8]) -> String {
+    let mut name: String = String::from_utf8_lossy(
+        find_first_field(function, grammar.name_field)
+            .map(|n| node_source_code(&n, source))
+            .unwrap_or(b""),
+    )
+    .to_string();
+    if let Some(idx) = name.find('(') {
+        name.truncate(idx);
+    }
+    name.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+/// Returns the qualified name of a function, that is its name preceded by the names of the classes, namespaces
+/// and modules enclosing it, from the outermost to the innermost. Anonymous functions have no qualified name.
+///
+/// # Arguments
+///
+/// * `function` - The node of the function.
+/// * `name` - The name of the function.
+/// * `grammar` - The grammar of the language.
+/// * `source` - The source code of the whole file.
+fn qualified_name(function: &Node, name: &str, grammar: &Grammar, source: &[u8]) -> String {
+    if name.is_empty() {
+        return String::new();
+    }
+    let mut names: Vec<String> = vec![name.to_string()];
+    let mut ancestor: Option<Node> = function.parent();
+    while let Some(node) = ancestor {
+        if let Some(scope) = grammar
+            .scope_nodes
+            .get(node.kind())
+            .and_then(|(field, _)| node.child_by_field_name(field))
+        {
+            let mut scope_name: String =
+                String::from_utf8_lossy(node_source_code(&scope, source)).to_string();
+            // The type parameters of generic classes are not part of their name.
+            if let Some(idx) = scope_name.find(['<', '[']) {
+                scope_name.truncate(idx);
+            }
+            names.push(scope_name.chars().filter(|c| !c.is_whitespace()).collect());
+        }
+        ancestor = node.parent();
+    }
+    names.reverse();
+    names.join(grammar.scope_separator)
+}
+
+/// Returns the kind of the innermost declaration enclosing a function, such as class, struct or module,
+/// or `top-level` if the function is not enclosed in any.
+///
+/// # Arguments
+///
+/// * `function` - The node of the function.
+/// * `grammar` - The grammar of the language.
+fn enclosing_scope(function: &Node, grammar: &Grammar) -> &'static str {
+    let mut ancestor: Option<Node> = function.parent();
+    while let Some(node) = ancestor {
+        if let Some((_, kind)) = grammar.scope_nodes.get(node.kind()) {
+            return kind;
+        }
+        ancestor = node.parent();
+    }
+    "top-level"
+}
+
+/// Checks whether a function is documented, that is whether it is directly preceded by a comment
+/// or, in languages supporting docstrings, whether its body starts with a string literal.
+///
+/// # Arguments
+///
+/// * `function` - The node of the function.
+/// * `grammar` - The grammar of the language.
+fn has_doc_comment(function: &Node, grammar: &Grammar) -> bool {
+    let leading_comment: bool = function
+        .prev_sibling()
+        .filter(|n| grammar.comment_nodes.contains(n.kind()))
+        .is_some_and(|n| n.end_position().row + 1 >= function.start_position().row);
+
+    let docstring = || {
+        function
+            .child_by_field_name("body")
+            .and_then(|body| body.named_child(0))
+            .filter(|n| n.kind() == "expression_statement")
+            .and_then(|n| n.named_child(0))
+            .is_some_and(|n| grammar.string_literal_nodes.contains(n.kind()))
+    };
+
+    leading_comment || (grammar.docstrings && docstring())
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::Path;
@@ -1396,6 +2695,7 @@ mod tests {
     use crate::utils::dataframes;
     use crate::utils::dataframes::*;
     use crate::utils::fs::*;
+    use crate::utils::json::open_json_from_path;
     use crate::utils::logger::test_logger;
 
     use super::*;
@@ -1429,6 +2729,7 @@ mod tests {
         if should_pass {
             run(
                 input_file_path,
+                false,
                 None,
                 None,
                 keywords,
@@ -1438,7 +2739,25 @@ mod tests {
                 8,
                 0,
                 false,
+                false,
                 ignore_comments,
+                Quoting::default(),
+                None,
+                0,
+                None,
+                "auto",
+                None,
+                false,
+                false,
+                false,
+                false,
+                ComplexityFilter::default(),
+                None,
+                TreeLimits::default(),
+                "id",
+                "name",
+                "language",
+                "project",
                 test_logger(),
             )?;
 
@@ -1504,6 +2823,7 @@ mod tests {
         } else {
             ensure!(run(
                 input_file_path,
+                false,
                 None,
                 None,
                 keywords,
@@ -1513,7 +2833,25 @@ mod tests {
                 8,
                 0,
                 false,
+                false,
                 ignore_comments,
+                Quoting::default(),
+                None,
+                0,
+                None,
+                "auto",
+                None,
+                false,
+                false,
+                false,
+                false,
+                ComplexityFilter::default(),
+                None,
+                TreeLimits::default(),
+                "id",
+                "name",
+                "language",
+                "project",
                 test_logger()
             )
             .is_err());
@@ -1521,6 +2859,7 @@ mod tests {
 
         delete_file(&output_file_path, true)?;
         delete_file(&logs_file_path, true)?;
+        delete_dir(format!("{output_file_path}.keywords"), true)?;
 
         for path in input_df {
             delete_dir(format!("{path}.functions"), true)?;
@@ -1555,6 +2894,24 @@ mod tests {
         test_parse(&input_file_path, &keywords, None, false, true)
     }
 
+    #[test]
+    fn parse_kotlin() -> Result<()> {
+        let keywords = vec!["tests/data/keywords/kotlin_float.json"];
+
+        let input_file_path = format!("{TEST_DATA}/parse_kotlin.csv");
+
+        test_parse(&input_file_path, &keywords, None, false, true)
+    }
+
+    #[test]
+    fn parse_python_docstrings() -> Result<()> {
+        let keywords = vec!["tests/data/keywords/python_float.json"];
+
+        let input_file_path = format!("{TEST_DATA}/parse_python.csv");
+
+        test_parse(&input_file_path, &keywords, None, false, true)
+    }
+
     #[test]
     fn invalid_file() -> Result<()> {
         let keywords = vec!["tests/data/keywords/c_float.json"];
@@ -1594,6 +2951,848 @@ mod tests {
         )
     }
 
+    #[test]
+    fn parser_cache() -> Result<()> {
+        let mut parsers = ParserCache::default();
+        for language in ["c", "java", "c", "java", "c"] {
+            let (_, parser) = parsers.get(language)?;
+            assert!(parser.language().is_some());
+        }
+        // A single parser is created per language.
+        assert_eq!(parsers.parsers.len(), 2);
+
+        assert!(parsers.get("cobol").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn qualified_name_test() -> Result<()> {
+        let mut parsers: ParserCache = ParserCache::default();
+        let mut qualified_names =
+            |language: &str, code: &str| -> Result<Vec<(String, &'static str)>> {
+                let (grammar, parser) = parsers.get(language)?;
+                let tree: Tree = parser.parse(code, None).unwrap();
+                Ok(find_kind(&tree.root_node(), &grammar.function_nodes)
+                    .into_iter()
+                    .rev()
+                    .map(|node| {
+                        let name: String = function_name(&node, grammar, code.as_bytes());
+                        (
+                            qualified_name(&node, &name, grammar, code.as_bytes()),
+                            enclosing_scope(&node, grammar),
+                        )
+                    })
+                    .collect())
+            };
+        let expected = |names: &[(&str, &'static str)]| -> Vec<(String, &'static str)> {
+            names
+                .iter()
+                .map(|(name, scope)| (name.to_string(), *scope))
+                .collect()
+        };
+
+        assert_eq!(
+            qualified_names(
+                "c++",
+                "namespace a { namespace b { class C { void run() {} }; } void run() {} }\nvoid run() {}"
+            )?,
+            expected(&[
+                ("a::b::C::run", "class"),
+                ("a::run", "namespace"),
+                ("run", "top-level")
+            ])
+        );
+        assert_eq!(
+            qualified_names(
+                "java",
+                "class Outer<T> { interface Inner { default void run() {} } void run() {} }"
+            )?,
+            expected(&[("Outer.Inner.run", "interface"), ("Outer.run", "class")])
+        );
+        assert_eq!(
+            qualified_names("rust", "mod m { impl<T> S<T> { fn run() {} } fn run() {} }")?,
+            expected(&[("m::S::run", "impl"), ("m::run", "module")])
+        );
+        // Anonymous functions have no qualified name but are enclosed in a declaration.
+        assert_eq!(
+            qualified_names("python", "class A:\n    f = lambda x: x\n")?,
+            expected(&[("", "class")])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn resolve_header_languages_test() {
+        let ids: [u32; 8] = [1, 1, 2, 2, 3, 3, 4, 4];
+        let paths: [&str; 8] = [
+            "p1/a.c",
+            "p1/a.h",
+            "p2/a.cpp",
+            "p2/a.h",
+            "p3/a.h",
+            "p3/b.HPP",
+            "p4/A.java",
+            "p4/b.h",
+        ];
+        let languages: [&str; 8] = ["c", "c", "c++", "c", "c", "c", "java", "c"];
+
+        // Headers follow the sources of their project, header-only projects are resolved when parsed.
+        assert_eq!(
+            resolve_header_languages(&ids, &paths, &languages, "auto"),
+            [
+                "c",
+                "c",
+                "c++",
+                "c++",
+                UNRESOLVED_HEADER,
+                "c++",
+                "java",
+                UNRESOLVED_HEADER
+            ]
+        );
+        assert_eq!(
+            resolve_header_languages(&ids, &paths, &languages, "c"),
+            ["c", "c", "c++", "c", "c", "c++", "java", "c"]
+        );
+
+        assert_eq!(
+            header_language(b"namespace linalg {\ntemplate <typename T> T dot(const T* a);\n}"),
+            "c++"
+        );
+        assert_eq!(
+            header_language(b"#include <math.h>\ndouble dot(const double* a, int n);"),
+            "c"
+        );
+    }
+
+    #[test]
+    fn test_code() -> Result<()> {
+        let input_path: &str = "target/tests/parse_test_code/input.csv";
+        let output_path: &str = "target/tests/parse_test_code/functions.csv";
+        let logs_path: &str = "target/tests/parse_test_code/function_logs.csv";
+        let stats: String = format!("{TEST_DATA}/test_code/stats.py");
+        let check_stats: String = format!("{TEST_DATA}/test_code/check_stats.py");
+
+        // Returns the is_test column of the functions and of the files, ordered by path.
+        let parse = |input: String,
+                     exclude_tests: bool,
+                     exclude_generated: bool|
+         -> Result<(Vec<i64>, Vec<i64>)> {
+            write_file(input_path, input)?;
+            run(
+                input_path,
+                false,
+                Some(output_path),
+                Some(logs_path),
+                &["tests/data/keywords/python_float.json"],
+                false,
+                None,
+                "ignore",
+                1,
+                0,
+                false,
+                true,
+                false,
+                Quoting::default(),
+                None,
+                0,
+                None,
+                "auto",
+                None,
+                exclude_tests,
+                exclude_generated,
+                false,
+                false,
+                ComplexityFilter::default(),
+                None,
+                TreeLimits::default(),
+                "id",
+                "name",
+                "language",
+                "project",
+                test_logger(),
+            )?;
+            let labels = |path: &str, key: &str| -> Result<Vec<i64>> {
+                let df: DataFrame =
+                    open_csv(path, None, None)?.sort(vec![key], SortMultipleOptions::new())?;
+                Ok(df.column("is_test")?.i64()?.into_no_null_iter().collect())
+            };
+            Ok((labels(output_path, "path")?, labels(logs_path, "name")?))
+        };
+
+        // The test file is detected by its import of pytest.
+        let unlabelled: String =
+            format!("id,name,language\n0,{check_stats},python\n0,{stats},python\n");
+        assert_eq!(
+            parse(unlabelled.clone(), false, false)?,
+            (vec![1, 0], vec![1, 0])
+        );
+        // Excluded test files are logged without functions.
+        assert_eq!(parse(unlabelled, true, false)?, (vec![0], vec![1, 0]));
+        let logs: DataFrame =
+            open_csv(logs_path, None, None)?.sort(vec!["name"], SortMultipleOptions::new())?;
+        assert_eq!(logs.column("functions")?.i64()?.get(0), Some(0));
+
+        // The labels of the input file take precedence over the detection.
+        let labelled: String = format!(
+            "id,name,language,is_test\n0,{check_stats},python,false\n0,{stats},python,true\n"
+        );
+        assert_eq!(parse(labelled, true, false)?, (vec![0], vec![0, 1]));
+
+        // The commits of the file log of the download command and the names of the projects are copied to the outputs,
+        // wherever their columns are.
+        let with_commits: String = format!(
+            "id,name,commit,language,project,is_test\n0,{check_stats},abc,python,\"a/b,c\",false\n0,{stats},,python,d/e,true\n"
+        );
+        assert_eq!(parse(with_commits, false, false)?, (vec![0, 1], vec![0, 1]));
+        let provenance = |path: &str, key: &str, column: &str| -> Result<Vec<String>> {
+            let df: DataFrame = open_csv(
+                path,
+                Some(Schema::from_iter(vec![Field::new(
+                    column.into(),
+                    DataType::String,
+                )])),
+                None,
+            )?
+            .sort(vec![key], SortMultipleOptions::new())?;
+            Ok(dataframes::str(&df, column)?
+                .into_iter()
+                .map(|c| c.to_string())
+                .collect())
+        };
+        assert_eq!(provenance(output_path, "path", "commit")?, vec!["abc", ""]);
+        assert_eq!(provenance(logs_path, "name", "commit")?, vec!["abc", ""]);
+        assert_eq!(
+            provenance(output_path, "path", "project")?,
+            vec!["a/b,c", "d/e"]
+        );
+        assert_eq!(
+            provenance(logs_path, "name", "project")?,
+            vec!["a/b,c", "d/e"]
+        );
+
+        // The files labelled as generated are logged without functions, and the unlabelled ones are detected.
+        let generated: String = format!(
+            "id,name,language,likely_generated\n0,{check_stats},python,\n0,{stats},python,true\n"
+        );
+        assert_eq!(parse(generated, false, true)?, (vec![1], vec![1, 0]));
+
+        delete_dir(format!("{stats}.functions"), true)?;
+        delete_dir(format!("{check_stats}.functions"), true)?;
+        delete_dir("target/tests/parse_test_code", false)
+    }
+
+    #[test]
+    fn keyword_matches() -> Result<()> {
+        let input_file_path = format!("{TEST_DATA}/matches.csv");
+        let output_file_path = format!("{input_file_path}.functions.csv");
+        let logs_file_path = format!("{input_file_path}.function_logs.csv");
+        let matches_file_path = format!("{input_file_path}.matches.csv");
+        let keyword_counts_file_path = format!("{input_file_path}.keyword_counts.csv");
+
+        run(
+            &input_file_path,
+            false,
+            None,
+            None,
+            &["tests/data/keywords/c_float.json"],
+            false,
+            None,
+            "ignore",
+            1,
+            0,
+            false,
+            true,
+            false,
+            Quoting::default(),
+            Some(&matches_file_path),
+            1,
+            Some(&keyword_counts_file_path),
+            "auto",
+            None,
+            false,
+            false,
+            false,
+            false,
+            ComplexityFilter::default(),
+            None,
+            TreeLimits::default(),
+            "id",
+            "name",
+            "language",
+            "project",
+            test_logger(),
+        )?;
+
+        // Matches in comments and strings are ignored, and lines are counted in the whole file.
+        assert_eq!(
+            std::fs::read_to_string(&matches_file_path)?,
+            std::fs::read_to_string(format!("{matches_file_path}.expected"))?
+        );
+        assert_eq!(
+            std::fs::read_to_string(&keyword_counts_file_path)?,
+            "id,path,keyword_file,keyword,count\n\
+             1,tests/data/phases/parse/matches.c.functions/5-1,tests/data/keywords/c_float.json,double,1\n\
+             1,tests/data/phases/parse/matches.c.functions/5-1,tests/data/keywords/c_float.json,float,3\n"
+        );
+
+        delete_file(&output_file_path, false)?;
+        delete_file(&logs_file_path, false)?;
+        delete_file(&matches_file_path, false)?;
+        delete_file(&keyword_counts_file_path, false)?;
+        delete_dir(format!("{output_file_path}.keywords"), false)?;
+        delete_dir(format!("{TEST_DATA}/matches.c.functions"), false)
+    }
+
+    #[test]
+    fn input_columns() -> Result<()> {
+        let dir: &str = "target/tests/parse_input_columns";
+        let source_path: String = format!("{dir}/matches.c");
+        let input_path: String = format!("{dir}/files.csv");
+        let output_path: String = format!("{dir}/functions.csv");
+        let logs_path: String = format!("{dir}/function_logs.csv");
+        write_file(
+            &source_path,
+            std::fs::read(format!("{TEST_DATA}/matches.c"))?,
+        )?;
+        // Output of another tool, with other column names in another order and another label of the language.
+        write_file(
+            &input_path,
+            format!("lang,path,project\nC,{source_path},7\n"),
+        )?;
+
+        let parse = |ids: &str, names: &str, languages: &str| {
+            run(
+                &input_path,
+                false,
+                Some(&output_path),
+                Some(&logs_path),
+                &["tests/data/keywords/c_float.json"],
+                false,
+                None,
+                "ignore",
+                1,
+                0,
+                false,
+                true,
+                false,
+                Quoting::default(),
+                None,
+                0,
+                None,
+                "auto",
+                None,
+                false,
+                false,
+                false,
+                false,
+                ComplexityFilter::default(),
+                None,
+                TreeLimits::default(),
+                ids,
+                names,
+                languages,
+                "project",
+                test_logger(),
+            )
+        };
+        parse("project", "path", "lang")?;
+        let logs: DataFrame = open_csv(&logs_path, None, None)?;
+        assert_eq!(dataframes::str(&logs, "name")?, vec![source_path.as_str()]);
+        assert_eq!(dataframes::str(&logs, "language")?, vec!["c"]);
+        let output: DataFrame = open_csv(&output_path, None, None)?;
+        ensure!(output.height() > 0);
+        assert!(output
+            .column("id")?
+            .i64()?
+            .into_no_null_iter()
+            .all(|id| id == 7));
+
+        assert!(parse("id", "name", "language").is_err());
+        delete_dir(dir, false)
+    }
+
+    #[test]
+    fn compressed_functions() -> Result<()> {
+        let dir: &str = "target/tests/parse_compressed";
+        let source_path: String = format!("{dir}/matches.c");
+        let input_path: String = format!("{dir}/matches.csv");
+        let output_path: String = format!("{dir}/functions.csv");
+        let logs_path: String = format!("{dir}/function_logs.csv");
+        write_file(
+            &source_path,
+            std::fs::read(format!("{TEST_DATA}/matches.c"))?,
+        )?;
+
+        // Returns the paths of the functions and their contents.
+        let parse = |compress: bool| -> Result<Vec<(String, Vec<u8>)>> {
+            write_file(
+                &input_path,
+                format!("id,name,language\n0,{source_path},c\n"),
+            )?;
+            run(
+                &input_path,
+                false,
+                Some(&output_path),
+                Some(&logs_path),
+                &["tests/data/keywords/c_float.json"],
+                false,
+                None,
+                "ignore",
+                1,
+                0,
+                false,
+                true,
+                false,
+                Quoting::default(),
+                None,
+                0,
+                None,
+                "auto",
+                None,
+                false,
+                false,
+                compress,
+                false,
+                ComplexityFilter::default(),
+                None,
+                TreeLimits::default(),
+                "id",
+                "name",
+                "language",
+                "project",
+                test_logger(),
+            )?;
+            let output: DataFrame = open_csv(&output_path, None, None)?;
+            let mut functions: Vec<(String, Vec<u8>)> = dataframes::str(&output, "path")?
+                .into_iter()
+                .map(|path| Ok((path.to_string(), load_file(path, 1024 * 1024)?.unwrap())))
+                .collect::<Result<_>>()?;
+            functions.sort();
+            delete_dir(format!("{source_path}.functions"), false)?;
+            Ok(functions)
+        };
+
+        let plain: Vec<(String, Vec<u8>)> = parse(false)?;
+        let compressed: Vec<(String, Vec<u8>)> = parse(true)?;
+        assert!(!plain.is_empty());
+        assert_eq!(plain.len(), compressed.len());
+        for ((plain_path, plain_code), (compressed_path, compressed_code)) in
+            plain.iter().zip(&compressed)
+        {
+            assert_eq!(format!("{plain_path}.zst"), *compressed_path);
+            assert_eq!(plain_code, compressed_code);
+        }
+        delete_dir(dir, false)
+    }
+
+    #[test]
+    fn stats_only() -> Result<()> {
+        let dir: &str = "target/tests/parse_stats_only";
+        let source_path: String = format!("{dir}/matches.c");
+        let input_path: String = format!("{dir}/matches.csv");
+        let output_path: String = format!("{dir}/functions.csv");
+        let logs_path: String = format!("{dir}/function_logs.csv");
+        write_file(
+            &source_path,
+            std::fs::read(format!("{TEST_DATA}/matches.c"))?,
+        )?;
+        write_file(
+            &input_path,
+            format!("id,name,language\n0,{source_path},c\n"),
+        )?;
+
+        // Returns the sorted rows of the functions file and of the file log.
+        let parse = |stats_only: bool| -> Result<(Vec<String>, Vec<String>)> {
+            delete_dir(format!("{source_path}.functions"), true)?;
+            run(
+                &input_path,
+                false,
+                Some(&output_path),
+                Some(&logs_path),
+                &["tests/data/keywords/c_float.json"],
+                false,
+                None,
+                "ignore",
+                1,
+                0,
+                false,
+                true,
+                false,
+                Quoting::default(),
+                None,
+                0,
+                None,
+                "auto",
+                None,
+                false,
+                false,
+                false,
+                stats_only,
+                ComplexityFilter::default(),
+                None,
+                TreeLimits::default(),
+                "id",
+                "name",
+                "language",
+                "project",
+                test_logger(),
+            )?;
+            let rows = |path: &str| -> Result<Vec<String>> {
+                let mut rows: Vec<String> = file_lines(path)?.collect::<Result<_, _>>()?;
+                rows.sort();
+                Ok(rows)
+            };
+            Ok((rows(&output_path)?, rows(&logs_path)?))
+        };
+
+        let with_functions: (Vec<String>, Vec<String>) = parse(false)?;
+        assert!(Path::new(&format!("{source_path}.functions")).exists());
+        // The statistics are the same, but no function is written.
+        assert_eq!(parse(true)?, with_functions);
+        assert!(with_functions.0.len() > 1);
+        assert!(!Path::new(&format!("{source_path}.functions")).exists());
+        delete_dir(dir, false)
+    }
+
+    #[test]
+    fn input_dir() -> Result<()> {
+        let dir: &str = "target/tests/parse_dir/corpus";
+        let code: &[u8] = &std::fs::read(format!("{TEST_DATA}/matches.c"))?;
+        let ids = |df: &DataFrame| -> Result<Vec<u32>> {
+            Ok(df
+                .column("id")?
+                .cast(&DataType::UInt32)?
+                .u32()?
+                .into_no_null_iter()
+                .collect())
+        };
+        // Returns the listing and the ids of the functions extracted from a tree of projects.
+        let parse = |projects: [&str; 2]| -> Result<(DataFrame, Vec<u32>)> {
+            delete_dir("target/tests/parse_dir", true)?;
+            for project in projects {
+                write_file(format!("{dir}/{project}/src/matches.c"), code)?;
+                write_file(format!("{dir}/{project}/README.txt"), "float")?;
+            }
+            // Files outside of the projects are ignored.
+            write_file(format!("{dir}/root.c"), code)?;
+            run(
+                &format!("{dir}/"),
+                true,
+                None,
+                None,
+                &["tests/data/keywords/c_float.json"],
+                false,
+                None,
+                "ignore",
+                2,
+                0,
+                false,
+                false,
+                false,
+                Quoting::default(),
+                None,
+                0,
+                None,
+                "auto",
+                None,
+                false,
+                false,
+                false,
+                false,
+                ComplexityFilter::default(),
+                None,
+                TreeLimits::default(),
+                "project_id",
+                "path",
+                "lang",
+                "project",
+                test_logger(),
+            )?;
+            let listing: DataFrame = open_csv(&format!("{dir}.files.csv"), None, None)?;
+            let output: DataFrame = open_csv(&format!("{dir}.functions.csv"), None, None)?;
+            let mut function_ids: Vec<u32> = ids(&output)?;
+            function_ids.sort_unstable();
+            Ok((listing, function_ids))
+        };
+
+        // The projects are numbered in alphabetical order.
+        let (listing, function_ids) = parse(["beta", "alpha"])?;
+        assert_eq!(ids(&listing)?, [0, 1]);
+        assert_eq!(dataframes::str(&listing, "project")?, ["alpha", "beta"]);
+        assert_eq!(
+            dataframes::str(&listing, "name")?,
+            [
+                format!("{dir}/alpha/src/matches.c"),
+                format!("{dir}/beta/src/matches.c")
+            ]
+        );
+        assert_eq!(dataframes::str(&listing, "language")?, ["c", "c"]);
+        assert_eq!(function_ids, [0, 1]);
+        assert!(Path::new(&format!("{dir}.function_logs.csv")).exists());
+
+        // Projects named after their ids keep them.
+        let (listing, function_ids) = parse(["7", "12"])?;
+        assert_eq!(ids(&listing)?, [12, 7]);
+        assert_eq!(function_ids, [7, 12]);
+
+        delete_dir("target/tests/parse_dir", false)
+    }
+
+    #[test]
+    fn emit_ast() -> Result<()> {
+        let dir: &str = "target/tests/parse_ast";
+        let source_path: String = format!("{dir}/matches.c");
+        let input_path: String = format!("{dir}/matches.csv");
+        write_file(
+            &source_path,
+            std::fs::read(format!("{TEST_DATA}/matches.c"))?,
+        )?;
+        write_file(
+            &input_path,
+            format!("id,name,language\n0,{source_path},c\n"),
+        )?;
+        let parse = |format: &str| -> Result<()> {
+            run(
+                &input_path,
+                false,
+                Some(&format!("{dir}/functions.csv")),
+                Some(&format!("{dir}/function_logs.csv")),
+                &["tests/data/keywords/c_float.json"],
+                false,
+                None,
+                "ignore",
+                1,
+                0,
+                false,
+                true,
+                false,
+                Quoting::default(),
+                None,
+                0,
+                None,
+                "auto",
+                None,
+                false,
+                false,
+                false,
+                false,
+                ComplexityFilter::default(),
+                Some(format),
+                TreeLimits::default(),
+                "id",
+                "name",
+                "language",
+                "project",
+                test_logger(),
+            )
+        };
+        let function_path: String = format!("{source_path}.functions/5-1");
+
+        parse("sexp")?;
+        let sexp: String = std::fs::read_to_string(format!("{function_path}.ast"))?;
+        assert!(sexp.starts_with("(function_definition type: (primitive_type) declarator:"));
+        // Only the functions matching the keywords are extracted.
+        assert!(!Path::new(&format!("{source_path}.functions/13-1.ast")).exists());
+
+        parse("json")?;
+        let ast: JsonValue = open_json_from_path(&format!("{function_path}.ast.json"))?;
+        assert_eq!(ast["kind"], "function_definition");
+        assert_eq!(ast["start"], 0);
+        // The byte ranges are relative to the code of the function.
+        assert_eq!(ast["end"], std::fs::read(&function_path)?.len());
+        assert_eq!(ast["children"][0]["kind"], "primitive_type");
+        assert_eq!(ast["children"][0]["field"], "type");
+        assert_eq!(ast["children"][0]["start"], 0);
+        assert_eq!(ast["children"][0]["end"], 5);
+
+        delete_dir(dir, false)
+    }
+
+    #[test]
+    fn tree_limits() -> Result<()> {
+        let mut parsers: ParserCache = ParserCache::default();
+        let (_, parser) = parsers.get("c")?;
+        let limits = |max_depth: Option<usize>, max_nodes: Option<usize>| TreeLimits {
+            max_depth,
+            max_nodes,
+        };
+
+        // translation_unit > declaration > (primitive_type, identifier, ;)
+        let tree: Tree = parser.parse("int x;", None).unwrap();
+        let root: Node = tree.root_node();
+        assert!(!TreeLimits::default().exceeded_by(&root));
+        assert!(!limits(Some(2), Some(5)).exceeded_by(&root));
+        assert!(limits(Some(1), None).exceeded_by(&root));
+        assert!(limits(None, Some(4)).exceeded_by(&root));
+
+        // Every term of the sum nests the expression one level deeper.
+        let code: String = format!("int x = {};\n", vec!["1"; 500].join(" + "));
+        let tree: Tree = parser.parse(&code, None).unwrap();
+        assert!(limits(Some(100), None).exceeded_by(&tree.root_node()));
+        assert!(!limits(Some(1000), Some(10_000)).exceeded_by(&tree.root_node()));
+
+        // The files exceeding the limits are logged without functions.
+        let dir: &str = "target/tests/parse_limits";
+        let source_path: String = format!("{dir}/matches.c");
+        let input_path: String = format!("{dir}/matches.csv");
+        let output_path: String = format!("{dir}/functions.csv");
+        let logs_path: String = format!("{dir}/function_logs.csv");
+        write_file(
+            &source_path,
+            std::fs::read(format!("{TEST_DATA}/matches.c"))?,
+        )?;
+        write_file(
+            &input_path,
+            format!("id,name,language\n0,{source_path},c\n"),
+        )?;
+        run(
+            &input_path,
+            false,
+            Some(&output_path),
+            Some(&logs_path),
+            &["tests/data/keywords/c_float.json"],
+            false,
+            None,
+            "ignore",
+            1,
+            0,
+            false,
+            true,
+            false,
+            Quoting::default(),
+            None,
+            0,
+            None,
+            "auto",
+            None,
+            false,
+            false,
+            false,
+            false,
+            ComplexityFilter::default(),
+            None,
+            limits(None, Some(10)),
+            "id",
+            "name",
+            "language",
+            "project",
+            test_logger(),
+        )?;
+        assert_eq!(file_lines(&output_path)?.count(), 1);
+        let logs: Vec<String> = file_lines(&logs_path)?.collect::<Result<_, _>>()?;
+        assert_eq!(logs[1], format!("0,{source_path},c,-1,-1,-1,too_complex,0"));
+
+        delete_dir(dir, false)
+    }
+
+    #[test]
+    fn complexity_filter() -> Result<()> {
+        let dir: &str = "target/tests/parse_complexity";
+        let source_path: String = format!("{dir}/complexity.c");
+        let input_path: String = format!("{dir}/complexity.csv");
+        let output_path: String = format!("{dir}/functions.csv");
+        let logs_path: String = format!("{dir}/function_logs.csv");
+        write_file(
+            &source_path,
+            "float half(float x) {\n    return x / 2;\n}\n\n\
+             float sum(float *xs, int n) {\n    float s = 0;\n    for (int i = 0; i < n; i++) {\n        \
+             if (xs[i] > 0) {\n            s += xs[i];\n        }\n    }\n    return s;\n}\n",
+        )?;
+        write_file(
+            &input_path,
+            format!("id,name,language\n0,{source_path},c\n"),
+        )?;
+
+        // Returns the paths of the functions and the number of functions with keywords in the file log.
+        let parse = |complexity: ComplexityFilter| -> Result<(Vec<String>, u32)> {
+            delete_dir(format!("{source_path}.functions"), true)?;
+            run(
+                &input_path,
+                false,
+                Some(&output_path),
+                Some(&logs_path),
+                &["tests/data/keywords/c_float.json"],
+                false,
+                None,
+                "ignore",
+                1,
+                0,
+                false,
+                true,
+                false,
+                Quoting::default(),
+                None,
+                0,
+                None,
+                "auto",
+                None,
+                false,
+                false,
+                false,
+                false,
+                complexity,
+                None,
+                TreeLimits::default(),
+                "id",
+                "name",
+                "language",
+                "project",
+                test_logger(),
+            )?;
+            let output: DataFrame = open_csv(&output_path, None, None)?;
+            let mut paths: Vec<String> = dataframes::str(&output, "path")?
+                .into_iter()
+                .map(str::to_string)
+                .collect();
+            paths.sort();
+            let logs: DataFrame = open_csv(&logs_path, None, None)?;
+            let with_kw: u32 = logs
+                .column("functions_with_kw")?
+                .cast(&DataType::UInt32)?
+                .u32()?
+                .get(0)
+                .unwrap_or(0);
+            Ok((paths, with_kw))
+        };
+        let half: String = format!("{source_path}.functions/1-1");
+        let sum: String = format!("{source_path}.functions/5-1");
+
+        assert_eq!(
+            parse(ComplexityFilter::default())?,
+            (vec![half.clone(), sum.clone()], 2)
+        );
+
+        let loops = ComplexityFilter {
+            min_loops: 1,
+            ..ComplexityFilter::default()
+        };
+        assert_eq!(parse(loops)?, (vec![sum.clone()], 2));
+        assert!(!Path::new(&half).exists());
+        assert!(Path::new(&sum).exists());
+
+        // The statistics of the functions that are not written out can be kept.
+        let (paths, _) = parse(ComplexityFilter {
+            keep_stats: true,
+            ..loops
+        })?;
+        assert_eq!(paths, ["filtered".to_string(), sum.clone()]);
+
+        let short = ComplexityFilter {
+            max_loc: Some(3),
+            ..ComplexityFilter::default()
+        };
+        assert_eq!(parse(short)?, (vec![half.clone()], 2));
+        let long = ComplexityFilter {
+            min_loc: 4,
+            min_conditionals: 1,
+            ..ComplexityFilter::default()
+        };
+        assert_eq!(parse(long)?, (vec![sum], 2));
+
+        delete_dir(dir, false)
+    }
+
     #[test]
     fn ignore_comments_go() -> Result<()> {
         let keywords = vec![
@@ -1606,4 +3805,53 @@ mod tests {
 
         test_parse(&input_file_path, &keywords, None, true, true)
     }
+
+    #[test]
+    fn processing_order_test() -> Result<()> {
+        let project_ids: Vec<u32> = vec![1, 2, 1, 3, 2, 1, 4, 3];
+
+        // Without grouping, the files are shuffled individually.
+        let files: Vec<usize> = processing_order(&project_ids, 7, false, None);
+        let mut sorted: Vec<usize> = files.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..project_ids.len()).collect::<Vec<usize>>());
+
+        // With grouping, the files of a project follow each other in the order of the input file.
+        let grouped: Vec<usize> = processing_order(&project_ids, 7, true, None);
+        assert_eq!(grouped, processing_order(&project_ids, 7, true, None));
+        let mut sorted: Vec<usize> = grouped.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..project_ids.len()).collect::<Vec<usize>>());
+        let mut order: Vec<u32> = grouped.iter().map(|idx| project_ids[*idx]).collect();
+        order.dedup();
+        assert_eq!(order.len(), 4);
+        for project in [1, 2, 3, 4] {
+            let positions: Vec<usize> = grouped
+                .iter()
+                .copied()
+                .filter(|idx| project_ids[*idx] == project)
+                .collect();
+            assert!(positions.windows(2).all(|w| w[0] < w[1]));
+        }
+
+        // The shards partition the projects.
+        let shards: Vec<Vec<usize>> = (1..=2)
+            .map(|index| {
+                Ok(processing_order(
+                    &project_ids,
+                    7,
+                    true,
+                    Some(Shard::new(index, 2)?),
+                ))
+            })
+            .collect::<Result<_>>()?;
+        let shard_projects = |shard: &Vec<usize>| -> HashSet<u32> {
+            shard.iter().map(|idx| project_ids[*idx]).collect()
+        };
+        assert!(shard_projects(&shards[0]).is_disjoint(&shard_projects(&shards[1])));
+        let mut union: Vec<usize> = shards.concat();
+        union.sort_unstable();
+        assert_eq!(union, (0..project_ids.len()).collect::<Vec<usize>>());
+        Ok(())
+    }
 }
@@ -0,0 +1,426 @@
+// Copyright 2026 Andrea Gilot
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![doc = include_str!("../docs/sample_functions.md")]
+
+use std::collections::{BTreeMap, HashSet};
+use std::io::Write;
+
+use anyhow::{ensure, Context, Result};
+use clap::{Arg, ArgAction, Command};
+use polars::prelude::{DataType, Field, Schema};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use tracing::{info, warn};
+
+use crate::utils::csv::{validate_columns, CSVFile, Quoting};
+use crate::utils::fs::*;
+use crate::utils::logger::{log_output_file, log_seed, Logger};
+
+/// Command line arguments parsing.
+pub fn cli() -> Command {
+    Command::new("sample_functions")
+        .about("Randomly samples a fixed number of functions per language from the output of the parse command.")
+        .long_about(include_str!("../docs/sample_functions.md"))
+        .disable_version_flag(true)
+        .arg(
+            Arg::new("input")
+                .short('i')
+                .long("input")
+                .value_name("INPUT_FILE.csv")
+                .help("Path to the functions csv file produced by the parse command.")
+                .required(true),
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .value_name("OUTPUT_FILE.csv")
+                .help("Path to the output csv file. By default, the suffix '.sample.csv' is appended to the input path.")
+                .required(false),
+        )
+        .arg(
+            Arg::new("per-language")
+                .short('n')
+                .long("per-language")
+                .value_name("N")
+                .help("Number of functions to sample for every language. \
+                       Languages with fewer eligible functions are sampled entirely.")
+                .required(true)
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("lang")
+                .long("lang")
+                .value_name("LANGUAGE")
+                .help("Only sample functions of the given languages, as written in the language column. By default, all languages are sampled.")
+                .num_args(1..)
+                .required(false),
+        )
+        .arg(
+            Arg::new("min-loc")
+                .long("min-loc")
+                .value_name("LINES")
+                .help("Minimum number of lines of the sampled functions.")
+                .required(false)
+                .value_parser(clap::value_parser!(u32)),
+        )
+        .arg(
+            Arg::new("max-loc")
+                .long("max-loc")
+                .value_name("LINES")
+                .help("Maximum number of lines of the sampled functions.")
+                .required(false)
+                .value_parser(clap::value_parser!(u32)),
+        )
+        .arg(
+            Arg::new("min-loops")
+                .long("min-loops")
+                .value_name("LOOPS")
+                .help("Minimum number of loop statements of the sampled functions.")
+                .default_value("0")
+                .value_parser(clap::value_parser!(u32)),
+        )
+        .arg(
+            Arg::new("exclude-tests")
+                .long("exclude-tests")
+                .help("Do not sample functions of test files.")
+                .default_value("false")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("seed")
+                .short('s')
+                .long("seed")
+                .value_name("SEED")
+                .help("Seed used to sample the functions.")
+                .default_value("6012451287416372310")
+                .value_parser(clap::value_parser!(u64)),
+        )
+        .arg(
+            Arg::new("force")
+                .short('f')
+                .long("force")
+                .help("Override the output file if it already exists.")
+                .default_value("false")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+/// Constraints on the functions that can be sampled.
+#[derive(Default)]
+pub struct FunctionFilters {
+    /// Minimum number of lines, if any.
+    pub min_loc: Option<u32>,
+    /// Maximum number of lines, if any.
+    pub max_loc: Option<u32>,
+    /// Minimum number of loop statements.
+    pub min_loops: u32,
+    /// Whether to discard the functions of test files.
+    pub exclude_tests: bool,
+}
+
+impl FunctionFilters {
+    /// Checks whether a function satisfies the constraints.
+    ///
+    /// # Arguments
+    ///
+    /// * `loc` - The number of lines of the function.
+    /// * `loops` - The number of loop statements of the function.
+    /// * `is_test` - Whether the function belongs to a test file.
+    fn keeps(&self, loc: u32, loops: u32, is_test: bool) -> bool {
+        self.min_loc.is_none_or(|min| loc >= min)
+            && self.max_loc.is_none_or(|max| loc <= max)
+            && loops >= self.min_loops
+            && !(self.exclude_tests && is_test)
+    }
+}
+
+/// Randomly samples functions of the output of the parse command, with the same number of functions for every language.
+/// The functions satisfying the constraints are shuffled separately for every language and the first ones are kept.
+/// Rows are copied unchanged, in the order of the input file.
+///
+/// # Arguments
+///
+/// * `input_path` - The path to the functions CSV file.
+/// * `output_path` - The optional path to the output CSV file. Defaults to the input path with the suffix '.sample.csv'.
+/// * `per_language` - The number of functions to sample for every language.
+/// * `languages` - The languages to sample, or None for all the languages of the input file.
+/// * `filters` - The constraints on the sampled functions.
+/// * `seed` - The seed used to sample the functions.
+/// * `force` - Whether to override the output file if it already exists.
+/// * `logger` - The logger displaying the progress.
+///
+/// # Returns
+///
+/// A result indicating success or failure of the operation.
+pub fn run(
+    input_path: &str,
+    output_path: Option<&str>,
+    per_language: usize,
+    languages: Option<&[&str]>,
+    filters: &FunctionFilters,
+    seed: u64,
+    force: bool,
+    logger: &Logger,
+) -> Result<()> {
+    let default_output_path: String = format!("{input_path}.sample.csv");
+    let output_path: &str = output_path.unwrap_or(&default_output_path);
+    logger.record_inputs(&[input_path]);
+    logger.record_outputs(&[output_path]);
+
+    check_path(input_path)?;
+    log_output_file(output_path, false, force)?;
+
+    let mut required: Vec<&str> = vec!["language", "loc", "loop_statements"];
+    if filters.exclude_tests {
+        required.push("is_test");
+    }
+    validate_columns(
+        input_path,
+        &required,
+        &Schema::from_iter(vec![
+            Field::new("loc".into(), DataType::UInt32),
+            Field::new("loop_statements".into(), DataType::UInt32),
+        ]),
+    )?;
+    let (header, rows): (Vec<String>, Vec<Vec<String>>) =
+        logger.run_task("Loading input file", || read_rows(input_path))?;
+    info!("{} functions found.", rows.len());
+    logger.record_count("functions read", rows.len() as u64);
+
+    let column = |name: &str| -> Result<usize> {
+        header
+            .iter()
+            .position(|h| h == name)
+            .with_context(|| format!("File {input_path} does not contain column '{name}'."))
+    };
+    let language_idx: usize = column("language")?;
+    let loc_idx: usize = column("loc")?;
+    let loops_idx: usize = column("loop_statements")?;
+    let test_idx: Option<usize> = if filters.exclude_tests {
+        Some(column("is_test")?)
+    } else {
+        None
+    };
+
+    // Eligible rows of every language, in the order of the input file.
+    let mut eligible: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    if let Some(languages) = languages {
+        for language in languages {
+            eligible.insert(language, Vec::new());
+        }
+    }
+    for (i, row) in rows.iter().enumerate() {
+        let language: &str = &row[language_idx];
+        if languages.is_some_and(|l| !l.contains(&language)) {
+            continue;
+        }
+        let number = |idx: usize| -> Result<u32> {
+            row[idx].parse().with_context(|| {
+                format!(
+                    "Could not parse column '{}' of row {} of {input_path}",
+                    header[idx],
+                    i + 1
+                )
+            })
+        };
+        let is_test: bool = test_idx.is_some_and(|idx| row[idx] == "1" || row[idx] == "true");
+        if filters.keeps(number(loc_idx)?, number(loops_idx)?, is_test) {
+            eligible.entry(language).or_default().push(i);
+        }
+    }
+
+    log_seed(seed);
+    let selected: HashSet<usize> = sample(&eligible, per_language, seed);
+
+    let mut output_file: CSVFile = CSVFile::new(output_path, FileMode::Overwrite)?;
+    output_file.write_header(&header.iter().map(|h| h.as_str()).collect::<Vec<_>>())?;
+    for (_, row) in rows
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| selected.contains(i))
+    {
+        writeln!(
+            output_file,
+            "{}",
+            row.iter()
+                .map(|f| Quoting::Rfc4180.escape(f))
+                .collect::<Vec<_>>()
+                .join(",")
+        )?;
+    }
+
+    for (language, indices) in &eligible {
+        let count: usize = indices.len().min(per_language);
+        if count < per_language {
+            warn!("{language}: only {count} eligible functions, fewer than the quota of {per_language}");
+        } else {
+            info!(
+                "{language}: {count} functions sampled out of {}",
+                indices.len()
+            );
+        }
+    }
+    logger.record_count("functions sampled", selected.len() as u64);
+    Ok(())
+}
+
+/// Reads the rows of a CSV file.
+///
+/// # Arguments
+///
+/// * `input_path` - The path to the CSV file.
+///
+/// # Returns
+///
+/// The header and the rows of the file.
+fn read_rows(input_path: &str) -> Result<(Vec<String>, Vec<Vec<String>>)> {
+    let mut reader = csv::Reader::from_reader(open_file(input_path, FileMode::Read)?);
+    let header: Vec<String> = reader.headers()?.iter().map(|h| h.to_string()).collect();
+    let rows = reader
+        .records()
+        .enumerate()
+        .map(|(i, record)| {
+            let record =
+                record.with_context(|| format!("Could not parse row {i} of {input_path}"))?;
+            ensure!(
+                record.len() == header.len(),
+                "Row {i} of {input_path} does not have {} fields",
+                header.len()
+            );
+            Ok(record.iter().map(|f| f.to_string()).collect())
+        })
+        .collect::<Result<_>>()?;
+    Ok((header, rows))
+}
+
+/// Randomly samples a fixed number of rows of every language.
+///
+/// # Arguments
+///
+/// * `eligible` - The indices of the eligible rows of every language.
+/// * `per_language` - The number of rows to sample for every language.
+/// * `seed` - The seed used to shuffle the rows.
+///
+/// # Returns
+///
+/// The indices of the sampled rows. Languages with fewer eligible rows than `per_language` are sampled entirely.
+fn sample(eligible: &BTreeMap<&str, Vec<usize>>, per_language: usize, seed: u64) -> HashSet<usize> {
+    let mut rng: StdRng = SeedableRng::seed_from_u64(seed);
+    eligible
+        .values()
+        .flat_map(|indices| {
+            let mut indices: Vec<usize> = indices.clone();
+            indices.shuffle(&mut rng);
+            indices.truncate(per_language);
+            indices
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::logger::test_logger;
+
+    const TEST_DATA: &str = "tests/data/phases/sample_functions";
+
+    #[test]
+    fn sample_functions_test() -> Result<()> {
+        let input_path: String = format!("{TEST_DATA}/functions.csv");
+        let output_path: &str = "target/tests/sample_functions.csv";
+        let filters: FunctionFilters = FunctionFilters {
+            min_loc: Some(3),
+            max_loc: Some(10),
+            min_loops: 1,
+            exclude_tests: true,
+        };
+
+        run(
+            &input_path,
+            Some(output_path),
+            2,
+            None,
+            &filters,
+            42,
+            true,
+            test_logger(),
+        )?;
+        let (header, sample_rows) = read_rows(output_path)?;
+        let (input_header, input_rows) = read_rows(&input_path)?;
+        assert_eq!(header, input_header);
+
+        // Rows are copied unchanged and satisfy the constraints.
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        for row in &sample_rows {
+            assert!(input_rows.contains(row));
+            let loc: u32 = row[4].parse()?;
+            assert!((3..=10).contains(&loc));
+            assert!(row[5] != "0");
+            assert_eq!(row[6], "0");
+            *counts.entry(row[3].clone()).or_default() += 1;
+        }
+        assert_eq!(
+            counts,
+            BTreeMap::from([
+                ("c".to_string(), 2),
+                ("java".to_string(), 1),
+                ("python".to_string(), 2)
+            ])
+        );
+
+        // The sample only depends on the seed.
+        run(
+            &input_path,
+            Some(output_path),
+            2,
+            None,
+            &filters,
+            42,
+            true,
+            test_logger(),
+        )?;
+        assert_eq!(read_rows(output_path)?.1, sample_rows);
+
+        // Restricting the languages.
+        run(
+            &input_path,
+            Some(output_path),
+            10,
+            Some(&["c"]),
+            &FunctionFilters::default(),
+            42,
+            true,
+            test_logger(),
+        )?;
+        let (_, c_rows) = read_rows(output_path)?;
+        assert_eq!(c_rows.len(), 8);
+        assert!(c_rows.iter().all(|row| row[3] == "c"));
+
+        delete_file(output_path, false)
+    }
+
+    #[test]
+    fn sample_test() {
+        let eligible: BTreeMap<&str, Vec<usize>> =
+            BTreeMap::from([("c", (0..10).collect()), ("go", vec![10, 11])]);
+        let selected: HashSet<usize> = sample(&eligible, 3, 1);
+        assert_eq!(selected.len(), 5);
+        assert_eq!(selected.iter().filter(|i| **i < 10).count(), 3);
+        assert!(selected.contains(&10) && selected.contains(&11));
+        assert_eq!(selected, sample(&eligible, 3, 1));
+    }
+}
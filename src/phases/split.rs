@@ -0,0 +1,360 @@
+// Copyright 2026 Andrea Gilot
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![doc = include_str!("../docs/split.md")]
+
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+
+use anyhow::{ensure, Context, Result};
+use clap::{Arg, ArgAction, Command};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use tracing::info;
+
+use crate::utils::csv::{revert_placeholders, CSVFile, Quoting};
+use crate::utils::fs::*;
+use crate::utils::logger::{log_output_file, log_seed, Logger};
+
+/// Names of the splits, in the order in which the groups are assigned to them.
+const SPLITS: [&str; 3] = ["train", "validation", "test"];
+
+/// Command line arguments parsing.
+pub fn cli() -> Command {
+    Command::new("split")
+        .about("Splits the rows of a CSV file into train, validation and test files, keeping the rows of a project in the same file.")
+        .long_about(include_str!("../docs/split.md"))
+        .disable_version_flag(true)
+        .arg(
+            Arg::new("input")
+                .short('i')
+                .long("input")
+                .value_name("INPUT_FILE.csv")
+                .help("Path to the csv file to split, such as the output of the parse command.")
+                .required(true),
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .value_name("PREFIX")
+                .help("Prefix of the output files, which are named PREFIX.train.csv, PREFIX.validation.csv and PREFIX.test.csv. \
+                       By default, the path of the input file is used as prefix.")
+                .required(false),
+        )
+        .arg(
+            Arg::new("column")
+                .long("column")
+                .value_name("COLUMN_NAME")
+                .help("Name of the column identifying the project of each row. Rows with the same value are written to the same file.")
+                .default_value("id"),
+        )
+        .arg(
+            Arg::new("train")
+                .long("train")
+                .value_name("FRACTION")
+                .help("Fraction of the projects in the train file.")
+                .default_value("0.8")
+                .value_parser(clap::value_parser!(f64)),
+        )
+        .arg(
+            Arg::new("validation")
+                .long("validation")
+                .value_name("FRACTION")
+                .help("Fraction of the projects in the validation file.")
+                .default_value("0.1")
+                .value_parser(clap::value_parser!(f64)),
+        )
+        .arg(
+            Arg::new("test")
+                .long("test")
+                .value_name("FRACTION")
+                .help("Fraction of the projects in the test file.")
+                .default_value("0.1")
+                .value_parser(clap::value_parser!(f64)),
+        )
+        .arg(
+            Arg::new("seed")
+                .short('s')
+                .long("seed")
+                .value_name("SEED")
+                .help("Seed used to randomly assign the projects to the files.")
+                .default_value("6012451287416372310")
+                .value_parser(clap::value_parser!(u64)),
+        )
+        .arg(
+            Arg::new("force")
+                .short('f')
+                .long("force")
+                .help("Override the output files if they already exist.")
+                .default_value("false")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+/// Splits the rows of a CSV file into train, validation and test files.
+/// The projects are shuffled and assigned to the files according to the fractions,
+/// and every row is written to the file of its project, so that no project appears in two files.
+/// Rows are copied unchanged, whether they use RFC 4180 quoting or the legacy placeholders.
+///
+/// # Arguments
+///
+/// * `input_path` - The path to the input CSV file.
+/// * `output_prefix` - The optional prefix of the output files. Defaults to the input path.
+/// * `column` - The name of the column identifying the project of each row.
+/// * `fractions` - The fractions of the projects in the train, validation and test files.
+/// * `seed` - The seed used to shuffle the projects.
+/// * `force` - Whether to override the output files if they already exist.
+/// * `logger` - The logger displaying the progress.
+///
+/// # Returns
+///
+/// A result indicating success or failure of the operation.
+pub fn run(
+    input_path: &str,
+    output_prefix: Option<&str>,
+    column: &str,
+    fractions: [f64; 3],
+    seed: u64,
+    force: bool,
+    logger: &Logger,
+) -> Result<()> {
+    ensure!(
+        fractions.iter().all(|f| (0.0..=1.0).contains(f)),
+        "The fractions must be between 0 and 1"
+    );
+    ensure!(
+        (fractions.iter().sum::<f64>() - 1.0).abs() < 1e-9,
+        "The fractions must sum to 1"
+    );
+
+    let output_prefix: &str = output_prefix.unwrap_or(input_path);
+    let output_paths: Vec<String> = SPLITS
+        .iter()
+        .map(|split| format!("{output_prefix}.{split}.csv"))
+        .collect();
+    logger.record_inputs(&[input_path]);
+    logger.record_outputs(&output_paths.iter().map(|p| p.as_str()).collect::<Vec<_>>());
+
+    check_path(input_path)?;
+    for output_path in &output_paths {
+        log_output_file(output_path, false, force)?;
+    }
+
+    let (header, rows): (Vec<String>, Vec<(String, Vec<String>)>) =
+        logger.run_task("Loading input file", || read_rows(input_path, column))?;
+
+    let projects: HashSet<&str> = rows.iter().map(|(project, _)| project.as_str()).collect();
+
+    info!("{} rows and {} projects found.", rows.len(), projects.len());
+
+    log_seed(seed);
+    let assignment: HashMap<&str, usize> =
+        assign_splits(projects.into_iter().collect(), fractions, seed);
+
+    let mut files: Vec<CSVFile> = output_paths
+        .iter()
+        .map(|path| {
+            let mut file: CSVFile = CSVFile::new(path, FileMode::Overwrite)?;
+            file.write_header(&header.iter().map(|h| h.as_str()).collect::<Vec<_>>())?;
+            Ok(file)
+        })
+        .collect::<Result<_>>()?;
+
+    let mut row_counts: [usize; 3] = [0; 3];
+    logger.run_task("Writing splits", || {
+        for (project, fields) in &rows {
+            let split: usize = assignment[project.as_str()];
+            writeln!(
+                files[split],
+                "{}",
+                fields
+                    .iter()
+                    .map(|f| Quoting::Rfc4180.escape(f))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            )?;
+            row_counts[split] += 1;
+        }
+        Ok(())
+    })?;
+
+    for (i, split) in SPLITS.iter().enumerate() {
+        info!(
+            "{split}: {} projects, {} rows",
+            assignment.values().filter(|s| **s == i).count(),
+            row_counts[i]
+        );
+    }
+
+    Ok(())
+}
+
+/// Reads the rows of a CSV file with the project they belong to.
+///
+/// # Arguments
+///
+/// * `input_path` - The path to the CSV file.
+/// * `column` - The name of the column identifying the project of each row.
+///
+/// # Returns
+///
+/// The header of the file and every row with its project, whose placeholders are reverted.
+fn read_rows(input_path: &str, column: &str) -> Result<(Vec<String>, Vec<(String, Vec<String>)>)> {
+    let mut reader = csv::Reader::from_reader(open_file(input_path, FileMode::Read)?);
+    let header: Vec<String> = reader.headers()?.iter().map(|h| h.to_string()).collect();
+    let idx: usize = header
+        .iter()
+        .position(|h| h == column)
+        .with_context(|| format!("File {input_path} does not contain column '{column}'."))?;
+
+    let rows = reader
+        .records()
+        .enumerate()
+        .map(|(i, record)| {
+            let record =
+                record.with_context(|| format!("Could not parse row {i} of {input_path}"))?;
+            let fields: Vec<String> = record.iter().map(|f| f.to_string()).collect();
+            Ok((revert_placeholders(&fields[idx]), fields))
+        })
+        .collect::<Result<_>>()?;
+    Ok((header, rows))
+}
+
+/// Randomly assigns projects to the splits.
+///
+/// # Arguments
+///
+/// * `projects` - The distinct projects.
+/// * `fractions` - The fractions of the projects in each split.
+/// * `seed` - The seed used to shuffle the projects.
+///
+/// # Returns
+///
+/// The index of the split of every project.
+fn assign_splits(mut projects: Vec<&str>, fractions: [f64; 3], seed: u64) -> HashMap<&str, usize> {
+    // The assignment does not depend on the order of the rows.
+    projects.sort_unstable();
+    let mut rng: StdRng = SeedableRng::seed_from_u64(seed);
+    projects.shuffle(&mut rng);
+
+    let n: f64 = projects.len() as f64;
+    let train_end: usize = (fractions[0] * n).round() as usize;
+    let validation_end: usize = ((fractions[0] + fractions[1]) * n).round() as usize;
+
+    projects
+        .into_iter()
+        .enumerate()
+        .map(|(i, project)| {
+            let split: usize = if i < train_end {
+                0
+            } else if i < validation_end {
+                1
+            } else {
+                2
+            };
+            (project, split)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::utils::logger::test_logger;
+
+    const TEST_DATA: &str = "tests/data/phases/split";
+
+    #[test]
+    fn split_test() -> Result<()> {
+        let input_path: String = format!("{TEST_DATA}/functions.csv");
+        let output_paths: Vec<String> = SPLITS
+            .iter()
+            .map(|split| format!("{input_path}.{split}.csv"))
+            .collect();
+
+        run(
+            &input_path,
+            None,
+            "id",
+            [0.6, 0.2, 0.2],
+            42,
+            true,
+            test_logger(),
+        )?;
+
+        let (header, rows) = read_rows(&input_path, "id")?;
+        let mut all_rows: Vec<Vec<String>> = Vec::new();
+        let mut projects: Vec<HashSet<String>> = Vec::new();
+        for output_path in &output_paths {
+            let (split_header, split_rows) = read_rows(output_path, "id")?;
+            assert_eq!(header, split_header);
+            projects.push(split_rows.iter().map(|(p, _)| p.clone()).collect());
+            all_rows.extend(split_rows.into_iter().map(|(_, fields)| fields));
+            delete_file(output_path, false)?;
+        }
+
+        // Every row is written once, unchanged, and the projects are not shared between files.
+        let mut expected_rows: Vec<Vec<String>> = rows.into_iter().map(|(_, f)| f).collect();
+        expected_rows.sort();
+        all_rows.sort();
+        assert_eq!(expected_rows, all_rows);
+        assert_eq!(
+            projects.iter().map(|p| p.len()).collect::<Vec<_>>(),
+            vec![3, 1, 1]
+        );
+        assert!(projects[0].is_disjoint(&projects[1]));
+        assert!(projects[0].is_disjoint(&projects[2]));
+        assert!(projects[1].is_disjoint(&projects[2]));
+
+        assert!(run(
+            &input_path,
+            None,
+            "id",
+            [0.5, 0.2, 0.2],
+            42,
+            true,
+            test_logger()
+        )
+        .is_err());
+        assert!(run(
+            &input_path,
+            None,
+            "missing",
+            [0.6, 0.2, 0.2],
+            42,
+            true,
+            test_logger()
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn assign_splits_test() {
+        let names: Vec<String> = (0..10).map(|i| i.to_string()).collect();
+        let projects: Vec<&str> = names.iter().map(|n| n.as_str()).collect();
+
+        let assignment = assign_splits(projects.clone(), [0.7, 0.2, 0.1], 1);
+        let count = |s: usize| assignment.values().filter(|v| **v == s).count();
+        assert_eq!((count(0), count(1), count(2)), (7, 2, 1));
+
+        // The assignment only depends on the set of projects and the seed.
+        let mut reversed: Vec<&str> = projects.clone();
+        reversed.reverse();
+        assert_eq!(assignment, assign_splits(reversed, [0.7, 0.2, 0.1], 1));
+    }
+}
@@ -0,0 +1,423 @@
+// Copyright 2026 Andrea Gilot
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![doc = include_str!("../docs/tokens.md")]
+
+use std::collections::HashMap;
+use std::iter::FromIterator as _;
+
+use anyhow::{bail, ensure, Result};
+use clap::{Arg, ArgAction, Command};
+use polars::frame::DataFrame;
+use polars::prelude::{AnyValue, Column, DataType, Field, Schema};
+use tracing::{info, warn};
+use tree_sitter::{Node, Parser};
+use walkdir::WalkDir;
+
+use crate::phases::parse::{extension_language, language_to_grammar, Grammar};
+use crate::utils::csv::{revert_placeholders, validate_columns};
+use crate::utils::fs::*;
+use crate::utils::logger::{log_output_file, log_write_output, Logger};
+use crate::utils::progress::Progress;
+use crate::utils::regex::Matcher;
+
+/// Command line arguments parsing.
+pub fn cli() -> Command {
+    Command::new("tokens")
+        .about("Computes the frequency of the tokens and n-grams of the files of a corpus, per language.")
+        .long_about(include_str!("../docs/tokens.md"))
+        .disable_version_flag(true)
+        .arg(
+            Arg::new("input")
+                .short('i')
+                .long("input")
+                .value_name("INPUT_FILE.csv")
+                .help("Path to the csv file listing the files to tokenize, such as the file log produced by the download command. \
+                       It must contain the columns 'name' (path to the file) and 'language'.")
+                .required_unless_present("dir")
+                .conflicts_with("dir"),
+        )
+        .arg(
+            Arg::new("dir")
+                .long("dir")
+                .value_name("DIRECTORY")
+                .help("Directory to tokenize instead of a list of files. The language of each file is inferred from its extension.")
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .value_name("OUTPUT_FILE.csv")
+                .help("Path to the output csv file storing the frequencies. \
+                       By default, the name of the output file is the same as the input file or directory with the suffix '.tokens.csv'.")
+                .required(false),
+        )
+        .arg(
+            Arg::new("ngrams")
+                .long("ngrams")
+                .value_name("N")
+                .help("Maximal length of the n-grams. The frequencies of all the n-grams of length 1 to N are computed.")
+                .default_value("1")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("min-count")
+                .long("min-count")
+                .value_name("COUNT")
+                .help("Minimal number of occurrences of an n-gram to be written in the output file.")
+                .default_value("1")
+                .value_parser(clap::value_parser!(u64)),
+        )
+        .arg(
+            Arg::new("lexer")
+                .long("lexer")
+                .value_name("LEXER")
+                .help("How the files are split into tokens.\n\
+                       words: words made of letters, digits and underscores\n\
+                       tree-sitter: leaves of the syntax tree, comments excluded (only for the languages supported by the parse command)")
+                .value_parser(["words", "tree-sitter"])
+                .default_value("words"),
+        )
+        .arg(
+            Arg::new("force")
+                .short('f')
+                .long("force")
+                .help("Override the output file if it already exists.")
+                .default_value("false")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("no-output")
+                .long("no-output")
+                .help("Does not write the output file. Prints statistics only.")
+                .default_value("false")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(vec!["output", "force"]),
+        )
+}
+
+/// Computes the frequency of the tokens and n-grams of a corpus, per language.
+///
+/// # Arguments
+///
+/// * `input_path` - The path to the csv file listing the files, or to the directory to tokenize if `dir` is true.
+/// * `dir` - Whether the input is a directory.
+/// * `output_path` - The optional path to the output CSV file. Defaults to the input path with ".tokens.csv" appended.
+/// * `ngrams` - The maximal length of the n-grams.
+/// * `min_count` - The minimal number of occurrences of an n-gram to be written.
+/// * `lexer` - The lexer used to split files into tokens, either "words" or "tree-sitter".
+/// * `force` - Whether to override the output file if it already exists.
+/// * `no_output` - Whether to skip writing the output file.
+/// * `logger` - The logger displaying the progress.
+///
+/// # Returns
+///
+/// A result indicating success or failure of the operation.
+pub fn run(
+    input_path: &str,
+    dir: bool,
+    output_path: Option<&str>,
+    ngrams: usize,
+    min_count: u64,
+    lexer: &str,
+    force: bool,
+    no_output: bool,
+    logger: &Logger,
+) -> Result<()> {
+    ensure!(ngrams > 0, "The length of the n-grams must be positive");
+
+    let default_output_path = format!("{}.tokens.csv", input_path.trim_end_matches('/'));
+    let output_path = output_path.unwrap_or(&default_output_path);
+    logger.record_inputs(&[input_path]);
+    if !no_output {
+        logger.record_outputs(&[output_path]);
+    }
+
+    check_path(input_path)?;
+    log_output_file(output_path, no_output, force)?;
+
+    // The files of a directory are listed with their extension, from which their language is inferred once read.
+    let files: Vec<(String, String)> = logger.run_task("Loading files", || {
+        if dir {
+            Ok(WalkDir::new(input_path)
+                .sort_by_file_name()
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_file())
+                .filter_map(|e| {
+                    let ext: String = e.path().extension()?.to_str()?.to_string();
+                    Some((e.path().to_string_lossy().to_string(), ext))
+                })
+                .collect())
+        } else {
+            let schema: Schema = Schema::from_iter(vec![
+                Field::new("name".into(), DataType::String),
+                Field::new("language".into(), DataType::String),
+            ]);
+            validate_columns(input_path, &["name", "language"], &schema)?;
+            let input_file: DataFrame =
+                open_csv(input_path, Some(schema), Some(vec!["name", "language"]))?;
+            (0..input_file.height())
+                .map(|idx| match &input_file.get_row(idx)?.0[..] {
+                    [AnyValue::String(path), AnyValue::String(language)] => {
+                        Ok((revert_placeholders(path), language.to_string()))
+                    }
+                    _ => bail!("Could not parse row {idx} in the input file"),
+                })
+                .collect()
+        }
+    })?;
+
+    info!("  {} files found.", files.len());
+
+    let word_matcher: Matcher = Matcher::words_matcher();
+    let mut grammars: HashMap<String, Option<Grammar>> = HashMap::new();
+    let mut parser: Parser = Parser::new();
+
+    // Frequencies of the n-grams, indexed by language, length and n-gram.
+    let mut frequencies: HashMap<(String, usize, Vec<u8>), NgramFrequency> = HashMap::new();
+    let mut skipped: usize = 0;
+
+    let progress_bar: Progress = logger.progress_bar(Some(files.len() as u64))?;
+
+    for (path, language) in files {
+        progress_bar.inc(1);
+
+        let source: Vec<u8> = match load_file(&path, 1024 * 1024 * 1024)? {
+            Ok(source) => source,
+            Err(_) => {
+                warn!("File {path} is too large and was skipped");
+                skipped += 1;
+                progress_bar.fail();
+                continue;
+            }
+        };
+        let language: String = if dir {
+            extension_language(&language, &source)
+        } else {
+            language
+        };
+
+        let tokens: Vec<&[u8]> = if lexer == "tree-sitter" {
+            let grammar: &Option<Grammar> = grammars
+                .entry(language.clone())
+                .or_insert_with(|| language_to_grammar(&language));
+            match grammar {
+                Some(g) => {
+                    parser.set_language(&g.lang)?;
+                    match parser.parse(&source, None) {
+                        Some(tree) => syntax_tokens(tree.root_node(), &source, g),
+                        None => {
+                            warn!("Could not parse file {path}");
+                            skipped += 1;
+                            progress_bar.fail();
+                            continue;
+                        }
+                    }
+                }
+                None => {
+                    skipped += 1;
+                    progress_bar.fail();
+                    continue;
+                }
+            }
+        } else {
+            word_matcher.matches(&source)
+        };
+
+        // Frequencies of the n-grams in the file.
+        let mut file_frequencies: HashMap<(usize, Vec<u8>), u64> = HashMap::new();
+        for n in 1..=ngrams {
+            for window in tokens.windows(n) {
+                *file_frequencies.entry((n, window.join(&b' '))).or_default() += 1;
+            }
+        }
+
+        for ((n, ngram), count) in file_frequencies {
+            let frequency = frequencies.entry((language.clone(), n, ngram)).or_default();
+            frequency.count += count;
+            frequency.files += 1;
+        }
+    }
+    progress_bar.finish();
+
+    if skipped > 0 {
+        info!("  {skipped} files could not be tokenized and were skipped.");
+    }
+
+    let mut rows: Vec<((String, usize, Vec<u8>), NgramFrequency)> = frequencies
+        .into_iter()
+        .filter(|(_, f)| f.count >= min_count)
+        .collect();
+    rows.sort_by(|(a, fa), (b, fb)| {
+        (&a.0, a.1, std::cmp::Reverse(fa.count), &a.2).cmp(&(
+            &b.0,
+            b.1,
+            std::cmp::Reverse(fb.count),
+            &b.2,
+        ))
+    });
+
+    info!("  {} distinct n-grams found.", rows.len());
+
+    let mut table: DataFrame = DataFrame::new(vec![
+        Column::new(
+            "language".into(),
+            rows.iter()
+                .map(|(k, _)| k.0.as_str())
+                .collect::<Vec<&str>>(),
+        ),
+        Column::new(
+            "n".into(),
+            rows.iter().map(|(k, _)| k.1 as u32).collect::<Vec<u32>>(),
+        ),
+        Column::new(
+            "ngram".into(),
+            rows.iter()
+                .map(|(k, _)| String::from_utf8_lossy(&k.2).to_string())
+                .collect::<Vec<String>>(),
+        ),
+        Column::new(
+            "count".into(),
+            rows.iter().map(|(_, f)| f.count).collect::<Vec<u64>>(),
+        ),
+        Column::new(
+            "files".into(),
+            rows.iter().map(|(_, f)| f.files).collect::<Vec<u64>>(),
+        ),
+    ])?;
+
+    log_write_output(logger, output_path, &mut table, no_output)
+}
+
+/// Number of occurrences of an n-gram.
+#[derive(Default)]
+struct NgramFrequency {
+    /// Total number of occurrences.
+    count: u64,
+    /// Number of files in which the n-gram occurs.
+    files: u64,
+}
+
+/// Returns the text of the leaves of a syntax tree in order of appearance, comments excluded.
+///
+/// # Arguments
+///
+/// * `root` - The root of the syntax tree.
+/// * `source` - The source code of the file.
+/// * `grammar` - The grammar of the language.
+fn syntax_tokens<'a>(root: Node, source: &'a [u8], grammar: &Grammar) -> Vec<&'a [u8]> {
+    let mut tokens: Vec<&[u8]> = Vec::new();
+    let mut call_stack: Vec<Node> = vec![root];
+    while let Some(node) = call_stack.pop() {
+        if grammar.comment_nodes.contains(node.kind()) {
+            continue;
+        }
+        if node.child_count() == 0 {
+            if node.end_byte() > node.start_byte() {
+                tokens.push(&source[node.byte_range()]);
+            }
+        } else {
+            // Children are pushed in reverse order so that they are visited from left to right.
+            let children: Vec<Node> = node.children(&mut node.walk()).collect();
+            call_stack.extend(children.into_iter().rev());
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::utils::dataframes;
+    use crate::utils::logger::test_logger;
+    use anyhow::ensure;
+
+    const TEST_DATA: &str = "tests/data/phases/tokens";
+
+    fn tokens_test(lexer: &str, ngrams: usize, min_count: u64) -> Result<()> {
+        let input_path = format!("{TEST_DATA}/files.csv");
+        let output_path = format!("{input_path}.{lexer}.{ngrams}.tokens.csv");
+
+        delete_file(&output_path, true)?;
+        run(
+            &input_path,
+            false,
+            Some(&output_path),
+            ngrams,
+            min_count,
+            lexer,
+            false,
+            false,
+            test_logger(),
+        )?;
+
+        let expected_df = open_csv(&format!("{output_path}.expected"), None, None)?;
+        let output_df = open_csv(&output_path, None, None)?;
+
+        ensure!(
+            expected_df.equals(&output_df),
+            "Token frequencies do not match expected result."
+        );
+
+        delete_file(&output_path, false)
+    }
+
+    #[test]
+    fn words_unigrams() -> Result<()> {
+        tokens_test("words", 1, 1)
+    }
+
+    #[test]
+    fn tree_sitter_bigrams() -> Result<()> {
+        tokens_test("tree-sitter", 2, 2)
+    }
+
+    #[test]
+    fn tree_sitter_dir() -> Result<()> {
+        let dir: &str = "target/tests/tokens_dir";
+        let output_path: String = format!("{dir}.tokens.csv");
+        delete_dir(dir, true)?;
+        for (name, content) in [
+            ("a.py", "def f(x):\n    return x\n"),
+            ("b.rs", "fn f(x: i32) -> i32 { x }\n"),
+            ("c.h", "namespace n { int f(); }\n"),
+            ("notes.txt", "not code\n"),
+        ] {
+            write_file(format!("{dir}/{name}"), content)?;
+        }
+        run(
+            dir,
+            true,
+            Some(&output_path),
+            1,
+            1,
+            "tree-sitter",
+            true,
+            false,
+            test_logger(),
+        )?;
+
+        // The languages are inferred from the extensions, and from the content of the headers.
+        let output: DataFrame = open_csv(&output_path, None, None)?;
+        let mut languages: Vec<&str> = dataframes::str(&output, "language")?;
+        languages.dedup();
+        assert_eq!(languages, vec!["c++", "python", "rust"]);
+
+        delete_file(&output_path, false)?;
+        delete_dir(dir, false)
+    }
+}
@@ -0,0 +1,374 @@
+// Copyright 2026 Andrea Gilot
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![doc = include_str!("../docs/verify_languages.md")]
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::iter::FromIterator as _;
+
+use anyhow::{Context, Result};
+use clap::{Arg, ArgAction, Command};
+use polars::frame::DataFrame;
+use polars::prelude::{Column, DataType, Field, Schema};
+use tracing::info;
+
+use crate::utils::csv::validate_columns;
+use crate::utils::dataframes;
+use crate::utils::fs::*;
+use crate::utils::language::normalize_language;
+use crate::utils::logger::{log_output_file, log_write_output, Logger};
+
+/// Command line arguments parsing.
+pub fn cli() -> Command {
+    Command::new("verify_languages")
+        .about("Reports the projects whose languages on GitHub have no file kept by the download command")
+        .long_about(include_str!("../docs/verify_languages.md"))
+        .disable_version_flag(true)
+        .arg(
+            Arg::new("input")
+                .short('i')
+                .long("input")
+                .value_name("LANGUAGES_FILE.csv")
+                .help("Path to the csv file storing the languages of the projects produced by the languages command.")
+                .required(true),
+        )
+        .arg(
+            Arg::new("files")
+                .long("files")
+                .value_name("FILE_LOG.csv")
+                .help("Path to the file log produced by the download command.")
+                .required(true),
+        )
+        .arg(
+            Arg::new("projects")
+                .long("projects")
+                .value_name("PROJECT_LOG.csv")
+                .help("Path to the project log produced by the download command. \
+                       If specified, every project downloaded successfully is checked, including the projects without kept files. \
+                       Otherwise, only the projects with at least one file in the file log are checked.")
+                .required(false),
+        )
+        .arg(
+            Arg::new("languages")
+                .long("languages")
+                .value_name("LANGUAGES")
+                .help("Names of the languages to check, as in the keyword files. \
+                       By default, the languages of the files in the file log are checked.")
+                .num_args(1..)
+                .required(false),
+        )
+        .arg(
+            Arg::new("min-bytes")
+                .long("min-bytes")
+                .value_name("BYTES")
+                .help("Size in bytes above which the languages reported by GitHub are checked.")
+                .default_value("0")
+                .value_parser(clap::value_parser!(u64)),
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .value_name("OUTPUT_FILE.csv")
+                .help("Path to the output csv file storing the discrepancies. \
+                       By default, the name of the output file is the same as the input file with the suffix '.verify_languages.csv'.")
+                .required(false),
+        )
+        .arg(
+            Arg::new("force")
+                .short('f')
+                .long("force")
+                .help("Override the output file if it already exists.")
+                .default_value("false")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("no-output")
+                .long("no-output")
+                .help("Does not write the output file. Prints statistics only.")
+                .default_value("false")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(vec!["output", "force"]),
+        )
+}
+
+/// Reports the projects for which GitHub reports a language of which the download command kept no file.
+///
+/// # Arguments
+///
+/// * `input_path` - The path to the languages of the projects produced by the languages command.
+/// * `files_path` - The path to the file log produced by the download command.
+/// * `projects_path` - The optional path to the project log produced by the download command.
+///   If specified, every project downloaded successfully is checked. Otherwise, only the projects of the file log are checked.
+/// * `languages` - The languages to check. If not specified, the languages of the files in the file log are checked.
+/// * `min_bytes` - The size in bytes above which the languages reported by GitHub are checked.
+/// * `output_path` - The optional path to the output CSV file. Defaults to the input path with ".verify_languages.csv" appended.
+/// * `force` - Whether to override the output file if it already exists.
+/// * `no_output` - Whether to skip writing the output file.
+/// * `logger` - The logger displaying the progress.
+///
+/// # Returns
+///
+/// A result indicating success or failure of the operation.
+pub fn run(
+    input_path: &str,
+    files_path: &str,
+    projects_path: Option<&str>,
+    languages: Option<Vec<&str>>,
+    min_bytes: u64,
+    output_path: Option<&str>,
+    force: bool,
+    no_output: bool,
+    logger: &Logger,
+) -> Result<()> {
+    let default_output_path = format!("{input_path}.verify_languages.csv");
+    let output_path = output_path.unwrap_or(&default_output_path);
+    logger.record_inputs(
+        &[Some(input_path), Some(files_path), projects_path]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<&str>>(),
+    );
+    if !no_output {
+        logger.record_outputs(&[output_path]);
+    }
+
+    // Checks if the input files exist
+    check_path(input_path)?;
+    check_path(files_path)?;
+    if let Some(projects_path) = projects_path {
+        check_path(projects_path)?;
+    }
+
+    // Checks if the output file already exists
+    log_output_file(output_path, no_output, force)?;
+
+    let projects: DataFrame = logger.run_task("Loading languages", || {
+        let schema: Schema = Schema::from_iter(vec![
+            Field::new("id".into(), DataType::UInt32),
+            Field::new("name".into(), DataType::String),
+            Field::new("languages".into(), DataType::String),
+        ]);
+        validate_columns(input_path, &["id", "name", "languages"], &schema)?;
+        open_csv(
+            input_path,
+            Some(schema),
+            Some(vec!["id", "name", "languages"]),
+        )
+    })?;
+
+    // Number of kept files of each language in each project.
+    let kept_files: HashMap<u32, HashMap<String, u32>> =
+        logger.run_task("Loading file log", || {
+            let schema: Schema = Schema::from_iter(vec![
+                Field::new("id".into(), DataType::UInt32),
+                Field::new("language".into(), DataType::String),
+            ]);
+            validate_columns(files_path, &["id", "language"], &schema)?;
+            let files: DataFrame =
+                open_csv(files_path, Some(schema), Some(vec!["id", "language"]))?;
+            let mut kept_files: HashMap<u32, HashMap<String, u32>> = HashMap::new();
+            for (id, language) in dataframes::u32(&files, "id")?
+                .into_iter()
+                .zip(dataframes::str(&files, "language")?)
+            {
+                *kept_files
+                    .entry(id)
+                    .or_default()
+                    .entry(normalize(language))
+                    .or_default() += 1;
+            }
+            Ok(kept_files)
+        })?;
+
+    // Projects whose files were analysed.
+    let downloaded: HashSet<u32> = match projects_path {
+        Some(projects_path) => logger.run_task("Loading project log", || {
+            // The directories recorded as separate projects with --split-subdirs have ids suffixed with their path.
+            let schema: Schema = Schema::from_iter(vec![
+                Field::new("id".into(), DataType::String),
+                Field::new("path".into(), DataType::String),
+            ]);
+            validate_columns(projects_path, &["id", "path"], &schema)?;
+            let project_log: DataFrame =
+                open_csv(projects_path, Some(schema), Some(vec!["id", "path"]))?;
+            dataframes::str(&project_log, "id")?
+                .into_iter()
+                .zip(dataframes::str(&project_log, "path")?)
+                .filter(|(_, path)| *path != "error" && *path != "oversized")
+                .map(|(id, _)| {
+                    let repository: &str = id.split('/').next().unwrap_or_default();
+                    repository
+                        .parse::<u32>()
+                        .with_context(|| format!("Invalid id {id} in {projects_path}"))
+                })
+                .collect()
+        })?,
+        None => kept_files.keys().copied().collect(),
+    };
+
+    let checked: BTreeSet<String> = match languages {
+        Some(languages) => languages.into_iter().map(normalize).collect(),
+        None => kept_files
+            .values()
+            .flat_map(|languages| languages.keys().cloned())
+            .collect(),
+    };
+    info!(
+        "{} downloaded projects found, checking the languages {}.",
+        downloaded.len(),
+        checked.iter().cloned().collect::<Vec<String>>().join(", ")
+    );
+
+    // Discrepancies, and number of projects claiming each language on GitHub.
+    let mut ids: Vec<u32> = Vec::new();
+    let mut names: Vec<&str> = Vec::new();
+    let mut missing: Vec<String> = Vec::new();
+    let mut bytes: Vec<u64> = Vec::new();
+    let mut claimed: HashMap<&str, u32> = HashMap::new();
+    for ((id, name), github_languages) in dataframes::u32(&projects, "id")?
+        .into_iter()
+        .zip(dataframes::str(&projects, "name")?)
+        .zip(dataframes::str(&projects, "languages")?)
+    {
+        if !downloaded.contains(&id) {
+            continue;
+        }
+        let kept: Option<&HashMap<String, u32>> = kept_files.get(&id);
+        for (language, size) in github_languages.split(';').filter_map(|pair| {
+            let (language, size) = pair.rsplit_once(':')?;
+            Some((normalize(language), size.parse::<u64>().ok()?))
+        }) {
+            let Some(language) = checked.get(&language) else {
+                continue;
+            };
+            if size <= min_bytes {
+                continue;
+            }
+            *claimed.entry(language).or_default() += 1;
+            if kept.and_then(|kept| kept.get(language)).is_none() {
+                ids.push(id);
+                names.push(name);
+                missing.push(language.clone());
+                bytes.push(size);
+            }
+        }
+    }
+
+    for language in &checked {
+        let claimed: u32 = claimed.get(language.as_str()).copied().unwrap_or_default();
+        let discrepancies: usize = missing.iter().filter(|m| *m == language).count();
+        info!(
+            "{language}: {discrepancies} of {claimed} projects without kept files ({:.2} %)",
+            if claimed == 0 {
+                0.0
+            } else {
+                discrepancies as f64 / claimed as f64 * 100.0
+            }
+        );
+    }
+    logger.record_count("discrepancies", missing.len() as u64);
+
+    let mut report: DataFrame = DataFrame::new(vec![
+        Column::new("id".into(), ids),
+        Column::new("name".into(), names),
+        Column::new("language".into(), missing),
+        Column::new("github_bytes".into(), bytes),
+    ])?;
+
+    log_write_output(logger, output_path, &mut report, no_output)
+}
+
+/// Normalizes the name of a language so that the names of GitHub and of the keyword files can be compared.
+/// The names are compared by their canonical names, and the headers of a language are counted as files of the language.
+///
+/// # Arguments
+///
+/// * `language` - The name of the language.
+fn normalize(language: &str) -> String {
+    let language: String = normalize_language(language);
+    match language.strip_suffix("_header") {
+        Some(language) => language.to_string(),
+        None => language,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::utils::logger::test_logger;
+
+    const TEST_DATA: &str = "tests/data/phases/verify_languages";
+
+    fn open_report(path: &str) -> Result<DataFrame> {
+        open_csv(
+            path,
+            Some(Schema::from_iter(vec![Field::new(
+                "id".into(),
+                DataType::UInt32,
+            )])),
+            None,
+        )
+    }
+
+    #[test]
+    fn normalize_test() {
+        assert_eq!(normalize("C++"), "c++");
+        assert_eq!(normalize("c++_header"), "c++");
+        assert_eq!(normalize("Standard ML"), "standard ml");
+    }
+
+    #[test]
+    fn verify_languages_test() -> Result<()> {
+        let input_path: String = format!("{TEST_DATA}/repos.csv");
+        let output_path: String = format!("{input_path}.verify_languages.csv");
+
+        // Only the projects of the file log are checked.
+        delete_file(&output_path, true)?;
+        run(
+            &input_path,
+            &format!("{TEST_DATA}/file_log.csv"),
+            None,
+            None,
+            0,
+            None,
+            false,
+            false,
+            test_logger(),
+        )?;
+        let report: DataFrame = open_report(&output_path)?;
+        assert_eq!(dataframes::u32(&report, "id")?, vec![2]);
+        assert_eq!(dataframes::str(&report, "language")?, vec!["c++"]);
+
+        // The downloaded projects without kept files are checked with the project log.
+        run(
+            &input_path,
+            &format!("{TEST_DATA}/file_log.csv"),
+            Some(&format!("{TEST_DATA}/project_log.csv")),
+            Some(vec!["C", "Fortran"]),
+            100,
+            None,
+            true,
+            false,
+            test_logger(),
+        )?;
+        let report: DataFrame = open_report(&output_path)?;
+        assert_eq!(dataframes::u32(&report, "id")?, vec![3, 4]);
+        assert_eq!(dataframes::str(&report, "language")?, vec!["c", "fortran"]);
+
+        delete_file(&output_path, false)
+    }
+}
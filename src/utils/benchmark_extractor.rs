@@ -0,0 +1,59 @@
+// Copyright 2026 Andrea Gilot
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Interface of the extractors of benchmarks, i.e. of self-contained source files containing a function and all its
+//! dependencies, one per language supported by the `extract_benchmarks` command.
+//!
+//! An extractor only builds the benchmark of a function in a downloaded project. Downloading the projects, resuming
+//! an interrupted run, writing the benchmarks and the output file and removing duplicates are shared by all languages.
+
+use std::path::Path;
+
+use anyhow::Result;
+
+/// Benchmark of a function built by an extractor.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Benchmark {
+    /// Source code of the benchmark.
+    pub code: Vec<u8>,
+    /// Return type of the function, as spelled in the benchmark.
+    pub return_type: String,
+    /// Types of the parameters of the function, in order.
+    pub param_types: Vec<String>,
+}
+
+/// Extractor of the benchmarks of the functions of a language.
+pub trait BenchmarkExtractor {
+    /// Name of the language, as given to `--lang`.
+    fn language(&self) -> &'static str;
+
+    /// Extension of the benchmark files, without the dot.
+    fn extension(&self) -> &'static str;
+
+    /// Path to the keyword file listing the extensions of the source files kept when the projects are downloaded.
+    fn keywords_file(&self) -> &'static str;
+
+    /// Builds the benchmark of a function.
+    ///
+    /// # Arguments
+    ///
+    /// * `project` - The root directory of the downloaded project.
+    /// * `root_file` - The file of the project defining the function.
+    /// * `function` - The name of the function.
+    ///
+    /// # Returns
+    ///
+    /// The benchmark, or an error whose chain contains the reason of the failure recorded in the output file.
+    fn extract(&self, project: &Path, root_file: &Path, function: &str) -> Result<Benchmark>;
+}
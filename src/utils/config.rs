@@ -0,0 +1,310 @@
+// Copyright 2026 Andrea Gilot
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Configuration file providing default values for the options of the subcommands.
+//!
+//! The top-level keys of the file apply to every subcommand with an option of the same name,
+//! and the keys of a table named after a subcommand only apply to this subcommand:
+//!
+//! ```toml
+//! tokens = "ghtokens.csv"
+//! seed = 42
+//!
+//! [download]
+//! dest = "projects"
+//! threads = 16
+//! ```
+//!
+//! Options given on the command line take precedence over the values of the file.
+
+use std::ffi::OsString;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use clap::builder::Resettable;
+use clap::{Arg, Command};
+
+/// Name of the configuration file looked up in the working directory when no file is given with --config.
+pub const DEFAULT_CONFIG: &str = "scyros.toml";
+
+/// Default values of the options of the subcommands.
+#[derive(Default)]
+pub struct Config {
+    /// Path to the configuration file.
+    path: String,
+    /// Content of the configuration file.
+    table: toml::Table,
+}
+
+/// Argument selecting the configuration file.
+pub fn config_arg() -> Arg {
+    Arg::new("config")
+        .long("config")
+        .value_name("CONFIG.toml")
+        .help(format!(
+            "Path to a TOML file providing default values for the options of the subcommands, such as tokens, dest, seed or threads. \
+             Options given on the command line override the file. By default, the file {DEFAULT_CONFIG} is used if it exists in the working directory."
+        ))
+        .global(true)
+}
+
+impl Config {
+    /// Loads the configuration file given with --config in the command line arguments,
+    /// or the file [`DEFAULT_CONFIG`] of the working directory if it exists.
+    /// The configuration is empty if neither is present.
+    ///
+    /// # Arguments
+    ///
+    /// * `args` - The command line arguments, which are not parsed yet.
+    pub fn from_args(args: impl IntoIterator<Item = OsString>) -> Result<Self> {
+        let mut args = args.into_iter();
+        let mut path: Option<String> = None;
+        while let Some(arg) = args.next() {
+            let arg: String = arg.to_string_lossy().to_string();
+            if arg == "--config" {
+                path = args.next().map(|p| p.to_string_lossy().to_string());
+            } else if let Some(p) = arg.strip_prefix("--config=") {
+                path = Some(p.to_string());
+            } else if arg == "--" {
+                break;
+            }
+        }
+
+        match path {
+            Some(path) => Self::load(&path),
+            None if Path::new(DEFAULT_CONFIG).is_file() => Self::load(DEFAULT_CONFIG),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Loads a configuration file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the configuration file.
+    pub fn load(path: &str) -> Result<Self> {
+        let content: String = std::fs::read_to_string(path)
+            .with_context(|| format!("Could not read config file {path}"))?;
+        Ok(Self {
+            path: path.to_string(),
+            table: content
+                .parse()
+                .with_context(|| format!("Invalid config file {path}"))?,
+        })
+    }
+
+    /// Sets the values of the configuration file as the default values of the options of the subcommands.
+    /// Options that have a default value in the file are no longer required.
+    ///
+    /// # Arguments
+    ///
+    /// * `cli` - The command whose subcommands are configured.
+    ///
+    /// # Returns
+    ///
+    /// The configured command, or an error if the file contains options that do not exist.
+    pub fn apply(&self, mut cli: Command) -> Result<Command> {
+        let subcommands: Vec<String> = cli
+            .get_subcommands()
+            .map(|s| s.get_name().to_string())
+            .collect();
+
+        for (key, value) in &self.table {
+            match value {
+                toml::Value::Table(table) => {
+                    if !subcommands.contains(key) {
+                        bail!("Unknown subcommand '{key}' in config file {}", self.path);
+                    }
+                    for (option, value) in table {
+                        // Safe unwrap: the subcommand exists.
+                        let subcommand: &mut Command = cli.find_subcommand_mut(key).unwrap();
+                        if !self.set_default(subcommand, option, value)? {
+                            bail!(
+                                "Unknown option '{option}' of subcommand {key} in config file {}",
+                                self.path
+                            );
+                        }
+                    }
+                }
+                _ => {
+                    if !subcommands.iter().any(|s| has_option(&cli, s, key)) {
+                        bail!("Unknown option '{key}' in config file {}", self.path);
+                    }
+                    for subcommand in &subcommands {
+                        // Values of the table of the subcommand take precedence.
+                        let overridden: bool = self
+                            .table
+                            .get(subcommand)
+                            .and_then(|t| t.as_table())
+                            .is_some_and(|t| t.contains_key(key));
+                        if !overridden {
+                            // Safe unwrap: the subcommand exists.
+                            self.set_default(
+                                cli.find_subcommand_mut(subcommand).unwrap(),
+                                key,
+                                value,
+                            )?;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(cli)
+    }
+
+    /// Sets the default value of an option of a subcommand.
+    ///
+    /// # Arguments
+    ///
+    /// * `subcommand` - The subcommand.
+    /// * `option` - The long name of the option.
+    /// * `value` - The default value.
+    ///
+    /// # Returns
+    ///
+    /// Whether the subcommand has this option.
+    fn set_default(
+        &self,
+        subcommand: &mut Command,
+        option: &str,
+        value: &toml::Value,
+    ) -> Result<bool> {
+        let Some(id) = subcommand
+            .get_arguments()
+            .find(|a| a.get_long() == Some(option))
+            .map(|a| a.get_id().clone())
+        else {
+            return Ok(false);
+        };
+        let value: String = match value {
+            toml::Value::String(s) => s.clone(),
+            toml::Value::Integer(i) => i.to_string(),
+            toml::Value::Float(f) => f.to_string(),
+            toml::Value::Boolean(b) => b.to_string(),
+            _ => bail!(
+                "Unsupported value for option '{option}' in config file {}",
+                self.path
+            ),
+        };
+        *subcommand = std::mem::take(subcommand).mut_arg(id, |a| {
+            a.default_value(value)
+                .required(false)
+                .required_unless_present(Resettable::Reset)
+        });
+        Ok(true)
+    }
+}
+
+/// Whether a subcommand has an option with the given long name.
+fn has_option(cli: &Command, subcommand: &str, option: &str) -> bool {
+    cli.find_subcommand(subcommand)
+        .is_some_and(|s| s.get_arguments().any(|a| a.get_long() == Some(option)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_DATA: &str = "tests/data/config";
+
+    /// A command with two subcommands sharing some options.
+    fn test_cli() -> Command {
+        Command::new("test")
+            .subcommand(
+                Command::new("download")
+                    .arg(Arg::new("tokens").long("tokens").required(true))
+                    .arg(Arg::new("dest").long("dest").required(true))
+                    .arg(
+                        Arg::new("threads")
+                            .long("threads")
+                            .default_value("1")
+                            .value_parser(clap::value_parser!(usize)),
+                    )
+                    .arg(
+                        Arg::new("force")
+                            .long("force")
+                            .action(clap::ArgAction::SetTrue),
+                    ),
+            )
+            .subcommand(
+                Command::new("ids")
+                    .arg(
+                        Arg::new("tokens")
+                            .long("tokens")
+                            .required_unless_present("auth"),
+                    )
+                    .arg(Arg::new("auth").long("auth"))
+                    .arg(
+                        Arg::new("seed")
+                            .long("seed")
+                            .value_parser(clap::value_parser!(u64)),
+                    ),
+            )
+            .arg(config_arg())
+    }
+
+    #[test]
+    fn apply_test() -> Result<()> {
+        let config_path: String = format!("{TEST_DATA}/scyros.toml");
+        let config =
+            Config::from_args(["scyros", "ids", "--config", &config_path].map(OsString::from))?;
+        let cli = config.apply(test_cli())?;
+
+        let matches = cli.clone().try_get_matches_from(["test", "download"])?;
+        let download = matches.subcommand_matches("download").unwrap();
+        assert_eq!(
+            download.get_one::<String>("tokens").unwrap(),
+            "ghtokens.csv"
+        );
+        assert_eq!(download.get_one::<String>("dest").unwrap(), "projects");
+        assert_eq!(*download.get_one::<usize>("threads").unwrap(), 16);
+        assert!(download.get_flag("force"));
+
+        let matches =
+            cli.try_get_matches_from(["test", "ids", "--tokens", "other.csv", "--seed", "7"])?;
+        let ids = matches.subcommand_matches("ids").unwrap();
+        assert_eq!(ids.get_one::<String>("tokens").unwrap(), "other.csv");
+        assert_eq!(*ids.get_one::<u64>("seed").unwrap(), 7);
+
+        let config = Config::from_args([format!("--config={config_path}")].map(OsString::from))?;
+        assert!(config
+            .apply(test_cli())?
+            .try_get_matches_from(["test", "ids"])
+            .is_ok());
+
+        // Without configuration file, the options are still required.
+        assert!(test_cli()
+            .try_get_matches_from(["test", "download"])
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_config_test() -> Result<()> {
+        let config = |content: &str| Config {
+            path: "scyros.toml".to_string(),
+            table: content.parse().unwrap(),
+        };
+        assert!(config("unknown = 1").apply(test_cli()).is_err());
+        assert!(config("[unknown]\nseed = 1").apply(test_cli()).is_err());
+        assert!(config("[download]\nseed = 1").apply(test_cli()).is_err());
+        assert!(config("seed = [1, 2]").apply(test_cli()).is_err());
+        assert!(config("seed = 'abc'")
+            .apply(test_cli())?
+            .try_get_matches_from(["test", "ids", "--tokens", "t.csv"])
+            .is_err());
+        assert!(Config::load(&format!("{TEST_DATA}/missing.toml")).is_err());
+        Ok(())
+    }
+}
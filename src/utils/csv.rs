@@ -15,45 +15,83 @@
 //! Utility functions for working with CSV files.
 
 use super::fs::*;
-use anyhow::{anyhow, bail, Context, Result};
-use csv::{Reader, StringRecord};
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use csv::{ByteRecord, Reader, StringRecord};
+use polars::prelude::{DataType, Schema};
 use std::collections::HashMap;
 use std::fs::File;
 use std::hash::Hash;
 use std::io;
-use std::io::BufWriter;
-use std::io::Write;
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
 use std::str::FromStr;
+use tracing::warn;
 
+/// A CSV file opened for reading or writing.
+///
+/// Rows are written to the file as soon as they are complete, i.e. once their final newline (outside quotes) is
+/// written, so that a process dying in the middle of a write never leaves a partial row at the end of the file and
+/// never loses a row that was complete.
+/// Files with the .zst extension are decompressed when they are read.
 #[derive(Debug)]
 pub struct CSVFile {
     path: String,
-    writer: Option<BufWriter<File>>,
+    writer: Option<File>,
+    /// Complete rows not yet written to the file, written at the end of the current write.
+    rows: Vec<u8>,
+    /// Beginning of the row currently being written.
+    partial_row: Vec<u8>,
+    /// Whether the row currently being written is inside a quoted field.
+    in_quotes: bool,
 }
 
 impl Write for CSVFile {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        self.writer
-            .as_mut()
-            .ok_or_else(|| {
-                io::Error::new(
-                    io::ErrorKind::ReadOnlyFilesystem,
-                    "The file is not opened in write mode",
-                )
-            })?
-            .write(buf)
+        if self.writer.is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::ReadOnlyFilesystem,
+                "The file is not opened in write mode",
+            ));
+        }
+        for byte in buf {
+            self.partial_row.push(*byte);
+            match byte {
+                b'"' => self.in_quotes = !self.in_quotes,
+                b'\n' if !self.in_quotes => self.rows.append(&mut self.partial_row),
+                _ => {}
+            }
+        }
+        self.write_rows()?;
+        Ok(buf.len())
     }
 
+    /// Writes the complete rows to the file. The row currently being written is kept until it is complete.
     fn flush(&mut self) -> io::Result<()> {
-        self.writer
-            .as_mut()
-            .ok_or_else(|| {
-                io::Error::new(
-                    io::ErrorKind::ReadOnlyFilesystem,
-                    "The file is not opened in write mode",
-                )
-            })?
-            .flush()
+        self.write_rows()?;
+        match self.writer.as_mut() {
+            Some(f) => f.flush(),
+            None => Err(io::Error::new(
+                io::ErrorKind::ReadOnlyFilesystem,
+                "The file is not opened in write mode",
+            )),
+        }
+    }
+}
+
+impl Drop for CSVFile {
+    fn drop(&mut self) {
+        // The file is closed normally, the last row is terminated so that appending to the file later keeps it.
+        // A row ending inside a quoted field cannot be terminated and is dropped instead.
+        if self.in_quotes {
+            warn!(
+                "Dropping an unterminated row of {} bytes at the end of {}",
+                self.partial_row.len(),
+                self.path
+            );
+        } else if !self.partial_row.is_empty() {
+            self.partial_row.push(b'\n');
+            self.rows.append(&mut self.partial_row);
+        }
+        let _ = self.write_rows();
     }
 }
 
@@ -68,20 +106,36 @@ impl CSVFile {
     /// # Returns
     ///
     /// A CSV file in the specified mode or an error if the file could not be opened.
+    /// In append mode, a partial row left at the end of the file by an interrupted run is removed.
     pub fn new(path: &str, mode: FileMode) -> Result<Self> {
+        let file: File = open_file(path, mode)?;
+        if mode == FileMode::Append {
+            drop_partial_row(path)?;
+        }
         Ok(Self {
             path: path.to_string(),
-            writer: {
-                let file: File = open_file(path, mode)?;
-                if mode == FileMode::Read {
-                    None
-                } else {
-                    Some(BufWriter::new(file))
-                }
+            writer: if mode == FileMode::Read {
+                None
+            } else {
+                Some(file)
             },
+            rows: Vec::new(),
+            partial_row: Vec::new(),
+            in_quotes: false,
         })
     }
 
+    /// Writes the buffered complete rows to the file in a single write.
+    fn write_rows(&mut self) -> io::Result<()> {
+        if let Some(f) = self.writer.as_mut() {
+            if !self.rows.is_empty() {
+                f.write_all(&self.rows)?;
+                self.rows.clear();
+            }
+        }
+        Ok(())
+    }
+
     // TODO: Test
     /// Switches the mode of the file.
     ///
@@ -96,8 +150,8 @@ impl CSVFile {
         Self::new(&self.path, mode)
     }
 
-    /// Opens a reader for this file
-    fn read(&self) -> Result<Reader<File>> {
+    /// Opens a reader for this file, decompressing it if it is compressed with zstd.
+    fn read(&self) -> Result<Reader<Box<dyn Read>>> {
         if self.writer.is_some() {
             bail!(
                 "Cannot read from {} since it is in write-only mode",
@@ -106,9 +160,7 @@ impl CSVFile {
         } else {
             Ok(csv::ReaderBuilder::new()
                 .has_headers(true)
-                .double_quote(false)
-                .escape(Some(b'\\'))
-                .from_reader(open_file(&self.path, FileMode::Read)?))
+                .from_reader(open_reader(&self.path)?))
         }
     }
 
@@ -130,7 +182,7 @@ impl CSVFile {
                 self.path
             ),
             Some(f) => {
-                if f.get_ref().metadata()?.len() == 0 {
+                if f.metadata()?.len() == 0 && self.rows.is_empty() && self.partial_row.is_empty() {
                     writeln!(self, "{}", header.join(","))?
                 }
                 Ok(())
@@ -212,16 +264,305 @@ impl CSVFile {
         T: FromStr + Eq + Hash,
     {
         let keys: Vec<T> = self.column(i)?;
-        let lines: Vec<String> = std::fs::read_to_string(&self.path)?
-            .lines()
-            .map(|s| s.to_string())
-            .collect();
+        let mut content: String = String::new();
+        open_reader(&self.path)?.read_to_string(&mut content)?;
+        let lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
         if lines.is_empty() {
             Ok(HashMap::new())
         } else {
             Ok(keys.into_iter().zip(lines[1..].to_vec()).collect())
         }
     }
+
+    /// Returns the names of the columns of this file, or an empty vector if the file is empty.
+    pub fn header(&self) -> Result<Vec<String>> {
+        Ok(self
+            .read()?
+            .headers()
+            .with_context(|| format!("Could not read the header of {}", self.path))?
+            .iter()
+            .map(|h| h.to_string())
+            .collect())
+    }
+
+    /// Checks whether the header of this file contains a column.
+    ///
+    /// # Arguments
+    ///
+    /// * `column` - The name of the column.
+    ///
+    /// # Returns
+    ///
+    /// Whether the column is in the header, or an error if the header could not be read.
+    pub fn has_column(&self, column: &str) -> Result<bool> {
+        Ok(self
+            .read()?
+            .headers()
+            .with_context(|| format!("Could not read the header of {}", self.path))?
+            .iter()
+            .any(|h| h == column))
+    }
+}
+
+/// Appends a column to a CSV file that does not have it yet, such as a file written by an earlier version of the tool,
+/// so that new rows can be appended to the file. The existing rows are left unchanged apart from the new value.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file.
+/// * `column` - The name of the column.
+/// * `default` - The value of the column in the existing rows.
+///
+/// # Returns
+///
+/// Whether the column was added, or an error if the file could not be rewritten.
+pub fn add_missing_column(path: &str, column: &str, default: &str) -> Result<bool> {
+    let header: String = file_lines(path)?.next().transpose()?.unwrap_or_default();
+    if header.split(',').any(|h| h == column) {
+        return Ok(false);
+    }
+    upgrade_header(path, &[&header, column], &[(column, default)])
+}
+
+/// Upgrades a CSV file written by an earlier version of the tool to the current header, so that new rows can be
+/// appended to it. The columns missing from the file are inserted at their position in the header, with their default
+/// value in the existing rows, which are otherwise left unchanged. A partial row at the end of the file is kept as is.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file.
+/// * `header` - The current header, some of its entries possibly made of several comma-separated columns.
+/// * `defaults` - The columns that earlier versions did not write, with their value in the existing rows.
+///
+/// # Returns
+///
+/// Whether the file was upgraded, or an error if it could not be rewritten or if its header cannot be upgraded to the
+/// current one, e.g. because it has columns the current header does not have.
+pub fn upgrade_header(path: &str, header: &[&str], defaults: &[(&str, &str)]) -> Result<bool> {
+    // Only the header is read when it is already the current one, logs can be several GB large.
+    let mut reader = BufReader::new(open_file(path, FileMode::Read)?);
+    let mut old_header: Vec<u8> = Vec::new();
+    reader
+        .read_until(b'\n', &mut old_header)
+        .with_context(|| format!("Could not read {path}"))?;
+    if old_header.pop() != Some(b'\n') {
+        return Ok(false);
+    }
+    let old_header: String = String::from_utf8_lossy(&old_header).to_string();
+    let old_columns: Vec<&str> = old_header.split(',').collect();
+    let columns: Vec<&str> = header.iter().flat_map(|h| h.split(',')).collect();
+    if old_columns == columns {
+        return Ok(false);
+    }
+
+    let unknown: Vec<&str> = old_columns
+        .iter()
+        .filter(|c| !columns.contains(c))
+        .copied()
+        .collect();
+    let missing: Vec<&str> = columns
+        .iter()
+        .filter(|c| !old_columns.contains(c) && !defaults.iter().any(|(d, _)| d == *c))
+        .copied()
+        .collect();
+    ensure!(
+        unknown.is_empty() && missing.is_empty(),
+        "The header of {path} cannot be upgraded to the current header, unexpected columns: [{}], missing columns: [{}]",
+        unknown.join(", "),
+        missing.join(", ")
+    );
+
+    // Every column is either copied from the existing row or filled with its default value.
+    let sources: Vec<Result<usize, &[u8]>> = columns
+        .iter()
+        .map(|c| match old_columns.iter().position(|o| o == c) {
+            Some(i) => Ok(i),
+            None => Err(defaults
+                .iter()
+                .find(|(d, _)| d == c)
+                .map(|(_, value)| value.as_bytes())
+                .unwrap_or_default()),
+        })
+        .collect();
+
+    // The rows are streamed to a temporary file, which then replaces the original one.
+    let temp_path: String = format!("{path}.temp");
+    let mut writer = BufWriter::new(open_file(&temp_path, FileMode::Overwrite)?);
+    writer.write_all(columns.join(",").as_bytes())?;
+    writer.write_all(b"\n")?;
+    let mut row: Vec<u8> = Vec::new();
+    let mut in_quotes: bool = false;
+    let mut row_number: usize = 1;
+    loop {
+        let read_start: usize = row.len();
+        if reader
+            .read_until(b'\n', &mut row)
+            .with_context(|| format!("Could not read {path}"))?
+            == 0
+        {
+            break;
+        }
+        // A newline inside a quoted field does not end the row.
+        let quotes: usize = row[read_start..].iter().filter(|b| **b == b'"').count();
+        in_quotes ^= quotes % 2 == 1;
+        if in_quotes || row.last() != Some(&b'\n') {
+            continue;
+        }
+        row_number += 1;
+        let fields: Vec<&[u8]> = split_row(&row[..row.len() - 1]);
+        ensure!(
+            fields.len() == old_columns.len(),
+            "Row {row_number} of {path} has {} fields, but its header has {} columns",
+            fields.len(),
+            old_columns.len()
+        );
+        let values: Vec<&[u8]> = sources
+            .iter()
+            .map(|source| match source {
+                Ok(i) => fields[*i],
+                Err(default) => default,
+            })
+            .collect();
+        writer.write_all(&values.join(&b','))?;
+        writer.write_all(b"\n")?;
+        row.clear();
+    }
+    // The partial row left by an interrupted run is removed when the file is opened for appending.
+    writer.write_all(&row)?;
+    writer
+        .flush()
+        .with_context(|| format!("Could not write {temp_path}"))?;
+    drop(writer);
+
+    std::fs::rename(&temp_path, path).with_context(|| format!("Could not overwrite {path}"))?;
+    Ok(true)
+}
+
+/// Splits a CSV row into its fields, as they are written in the file. Commas inside quoted fields do not split them.
+///
+/// # Arguments
+///
+/// * `row` - The row, without its final newline.
+pub fn split_row(row: &[u8]) -> Vec<&[u8]> {
+    let mut fields: Vec<&[u8]> = Vec::new();
+    let mut field_start: usize = 0;
+    let mut in_quotes: bool = false;
+    for (i, b) in row.iter().enumerate() {
+        match b {
+            b'"' => in_quotes = !in_quotes,
+            b',' if !in_quotes => {
+                fields.push(&row[field_start..i]);
+                field_start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    fields.push(&row[field_start..]);
+    fields
+}
+
+/// Removes the partial row at the end of a file, which was left by a process that died while writing it.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file.
+fn drop_partial_row(path: &str) -> Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .with_context(|| format!("Could not open {path}"))?;
+    let len: u64 = file.metadata()?.len();
+
+    // Searches the last newline from the end of the file.
+    let mut end: u64 = len;
+    let mut buf: Vec<u8> = vec![0; 8192];
+    while end > 0 {
+        let start: u64 = end.saturating_sub(buf.len() as u64);
+        let chunk: &mut [u8] = &mut buf[..(end - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(chunk)?;
+        if let Some(i) = chunk.iter().rposition(|b| *b == b'\n') {
+            end = start + i as u64 + 1;
+            break;
+        }
+        end = start;
+    }
+
+    if end < len {
+        warn!(
+            "Removing a partial row of {} bytes at the end of {path}",
+            len - end
+        );
+        file.set_len(end)?;
+    }
+    Ok(())
+}
+
+/// Strategy used to store fields containing commas, quotes or newlines in CSV rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Quoting {
+    /// Fields are enclosed in double quotes and inner quotes are doubled, as specified by RFC 4180.
+    #[default]
+    Rfc4180,
+    /// Commas and quotes are replaced by the `-was_comma-` and `-was_quote-` placeholders.
+    /// This is the format produced by earlier versions of the tool.
+    Placeholders,
+}
+
+impl Quoting {
+    /// Returns the quoting strategy selected on the command line.
+    ///
+    /// # Arguments
+    ///
+    /// * `legacy` - Whether the placeholder format was requested.
+    pub fn from_legacy_flag(legacy: bool) -> Self {
+        if legacy {
+            Quoting::Placeholders
+        } else {
+            Quoting::Rfc4180
+        }
+    }
+
+    /// Escapes a field so that it can be written as is in a CSV row.
+    ///
+    /// # Arguments
+    ///
+    /// * `field` - The content of the field.
+    ///
+    /// # Returns
+    ///
+    /// The escaped field.
+    pub fn escape(self, field: &str) -> String {
+        match self {
+            Quoting::Rfc4180 => {
+                if field.contains([',', '"', '\n', '\r']) {
+                    format!("\"{}\"", field.replace('"', "\"\""))
+                } else {
+                    field.to_string()
+                }
+            }
+            Quoting::Placeholders => field
+                .replace(",", "-was_comma-")
+                .replace("\"", "-was_quote-"),
+        }
+    }
+}
+
+/// Reverts the `-was_comma-` and `-was_quote-` placeholders of a field read from a CSV file.
+/// Fields written with RFC 4180 quoting are left unchanged, which allows reading both formats.
+///
+/// # Arguments
+///
+/// * `field` - The field as read from the CSV file.
+///
+/// # Returns
+///
+/// The original content of the field.
+pub fn revert_placeholders(field: &str) -> String {
+    field
+        .replace("-was_comma-", ",")
+        .replace("-was_quote-", "\"")
 }
 
 /// Cleans a string to be safely stored in a CSV file by removing quotes and replacing commas and newlines with spaces.
@@ -243,12 +584,121 @@ pub fn clean_string_to_csv(s: &str) -> String {
         .to_string()
 }
 
+/// Checks that an input file has the columns required by a command and that their values have the expected types,
+/// so that mistakes in the input are reported before the file is loaded.
+/// All the missing and mistyped columns are reported at once, together with the columns found in the file.
+///
+/// # Arguments
+///
+/// * `path` - The path to the input CSV file.
+/// * `required` - The columns the file must contain.
+/// * `schema` - The expected types of the columns, checked for the columns of the file. Empty values are accepted.
+///
+/// # Returns
+///
+/// An error describing every missing and mistyped column if the file does not match.
+pub fn validate_columns(path: &str, required: &[&str], schema: &Schema) -> Result<()> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .from_reader(open_reader(path)?);
+    let header: Vec<String> = reader
+        .headers()
+        .with_context(|| format!("Could not read the header of {path}"))?
+        .iter()
+        .map(|h| h.to_string())
+        .collect();
+    let missing: Vec<&str> = required
+        .iter()
+        .copied()
+        .filter(|column| !header.iter().any(|h| h == column))
+        .collect();
+
+    // The index in the file, the name and the type of the columns whose values are checked.
+    let mut checked: Vec<(usize, &str, &DataType)> = schema
+        .iter()
+        .filter(|(_, dtype)| **dtype != DataType::String)
+        .filter_map(|(name, dtype)| {
+            header
+                .iter()
+                .position(|h| h == name.as_str())
+                .map(|i| (i, name.as_str(), dtype))
+        })
+        .collect();
+    let mut mistyped: Vec<String> = Vec::new();
+    let mut record: ByteRecord = ByteRecord::new();
+    while !checked.is_empty()
+        && reader
+            .read_byte_record(&mut record)
+            .with_context(|| format!("Could not read {path}"))?
+    {
+        let line: u64 = record.position().map(|p| p.line()).unwrap_or_default();
+        checked.retain(|(i, name, dtype)| {
+            let value = String::from_utf8_lossy(record.get(*i).unwrap_or_default());
+            let valid: bool = parses_as(value.trim(), dtype);
+            if !valid {
+                mistyped.push(format!(
+                    "column '{name}' must contain {}, found '{value}' at line {line}",
+                    type_description(dtype)
+                ));
+            }
+            valid
+        });
+    }
+
+    if missing.is_empty() && mistyped.is_empty() {
+        return Ok(());
+    }
+    let mut message: String = format!("Invalid input file {path}:");
+    if !missing.is_empty() {
+        message.push_str(&format!("\n  missing columns: {}", missing.join(", ")));
+    }
+    for error in mistyped {
+        message.push_str(&format!("\n  {error}"));
+    }
+    message.push_str(&format!("\n  columns found: {}", header.join(", ")));
+    bail!(message)
+}
+
+/// Whether a value of a CSV file can be read with a type. Empty values are read as missing values of any type.
+fn parses_as(value: &str, dtype: &DataType) -> bool {
+    value.is_empty()
+        || match dtype {
+            DataType::UInt8 => value.parse::<u8>().is_ok(),
+            DataType::UInt16 => value.parse::<u16>().is_ok(),
+            DataType::UInt32 => value.parse::<u32>().is_ok(),
+            DataType::UInt64 => value.parse::<u64>().is_ok(),
+            DataType::Int8 => value.parse::<i8>().is_ok(),
+            DataType::Int16 => value.parse::<i16>().is_ok(),
+            DataType::Int32 => value.parse::<i32>().is_ok(),
+            DataType::Int64 => value.parse::<i64>().is_ok(),
+            DataType::Float32 | DataType::Float64 => value.parse::<f64>().is_ok(),
+            DataType::Boolean => {
+                value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false")
+            }
+            _ => true,
+        }
+}
+
+/// Describes the values of a type in the error messages of [`validate_columns`].
+fn type_description(dtype: &DataType) -> &'static str {
+    match dtype {
+        DataType::UInt8 | DataType::UInt16 | DataType::UInt32 | DataType::UInt64 => {
+            "non-negative integers"
+        }
+        DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Int64 => "integers",
+        DataType::Float32 | DataType::Float64 => "numbers",
+        DataType::Boolean => "booleans (true or false)",
+        _ => "values",
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
     use std::net::IpAddr;
 
     use anyhow::ensure;
+    use polars::prelude::Field;
 
     use super::*;
 
@@ -267,6 +717,56 @@ mod tests {
         delete_file("tests/data/non_existent.csv", false)
     }
 
+    #[test]
+    fn partial_rows_test() -> Result<()> {
+        let path = "tests/data/partial_rows.csv";
+
+        // A partial row left by an interrupted run is removed when the file is opened in append mode.
+        write_file(path, "id,name\n1,\"a\nb\"\n2,c")?;
+        let mut file = CSVFile::new(path, FileMode::Append)?;
+        assert_eq!(std::fs::read_to_string(path)?, "id,name\n1,\"a\nb\"\n");
+
+        // Rows reach the file as soon as they are complete, but not before, including rows with quoted newlines.
+        write!(file, "3,\"d\ne")?;
+        file.flush()?;
+        assert_eq!(std::fs::read_to_string(path)?, "id,name\n1,\"a\nb\"\n");
+        writeln!(file, "\"")?;
+        write!(file, "4,")?;
+        assert_eq!(
+            std::fs::read_to_string(path)?,
+            "id,name\n1,\"a\nb\"\n3,\"d\ne\"\n"
+        );
+
+        // The last row is terminated when the file is closed, so that it is kept by the next append.
+        write!(file, "f")?;
+        drop(file);
+        assert_eq!(
+            std::fs::read_to_string(path)?,
+            "id,name\n1,\"a\nb\"\n3,\"d\ne\"\n4,f\n"
+        );
+        CSVFile::new(path, FileMode::Append)?;
+        assert_eq!(
+            std::fs::read_to_string(path)?,
+            "id,name\n1,\"a\nb\"\n3,\"d\ne\"\n4,f\n"
+        );
+
+        // A last row ending inside a quoted field is dropped when the file is closed.
+        let mut file = CSVFile::new(path, FileMode::Append)?;
+        write!(file, "5,\"g")?;
+        drop(file);
+        assert_eq!(
+            std::fs::read_to_string(path)?,
+            "id,name\n1,\"a\nb\"\n3,\"d\ne\"\n4,f\n"
+        );
+
+        // A file without any complete row is emptied.
+        write_file(path, "id,na")?;
+        CSVFile::new(path, FileMode::Append)?.write_header(&["id", "name"])?;
+        assert_eq!(std::fs::read_to_string(path)?, "id,name\n");
+
+        delete_file(path, false)
+    }
+
     #[test]
     fn read_test() -> Result<()> {
         CSVFile::new("tests/data/small_file.csv", FileMode::Read)?.read()?;
@@ -314,6 +814,128 @@ mod tests {
         ensure!(file.column::<i8>(0).is_err());
         Ok(())
     }
+
+    #[test]
+    fn compressed_test() -> Result<()> {
+        let path = "target/tests/compressed.csv.zst";
+        write_compressed_file(path, "id,name\n1,\"a,b\"\n2,c\n")?;
+
+        let file = CSVFile::new(path, FileMode::Read)?;
+        assert_eq!(file.header()?, ["id", "name"]);
+        assert_eq!(file.column::<u32>(0)?, [1, 2]);
+        assert_eq!(
+            file.indexed_lines::<u32>(0)?,
+            HashMap::from([(1, "1,\"a,b\"".to_string()), (2, "2,c".to_string())])
+        );
+        validate_columns(
+            path,
+            &["id"],
+            &Schema::from_iter(vec![Field::new("id".into(), DataType::UInt32)]),
+        )?;
+        assert_eq!(open_csv(path, None, None)?.shape(), (2, 2));
+
+        delete_file(path, false)
+    }
+
+    #[test]
+    fn validate_columns_test() -> Result<()> {
+        let path = "target/tests/validate_columns.csv";
+        write_file(
+            path,
+            "id,name,stars,fork\n1,a,3,false\n2,\"b,c\",,true\n3,d,many,maybe\n",
+        )?;
+        let schema = Schema::from_iter(vec![
+            Field::new("id".into(), DataType::UInt32),
+            Field::new("stars".into(), DataType::UInt32),
+            Field::new("fork".into(), DataType::Boolean),
+            Field::new("created".into(), DataType::UInt64),
+        ]);
+
+        validate_columns(path, &["id", "name"], &Schema::default())?;
+        let error = validate_columns(path, &["id", "latest_commit", "created"], &schema)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            error,
+            format!(
+                "Invalid input file {path}:\n  \
+                 missing columns: latest_commit, created\n  \
+                 column 'stars' must contain non-negative integers, found 'many' at line 4\n  \
+                 column 'fork' must contain booleans (true or false), found 'maybe' at line 4\n  \
+                 columns found: id, name, stars, fork"
+            )
+        );
+        delete_file(path, false)
+    }
+
+    #[test]
+    fn quoting_test() {
+        for quoting in [Quoting::Rfc4180, Quoting::Placeholders] {
+            assert_eq!(quoting.escape("src/main.rs"), "src/main.rs");
+        }
+        assert_eq!(Quoting::Rfc4180.escape("a,b"), "\"a,b\"");
+        assert_eq!(Quoting::Rfc4180.escape("a\"b"), "\"a\"\"b\"");
+        assert_eq!(Quoting::Rfc4180.escape("a\nb"), "\"a\nb\"");
+        assert_eq!(
+            Quoting::Placeholders.escape("a,\"b"),
+            "a-was_comma--was_quote-b"
+        );
+        assert_eq!(revert_placeholders("a-was_comma--was_quote-b"), "a,\"b");
+        assert_eq!(revert_placeholders("a,b"), "a,b");
+    }
+
+    #[test]
+    fn add_missing_column_test() -> Result<()> {
+        let path: &str = "tests/data/missing_column.csv";
+        write_file(path, "id,path\n1,a\n2,\"b\nc\"\n")?;
+        assert!(!CSVFile::new(path, FileMode::Read)?.has_column("collected_at")?);
+
+        assert!(add_missing_column(path, "collected_at", "0")?);
+        assert_eq!(
+            std::fs::read_to_string(path)?,
+            "id,path,collected_at\n1,a,0\n2,\"b\nc\",0\n"
+        );
+        assert!(CSVFile::new(path, FileMode::Read)?.has_column("collected_at")?);
+
+        // Files which already have the column are left unchanged.
+        assert!(!add_missing_column(path, "collected_at", "0")?);
+        assert_eq!(
+            CSVFile::new(path, FileMode::Read)?.column::<u32>(2)?,
+            vec![0, 0]
+        );
+        delete_file(path, false)
+    }
+
+    #[test]
+    fn upgrade_header_test() -> Result<()> {
+        let path: &str = "target/tests/upgrade_header.csv";
+        let header: [&str; 4] = ["id", "path,name", "is_test", "status"];
+        let defaults: [(&str, &str); 2] = [("is_test", ""), ("status", "ok")];
+        write_file(path, "id,path,name\n1,a,\"b,\nc\"\n2,d,e\n3,f")?;
+
+        // Missing columns are inserted at their position, the partial row is left for the next append.
+        assert!(upgrade_header(path, &header, &defaults)?);
+        assert_eq!(
+            std::fs::read_to_string(path)?,
+            "id,path,name,is_test,status\n1,a,\"b,\nc\",,ok\n2,d,e,,ok\n3,f"
+        );
+        assert!(!upgrade_header(path, &header, &defaults)?);
+
+        // Files written with other columns are not upgraded.
+        assert!(upgrade_header(path, &["id", "path", "status"], &defaults).is_err());
+        assert!(upgrade_header(path, &[&header[..], &["size"]].concat(), &defaults).is_err());
+
+        // Rows of the wrong width are reported.
+        write_file(path, "id,path\n1,a,b\n")?;
+        assert!(upgrade_header(
+            path,
+            &header,
+            &[("name", ""), ("is_test", ""), ("status", "")]
+        )
+        .is_err());
+        delete_file(path, false)
+    }
+
     #[test]
     fn indexed_lines_test() -> Result<()> {
         let file = CSVFile::new("tests/data/small_file.csv", FileMode::Read)?;
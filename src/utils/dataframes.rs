@@ -14,7 +14,8 @@
 
 //! Utility functions for working with DataFrames.
 
-use anyhow::{Context, Result};
+use anyhow::{bail, ensure, Context, Result};
+use clap::{Arg, ArgMatches};
 use polars::frame::DataFrame;
 
 /// Extracts a column of 32 bits integers from a DataFrame and returns it as a vector. The column must not contain null values.
@@ -47,6 +48,23 @@ pub fn u32(df: &DataFrame, column: &str) -> Result<Vec<u32>> {
     })?;
     Ok(u32_col.into_no_null_iter().collect())
 }
+
+/// Extracts a column of booleans from a DataFrame and returns it as a vector. The column must not contain null values.
+///
+/// # Arguments
+/// * `df` - The DataFrame containing the column.
+/// * `column` - The name of the column to extract.
+///
+/// # Returns
+/// A vector containing the values of the column, or an error if the column does not exist, cannot be converted to booleans, or contains null values.
+pub fn bool(df: &DataFrame, column: &str) -> Result<Vec<bool>> {
+    let bool_col = df
+        .column(column)?
+        .bool()
+        .with_context(|| format!("Could not convert column {column} to booleans"))?;
+    Ok(bool_col.into_no_null_iter().collect())
+}
+
 /// Extracts a column of strings from a DataFrame and returns it as a vector.
 ///
 /// # Arguments
@@ -84,3 +102,125 @@ pub fn has_columns<'a>(df: &DataFrame, columns: impl IntoIterator<Item = &'a str
 pub fn has_column(df: &DataFrame, column: &str) -> bool {
     has_columns(df, [column])
 }
+
+/// Columns written to the output file of a subcommand, chosen with the arguments defined by [`output_column_args`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OutputColumns {
+    /// Names of the columns kept, in order. All the columns are kept if empty.
+    pub select: Vec<String>,
+    /// Pairs of old and new names of the renamed columns.
+    pub rename: Vec<(String, String)>,
+}
+
+impl OutputColumns {
+    /// Reads the selected and renamed columns from the arguments defined by [`output_column_args`].
+    ///
+    /// # Arguments
+    ///
+    /// * `args` - The arguments of the subcommand.
+    pub fn from_args(args: &ArgMatches) -> Result<Self> {
+        let select: Vec<String> = args
+            .get_many::<String>("select")
+            .unwrap_or_default()
+            .cloned()
+            .collect();
+        let rename: Vec<(String, String)> = args
+            .get_many::<String>("rename")
+            .unwrap_or_default()
+            .map(|pair| match pair.split_once('=') {
+                Some((old, new)) if !old.is_empty() && !new.is_empty() => {
+                    Ok((old.to_string(), new.to_string()))
+                }
+                _ => bail!("Invalid column renaming '{pair}', expected OLD=NEW"),
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { select, rename })
+    }
+
+    /// Keeps the selected columns of a DataFrame, then renames its columns.
+    ///
+    /// # Arguments
+    /// * `df` - The DataFrame to write to the output file.
+    ///
+    /// # Returns
+    /// The DataFrame with the selected and renamed columns, or an error if one of the columns does not exist.
+    pub fn apply(&self, df: DataFrame) -> Result<DataFrame> {
+        let mut df: DataFrame = if self.select.is_empty() {
+            df
+        } else {
+            for column in &self.select {
+                ensure!(
+                    has_column(&df, column),
+                    "Cannot select column '{column}': the output does not contain it."
+                );
+            }
+            df.select(&self.select)?
+        };
+        for (old, new) in &self.rename {
+            ensure!(
+                has_column(&df, old),
+                "Cannot rename column '{old}': the output does not contain it."
+            );
+            df.rename(old, new.as_str().into())?;
+        }
+        Ok(df)
+    }
+}
+
+/// Arguments selecting and renaming the columns of the output file, shared by the filter and duplicate subcommands.
+pub fn output_column_args() -> Vec<Arg> {
+    vec![
+        Arg::new("select")
+            .long("select")
+            .value_name("COLUMNS")
+            .help("Comma-separated list of the columns written to the output file, in order. By default, all the columns are written.")
+            .value_delimiter(','),
+        Arg::new("rename")
+            .long("rename")
+            .value_name("OLD=NEW")
+            .help("Comma-separated list of columns of the output file to rename, applied after --select.")
+            .value_delimiter(','),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polars::df;
+
+    #[test]
+    fn output_columns_test() -> Result<()> {
+        let df: DataFrame = df!("id" => [1u32, 2], "name" => ["a", "b"], "size" => [10u64, 20])?;
+        let args = |argv: &[&str]| {
+            clap::Command::new("test")
+                .args(output_column_args())
+                .try_get_matches_from(std::iter::once("test").chain(argv.iter().copied()))
+        };
+
+        assert_eq!(OutputColumns::default().apply(df.clone())?, df);
+
+        let columns: OutputColumns = OutputColumns::from_args(&args(&[
+            "--select",
+            "name,id",
+            "--rename",
+            "id=project_id",
+        ])?)?;
+        assert_eq!(
+            columns.apply(df.clone())?,
+            df!("name" => ["a", "b"], "project_id" => [1u32, 2])?
+        );
+
+        for argv in [&["--rename", "id"][..], &["--rename", "id="][..]] {
+            assert!(OutputColumns::from_args(&args(argv)?).is_err());
+        }
+        for argv in [
+            &["--select", "id,missing"][..],
+            &["--select", "id", "--rename", "name=n"][..],
+        ] {
+            assert!(OutputColumns::from_args(&args(argv)?)?
+                .apply(df.clone())
+                .is_err());
+        }
+        Ok(())
+    }
+}
@@ -15,14 +15,17 @@
 //! Utility functions for file operations and I/O.
 
 use anyhow::{bail, Context, Error, Result};
+use memmap2::Mmap;
 use pathdiff::diff_paths;
 use polars::io::SerWriter;
 use polars::prelude::{CsvReadOptions, CsvWriter, Schema};
 use polars::{frame::DataFrame, io::SerReader};
+use sysinfo::Disks;
 use walkdir::WalkDir;
 
 use std::fs;
-use std::io::BufWriter;
+use std::io::{BufWriter, Cursor, Read};
+use std::ops::Deref;
 use std::path::{Component, PathBuf};
 use std::sync::Arc;
 use std::{
@@ -104,6 +107,8 @@ pub fn load_file(
     let file_size = metadata.len();
     if file_size > memory_limit {
         Ok(Err(file_size))
+    } else if is_compressed(&path) {
+        decompress_file(&path, memory_limit)
     } else {
         std::fs::read(&path)
             .map(Ok)
@@ -111,6 +116,129 @@ pub fn load_file(
     }
 }
 
+/// Content of a file read by [`map_file`].
+pub enum FileContent {
+    /// A file mapped into memory.
+    Mapped(Mmap),
+    /// A compressed file, decompressed into memory.
+    Decompressed(Vec<u8>),
+}
+
+impl Deref for FileContent {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            FileContent::Mapped(mmap) => mmap,
+            FileContent::Decompressed(content) => content,
+        }
+    }
+}
+
+/// Maps a file into memory if its size is less than a given limit.
+/// Unlike [`load_file`], the content of the file is not copied: pages are read on demand by the operating system,
+/// which avoids large allocations when the content is only scanned, e.g. to hash it or to match regular expressions.
+/// Compressed files (see [`is_compressed`]) cannot be mapped and are decompressed into memory instead.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file.
+/// * `memory_limit` - The maximum size of the file in bytes.
+///
+/// # Returns
+///
+/// A read-only view of the content of the file or an error if the file could not be read.
+/// Two kinds of errors are possible:
+/// * If the file size exceeds the memory limit, returns the size of the file.
+/// * If the file could not be mapped, returns an error.
+pub fn map_file(
+    path: impl AsRef<Path>,
+    memory_limit: u64,
+) -> Result<core::result::Result<FileContent, u64>> {
+    if is_compressed(&path) {
+        return Ok(decompress_file(&path, memory_limit)?.map(FileContent::Decompressed));
+    }
+    let file: File = open_file(&path, FileMode::Read)?;
+    let file_size: u64 = file
+        .metadata()
+        .with_context(|| {
+            format!(
+                "Could not fetch metadata for file {}",
+                &path.as_ref().display()
+            )
+        })?
+        .len();
+    if file_size > memory_limit {
+        Ok(Err(file_size))
+    } else {
+        // SAFETY: the files of the corpus are not modified while they are analysed.
+        // A file truncated by another process during the analysis would make reads fail with SIGBUS.
+        unsafe { Mmap::map(&file) }
+            .map(|mmap| Ok(FileContent::Mapped(mmap)))
+            .with_context(|| format!("Could not map file {}", &path.as_ref().display()))
+    }
+}
+
+/// Checks whether a file is compressed with zstd, i.e. whether its name ends with the .zst extension.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file.
+pub fn is_compressed(path: impl AsRef<Path>) -> bool {
+    path.as_ref().extension().is_some_and(|ext| ext == "zst")
+}
+
+/// Opens a file for reading, decompressing it on the fly if it is compressed with zstd.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file.
+///
+/// # Returns
+///
+/// A reader on the content of the file, or an error if the file could not be opened.
+pub fn open_reader(path: impl AsRef<Path>) -> Result<Box<dyn Read>> {
+    let file: File = open_file(&path, FileMode::Read)?;
+    if is_compressed(&path) {
+        Ok(Box::new(zstd::Decoder::new(file).with_context(|| {
+            format!("Could not decompress file {}", path.as_ref().display())
+        })?))
+    } else {
+        Ok(Box::new(file))
+    }
+}
+
+/// Decompresses a file compressed with zstd if its decompressed size is less than a given limit.
+///
+/// # Arguments
+///
+/// * `path` - The path to the compressed file.
+/// * `memory_limit` - The maximum size of the decompressed content in bytes.
+///
+/// # Returns
+///
+/// The decompressed content of the file, the decompressed size of the file if it exceeds the memory limit,
+/// or an error if the file could not be read or decompressed.
+fn decompress_file(
+    path: impl AsRef<Path>,
+    memory_limit: u64,
+) -> Result<core::result::Result<Vec<u8>, u64>> {
+    let mut content: Vec<u8> = Vec::new();
+    zstd::Decoder::new(open_file(&path, FileMode::Read)?)?
+        .take(memory_limit + 1)
+        .read_to_end(&mut content)
+        .with_context(|| format!("Could not decompress file {}", &path.as_ref().display()))?;
+    if content.len() as u64 > memory_limit {
+        let compressed: Vec<u8> = std::fs::read(&path)?;
+        Ok(Err(zstd::zstd_safe::get_frame_content_size(&compressed)
+            .ok()
+            .flatten()
+            .unwrap_or(content.len() as u64)))
+    } else {
+        Ok(Ok(content))
+    }
+}
+
 /// Returns an iterator on the lines of a file.
 ///
 /// # Arguments
@@ -161,6 +289,27 @@ pub fn create_dir(path: impl AsRef<Path>) -> Result<(), Error> {
     }
 }
 
+/// Returns the space available on the file system containing a directory.
+///
+/// # Arguments
+///
+/// * `path` - The path to the directory, which must exist.
+///
+/// # Returns
+///
+/// The number of bytes available, as reported for the disk mounted at the longest prefix of the path.
+pub fn available_space(path: impl AsRef<Path>) -> Result<u64> {
+    let path: PathBuf = fs::canonicalize(&path)
+        .with_context(|| format!("Could not resolve path {}", path.as_ref().display()))?;
+    Disks::new_with_refreshed_list()
+        .list()
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().components().count())
+        .map(|disk| disk.available_space())
+        .with_context(|| format!("Could not find the file system of {}", path.display()))
+}
+
 /// Deletes a directory.
 ///
 /// # Arguments
@@ -221,7 +370,38 @@ pub fn write_file(path: impl AsRef<Path>, content: impl AsRef<[u8]>) -> Result<(
     Ok(())
 }
 
-/// Reads a CSV file into a DataFrame.
+/// Writes content to a file compressed with zstd, creating the parent directories if necessary.
+///
+/// # Arguments
+/// * `path` - The path to the file, which should have the .zst extension to be decompressed when read.
+/// * `content` - The content to compress and write to the file.
+///
+/// # Returns
+/// An error if the content could not be compressed or the file could not be written.
+pub fn write_compressed_file(path: impl AsRef<Path>, content: impl AsRef<[u8]>) -> Result<()> {
+    write_file(
+        &path,
+        zstd::bulk::compress(content.as_ref(), zstd::DEFAULT_COMPRESSION_LEVEL)
+            .with_context(|| format!("Could not compress file {}", &path.as_ref().display()))?,
+    )
+}
+
+/// Computes the size and the blake3 hash of a file without loading it entirely in memory.
+///
+/// # Arguments
+/// * `path` - The path to the file.
+///
+/// # Returns
+/// The size of the file in bytes and its hash in hexadecimal.
+pub fn hash_file(path: &str) -> Result<(u64, String)> {
+    let mut hasher = blake3::Hasher::new();
+    hasher
+        .update_reader(open_file(path, FileMode::Read)?)
+        .with_context(|| format!("Could not read file {path}"))?;
+    Ok((hasher.count(), hasher.finalize().to_hex().to_string()))
+}
+
+/// Reads a CSV file into a DataFrame. Files with the .zst extension are decompressed.
 ///
 /// # Arguments
 /// * `path` - The path to the CSV file.
@@ -237,15 +417,27 @@ pub fn open_csv(
     schema: Option<Schema>,
     columns: Option<Vec<&str>>,
 ) -> Result<DataFrame, Error> {
-    CsvReadOptions::default()
+    let options: CsvReadOptions = CsvReadOptions::default()
         .with_columns(
             columns.map(|cols| Arc::from(cols.into_iter().map(|s| s.into()).collect::<Vec<_>>())),
         )
         .with_schema_overwrite(schema.map(Arc::new))
-        .with_has_header(true)
-        .into_reader_with_file_handle(BufReader::new(open_file(path, FileMode::Read)?))
-        .finish()
-        .with_context(|| format!("Could not read {path}"))
+        .with_has_header(true);
+    // Files compressed with zstd are decompressed in memory, since the reader needs to seek in the content.
+    if is_compressed(path) {
+        let mut content: Vec<u8> = Vec::new();
+        open_reader(path)?
+            .read_to_end(&mut content)
+            .with_context(|| format!("Could not decompress file {path}"))?;
+        options
+            .into_reader_with_file_handle(Cursor::new(content))
+            .finish()
+    } else {
+        options
+            .into_reader_with_file_handle(BufReader::new(open_file(path, FileMode::Read)?))
+            .finish()
+    }
+    .with_context(|| format!("Could not read {path}"))
 }
 
 /// Writes a DataFrame to a CSV file.
@@ -282,6 +474,45 @@ pub fn delete_empty_dirs(path: impl AsRef<Path>) -> Result<()> {
     Ok(())
 }
 
+/// Extracts a zip archive into a directory, skipping the entries that are unsafe to extract.
+/// An entry is unsafe if it is a symbolic link, or if its path is absolute or escapes the destination
+/// directory (e.g. `../../.bashrc`). Archives of untrusted repositories may contain such entries.
+///
+/// # Arguments
+///
+/// * `archive_path` - The path to the zip archive.
+/// * `dest` - The directory to extract the archive into. It is created if it does not exist.
+///
+/// # Returns
+///
+/// The names of the entries that were skipped, or an error if the archive could not be read or extracted.
+pub fn extract_zip(archive_path: impl AsRef<Path>, dest: impl AsRef<Path>) -> Result<Vec<String>> {
+    let mut archive = zip::ZipArchive::new(open_file(&archive_path, FileMode::Read)?)
+        .with_context(|| format!("Invalid zip archive {}", archive_path.as_ref().display()))?;
+    create_dir(&dest)?;
+
+    let mut skipped: Vec<String> = Vec::new();
+    for idx in 0..archive.len() {
+        let mut entry = archive.by_index(idx)?;
+        // enclosed_name returns None for absolute paths and paths escaping the destination directory
+        let relative_path: PathBuf = match entry.enclosed_name() {
+            Some(path) if !entry.is_symlink() => path,
+            _ => {
+                skipped.push(entry.name().to_string());
+                continue;
+            }
+        };
+        let path: PathBuf = dest.as_ref().join(relative_path);
+        if entry.is_dir() {
+            create_dir(&path)?;
+        } else {
+            std::io::copy(&mut entry, &mut open_file(&path, FileMode::Overwrite)?)
+                .with_context(|| format!("Could not extract {}", path.display()))?;
+        }
+    }
+    Ok(skipped)
+}
+
 /// Returns a list of files with a given extension in a directory and its subdirectories,
 /// sorted by their proximity to a pivot file.
 /// The proximity is defined as the number of directory levels to go up from the pivot file
@@ -409,6 +640,32 @@ mod io_tests {
         Ok(())
     }
 
+    #[test]
+    fn map_file_test() -> Result<()> {
+        let path = "tests/data/small_file.csv";
+        let mapped = map_file(path, 1024)?.expect("File is smaller than the limit");
+        assert_eq!(&mapped[..], &std::fs::read(path)?[..]);
+        assert_eq!(map_file(path, 10)?.err(), Some(mapped.len() as u64));
+
+        assert!(map_file("tests/data/empty.csv", 1024)?.unwrap().is_empty());
+        ensure!(map_file("tests/data/non_existent_file.txt", 1024).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn compressed_file_test() -> Result<()> {
+        let path = "target/tests/compressed_file.txt.zst";
+        let content = std::fs::read("tests/data/small_file.csv")?;
+        write_compressed_file(path, &content)?;
+        ensure!(is_compressed(path) && !is_compressed("tests/data/small_file.csv"));
+        ensure!(std::fs::read(path)? != content);
+
+        assert_eq!(load_file(path, 1024)?.unwrap(), content);
+        assert_eq!(&map_file(path, 1024)?.unwrap()[..], &content[..]);
+        assert_eq!(map_file(path, 10)?.err(), Some(content.len() as u64));
+        delete_file(path, false)
+    }
+
     #[test]
     fn create_delete_dir_test() -> Result<()> {
         let test_dir = "tests";
@@ -427,6 +684,13 @@ mod io_tests {
         Ok(())
     }
 
+    #[test]
+    fn available_space_test() -> Result<()> {
+        assert!(available_space("tests/data")? > 0);
+        assert!(available_space("tests/data/non_existent_dir").is_err());
+        Ok(())
+    }
+
     #[test]
     fn create_delete_file_test() -> Result<()> {
         let test_file = "tests/new_file.txt";
@@ -446,6 +710,46 @@ mod io_tests {
         Ok(())
     }
 
+    #[test]
+    fn extract_zip_test() -> Result<()> {
+        let archive_path = "tests/data/unsafe_archive.zip";
+        let dest = "tests/data/unsafe_archive";
+        delete_dir(dest, true)?;
+
+        {
+            let mut writer = zip::ZipWriter::new(open_file(archive_path, FileMode::Overwrite)?);
+            let options = zip::write::SimpleFileOptions::default();
+            writer.add_directory("project/src/", options)?;
+            writer.start_file("project/src/main.c", options)?;
+            write!(writer, "int main(void) {{ return 0; }}")?;
+            writer.start_file("project/../../escaped.txt", options)?;
+            write!(writer, "escaped")?;
+            writer.start_file("/tmp/absolute.txt", options)?;
+            write!(writer, "absolute")?;
+            writer.add_symlink("project/passwd", "/etc/passwd", options)?;
+            writer.finish()?;
+        }
+
+        let skipped: Vec<String> = extract_zip(archive_path, dest)?;
+        assert_eq!(
+            skipped,
+            vec![
+                "project/../../escaped.txt",
+                "/tmp/absolute.txt",
+                "project/passwd"
+            ]
+        );
+        assert_eq!(
+            std::fs::read_to_string(format!("{dest}/project/src/main.c"))?,
+            "int main(void) { return 0; }"
+        );
+        ensure!(!Path::new("tests/escaped.txt").exists());
+        ensure!(!Path::new(&format!("{dest}/project/passwd")).exists());
+
+        delete_dir(dest, false)?;
+        delete_file(archive_path, false)
+    }
+
     #[test]
     fn write_file_test() -> Result<()> {
         let path = "tests/data/abc.txt";
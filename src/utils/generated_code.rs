@@ -0,0 +1,172 @@
+// Copyright 2026 Andrea Gilot
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Heuristic detection of generated code, such as parsers, protocol buffers or minified code, so that generated files
+//! can be labelled or excluded.
+//!
+//! A file is considered likely generated if:
+//! * its first 2 KiB contain a marker of generated code (`DO NOT EDIT`, `generated by`, `auto-generated`, `@generated`, ...),
+//! * one of its lines is longer than 1000 bytes, as in minified code,
+//! * or it has at least 256 identifiers whose entropy is lower than 3 bits, i.e. it repeats a handful of identifiers
+//!   over and over, as in the tables of generated parsers.
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use regex::bytes::Regex;
+
+/// Number of bytes at the beginning of a file in which the markers of generated code are looked for.
+const MARKER_BYTES: usize = 2048;
+
+/// Length in bytes above which a line is considered minified.
+const MAX_LINE_LENGTH: usize = 1000;
+
+/// Number of identifiers from which the entropy of the identifiers of a file is considered meaningful.
+const MIN_IDENTIFIERS: usize = 256;
+
+/// Entropy in bits of the identifiers of a file below which it is considered generated.
+const MIN_ENTROPY: f64 = 3.0;
+
+/// Comments marking generated files.
+static GENERATED_MARKER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)(do not edit|generated by|auto-?generated|automatically generated|@generated|code generated)",
+    )
+    .unwrap()
+});
+
+/// Identifiers of the common programming languages.
+static IDENTIFIER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b[A-Za-z_][A-Za-z0-9_]*\b").unwrap());
+
+/// Detects generated code in a file read at once or line by line.
+#[derive(Debug, Default)]
+pub struct GeneratedCodeDetector {
+    /// Whether a marker of generated code was found.
+    marker: bool,
+    /// Whether a line longer than the maximal length was found.
+    long_line: bool,
+    /// Number of occurrences of every identifier.
+    identifiers: HashMap<Vec<u8>, usize>,
+    /// Number of bytes of the file read so far.
+    read: usize,
+}
+
+impl GeneratedCodeDetector {
+    /// Creates a detector that has not read anything yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads the next part of a file, which is either the whole file or one of its lines without its newline.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The part of the file following the parts read so far.
+    pub fn read(&mut self, text: &[u8]) {
+        if self.read < MARKER_BYTES && !self.marker {
+            let head: &[u8] = &text[..text.len().min(MARKER_BYTES - self.read)];
+            self.marker = GENERATED_MARKER.is_match(head);
+        }
+        self.long_line |= text
+            .split(|b| *b == b'\n')
+            .any(|line| line.len() > MAX_LINE_LENGTH);
+        for identifier in IDENTIFIER.find_iter(text) {
+            match self.identifiers.get_mut(identifier.as_bytes()) {
+                Some(count) => *count += 1,
+                None => {
+                    self.identifiers.insert(identifier.as_bytes().to_vec(), 1);
+                }
+            }
+        }
+        self.read += text.len() + 1;
+    }
+
+    /// Shannon entropy in bits of the identifiers read so far, 0 if there is none.
+    pub fn identifier_entropy(&self) -> f64 {
+        let total: f64 = self.identifiers.values().sum::<usize>() as f64;
+        self.identifiers
+            .values()
+            .map(|count| {
+                let p: f64 = *count as f64 / total;
+                -p * p.log2()
+            })
+            .sum()
+    }
+
+    /// Checks whether the file read so far is likely generated.
+    pub fn is_generated(&self) -> bool {
+        self.marker
+            || self.long_line
+            || (self.identifiers.values().sum::<usize>() >= MIN_IDENTIFIERS
+                && self.identifier_entropy() < MIN_ENTROPY)
+    }
+}
+
+/// Checks whether the content of a file is likely generated.
+///
+/// # Arguments
+///
+/// * `content` - The content of the file.
+pub fn is_generated(content: &[u8]) -> bool {
+    let mut detector: GeneratedCodeDetector = GeneratedCodeDetector::new();
+    detector.read(content);
+    detector.is_generated()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated() {
+        assert!(is_generated(
+            b"// Code generated by protoc-gen-go. DO NOT EDIT.\npackage pb\n"
+        ));
+        assert!(is_generated(b"/* This file is auto-generated */\nint x;\n"));
+        assert!(is_generated(
+            format!("var a={};\n", "1,".repeat(MAX_LINE_LENGTH)).as_bytes()
+        ));
+        assert!(is_generated("yy_state, yy_next,\n".repeat(200).as_bytes()));
+
+        assert!(!is_generated(
+            b"#include <stdio.h>\n\nint main(void) {\n    printf(\"Hello\\n\");\n    return 0;\n}\n"
+        ));
+        // Markers are only looked for at the beginning of the files.
+        assert!(!is_generated(
+            format!("{}// generated by hand\n", declarations(400)).as_bytes()
+        ));
+    }
+
+    /// Declarations of distinct variables, on one line each.
+    fn declarations(n: usize) -> String {
+        (0..n).map(|i| format!("int x{i};\n")).collect()
+    }
+
+    #[test]
+    fn test_lines() {
+        // Reading the lines of a file gives the same result as reading it at once.
+        let content: String = format!("{}/* Generated by Bison */\n", declarations(100));
+        let mut detector: GeneratedCodeDetector = GeneratedCodeDetector::new();
+        for line in content.as_bytes().split(|b| *b == b'\n') {
+            detector.read(line);
+        }
+        assert_eq!(detector.is_generated(), is_generated(content.as_bytes()));
+        assert!(detector.is_generated());
+
+        let mut detector: GeneratedCodeDetector = GeneratedCodeDetector::new();
+        detector.read(b"a b a b");
+        assert_eq!(detector.identifier_entropy(), 1.0);
+    }
+}
@@ -4,6 +4,7 @@
 
 #![allow(clippy::all)]
 
+use crate::utils::github_app::{Auth, GithubApp};
 use curl::easy::*;
 use std::str;
 use std::sync::*;
@@ -19,9 +20,58 @@ impl Github {
         }
     }
 
+    /** Creates a client authenticating with personal access tokens or as a GitHub App, or sending unauthenticated requests.
+     */
+    pub fn from_auth(auth: &Auth) -> Github {
+        match auth {
+            Auth::Tokens(tokens) => Github::new(tokens),
+            Auth::App(app) => Github {
+                tokens: Mutex::new(TokensManager {
+                    tokens: Vec::new(),
+                    current: 0,
+                    app: Some(app.clone()),
+                }),
+            },
+            // A single empty token, with which the requests are sent without Authorization header.
+            Auth::Anonymous => Github {
+                tokens: Mutex::new(TokensManager {
+                    tokens: vec![String::new()],
+                    current: 0,
+                    app: None,
+                }),
+            },
+        }
+    }
+
     /** Performs a github request of the specified url and returns the result string.  
      */
     pub fn request(&self, url: &str) -> Result<json::JsonValue, std::io::Error> {
+        self.request_with_headers(url).map(|(value, _)| value)
+    }
+
+    /** Performs a github request of a page of a paginated endpoint and returns the result and the url of the next page, taken from the Link header of the response.
+       The url is None on the last page.
+    */
+    pub fn request_page(
+        &self,
+        url: &str,
+    ) -> Result<(json::JsonValue, Option<String>), std::io::Error> {
+        self.request_with_headers(url)
+            .map(|(value, headers)| (value, next_link(&headers)))
+    }
+
+    /** Counts the items of a paginated endpoint by requesting its first page with a single item per page.
+       The count is the number of the last page given by the Link header, or the number of items of the first page if it is the only one.
+    */
+    pub fn request_count(&self, url: &str) -> Result<u64, std::io::Error> {
+        let separator = if url.contains('?') { '&' } else { '?' };
+        self.request_with_headers(&format!("{url}{separator}per_page=1"))
+            .map(|(value, headers)| last_page(&headers).unwrap_or(value.len() as u64))
+    }
+
+    /** Performs a github request of the specified url and returns the result and the headers of the response.
+     */
+    fn request_with_headers(&self, url: &str) -> Result<(json::JsonValue, String), std::io::Error> {
         let mut attempts = 0;
         let max_attempts = self.tokens.lock().unwrap().len();
         loop {
@@ -32,10 +82,12 @@ impl Github {
             conn.follow_location(true)?;
             let mut headers = List::new();
             headers.append("User-Agent: dcd").unwrap();
-            let token = self.tokens.lock().unwrap().get_token();
-            headers
-                .append(&format!("Authorization: token {}", token.0))
-                .unwrap();
+            let token = self.tokens.lock().unwrap().get_token()?;
+            if !token.0.is_empty() {
+                headers
+                    .append(&format!("Authorization: token {}", token.0))
+                    .unwrap();
+            }
             conn.http_headers(headers)?;
             {
                 let mut ct = conn.transfer();
@@ -57,7 +109,12 @@ impl Github {
             {
                 let result = json::parse(&to_string(&response));
                 match result {
-                    Ok(value) => return Ok(value),
+                    Ok(value) => {
+                        return Ok((
+                            value,
+                            String::from_utf8_lossy(&response_headers).into_owned(),
+                        ))
+                    }
                     Err(_) => {
                         return Err(std::io::Error::new(
                             std::io::ErrorKind::Other,
@@ -107,9 +164,64 @@ impl Github {
     }
 }
 
+/** Returns the url of the next page given by the Link header of a response, if any.
+   When the request was redirected, the headers of the last response are used.
+*/
+pub fn next_link(headers: &str) -> Option<String> {
+    link(headers, "next")
+}
+
+/** Returns the number of the last page given by the Link header of a response, if any.
+   With one item per page, it is the number of items of a paginated endpoint.
+*/
+pub fn last_page(headers: &str) -> Option<u64> {
+    let url = link(headers, "last")?;
+    let (_, query) = url.split_once('?')?;
+    query
+        .split('&')
+        .find_map(|param| param.strip_prefix("page="))
+        .and_then(|page| page.parse().ok())
+}
+
+/** Returns the url of a relation of the Link header of a response, e.g. the next page, if any.
+   When the request was redirected, the headers of the last response are used.
+*/
+fn link(headers: &str, rel: &str) -> Option<String> {
+    let link = headers
+        .lines()
+        .filter_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            if name.trim().eq_ignore_ascii_case("link") {
+                Some(value)
+            } else {
+                None
+            }
+        })
+        .last()?;
+    let rel = format!("rel=\"{rel}\"");
+    link.split(',').find_map(|part| {
+        let (url, params) = part.split_once(';')?;
+        if params
+            .split(';')
+            .any(|param| param.trim().eq_ignore_ascii_case(&rel))
+        {
+            Some(
+                url.trim()
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_string(),
+            )
+        } else {
+            None
+        }
+    })
+}
+
 struct TokensManager {
     tokens: Vec<String>,
     current: usize,
+    /// When set, installation tokens of the app are used instead of the tokens.
+    app: Option<Arc<GithubApp>>,
 }
 
 impl TokensManager {
@@ -124,17 +236,31 @@ impl TokensManager {
         for x in reader.records() {
             tokens.push(String::from(&x.unwrap()[0]));
         }
-        TokensManager { tokens, current: 0 }
+        TokensManager {
+            tokens,
+            current: 0,
+            app: None,
+        }
     }
 
     fn len(&self) -> usize {
-        self.tokens.len()
+        if self.app.is_some() {
+            1
+        } else {
+            self.tokens.len()
+        }
     }
 
     /** Returns a possibly valid token that should be used for the request and its id.
      */
-    fn get_token(&mut self) -> (String, usize) {
-        (self.tokens[self.current].clone(), self.current)
+    fn get_token(&mut self) -> Result<(String, usize), std::io::Error> {
+        match &self.app {
+            Some(app) => app
+                .token()
+                .map(|t| (t, 0))
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string())),
+            None => Ok((self.tokens[self.current].clone(), self.current)),
+        }
     }
 
     fn next_token(&mut self, id: usize) {
@@ -175,3 +301,35 @@ pub fn to_string(bytes: &[u8]) -> String {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_link_test() {
+        let headers = "HTTP/2 200\r\n\
+                       content-type: application/json\r\n\
+                       link: <https://api.github.com/repositories/1/pulls?per_page=100&page=2>; rel=\"next\", \
+                       <https://api.github.com/repositories/1/pulls?per_page=100&page=5>; rel=\"last\"\r\n\r\n";
+        assert_eq!(
+            next_link(headers),
+            Some("https://api.github.com/repositories/1/pulls?per_page=100&page=2".to_string())
+        );
+        let last_page = "HTTP/2 200\r\n\
+                         Link: <https://api.github.com/repositories/1/pulls?per_page=100&page=4>; rel=\"prev\", \
+                         <https://api.github.com/repositories/1/pulls?per_page=100&page=1>; rel=\"first\"\r\n\r\n";
+        assert_eq!(next_link(last_page), None);
+        assert_eq!(next_link("HTTP/2 200\r\ncontent-length: 2\r\n\r\n"), None);
+    }
+
+    #[test]
+    fn last_page_test() {
+        let headers = "HTTP/2 200\r\n\
+                       link: <https://api.github.com/repositories/1/contributors?per_page=1&page=2>; rel=\"next\", \
+                       <https://api.github.com/repositories/1/contributors?per_page=1&page=42>; rel=\"last\"\r\n\r\n";
+        assert_eq!(last_page(headers), Some(42));
+        // A single page has no Link header.
+        assert_eq!(last_page("HTTP/2 200\r\ncontent-length: 2\r\n\r\n"), None);
+    }
+}
@@ -0,0 +1,281 @@
+// Copyright 2026 Andrea Gilot
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Authentication to the GitHub API, either with personal access tokens or as a GitHub App.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Utc};
+use clap::{Arg, ArgMatches};
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use reqwest::header::{ACCEPT, AUTHORIZATION, USER_AGENT};
+use serde::{Deserialize, Serialize};
+
+/// Installation tokens are refreshed when they expire in less than this duration.
+const REFRESH_MARGIN: Duration = Duration::from_secs(5 * 60);
+
+/// Claims of the JSON Web Tokens identifying the app.
+#[derive(Serialize, Deserialize)]
+struct Claims {
+    /// Issue date, as a Unix timestamp.
+    iat: i64,
+    /// Expiration date, as a Unix timestamp.
+    exp: i64,
+    /// Identifier of the app.
+    iss: String,
+}
+
+/// Value of the tokens argument sending unauthenticated requests.
+pub const NO_TOKENS: &str = "none";
+
+/// Credentials used to query the GitHub API.
+#[derive(Clone)]
+pub enum Auth {
+    /// Path to a CSV file containing personal access tokens.
+    Tokens(String),
+    /// A GitHub App installation.
+    App(Arc<GithubApp>),
+    /// Unauthenticated requests, limited to 60 per hour.
+    Anonymous,
+}
+
+impl Auth {
+    /// Reads the credentials from the arguments defined by [`auth_args`] and the tokens argument.
+    /// The requests are not authenticated if no tokens file is given or if the tokens argument is [`NO_TOKENS`].
+    ///
+    /// # Arguments
+    ///
+    /// * `args` - The arguments of the subcommand.
+    pub fn from_args(args: &ArgMatches) -> Result<Self> {
+        match args.get_one::<String>("auth").map(|a| a.as_str()) {
+            Some("app") => Ok(Self::App(Arc::new(GithubApp::new(
+                // Safe unwraps: both arguments are required with --auth app
+                *args.get_one::<u64>("app-id").unwrap(),
+                args.get_one::<String>("app-key").unwrap(),
+                args.get_one::<u64>("installation-id").copied(),
+            )?))),
+            // Without tokens, e.g. for quick experiments, the requests are not authenticated.
+            _ => match args.get_one::<String>("tokens").map(|t| t.as_str()) {
+                None | Some(NO_TOKENS) => Ok(Self::Anonymous),
+                Some(tokens) => Ok(Self::Tokens(tokens.to_string())),
+            },
+        }
+    }
+}
+
+/// Arguments selecting how to authenticate to the GitHub API, shared by all subcommands querying it.
+pub fn auth_args() -> Vec<Arg> {
+    vec![
+        Arg::new("auth")
+            .long("auth")
+            .value_name("METHOD")
+            .help("How to authenticate to the GitHub API.\n\
+                   tokens: personal access tokens listed in the file given with --tokens (default)\n\
+                   app: installation access tokens of the GitHub App given with --app-id and --app-key, refreshed automatically")
+            .value_parser(["tokens", "app"]),
+        Arg::new("app-id")
+            .long("app-id")
+            .value_name("APP_ID")
+            .help("Id of the GitHub App used with --auth app.")
+            .required_if_eq("auth", "app")
+            .value_parser(clap::value_parser!(u64)),
+        Arg::new("app-key")
+            .long("app-key")
+            .value_name("KEY.pem")
+            .help("Path to the private key of the GitHub App used with --auth app, in PEM format.")
+            .required_if_eq("auth", "app"),
+        Arg::new("installation-id")
+            .long("installation-id")
+            .value_name("INSTALLATION_ID")
+            .help("Id of the installation of the GitHub App used with --auth app. By default, the first installation of the app is used.")
+            .value_parser(clap::value_parser!(u64)),
+    ]
+}
+
+/// A GitHub App authenticating with installation access tokens.
+pub struct GithubApp {
+    /// Id of the app.
+    app_id: u64,
+    /// Private key of the app.
+    key: EncodingKey,
+    /// Id of the installation, resolved on the first request if not given.
+    installation_id: Mutex<Option<u64>>,
+    /// Current installation access token and its expiration date.
+    token: Mutex<Option<(String, DateTime<Utc>)>>,
+}
+
+impl GithubApp {
+    /// Creates a GitHub App from its id and private key.
+    ///
+    /// # Arguments
+    ///
+    /// * `app_id` - The id of the app.
+    /// * `key_path` - The path to the private key of the app, in PEM format.
+    /// * `installation_id` - The id of the installation. If not specified, the first installation of the app is used.
+    pub fn new(app_id: u64, key_path: &str, installation_id: Option<u64>) -> Result<Self> {
+        let pem: Vec<u8> =
+            std::fs::read(key_path).with_context(|| format!("Could not read key {key_path}"))?;
+        Ok(Self {
+            app_id,
+            key: EncodingKey::from_rsa_pem(&pem)
+                .with_context(|| format!("Invalid RSA private key {key_path}"))?,
+            installation_id: Mutex::new(installation_id),
+            token: Mutex::new(None),
+        })
+    }
+
+    /// Returns a valid installation access token, requesting a new one if the current token expires soon.
+    pub fn token(&self) -> Result<String> {
+        let mut token = self.token.lock().map_err(|_| anyhow!("Mutex poisoned"))?;
+        match token.as_ref() {
+            Some((t, expires_at)) if !needs_refresh(*expires_at, Utc::now()) => Ok(t.clone()),
+            _ => {
+                let (t, expires_at) = self.request_token()?;
+                *token = Some((t.clone(), expires_at));
+                Ok(t)
+            }
+        }
+    }
+
+    /// Creates a JSON Web Token identifying the app, valid for 9 minutes.
+    fn jwt(&self) -> Result<String> {
+        let now: i64 = Utc::now().timestamp();
+        let claims: Claims = Claims {
+            // Issued one minute in the past to allow for clock drift
+            iat: now - 60,
+            exp: now + 9 * 60,
+            iss: self.app_id.to_string(),
+        };
+        encode(&Header::new(Algorithm::RS256), &claims, &self.key)
+            .context("Could not sign the JSON Web Token of the GitHub App")
+    }
+
+    /// Requests a new installation access token.
+    ///
+    /// # Returns
+    ///
+    /// The token and its expiration date.
+    fn request_token(&self) -> Result<(String, DateTime<Utc>)> {
+        let jwt: String = self.jwt()?;
+        let client = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()?;
+        let send = |request: reqwest::blocking::RequestBuilder| -> Result<json::JsonValue> {
+            let response = request
+                .header(AUTHORIZATION, format!("Bearer {jwt}"))
+                .header(ACCEPT, "application/vnd.github+json")
+                .header(USER_AGENT, "Scyros")
+                .send()?;
+            let status = response.status();
+            let body: json::JsonValue = json::parse(&response.text()?)?;
+            if !status.is_success() {
+                bail!(
+                    "GitHub App authentication failed ({status}): {}",
+                    body["message"]
+                );
+            }
+            Ok(body)
+        };
+
+        let mut installation_id = self
+            .installation_id
+            .lock()
+            .map_err(|_| anyhow!("Mutex poisoned"))?;
+        let id: u64 = match *installation_id {
+            Some(id) => id,
+            None => {
+                let installations = send(client.get("https://api.github.com/app/installations"))?;
+                let id: u64 = installations[0]["id"]
+                    .as_u64()
+                    .context("The GitHub App is not installed on any account")?;
+                *installation_id = Some(id);
+                id
+            }
+        };
+
+        let body = send(client.post(format!(
+            "https://api.github.com/app/installations/{id}/access_tokens"
+        )))?;
+        let token: String = body["token"]
+            .as_str()
+            .context("No token in the response of the GitHub API")?
+            .to_string();
+        let expires_at: DateTime<Utc> = body["expires_at"]
+            .as_str()
+            .context("No expiration date in the response of the GitHub API")
+            .and_then(|d| Ok(DateTime::parse_from_rfc3339(d)?.with_timezone(&Utc)))?;
+        Ok((token, expires_at))
+    }
+}
+
+/// Whether a token expiring at a given date must be refreshed.
+fn needs_refresh(expires_at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+    expires_at - now < chrono::Duration::from_std(REFRESH_MARGIN).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::{decode, DecodingKey, Validation};
+
+    const TEST_DATA: &str = "tests/data/github_app";
+
+    #[test]
+    fn jwt_test() -> Result<()> {
+        let app = GithubApp::new(42, &format!("{TEST_DATA}/key.pem"), Some(7))?;
+        let jwt: String = app.jwt()?;
+
+        let decoded = decode::<Claims>(
+            &jwt,
+            &DecodingKey::from_rsa_pem(&std::fs::read(format!("{TEST_DATA}/key.pub.pem"))?)?,
+            &Validation::new(Algorithm::RS256),
+        )?;
+        assert_eq!(decoded.claims.iss, "42");
+        assert!(decoded.claims.exp > decoded.claims.iat);
+
+        assert!(GithubApp::new(42, &format!("{TEST_DATA}/missing.pem"), None).is_err());
+        assert!(GithubApp::new(42, "tests/data/small_file.csv", None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn from_args_test() -> Result<()> {
+        let args = |argv: &[&str]| {
+            clap::Command::new("test")
+                .arg(Arg::new("tokens").long("tokens"))
+                .args(auth_args())
+                .try_get_matches_from(std::iter::once("test").chain(argv.iter().copied()))
+        };
+        assert!(matches!(Auth::from_args(&args(&[])?)?, Auth::Anonymous));
+        assert!(matches!(
+            Auth::from_args(&args(&["--tokens", NO_TOKENS])?)?,
+            Auth::Anonymous
+        ));
+        assert!(matches!(
+            Auth::from_args(&args(&["--tokens", "ghtokens.csv"])?)?,
+            Auth::Tokens(path) if path == "ghtokens.csv"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn needs_refresh_test() {
+        let now: DateTime<Utc> = Utc::now();
+        assert!(!needs_refresh(now + chrono::Duration::minutes(60), now));
+        assert!(needs_refresh(now + chrono::Duration::minutes(4), now));
+        assert!(needs_refresh(now - chrono::Duration::minutes(1), now));
+    }
+}
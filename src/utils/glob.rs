@@ -0,0 +1,145 @@
+// Copyright 2026 Andrea Gilot
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Glob patterns selecting files of a project by their path, e.g. to exclude vendored or generated code.
+//!
+//! A pattern is matched against the end of the path of a file relative to its project, starting at any directory,
+//! and matches the files inside the directories it matches: `vendor` matches `vendor/lib.c` and `src/vendor/x/lib.c`,
+//! and `src/gen/*.c` matches `lib/src/gen/parser.c`. In a pattern, `*` matches any sequence of characters except `/`,
+//! `**` any sequence of directories, and `?` any character except `/`.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use regex::RegexSet;
+
+/// Patterns of the directories of third-party code and of the files generated by common tools.
+pub const VENDORED_PATTERNS: [&str; 14] = [
+    "node_modules",
+    "bower_components",
+    "third_party",
+    "third-party",
+    "thirdparty",
+    "vendor",
+    "vendors",
+    "*.min.js",
+    "*.min.css",
+    "*.pb.go",
+    "*.pb.h",
+    "*.pb.cc",
+    "*_pb2.py",
+    "*.generated.*",
+];
+
+/// A set of glob patterns matched against paths relative to a project.
+#[derive(Debug, Clone)]
+pub struct Globs {
+    /// The patterns, as given.
+    patterns: Vec<String>,
+    /// The patterns translated into regular expressions.
+    regexes: RegexSet,
+}
+
+impl Globs {
+    /// Compiles a set of glob patterns.
+    ///
+    /// # Arguments
+    ///
+    /// * `patterns` - The glob patterns. Leading and trailing slashes are ignored.
+    pub fn new<S: AsRef<str>>(patterns: &[S]) -> Result<Self> {
+        let patterns: Vec<String> = patterns
+            .iter()
+            .map(|p| p.as_ref().trim_matches('/').to_string())
+            .filter(|p| !p.is_empty())
+            .collect();
+        let regexes: RegexSet = RegexSet::new(patterns.iter().map(|p| glob_to_regex(p)))
+            .with_context(|| format!("Invalid path patterns {}", patterns.join(", ")))?;
+        Ok(Self { patterns, regexes })
+    }
+
+    /// Returns whether the set has no pattern.
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Returns the patterns of the set.
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+
+    /// Checks whether a path relative to a project matches one of the patterns.
+    ///
+    /// # Arguments
+    ///
+    /// * `relative_path` - The path of the file relative to its project.
+    pub fn matches(&self, relative_path: &Path) -> bool {
+        !self.is_empty()
+            && self
+                .regexes
+                .is_match(&relative_path.to_string_lossy().replace('\\', "/"))
+    }
+}
+
+/// Translates a glob pattern into a regular expression matching the paths that end with a match of the pattern,
+/// or that are inside a directory matching it.
+fn glob_to_regex(pattern: &str) -> String {
+    let mut regex: String = String::from("^(.*/)?");
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex.push_str("(.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push_str("(/.*)?$");
+    regex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn globs_test() -> Result<()> {
+        let globs: Globs = Globs::new(&["node_modules/", "*.min.js", "src/gen/**/*.c", "a?c"])?;
+        assert!(globs.matches(Path::new("node_modules/left-pad/index.js")));
+        assert!(globs.matches(Path::new("web/node_modules/x.js")));
+        assert!(!globs.matches(Path::new("my_node_modules/x.js")));
+        assert!(globs.matches(Path::new("static/app.min.js")));
+        assert!(!globs.matches(Path::new("static/app.js")));
+        assert!(globs.matches(Path::new("src/gen/parser.c")));
+        assert!(globs.matches(Path::new("repo-0123abc/src/gen/a/b/lexer.c")));
+        assert!(!globs.matches(Path::new("src/gen/parser.h")));
+        assert!(globs.matches(Path::new("abc/main.c")));
+        assert!(!globs.matches(Path::new("a/c/main.c")));
+
+        let vendored: Globs = Globs::new(&VENDORED_PATTERNS)?;
+        assert!(vendored.matches(Path::new("repo/third_party/zlib/inflate.c")));
+        assert!(vendored.matches(Path::new("api/service.pb.go")));
+        assert!(!vendored.matches(Path::new("src/vendored.c")));
+
+        assert!(!Globs::new::<&str>(&[])?.matches(Path::new("vendor/lib.c")));
+        Ok(())
+    }
+}
@@ -138,6 +138,27 @@ pub fn get_field<T: FromJson>(json: &JsonValue, key: &str) -> Result<T::Output,
     T::parse(json[key].clone())
 }
 
+/// Gets a field from a JSON object that may be absent or null, and parses it to a given type.
+///
+/// # Arguments
+/// * `json` - The JSON object to get the field from.
+/// * `key` - The name of the field to get.
+///
+/// # Returns
+/// The value of the field parsed to the given type, None if the field does not exist or is null,
+/// or an error if the field cannot be parsed to the given type or if the JSON object is null.
+pub fn get_optional_field<T: FromJson>(
+    json: &JsonValue,
+    key: &str,
+) -> Result<Option<T::Output>, Error> {
+    ensure!(!json.is_null(), "Cannot get field from null json");
+    if json[key].is_null() {
+        Ok(None)
+    } else {
+        T::parse(json[key].clone()).map(Some)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -171,4 +192,14 @@ mod tests {
         ensure!(set.contains("\\t"));
         Ok(())
     }
+
+    #[test]
+    fn test_get_optional_field() -> Result<()> {
+        let json = json::parse(r#"{"line": 3, "path": null}"#)?;
+        assert_eq!(get_optional_field::<u64>(&json, "line")?, Some(3));
+        assert_eq!(get_optional_field::<String>(&json, "path")?, None);
+        assert_eq!(get_optional_field::<u64>(&json, "commit_id")?, None);
+        ensure!(get_optional_field::<String>(&json, "line").is_err());
+        Ok(())
+    }
 }
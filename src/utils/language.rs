@@ -0,0 +1,78 @@
+// Copyright 2026 Andrea Gilot
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Normalization of the names of programming languages.
+//!
+//! Inputs label languages inconsistently (`C++`, `cpp`, `Python3`, `Fortran Free Form`, ...). Every name is resolved to
+//! its canonical name: the lowercase name used in the keyword files and by the grammars of the `parse` command.
+
+/// Aliases of programming languages, in lowercase, with their canonical name.
+const LANGUAGE_ALIASES: [(&str, &str); 20] = [
+    ("cpp", "c++"),
+    ("cxx", "c++"),
+    ("cplusplus", "c++"),
+    ("csharp", "c#"),
+    ("cs", "c#"),
+    ("python2", "python"),
+    ("python3", "python"),
+    ("py", "python"),
+    ("ts", "typescript"),
+    ("golang", "go"),
+    ("rs", "rust"),
+    ("kt", "kotlin"),
+    ("fortran free form", "fortran"),
+    ("fortran fixed form", "fortran"),
+    ("fortran77", "fortran"),
+    ("fortran90", "fortran"),
+    ("fortran95", "fortran"),
+    ("f77", "fortran"),
+    ("f90", "fortran"),
+    ("f95", "fortran"),
+];
+
+/// Returns the canonical name of a programming language: its name in lowercase, without surrounding whitespace,
+/// with aliases resolved. Names that are not aliases are only lowercased.
+///
+/// # Arguments
+///
+/// * `language` - The name of the language, as found in an input.
+pub fn normalize_language(language: &str) -> String {
+    let language: String = language.trim().to_lowercase();
+    match LANGUAGE_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == language)
+    {
+        Some((_, canonical)) => canonical.to_string(),
+        None => language,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_language_test() {
+        for alias in ["C++", "cpp", "Cpp", " CXX "] {
+            assert_eq!(normalize_language(alias), "c++");
+        }
+        assert_eq!(normalize_language("Python3"), "python");
+        assert_eq!(normalize_language("C#"), "c#");
+        assert_eq!(normalize_language("Fortran Free Form"), "fortran");
+        assert_eq!(normalize_language("golang"), "go");
+        // Unknown languages and the headers of the keyword files are kept.
+        assert_eq!(normalize_language("Standard ML"), "standard ml");
+        assert_eq!(normalize_language("c++_header"), "c++_header");
+    }
+}
@@ -14,13 +14,20 @@
 
 use anyhow::{bail, Error, Result};
 use std::fmt::Display;
-use std::io::{self, Write};
+use std::io::{self, IsTerminal, Write};
 use std::sync::Arc;
-use std::sync::OnceLock;
+use std::sync::{Mutex, OnceLock};
 use std::time::Duration;
 use tracing::{error, info, warn, Level};
 
-use crate::utils::{csv::CSVFile, fs::FileMode, github::is_valid_token_file};
+use crate::utils::{
+    csv::CSVFile,
+    fs::FileMode,
+    github::is_valid_token_file,
+    github_app::Auth,
+    progress::{Progress, ProgressMode},
+    run_manifest::RunManifest,
+};
 
 use super::fs::write_csv;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
@@ -92,10 +99,13 @@ impl TaskLogger {
 #[derive(Clone)]
 struct MultiProgressWriter {
     progress: Arc<MultiProgress>,
+    /// Whether the standard error is a terminal, on which the bars are drawn.
+    terminal: bool,
 }
 
 struct MultiProgressLineWriter {
     progress: Arc<MultiProgress>,
+    terminal: bool,
     buf: Vec<u8>,
 }
 
@@ -105,6 +115,7 @@ impl<'a> tracing_subscriber::fmt::MakeWriter<'a> for MultiProgressWriter {
     fn make_writer(&'a self) -> Self::Writer {
         MultiProgressLineWriter {
             progress: Arc::clone(&self.progress),
+            terminal: self.terminal,
             buf: Vec::new(),
         }
     }
@@ -125,7 +136,13 @@ impl std::io::Write for MultiProgressLineWriter {
         if !self.buf.is_empty() {
             let s = String::from_utf8_lossy(&self.buf);
             for line in s.lines() {
-                let _ = self.progress.println(line);
+                // The bars are hidden when the standard error is not a terminal, e.g. when it is redirected
+                // to a log file, in which case the lines are written directly.
+                if self.terminal {
+                    let _ = self.progress.println(line);
+                } else {
+                    eprintln!("{line}");
+                }
             }
             self.buf.clear();
         }
@@ -142,6 +159,10 @@ impl Drop for MultiProgressLineWriter {
 pub struct Logger {
     /// The multiprogess bar used to log the progress of the tasks.
     progress: Arc<MultiProgress>,
+    /// The description of the current run, written next to its output files when it ends.
+    run: Mutex<Option<RunManifest>>,
+    /// How the progress of the items processed is displayed.
+    progress_mode: Mutex<ProgressMode>,
 }
 
 impl Logger {
@@ -154,10 +175,14 @@ impl Logger {
     pub fn new(debug: bool) -> Result<Self> {
         let logger = Self {
             progress: Arc::new(MultiProgress::new()),
+            run: Mutex::new(None),
+            progress_mode: Mutex::new(ProgressMode::default()),
         };
 
+        let terminal: bool = io::stderr().is_terminal();
         let writer = MultiProgressWriter {
             progress: Arc::clone(&logger.progress),
+            terminal,
         };
 
         let max_level = if debug { Level::DEBUG } else { Level::INFO };
@@ -166,6 +191,7 @@ impl Logger {
             .with_target(false)
             .without_time()
             .with_level(true)
+            .with_ansi(terminal)
             .with_max_level(max_level)
             .finish();
 
@@ -187,8 +213,11 @@ impl Logger {
         msg: impl Into<String>,
         f: impl FnOnce() -> anyhow::Result<T>,
     ) -> anyhow::Result<T> {
+        let msg: String = msg.into();
+        self.begin_stage(&msg);
         let task = TaskLogger::new(self, msg)?;
         let result = f();
+        self.end_stage();
 
         match &result {
             Ok(_) => task.success(),
@@ -198,6 +227,122 @@ impl Logger {
         result
     }
 
+    /// Sets how the progress of the items processed is displayed.
+    ///
+    /// # Arguments
+    /// * `mode` - The mode given with --progress.
+    pub fn set_progress_mode(&self, mode: ProgressMode) {
+        *self.progress_mode.lock().expect("Mutex poisoned") = mode;
+    }
+
+    /// Creates the progress of the items processed by a subcommand, displayed according to the progress mode.
+    ///
+    /// # Arguments
+    /// * `total` - The number of items to process, or `None` if it is unknown.
+    ///
+    /// # Returns
+    /// The progress, or an error if the progress bar could not be created.
+    pub fn progress_bar(&self, total: Option<u64>) -> Result<Progress> {
+        // The processing of the items lasts until the next task or the end of the run.
+        self.begin_stage("Processing");
+        Progress::new(*self.progress_mode.lock().expect("Mutex poisoned"), total)
+    }
+
+    /// Starts recording the files read and written by a run of a subcommand.
+    ///
+    /// # Arguments
+    /// * `manifest` - The description of the run.
+    pub fn start_run(&self, manifest: RunManifest) {
+        *self.run.lock().expect("Mutex poisoned") = Some(manifest);
+    }
+
+    /// Records input files of the current run, if any.
+    ///
+    /// # Arguments
+    /// * `paths` - The paths to the files.
+    pub fn record_inputs(&self, paths: &[&str]) {
+        if let Some(run) = self.run.lock().expect("Mutex poisoned").as_mut() {
+            run.add_inputs(paths);
+        }
+    }
+
+    /// Records output files of the current run, if any.
+    ///
+    /// # Arguments
+    /// * `paths` - The paths to the files.
+    pub fn record_outputs(&self, paths: &[&str]) {
+        if let Some(run) = self.run.lock().expect("Mutex poisoned").as_mut() {
+            run.add_outputs(paths);
+        }
+    }
+
+    /// Adds to a quantity counted by the current run, if any, which is listed in its report.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the quantity, e.g. "projects downloaded".
+    /// * `n` - The amount to add.
+    pub fn record_count(&self, name: &str, n: u64) {
+        if let Some(run) = self.run.lock().expect("Mutex poisoned").as_mut() {
+            run.add_count(name, n);
+        }
+    }
+
+    /// Records an error of the current run, if any, which is listed in its report by category.
+    ///
+    /// # Arguments
+    /// * `category` - The category of the error, e.g. "HTTP 404 Not Found".
+    pub fn record_error(&self, category: &str) {
+        if let Some(run) = self.run.lock().expect("Mutex poisoned").as_mut() {
+            run.add_error(category);
+        }
+    }
+
+    /// Ends the current stage of the run, if any, and starts a new one whose resources are recorded with `--telemetry`.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the stage.
+    fn begin_stage(&self, name: &str) {
+        if let Some(run) = self.run.lock().expect("Mutex poisoned").as_mut() {
+            run.begin_stage(name);
+        }
+    }
+
+    /// Ends the current stage of the run, if any.
+    fn end_stage(&self) {
+        if let Some(run) = self.run.lock().expect("Mutex poisoned").as_mut() {
+            run.end_stage();
+        }
+    }
+
+    /// Ends the current run, if any, and writes its description next to its output files.
+    /// The resources used by the run are logged if they are recorded.
+    ///
+    /// # Arguments
+    /// * `error` - The error that ended the run, if it failed.
+    /// * `report` - The optional path to the human-readable report of the run.
+    pub fn finish_run(&self, error: Option<&Error>, report: Option<&str>) -> Result<()> {
+        match self.run.lock().expect("Mutex poisoned").take() {
+            Some(mut run) => {
+                run.end_stage();
+                if let Some(telemetry) = run.telemetry() {
+                    info!("Resources used:");
+                    for stage in telemetry.stages() {
+                        info!("  {}: {}", stage.name, stage.summary());
+                    }
+                    info!("  Total: {}", telemetry.total().summary());
+                }
+                run.write(error.is_none())?;
+                match report {
+                    Some(path) => {
+                        run.write_report(path, error.map(|e| format!("{e:#}")).as_deref())
+                    }
+                    None => Ok(()),
+                }
+            }
+            None => Ok(()),
+        }
+    }
+
     /// Logs the tokens file being loaded.
     ///
     /// # Arguments
@@ -212,6 +357,32 @@ impl Logger {
                 .and_then(|_| CSVFile::new(tokens_file, FileMode::Read)?.column(0))
         })
     }
+
+    /// Logs the credentials used to query the GitHub API being loaded.
+    ///
+    /// # Arguments
+    /// * `auth` - The credentials.
+    ///
+    /// # Returns
+    ///
+    /// A result containing the tokens, a single installation token for a GitHub App, or a single empty token for unauthenticated
+    /// requests, or an error if the credentials are invalid.
+    pub fn log_auth(&self, auth: &Auth) -> Result<Vec<String>> {
+        match auth {
+            Auth::Tokens(tokens_file) => self.log_tokens(tokens_file),
+            Auth::App(app) => self.run_task(
+                "Requesting an installation token for the GitHub App",
+                || Ok(vec![app.token()?]),
+            ),
+            Auth::Anonymous => {
+                warn!(
+                    "No GitHub token given: the requests are not authenticated and GitHub limits them to 60 per hour. \
+                     Use --tokens or --auth app for anything larger than a small experiment."
+                );
+                Ok(vec![String::new()])
+            }
+        }
+    }
 }
 
 static TEST_LOGGER: OnceLock<Logger> = OnceLock::new();
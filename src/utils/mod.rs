@@ -12,12 +12,28 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod benchmark_extractor;
 pub mod bow;
+pub mod config;
 pub mod csv;
 pub mod dataframes;
 pub mod fs;
+pub mod generated_code;
 pub mod github;
 pub mod github_api;
+pub mod github_app;
+pub mod glob;
 pub mod json;
+pub mod language;
 pub mod logger;
+pub mod parallel;
+pub mod progress;
+pub mod rate_limit;
 pub mod regex;
+pub mod report;
+pub mod run_manifest;
+pub mod seed;
+pub mod shard;
+pub mod storage;
+pub mod telemetry;
+pub mod test_code;
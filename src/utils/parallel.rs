@@ -0,0 +1,282 @@
+// Copyright 2026 Andrea Gilot
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Pool of worker threads shared by the subcommands processing items in parallel.
+//!
+//! The workers take the items one at a time from a shared iterator, each with its own state, e.g. a token or parsers,
+//! and send their results to the calling thread, which handles them one at a time, e.g. by writing them to a file.
+//! The order in which the results are handled is therefore non-deterministic although the order of the items is.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use tracing::warn;
+
+/// What the pool does when an item fails.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FailurePolicy {
+    /// The workers stop after the item they are processing and the error is returned.
+    #[default]
+    Abort,
+    /// The error is logged as a warning and the remaining items are processed.
+    Continue,
+}
+
+/// Pool of worker threads, one per worker state given to [`WorkerPool::run`].
+#[derive(Default)]
+pub struct WorkerPool<'a> {
+    /// Maximal number of results waiting to be handled, unbounded if `None`.
+    capacity: Option<usize>,
+    /// What the pool does when an item fails.
+    policy: FailurePolicy,
+    /// Function called periodically by the calling thread while the items are processed, and its interval.
+    tick: Option<(Duration, Box<dyn FnMut() + 'a>)>,
+}
+
+impl<'a> WorkerPool<'a> {
+    /// Creates a pool that aborts on the first failure and does not bound the results waiting to be handled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bounds the number of results waiting to be handled, e.g. so that they fit in memory.
+    /// The workers wait when the bound is reached.
+    pub fn bounded(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    /// Sets what the pool does when an item fails.
+    pub fn failure_policy(mut self, policy: FailurePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Calls a function at a regular interval while the items are processed, even if no result is received.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - The time between two calls.
+    /// * `f` - The function, e.g. logging statistics.
+    pub fn on_tick(mut self, interval: Duration, f: impl FnMut() + 'a) -> Self {
+        self.tick = Some((interval, Box::new(f)));
+        self
+    }
+
+    /// Processes items in parallel, with one thread per worker state, until the iterator is empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `items` - The items to process.
+    /// * `workers` - The state of every worker, e.g. a token.
+    /// * `work` - The function processing an item, which returns `None` if there is no result to handle.
+    /// * `handle` - The function handling the results on the calling thread.
+    ///
+    /// # Returns
+    ///
+    /// An error if an item failed with [`FailurePolicy::Abort`], if a result could not be handled, or if a worker panicked.
+    pub fn run<I, S, T, W, H>(self, items: I, workers: Vec<S>, work: W, mut handle: H) -> Result<()>
+    where
+        I: Iterator + Send,
+        S: Send,
+        T: Send,
+        W: Fn(&mut S, I::Item) -> Result<Option<T>> + Sync,
+        H: FnMut(T) -> Result<()>,
+    {
+        let WorkerPool {
+            capacity,
+            policy,
+            mut tick,
+        } = self;
+        let items: Mutex<I> = Mutex::new(items);
+        // Set on the first failure so that the workers do not take new items.
+        let stop: AtomicBool = AtomicBool::new(false);
+        let (tx, rx) = match capacity {
+            Some(capacity) => crossbeam_channel::bounded::<Result<T>>(capacity),
+            None => crossbeam_channel::unbounded::<Result<T>>(),
+        };
+
+        crossbeam::thread::scope(|s| {
+            for mut state in workers {
+                let tx = tx.clone();
+                let (items, stop, work) = (&items, &stop, &work);
+                s.spawn(move |_| {
+                    while !stop.load(Ordering::Relaxed) {
+                        let Some(item) = items.lock().expect("Mutex poisoned").next() else {
+                            break;
+                        };
+                        let msg: Result<T> = match work(&mut state, item) {
+                            Ok(Some(result)) => Ok(result),
+                            Ok(None) => continue,
+                            Err(e) if policy == FailurePolicy::Continue => {
+                                warn!("{e:#}");
+                                continue;
+                            }
+                            Err(e) => {
+                                stop.store(true, Ordering::Relaxed);
+                                Err(e)
+                            }
+                        };
+                        // Stops when the calling thread has stopped listening, e.g. after an error.
+                        if tx.send(msg).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            // The channel is disconnected once every worker has ended.
+            drop(tx);
+
+            let result: Result<()> = receive(&rx, &mut handle, tick.as_mut());
+            if result.is_err() {
+                stop.store(true, Ordering::Relaxed);
+            }
+            // Unblocks the workers waiting for room in a bounded channel.
+            drop(rx);
+            result
+        })
+        .map_err(|e| anyhow!("A worker thread panicked: {e:?}"))?
+    }
+}
+
+/// Handles the results of the workers until they have all ended or one of the results is an error.
+///
+/// # Arguments
+///
+/// * `rx` - The channel of the results.
+/// * `handle` - The function handling the results.
+/// * `tick` - The interval and the function called periodically, if any.
+fn receive<T>(
+    rx: &Receiver<Result<T>>,
+    handle: &mut impl FnMut(T) -> Result<()>,
+    mut tick: Option<&mut (Duration, Box<dyn FnMut() + '_>)>,
+) -> Result<()> {
+    let mut last_tick: Instant = Instant::now();
+    loop {
+        let msg: Result<T> = match tick.as_mut() {
+            Some((interval, on_tick)) => {
+                if last_tick.elapsed() >= *interval {
+                    on_tick();
+                    last_tick = Instant::now();
+                }
+                match rx.recv_timeout(interval.saturating_sub(last_tick.elapsed())) {
+                    Ok(msg) => msg,
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    Err(RecvTimeoutError::Disconnected) => return Ok(()),
+                }
+            }
+            None => match rx.recv() {
+                Ok(msg) => msg,
+                Err(_) => return Ok(()),
+            },
+        };
+        handle(msg?)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::bail;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn test_worker_pool() -> Result<()> {
+        // Every item is processed once, by one of the workers, each with its own state.
+        let mut squares: Vec<u32> = Vec::new();
+        WorkerPool::new().bounded(2).run(
+            1..=100u32,
+            vec![0usize; 4],
+            |processed, i| {
+                *processed += 1;
+                Ok((i % 2 == 0).then_some(i * i))
+            },
+            |square| {
+                squares.push(square);
+                Ok(())
+            },
+        )?;
+        squares.sort_unstable();
+        assert_eq!(squares, (1..=50).map(|i| 4 * i * i).collect::<Vec<u32>>());
+
+        // The workers stop after the first failure, which is returned.
+        let processed: AtomicUsize = AtomicUsize::new(0);
+        let result = WorkerPool::new().run(
+            0..1_000_000u32,
+            vec![(); 4],
+            |_, i| {
+                processed.fetch_add(1, Ordering::Relaxed);
+                if i == 10 {
+                    bail!("Item {i} failed");
+                }
+                Ok(Some(i))
+            },
+            |_| Ok(()),
+        );
+        assert_eq!(result.unwrap_err().to_string(), "Item 10 failed");
+        assert!(processed.load(Ordering::Relaxed) < 1_000_000);
+
+        // An error of the calling thread also stops the workers.
+        let result = WorkerPool::new().bounded(1).run(
+            0..1_000_000u32,
+            vec![(); 4],
+            |_, i| Ok(Some(i)),
+            |i| match i {
+                10 => bail!("Result {i} could not be handled"),
+                _ => Ok(()),
+            },
+        );
+        assert!(result.is_err());
+
+        // The failed items are skipped when the pool continues.
+        let mut handled: usize = 0;
+        WorkerPool::new()
+            .failure_policy(FailurePolicy::Continue)
+            .run(
+                0..100u32,
+                vec![(); 4],
+                |_, i| match i % 10 {
+                    0 => bail!("Item {i} failed"),
+                    _ => Ok(Some(i)),
+                },
+                |_| {
+                    handled += 1;
+                    Ok(())
+                },
+            )?;
+        assert_eq!(handled, 90);
+        Ok(())
+    }
+
+    #[test]
+    fn test_tick() -> Result<()> {
+        let mut ticks: usize = 0;
+        WorkerPool::new()
+            .on_tick(Duration::from_millis(10), || ticks += 1)
+            .run(
+                0..2u32,
+                vec![(); 1],
+                |_, i| {
+                    std::thread::sleep(Duration::from_millis(50));
+                    Ok(Some(i))
+                },
+                |_| Ok(()),
+            )?;
+        assert!(ticks >= 2);
+        Ok(())
+    }
+}
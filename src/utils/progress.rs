@@ -0,0 +1,229 @@
+// Copyright 2026 Andrea Gilot
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Progress of the items processed by a subcommand.
+//!
+//! The progress shows the number of items processed out of the total, the throughput, the failures so far and the
+//! estimated time left. It is displayed according to `--progress`: as a progress bar, as plain lines logged
+//! periodically, which suits log files and jobs running without terminal, or not at all.
+
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use clap::Arg;
+use indicatif::{HumanDuration, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use tracing::info;
+
+/// Time between two lines of progress in plain mode.
+const PLAIN_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Argument selecting how the progress is displayed.
+pub fn progress_arg() -> Arg {
+    Arg::new("progress")
+        .long("progress")
+        .value_name("MODE")
+        .help("How to display the progress of the subcommands.\n\
+               bar: progress bar\n\
+               plain: line logged every 30 seconds, for log files and jobs running without terminal\n\
+               none: no progress")
+        .default_value("bar")
+        .value_parser(["none", "bar", "plain"])
+        .global(true)
+}
+
+/// How the progress is displayed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ProgressMode {
+    /// No progress is displayed.
+    None,
+    /// A progress bar is drawn on the terminal.
+    #[default]
+    Bar,
+    /// A line is logged periodically.
+    Plain,
+}
+
+impl ProgressMode {
+    /// Parses the mode given with `--progress`.
+    pub fn parse(s: &str) -> Result<Self> {
+        match s {
+            "none" => Ok(Self::None),
+            "bar" => Ok(Self::Bar),
+            "plain" => Ok(Self::Plain),
+            _ => bail!("Invalid progress mode '{s}', expected none, bar or plain"),
+        }
+    }
+}
+
+/// Progress of the items processed by a subcommand, created with [`crate::utils::logger::Logger::progress_bar`].
+pub struct Progress {
+    /// The progress bar, which is hidden unless the progress is displayed as a bar.
+    bar: ProgressBar,
+    /// How the progress is displayed.
+    mode: ProgressMode,
+    /// Number of items that failed so far.
+    failures: AtomicU64,
+    /// Message of the subcommand displayed after the progress.
+    message: Mutex<String>,
+    /// Time at which the last line of progress was logged in plain mode.
+    last_report: Mutex<Instant>,
+}
+
+impl Progress {
+    /// Creates the progress of a number of items.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - How the progress is displayed.
+    /// * `total` - The number of items to process, or `None` if it is unknown.
+    pub(crate) fn new(mode: ProgressMode, total: Option<u64>) -> Result<Self> {
+        let bar: ProgressBar = match total {
+            Some(total) => ProgressBar::new(total),
+            None => ProgressBar::new_spinner(),
+        };
+        if mode == ProgressMode::Bar {
+            bar.set_style(ProgressStyle::default_bar().template(match total {
+                Some(_) => {
+                    "{elapsed} {wide_bar} {pos}/{len} {percent}% | {per_sec} | ETA {eta}{msg}"
+                }
+                None => "{spinner} {elapsed} {pos} | {per_sec}{msg}",
+            })?);
+        } else {
+            bar.set_draw_target(ProgressDrawTarget::hidden());
+        }
+        Ok(Self {
+            bar,
+            mode,
+            failures: AtomicU64::new(0),
+            message: Mutex::new(String::new()),
+            last_report: Mutex::new(Instant::now()),
+        })
+    }
+
+    /// Advances the progress by a number of items.
+    pub fn inc(&self, n: u64) {
+        self.bar.inc(n);
+        if self.mode == ProgressMode::Plain {
+            let mut last_report = self.last_report.lock().expect("Mutex poisoned");
+            if last_report.elapsed() >= PLAIN_INTERVAL {
+                *last_report = Instant::now();
+                self.log();
+            }
+        }
+    }
+
+    /// Counts an item that failed. The item is not counted as processed, which is done with [`Progress::inc`].
+    pub fn fail(&self) {
+        self.failures.fetch_add(1, Ordering::Relaxed);
+        self.update_message();
+    }
+
+    /// Sets the message displayed after the progress, e.g. the number of requests answered from the cache.
+    pub fn set_message(&self, message: impl Display) {
+        *self.message.lock().expect("Mutex poisoned") = message.to_string();
+        self.update_message();
+    }
+
+    /// Sets the number of items to process.
+    pub fn set_length(&self, total: u64) {
+        self.bar.set_length(total);
+    }
+
+    /// Redraws the progress, e.g. to animate the spinner of an unknown number of items.
+    pub fn tick(&self) {
+        self.bar.tick();
+    }
+
+    /// Redraws the progress bar at a regular interval, so that the elapsed time is updated while an item is processed.
+    pub fn enable_steady_tick(&self, interval: Duration) {
+        if self.mode == ProgressMode::Bar {
+            self.bar.enable_steady_tick(interval);
+        }
+    }
+
+    /// Hides the progress bar while a function runs, e.g. to print a message.
+    pub fn suspend<T>(&self, f: impl FnOnce() -> T) -> T {
+        self.bar.suspend(f)
+    }
+
+    /// Ends the progress, and logs the final line of progress in plain mode.
+    pub fn finish(&self) {
+        self.bar.finish();
+        if self.mode == ProgressMode::Plain {
+            self.log();
+        }
+    }
+
+    /// Suffix of the progress made of the failures so far and of the message of the subcommand.
+    fn suffix(&self) -> String {
+        let failures: u64 = self.failures.load(Ordering::Relaxed);
+        let message = self.message.lock().expect("Mutex poisoned");
+        let mut suffix: String = String::new();
+        if failures > 0 {
+            suffix.push_str(&format!(" | failures: {failures}"));
+        }
+        if !message.is_empty() {
+            suffix.push_str(&format!(" | {message}"));
+        }
+        suffix
+    }
+
+    fn update_message(&self) {
+        if self.mode == ProgressMode::Bar {
+            self.bar.set_message(self.suffix());
+        }
+    }
+
+    /// Logs a line of progress.
+    fn log(&self) {
+        let position: u64 = self.bar.position();
+        let rate: f64 = self.bar.per_sec();
+        match self.bar.length() {
+            Some(total) => info!(
+                "Progress: {position}/{total} ({:.1} %) | {rate:.1}/s | ETA {}{}",
+                if total == 0 {
+                    100.0
+                } else {
+                    position as f64 / total as f64 * 100.0
+                },
+                HumanDuration(self.bar.eta()),
+                self.suffix()
+            ),
+            None => info!("Progress: {position} | {rate:.1}/s{}", self.suffix()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_test() -> Result<()> {
+        assert_eq!(ProgressMode::parse("plain")?, ProgressMode::Plain);
+        assert!(ProgressMode::parse("verbose").is_err());
+
+        let progress: Progress = Progress::new(ProgressMode::None, Some(4))?;
+        progress.inc(3);
+        progress.fail();
+        progress.set_message("requests from cache: 2");
+        assert_eq!(progress.suffix(), " | failures: 1 | requests from cache: 2");
+        assert_eq!(progress.bar.position(), 3);
+        progress.finish();
+        Ok(())
+    }
+}
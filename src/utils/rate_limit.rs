@@ -0,0 +1,145 @@
+// Copyright 2026 Andrea Gilot
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Throttling of the requests sent to GitHub by several threads.
+//!
+//! Requests are limited with a token bucket shared by the threads: the bucket is refilled continuously at the
+//! allowed rate, holds at most one second of requests, and every request takes one token from it.
+//! When the bucket is empty, the thread sending the request waits until a token becomes available.
+
+use std::sync::Mutex;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use anyhow::{ensure, Result};
+use clap::Arg;
+
+/// Token bucket limiting the number of requests sent per minute, shared by the threads sending them.
+#[derive(Debug)]
+pub struct RateLimiter {
+    /// Number of requests allowed per second.
+    rate: f64,
+    /// Maximal number of requests sent in a burst.
+    capacity: f64,
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    /// Number of available tokens, negative when requests are waiting for tokens.
+    tokens: f64,
+    /// The last time the bucket was refilled.
+    refilled: Instant,
+}
+
+impl RateLimiter {
+    /// Creates a rate limiter whose bucket is initially full.
+    ///
+    /// # Arguments
+    ///
+    /// * `requests` - The maximal number of requests per minute.
+    pub fn per_minute(requests: u32) -> Result<Self> {
+        ensure!(
+            requests > 0,
+            "The number of requests per minute must be positive"
+        );
+        let rate: f64 = requests as f64 / 60.0;
+        let capacity: f64 = rate.max(1.0).floor();
+        Ok(Self {
+            rate,
+            capacity,
+            bucket: Mutex::new(Bucket {
+                tokens: capacity,
+                refilled: Instant::now(),
+            }),
+        })
+    }
+
+    /// Blocks the calling thread until a request can be sent.
+    pub fn acquire(&self) {
+        let wait: Duration = self.reserve(Instant::now());
+        if !wait.is_zero() {
+            sleep(wait);
+        }
+    }
+
+    /// Takes a token from the bucket and returns how long to wait before sending the request.
+    /// Tokens taken from an empty bucket are owed, so that waiting requests are sent in turn.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The time at which the token is taken.
+    fn reserve(&self, now: Instant) -> Duration {
+        let mut bucket = self.bucket.lock().expect("Mutex poisoned");
+        let elapsed: Duration = now.saturating_duration_since(bucket.refilled);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * self.rate).min(self.capacity);
+        bucket.refilled = bucket.refilled.max(now);
+        bucket.tokens -= 1.0;
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / self.rate)
+        }
+    }
+}
+
+/// Argument limiting the number of requests sent to GitHub per minute.
+pub fn rate_limit_arg() -> Arg {
+    Arg::new("max-requests-per-minute")
+        .long("max-requests-per-minute")
+        .value_name("REQUESTS")
+        .help("Maximal number of requests sent to GitHub per minute by all threads together, \
+               to avoid triggering the abuse detection of GitHub on large runs. Unlimited if not specified.")
+        .value_parser(clap::value_parser!(u32).range(1..))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_limiter_test() -> Result<()> {
+        // One request every two seconds, without bursts.
+        let limiter: RateLimiter = RateLimiter::per_minute(30)?;
+        let start: Instant = limiter.bucket.lock().unwrap().refilled;
+        assert_eq!(limiter.reserve(start), Duration::ZERO);
+        assert_eq!(limiter.reserve(start), Duration::from_secs(2));
+        assert_eq!(limiter.reserve(start), Duration::from_secs(4));
+        // The waiting requests are served before later ones.
+        assert_eq!(
+            limiter.reserve(start + Duration::from_secs(5)),
+            Duration::from_secs(1)
+        );
+        // Unused tokens do not accumulate beyond the capacity of the bucket.
+        assert_eq!(
+            limiter.reserve(start + Duration::from_secs(60)),
+            Duration::ZERO
+        );
+        assert_eq!(
+            limiter.reserve(start + Duration::from_secs(60)),
+            Duration::from_secs(2)
+        );
+
+        // Bursts of one second of requests.
+        let limiter: RateLimiter = RateLimiter::per_minute(600)?;
+        let start: Instant = limiter.bucket.lock().unwrap().refilled;
+        for _ in 0..10 {
+            assert_eq!(limiter.reserve(start), Duration::ZERO);
+        }
+        assert_eq!(limiter.reserve(start), Duration::from_millis(100));
+
+        assert!(RateLimiter::per_minute(0).is_err());
+        Ok(())
+    }
+}
@@ -18,22 +18,30 @@ use crate::utils::bow::Bow;
 
 use super::fs::*;
 use super::json::*;
+use super::language::normalize_language;
 use anyhow::{anyhow, bail, Context, Result};
 use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
 use std::io::BufRead;
 use std::io::BufReader;
+use std::ops::Range;
 use std::path::Path;
 use tracing::warn;
 
 use regex::bytes::Regex;
 
+/// Prefix of the names of the capture groups matching the keywords of a matcher.
+const KEYWORD_GROUP: &str = "scyros_kw";
+
 /// A struct that holds a regex pattern and provides methods to find matches in texts and files.
 #[derive(Debug)]
 pub struct Matcher {
     /// The regex pattern to match against.
     /// If None, the matcher does not match anything.
     regex: Option<Regex>,
+    /// The keywords looked for, with the index of the capture group matching each of them.
+    /// Empty if the matcher does not look for keywords.
+    keywords: Vec<(String, usize)>,
 }
 
 impl Matcher {
@@ -42,12 +50,16 @@ impl Matcher {
         Matcher {
             // Safe unwrap as the pattern is valid
             regex: Some(Regex::new(r"\b\w+\b").unwrap()),
+            keywords: Vec::new(),
         }
     }
 
     /// Returns an empty matcher that does not match anything.
     pub fn empty_matcher() -> Self {
-        Matcher { regex: None }
+        Matcher {
+            regex: None,
+            keywords: Vec::new(),
+        }
     }
 
     /// Takes a sequence of keywords and returns a regex pattern that looks for any of them.
@@ -63,6 +75,7 @@ impl Matcher {
     ///
     ///  # Returns
     ///  A regex pattern looking for any of the keywords or subpatterns, or an error if the pattern is invalid.
+    ///  Every keyword is matched by its own named capture group, so that the matches can be attributed to the keywords.
     pub fn keywords_matcher<T>(
         keywords: impl IntoIterator<Item = T>,
         case_sensitive: bool,
@@ -72,10 +85,22 @@ impl Matcher {
     where
         T: ToString,
     {
-        let joined_keywords = keywords
+        let keywords: Vec<String> = keywords
             .into_iter()
             .filter_map(|s| Some(s.to_string()).filter(|s| !s.is_empty()))
-            .map(|s| if !regex_syntax { regex::escape(&s) } else { s })
+            .collect();
+        // The groups are named, since the patterns of the keywords can have their own groups.
+        let joined_keywords = keywords
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                let pattern: String = if !regex_syntax {
+                    regex::escape(s)
+                } else {
+                    s.to_string()
+                };
+                format!("(?P<{KEYWORD_GROUP}{i}>{pattern})")
+            })
             .collect::<Vec<String>>()
             .join("|");
         if !joined_keywords.is_empty() {
@@ -90,8 +115,23 @@ impl Matcher {
             } else {
                 format!("(?i){new_pattern}")
             };
+            let regex: Regex = Regex::new(&new_pattern_with_sensitivity)?;
+            let groups: HashMap<&str, usize> = regex
+                .capture_names()
+                .enumerate()
+                .filter_map(|(idx, name)| Some((name?, idx)))
+                .collect();
+            let keywords: Vec<(String, usize)> = keywords
+                .into_iter()
+                .enumerate()
+                .map(|(i, keyword)| {
+                    let group: usize = groups[format!("{KEYWORD_GROUP}{i}").as_str()];
+                    (keyword, group)
+                })
+                .collect();
             Ok(Self {
-                regex: Some(Regex::new(&new_pattern_with_sensitivity)?),
+                regex: Some(regex),
+                keywords,
             })
         } else {
             Ok(Self::words_matcher())
@@ -159,7 +199,8 @@ impl Matcher {
             .unwrap_or(false)
     }
 
-    /// Counts the number of matches of a pattern in a file.
+    /// Counts the number of matches of a pattern in a file, line by line.
+    /// Lines are matched as bytes, so that files that are not valid UTF-8 are supported.
     ///
     /// # Arguments
     ///
@@ -167,14 +208,87 @@ impl Matcher {
     pub fn count_matches_in_file(&self, path: impl AsRef<Path>) -> Result<usize> {
         let path_ref = path.as_ref();
         let mut count: usize = 0;
-        for l in BufReader::new(open_file(path_ref, FileMode::Read)?).lines() {
+        for l in BufReader::new(open_file(path_ref, FileMode::Read)?).split(b'\n') {
             let line =
                 l.with_context(|| format!("Could not read lines from {}", path_ref.display()))?;
-            count += self.count_matches_in_text(line.as_bytes());
+            count += self.count_matches_in_text(&line);
         }
         Ok(count)
     }
 
+    /// Returns the matches of the pattern in a text, in order of appearance.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to search for the pattern.
+    pub fn matches<'a>(&self, text: &'a [u8]) -> Vec<&'a [u8]> {
+        self.regex
+            .as_ref()
+            .map(|r| r.find_iter(text).map(|m| m.as_bytes()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the byte ranges of the matches of the pattern in a text, in order of appearance.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to search for the pattern.
+    pub fn match_ranges(&self, text: &[u8]) -> Vec<Range<usize>> {
+        self.regex
+            .as_ref()
+            .map(|r| r.find_iter(text).map(|m| m.range()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the byte offsets of the first matches of the pattern in a text, in order of appearance.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to search for the pattern.
+    /// * `limit` - The maximal number of matches returned.
+    pub fn match_offsets(&self, text: &[u8], limit: usize) -> Vec<usize> {
+        self.regex
+            .as_ref()
+            .map(|r| r.find_iter(text).take(limit).map(|m| m.start()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Counts the matches of every keyword of the matcher in a text.
+    /// Every match of the matcher is attributed to the keyword that matched it, so that the counts of the keywords
+    /// sum up to the number of matches of the matcher.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to search for the keywords.
+    ///
+    /// # Returns
+    ///
+    /// The keywords with at least one match and their number of matches, sorted by keyword.
+    pub fn keyword_counts(&self, text: &[u8]) -> Vec<(&str, usize)> {
+        let Some(regex) = &self.regex else {
+            return Vec::new();
+        };
+        let mut counts: Vec<usize> = vec![0; self.keywords.len()];
+        for captures in regex.captures_iter(text) {
+            if let Some(i) = self
+                .keywords
+                .iter()
+                .position(|(_, group)| captures.get(*group).is_some())
+            {
+                counts[i] += 1;
+            }
+        }
+        let mut counts: Vec<(&str, usize)> = self
+            .keywords
+            .iter()
+            .zip(counts)
+            .filter(|(_, count)| *count > 0)
+            .map(|((keyword, _), count)| (keyword.as_str(), count))
+            .collect();
+        counts.sort_unstable();
+        counts
+    }
+
     /// Returns a bag of words (a map from words matching the pattern to their frequency) from a text.
     ///
     /// # Arguments
@@ -198,6 +312,13 @@ pub fn count_text_lines(text: &[u8]) -> usize {
     text.lines().count()
 }
 
+/// Whether two languages may both be associated with an extension in keyword files.
+/// Only the `h` extension of headers can be shared, by C and C++, since the `parse` command tells their headers apart.
+pub fn shared_extension(ext: &str, first: &str, second: &str) -> bool {
+    let c_family = |language: &str| ["c", "c++"].contains(&language.to_lowercase().as_str());
+    ext.eq_ignore_ascii_case("h") && c_family(first) && c_family(second)
+}
+
 /// A structure representing a collection of files enumerating keywords to match against for different programming languages.
 /// Programming languages are identified by their name, and a mapping from file extensions to programming languages is also provided.
 ///
@@ -223,6 +344,7 @@ pub fn count_text_lines(text: &[u8]) -> usize {
 /// ...
 ///
 /// Note that the keywords are matched as whole words but case insensitively.
+/// The names of the languages are normalized with [`normalize_language`], so that `C++` and `cpp` both designate `c++`.
 /// Adding an other keyword file will add a new matcher for each language, in addition to the existing ones.
 ///
 /// # Invariants:
@@ -236,6 +358,9 @@ pub struct KeywordFiles {
     pub extensions_to_language: HashMap<String, String>,
     /// Whether to interpret the keywords as regular expressions. If false, the keywords are interpreted as whole words to match.
     pub regex_syntax: bool,
+    /// The matchers of each keyword file looking for the keywords of all its languages, for texts written in no
+    /// programming language such as READMEs
+    pub any_language_matchers: Vec<Matcher>,
 }
 
 impl KeywordFiles {
@@ -246,6 +371,7 @@ impl KeywordFiles {
             matchers: HashMap::new(),
             extensions_to_language: HashMap::new(),
             regex_syntax,
+            any_language_matchers: Vec::new(),
         }
     }
 
@@ -371,10 +497,12 @@ impl KeywordFiles {
                     .unwrap_or_default();
                 (name, extensions, keywords)
             };
+            // Aliases of a language share the matchers and the extensions of its canonical name.
+            let name: &str = &normalize_language(name);
 
             for ext in extensions {
                 match extensions_to_language.get(&ext) {
-                    Some(value) if value != name => {
+                    Some(value) if value != name && !shared_extension(&ext, value, name) => {
                         bail!(
                             "Extension {} is associated with both {} and {} when loading {}",
                             &ext,
@@ -383,14 +511,17 @@ impl KeywordFiles {
                             updated_paths.join(", ")
                         );
                     }
+                    // Shared extensions keep the language that declared them first.
+                    Some(_) => (),
                     None => {
-                        extensions_to_language.insert(ext.clone(), name.to_string());
+                        extensions_to_language.insert(ext, name.to_string());
                     }
-                    _ => (),
                 }
-                extensions_to_language.insert(ext, name.to_string());
             }
-            local_kw.insert(name.to_string(), keywords.clone());
+            local_kw
+                .entry(name.to_string())
+                .or_default()
+                .extend(keywords);
         }
 
         let cat2 = "keywords";
@@ -401,6 +532,13 @@ impl KeywordFiles {
 
         let file_matchers =
             Matcher::keywords_matchers(&local_kw, &global_kw, false, true, self.regex_syntax)?;
+        let mut any_language_matchers: Vec<Matcher> = self.any_language_matchers;
+        any_language_matchers.push(Matcher::keywords_matcher(
+            local_kw.values().flatten().chain(global_kw.iter()),
+            false,
+            true,
+            self.regex_syntax,
+        )?);
         let mut updated_matchers = self.matchers;
 
         for (lang, entry) in updated_matchers.iter_mut() {
@@ -429,6 +567,7 @@ impl KeywordFiles {
             matchers: updated_matchers,
             extensions_to_language,
             regex_syntax: self.regex_syntax,
+            any_language_matchers,
         })
     }
 
@@ -447,7 +586,7 @@ impl KeywordFiles {
                 .iter()
                 .map(|m| m.count_matches_in_file(path_ref))
                 .collect(),
-            None => Ok(vec![0, self.paths.len()]),
+            None => Ok(vec![0; self.paths.len()]),
         }
     }
 
@@ -466,6 +605,36 @@ impl KeywordFiles {
         }
     }
 
+    /// Counts the number of matches of the keywords of every language of each keyword file in a text.
+    ///
+    /// # Arguments
+    /// * `text` - The text to analyze.
+    ///
+    /// # Returns
+    /// A vector containing the number of matches of the keywords of each keyword file in the text.
+    pub fn count_matches_in_any_language(&self, text: &[u8]) -> Vec<usize> {
+        self.any_language_matchers
+            .iter()
+            .map(|m| m.count_matches_in_text(text))
+            .collect()
+    }
+
+    /// Returns the byte offsets of the first matches of each matcher of a given language in a text.
+    ///
+    /// # Arguments
+    /// * `lang` - The programming language whose matchers to use.
+    /// * `text` - The text to analyze.
+    /// * `limit` - The maximal number of matches returned for each matcher.
+    ///
+    /// # Returns
+    /// A vector containing the offsets of the first matches for each matcher of the given language in the text.
+    pub fn match_offsets_in_text(&self, lang: &str, text: &[u8], limit: usize) -> Vec<Vec<usize>> {
+        match self.matchers.get(lang) {
+            Some(m) => m.iter().map(|m| m.match_offsets(text, limit)).collect(),
+            None => vec![Vec::new(); self.paths.len()],
+        }
+    }
+
     /// Checks if any matcher of a given language finds matches in a text.
     ///
     /// # Arguments
@@ -482,10 +651,103 @@ impl KeywordFiles {
     }
 }
 
+/// Copies keyword files into the directory `<output>.keywords` next to an output file, so that the output can be
+/// reproduced even if the keyword files change afterwards. Their hashes are recorded with the inputs of the run manifest.
+/// The files keep their names, prefixed with their position in the list if several files have the same name.
+///
+/// When a previous run wrote to the same output, a warning is logged for every keyword file that differs from its archived copy,
+/// as the output then mixes the results of both versions, and the copy is replaced.
+///
+/// # Arguments
+///
+/// * `paths` - The paths to the keyword files.
+/// * `output_path` - The path to the output file.
+/// * `overwrite` - Whether the output file is overwritten, in which case the previous archive is deleted.
+///
+/// # Returns
+///
+/// The path to the directory, or `None` if there is no keyword file.
+pub fn archive_keyword_files(
+    paths: &[&str],
+    output_path: &str,
+    overwrite: bool,
+) -> Result<Option<String>> {
+    if paths.is_empty() {
+        return Ok(None);
+    }
+    let dir: String = format!("{output_path}.keywords");
+    if overwrite {
+        delete_dir(&dir, true)?;
+    }
+    create_dir(&dir)?;
+    let mut names: HashSet<String> = HashSet::new();
+    for (i, path) in paths.iter().enumerate() {
+        let name: String = Path::new(path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .with_context(|| format!("Invalid keyword file {path}"))?;
+        let name: String = if names.insert(name.clone()) {
+            name
+        } else {
+            format!("{i}-{name}")
+        };
+        let copy: String = format!("{dir}/{name}");
+        if Path::new(&copy).exists() && hash_file(&copy)?.1 != hash_file(path)?.1 {
+            warn!(
+                "The keyword file {path} differs from the copy archived with {output_path} by a previous run, \
+                 the output mixes the results of both versions."
+            );
+        }
+        std::fs::copy(path, &copy).with_context(|| format!("Could not copy {path} to {copy}"))?;
+    }
+    Ok(Some(dir))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn archive_keyword_files_test() -> Result<()> {
+        let output_path: &str = "target/tests/archive/output.csv";
+        let dir: String = format!("{output_path}.keywords");
+        let c_float: &str = "tests/data/keywords/c_float.json";
+        let local_c_float: &str = "target/tests/archive/c_float.json";
+        write_file(local_c_float, std::fs::read(c_float)?)?;
+
+        assert_eq!(archive_keyword_files(&[], output_path, true)?, None);
+        assert_eq!(
+            archive_keyword_files(
+                &[
+                    c_float,
+                    "tests/data/keywords/java_float.json",
+                    local_c_float
+                ],
+                output_path,
+                true
+            )?,
+            Some(dir.clone())
+        );
+        let mut archived: Vec<String> = std::fs::read_dir(&dir)?
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().to_string()))
+            .collect::<Result<_>>()?;
+        archived.sort();
+        assert_eq!(
+            archived,
+            ["2-c_float.json", "c_float.json", "java_float.json"]
+        );
+        assert_eq!(
+            hash_file(&format!("{dir}/c_float.json"))?,
+            hash_file(c_float)?
+        );
+
+        // Overwriting the output deletes the previous archive.
+        archive_keyword_files(&[c_float], output_path, true)?;
+        assert_eq!(std::fs::read_dir(&dir)?.count(), 1);
+
+        delete_dir("target/tests/archive", false)
+    }
+
     #[test]
     fn count_matches_test() -> Result<()> {
         let text = b"Parole, parole, parole, paroleParole parole_parole parole_Parole";
@@ -516,6 +778,14 @@ mod tests {
             9
         );
         assert_eq!(matcher_lower_sensitive_whole.count_matches_in_text(text), 2);
+        assert_eq!(
+            matcher_lower_sensitive_whole.match_offsets(text, 5),
+            vec![8, 16]
+        );
+        assert_eq!(
+            matcher_lower_sensitive_whole.match_offsets(text, 1),
+            vec![8]
+        );
         assert_eq!(matcher_lower_sensitive_part.count_matches_in_text(text), 6);
         assert_eq!(
             matcher_upper_unsensitive_whole.count_matches_in_text(text),
@@ -658,4 +928,58 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn keyword_counts_test() -> Result<()> {
+        let matcher: Matcher =
+            Matcher::keywords_matcher(["sqrt", "pow", "powf", "exp"], false, true, false)?;
+        let text: &[u8] = b"pow(sqrt(x), 2) + POW(y, 3) + powf(z, 2) + sqrtf(w) + expm1(v)";
+        assert_eq!(
+            matcher.keyword_counts(text),
+            [("pow", 2), ("powf", 1), ("sqrt", 1)]
+        );
+        assert_eq!(
+            matcher
+                .keyword_counts(text)
+                .iter()
+                .map(|(_, n)| n)
+                .sum::<usize>(),
+            matcher.count_matches_in_text(text)
+        );
+
+        // The groups of regular expressions do not shift the groups of the keywords.
+        let matcher: Matcher =
+            Matcher::keywords_matcher([r"(a|b)+c", r"d(e)?"], false, false, true)?;
+        assert_eq!(
+            matcher.keyword_counts(b"abc d de bc"),
+            [(r"(a|b)+c", 2), (r"d(e)?", 2)]
+        );
+
+        assert!(Matcher::empty_matcher().keyword_counts(text).is_empty());
+        assert!(Matcher::words_matcher().keyword_counts(text).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn shared_header_extension_test() -> Result<()> {
+        // C and C++ may both declare the .h headers, which keep the language loaded first.
+        let keyword_files: KeywordFiles = KeywordFiles::new(false).add_files(
+            &[
+                "tests/data/keywords/headers/c.json",
+                "tests/data/keywords/headers/c++.json",
+            ],
+            false,
+        )?;
+        assert_eq!(keyword_files.extensions_to_language["h"], "c");
+        assert_eq!(keyword_files.extensions_to_language["hpp"], "c++");
+        assert_eq!(
+            keyword_files.count_matches_in_any_language(b"A Float library, double checked."),
+            vec![2, 2]
+        );
+
+        assert!(shared_extension("h", "c++", "C"));
+        assert!(!shared_extension("c", "c", "c++"));
+        assert!(!shared_extension("h", "c", "objective-c"));
+        Ok(())
+    }
 }
@@ -0,0 +1,210 @@
+// Copyright 2026 Andrea Gilot
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Human-readable reports summarizing a run of a subcommand, rendered in Markdown or HTML.
+//!
+//! A report is a title followed by sections, each of them a list of named values or a two-column table.
+
+use clap::Arg;
+
+/// Argument of the path to the report of a run.
+pub fn report_arg() -> Arg {
+    Arg::new("report")
+        .long("report")
+        .value_name("REPORT.md")
+        .help("Write a summary of the run (inputs, options, counts, runtime and most frequent errors) to this file once it ends, \
+               in HTML if the path ends with .html and in Markdown otherwise.")
+        .global(true)
+}
+
+/// A report made of sections.
+pub struct Report {
+    /// Title of the report.
+    title: String,
+    /// Sections of the report, in order.
+    sections: Vec<Section>,
+}
+
+/// A section of a report.
+pub struct Section {
+    /// Title of the section.
+    title: String,
+    /// Headers of the columns, or `None` if the rows are rendered as a list of named values.
+    header: Option<[String; 2]>,
+    /// Rows of the section.
+    rows: Vec<(String, String)>,
+}
+
+impl Report {
+    /// Creates an empty report.
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            sections: Vec::new(),
+        }
+    }
+
+    /// Appends a section to the report.
+    pub fn add(&mut self, section: Section) {
+        self.sections.push(section);
+    }
+
+    /// Renders the report in Markdown.
+    pub fn to_markdown(&self) -> String {
+        let mut out: String = format!("# {}\n", self.title);
+        for section in &self.sections {
+            out.push_str(&format!("\n## {}\n\n", section.title));
+            if section.rows.is_empty() {
+                out.push_str("None.\n");
+                continue;
+            }
+            match &section.header {
+                Some([first, second]) => {
+                    out.push_str(&format!(
+                        "| {} | {} |\n",
+                        md_escape(first),
+                        md_escape(second)
+                    ));
+                    out.push_str("| --- | --- |\n");
+                    for (key, value) in &section.rows {
+                        out.push_str(&format!("| {} | {} |\n", md_escape(key), md_escape(value)));
+                    }
+                }
+                None => {
+                    for (key, value) in &section.rows {
+                        out.push_str(&format!("- **{}**: {}\n", md_escape(key), md_escape(value)));
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// Renders the report as a standalone HTML page.
+    pub fn to_html(&self) -> String {
+        let title: String = html_escape(&self.title);
+        let mut out: String = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n</head>\n<body>\n<h1>{title}</h1>\n"
+        );
+        for section in &self.sections {
+            out.push_str(&format!("<h2>{}</h2>\n", html_escape(&section.title)));
+            if section.rows.is_empty() {
+                out.push_str("<p>None.</p>\n");
+                continue;
+            }
+            match &section.header {
+                Some([first, second]) => {
+                    out.push_str(&format!(
+                        "<table>\n<tr><th>{}</th><th>{}</th></tr>\n",
+                        html_escape(first),
+                        html_escape(second)
+                    ));
+                    for (key, value) in &section.rows {
+                        out.push_str(&format!(
+                            "<tr><td>{}</td><td>{}</td></tr>\n",
+                            html_escape(key),
+                            html_escape(value)
+                        ));
+                    }
+                    out.push_str("</table>\n");
+                }
+                None => {
+                    out.push_str("<ul>\n");
+                    for (key, value) in &section.rows {
+                        out.push_str(&format!(
+                            "<li><b>{}</b>: {}</li>\n",
+                            html_escape(key),
+                            html_escape(value)
+                        ));
+                    }
+                    out.push_str("</ul>\n");
+                }
+            }
+        }
+        out.push_str("</body>\n</html>\n");
+        out
+    }
+}
+
+impl Section {
+    /// Creates a section listing named values.
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            header: None,
+            rows: Vec::new(),
+        }
+    }
+
+    /// Creates a section with a two-column table.
+    ///
+    /// # Arguments
+    ///
+    /// * `title` - The title of the section.
+    /// * `header` - The headers of the two columns.
+    pub fn table(title: impl Into<String>, header: [&str; 2]) -> Self {
+        Self {
+            title: title.into(),
+            header: Some(header.map(|h| h.to_string())),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Appends rows to the section.
+    pub fn rows<K: Into<String>>(mut self, rows: impl IntoIterator<Item = (K, String)>) -> Self {
+        self.rows
+            .extend(rows.into_iter().map(|(key, value)| (key.into(), value)));
+        self
+    }
+}
+
+/// Escapes the characters of a table cell that have a meaning in Markdown.
+fn md_escape(s: &str) -> String {
+    s.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+/// Escapes the characters that have a meaning in HTML.
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_test() {
+        let mut report: Report = Report::new("scyros sample");
+        report.add(Section::new("Run").rows([("Status", "completed".to_string())]));
+        report.add(Section::table("Counts", ["", "Count"]).rows([("a|b", "3".to_string())]));
+        report.add(Section::table(
+            "Top error categories",
+            ["Category", "Errors"],
+        ));
+
+        assert_eq!(
+            report.to_markdown(),
+            "# scyros sample\n\n## Run\n\n- **Status**: completed\n\n\
+             ## Counts\n\n|  | Count |\n| --- | --- |\n| a\\|b | 3 |\n\n\
+             ## Top error categories\n\nNone.\n"
+        );
+        let html: String = report.to_html();
+        assert!(html.contains("<li><b>Status</b>: completed</li>"));
+        assert!(html.contains("<tr><td>a|b</td><td>3</td></tr>"));
+        assert!(html.contains("<p>None.</p>"));
+    }
+}
@@ -0,0 +1,408 @@
+// Copyright 2026 Andrea Gilot
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Machine-readable description of a run of a subcommand, written next to each of its output files.
+//!
+//! For an output file `<output>`, the sidecar file `<output>.meta.json` records the version of scyros,
+//! the subcommand and all its resolved options, the seed and the master seed, the size and hash of the input files,
+//! the counts and error categories recorded by the subcommand, and the start and end of the run.
+//! With the global `--telemetry` option, it also records the resources used by the run and by each of its stages.
+//! The same information can be rendered as a human-readable report with the global `--report` option.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use clap::ArgMatches;
+use json::JsonValue;
+
+use crate::utils::fs::{hash_file, write_file};
+use crate::utils::report::{Report, Section};
+use crate::utils::telemetry::Telemetry;
+
+/// Description of a run of a subcommand.
+pub struct RunManifest {
+    /// Name of the subcommand.
+    command: String,
+    /// Values of the options of the subcommand, including default values.
+    options: JsonValue,
+    /// Start of the run.
+    started: DateTime<Utc>,
+    /// Files read by the run.
+    inputs: Vec<String>,
+    /// Files written by the run.
+    outputs: Vec<String>,
+    /// Quantities counted by the run, in the order they were first recorded.
+    counts: Vec<(String, u64)>,
+    /// Number of errors of each category, in the order they first occurred.
+    errors: Vec<(String, u64)>,
+    /// Resources used by the run, if they are recorded.
+    telemetry: Option<Telemetry>,
+}
+
+impl RunManifest {
+    /// Starts the description of a run.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The name of the subcommand.
+    /// * `args` - The parsed arguments of the subcommand.
+    pub fn new(command: &str, args: &ArgMatches) -> Self {
+        let mut options: JsonValue = JsonValue::new_object();
+        for id in args.ids() {
+            if let Some(raw) = args.get_raw(id.as_str()) {
+                let values: Vec<JsonValue> = raw
+                    .map(|v| JsonValue::from(v.to_string_lossy().to_string()))
+                    .collect();
+                options[id.as_str()] = match <[JsonValue; 1]>::try_from(values) {
+                    Ok([value]) => value,
+                    Err(values) => JsonValue::Array(values),
+                };
+            }
+        }
+        Self {
+            command: command.to_string(),
+            options,
+            started: Utc::now(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            counts: Vec::new(),
+            errors: Vec::new(),
+            telemetry: matches!(args.try_get_one::<bool>("telemetry"), Ok(Some(true)))
+                .then(Telemetry::new),
+        }
+    }
+
+    /// Records files read by the run.
+    pub fn add_inputs(&mut self, paths: &[&str]) {
+        self.inputs.extend(paths.iter().map(|p| p.to_string()));
+    }
+
+    /// Records files written by the run.
+    pub fn add_outputs(&mut self, paths: &[&str]) {
+        self.outputs.extend(paths.iter().map(|p| p.to_string()));
+    }
+
+    /// Adds to a quantity counted by the run.
+    pub fn add_count(&mut self, name: &str, n: u64) {
+        increment(&mut self.counts, name, n);
+    }
+
+    /// Records an error of the given category.
+    pub fn add_error(&mut self, category: &str) {
+        increment(&mut self.errors, category, 1);
+    }
+
+    /// Ends the current stage of the run, if any, and starts a new one whose resources are recorded with the telemetry.
+    pub fn begin_stage(&mut self, name: &str) {
+        if let Some(telemetry) = self.telemetry.as_mut() {
+            telemetry.begin_stage(name);
+        }
+    }
+
+    /// Ends the current stage of the run, if any.
+    pub fn end_stage(&mut self) {
+        if let Some(telemetry) = self.telemetry.as_mut() {
+            telemetry.end_stage();
+        }
+    }
+
+    /// Returns the resources used by the run, if they are recorded.
+    pub fn telemetry(&self) -> Option<&Telemetry> {
+        self.telemetry.as_ref()
+    }
+
+    /// Returns the description of the run.
+    ///
+    /// # Arguments
+    ///
+    /// * `finished` - The end of the run.
+    /// * `success` - Whether the run completed successfully.
+    fn to_json(&self, finished: DateTime<Utc>, success: bool) -> JsonValue {
+        let inputs: Vec<JsonValue> = self
+            .inputs
+            .iter()
+            .map(|path| match hash_file(path) {
+                Ok((size, hash)) => {
+                    json::object! { "path": path.as_str(), "size": size, "blake3": hash }
+                }
+                // Directories and files that could not be read are recorded without hash.
+                Err(_) => json::object! { "path": path.as_str(), "size": null, "blake3": null },
+            })
+            .collect();
+        let seed: Option<u64> = self.options["seed"].as_str().and_then(|s| s.parse().ok());
+        let master_seed: Option<u64> = self.options["master-seed"]
+            .as_str()
+            .and_then(|s| s.parse().ok());
+        json::object! {
+            "version": env!("CARGO_PKG_VERSION"),
+            "command": self.command.as_str(),
+            "options": self.options.clone(),
+            "seed": seed,
+            "master_seed": master_seed,
+            "inputs": inputs,
+            "outputs": self.outputs.clone(),
+            "counts": counters_to_json(&self.counts),
+            "errors": counters_to_json(&self.errors),
+            "started": self.started.to_rfc3339_opts(SecondsFormat::Secs, true),
+            "finished": finished.to_rfc3339_opts(SecondsFormat::Secs, true),
+            "success": success,
+            "telemetry": self.telemetry.as_ref().map(Telemetry::to_json),
+        }
+    }
+
+    /// Writes the description of the run next to every output file that exists.
+    ///
+    /// # Arguments
+    ///
+    /// * `success` - Whether the run completed successfully.
+    pub fn write(&self, success: bool) -> Result<()> {
+        let content: String = self.to_json(Utc::now(), success).pretty(2);
+        for output in self.outputs.iter().filter(|o| Path::new(o).is_file()) {
+            write_file(format!("{output}.meta.json"), &content)
+                .with_context(|| format!("Could not write the run manifest of {output}"))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the human-readable summary of the run.
+    ///
+    /// # Arguments
+    ///
+    /// * `finished` - The end of the run.
+    /// * `error` - The error that ended the run, if it failed.
+    fn to_report(&self, finished: DateTime<Utc>, error: Option<&str>) -> Report {
+        let mut report: Report = Report::new(format!("scyros {}", self.command));
+
+        let runtime: i64 = (finished - self.started).num_seconds();
+        report.add(Section::new("Run").rows([
+            ("Version", env!("CARGO_PKG_VERSION").to_string()),
+            (
+                "Status",
+                match error {
+                    Some(e) => format!("failed: {e}"),
+                    None => "completed".to_string(),
+                },
+            ),
+            (
+                "Started",
+                self.started.to_rfc3339_opts(SecondsFormat::Secs, true),
+            ),
+            (
+                "Finished",
+                finished.to_rfc3339_opts(SecondsFormat::Secs, true),
+            ),
+            (
+                "Runtime",
+                format!(
+                    "{}h {:02}m {:02}s",
+                    runtime / 3600,
+                    runtime / 60 % 60,
+                    runtime % 60
+                ),
+            ),
+        ]));
+        report.add(Section::table("Options", ["Option", "Value"]).rows(
+            self.options.entries().map(|(option, value)| {
+                let value: String = value.as_str().map_or_else(|| value.dump(), str::to_string);
+                (option, value)
+            }),
+        ));
+        report.add(Section::table("Inputs", ["File", "Size (bytes)"]).rows(
+            self.inputs.iter().map(|path| {
+                let size: String = std::fs::metadata(path)
+                    .ok()
+                    .filter(|m| m.is_file())
+                    .map_or_else(|| "-".to_string(), |m| m.len().to_string());
+                (path.as_str(), size)
+            }),
+        ));
+        report.add(
+            Section::table("Outputs", ["File", "Written"]).rows(self.outputs.iter().map(|path| {
+                let written: &str = if Path::new(path).is_file() {
+                    "yes"
+                } else {
+                    "no"
+                };
+                (path.as_str(), written.to_string())
+            })),
+        );
+        report.add(
+            Section::table("Counts", ["", "Count"]).rows(
+                self.counts
+                    .iter()
+                    .map(|(name, n)| (name.as_str(), n.to_string())),
+            ),
+        );
+
+        let mut errors: Vec<&(String, u64)> = self.errors.iter().collect();
+        // Stable sort: categories with the same number of errors stay in order of occurrence.
+        errors.sort_by(|a, b| b.1.cmp(&a.1));
+        let others: u64 = errors.iter().skip(TOP_ERRORS).map(|(_, n)| n).sum();
+        let mut section: Section = Section::table("Top error categories", ["Category", "Errors"])
+            .rows(
+                errors
+                    .iter()
+                    .take(TOP_ERRORS)
+                    .map(|(category, n)| (category.as_str(), n.to_string())),
+            );
+        if others > 0 {
+            section = section.rows([(
+                format!("{} other categories", errors.len() - TOP_ERRORS),
+                others.to_string(),
+            )]);
+        }
+        report.add(section);
+        if let Some(telemetry) = self.telemetry.as_ref() {
+            report.add(telemetry.to_section());
+        }
+        report
+    }
+
+    /// Writes the human-readable summary of the run, in HTML if the path ends with `.html` and in Markdown otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the report.
+    /// * `error` - The error that ended the run, if it failed.
+    pub fn write_report(&self, path: &str, error: Option<&str>) -> Result<()> {
+        let report: Report = self.to_report(Utc::now(), error);
+        let content: String = if path.ends_with(".html") || path.ends_with(".htm") {
+            report.to_html()
+        } else {
+            report.to_markdown()
+        };
+        write_file(path, &content).with_context(|| format!("Could not write the report {path}"))
+    }
+}
+
+/// Maximum number of error categories listed in the report, the others are summed up in a single row.
+const TOP_ERRORS: usize = 10;
+
+/// Returns counters as a JSON object mapping each key to its count.
+fn counters_to_json(counters: &[(String, u64)]) -> JsonValue {
+    let mut object: JsonValue = JsonValue::new_object();
+    for (key, count) in counters {
+        object[key.as_str()] = (*count).into();
+    }
+    object
+}
+
+/// Adds `n` to the counter of a key, which is appended if it is new.
+fn increment(counters: &mut Vec<(String, u64)>, key: &str, n: u64) {
+    match counters.iter_mut().find(|(k, _)| k == key) {
+        Some((_, count)) => *count += n,
+        None => counters.push((key.to_string(), n)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fs::delete_file;
+    use crate::utils::json::open_json_from_path;
+    use clap::{Arg, ArgAction, Command};
+
+    const TEST_DATA: &str = "tests/data/run_manifest";
+
+    #[test]
+    fn run_manifest_test() -> Result<()> {
+        let input_path: String = format!("{TEST_DATA}/input.csv");
+        let output_path: String = format!("{TEST_DATA}/output.csv");
+        let meta_path: String = format!("{output_path}.meta.json");
+
+        let args = Command::new("sample")
+            .arg(Arg::new("input").long("input"))
+            .arg(Arg::new("seed").long("seed").default_value("42"))
+            .arg(Arg::new("force").long("force").action(ArgAction::SetTrue))
+            .try_get_matches_from(["sample", "--input", &input_path])?;
+
+        let mut manifest = RunManifest::new("sample", &args);
+        manifest.add_inputs(&[&input_path]);
+        manifest.add_outputs(&[&output_path, "missing.csv"]);
+
+        write_file(&output_path, "id\n1\n")?;
+        manifest.write(true)?;
+        delete_file(&output_path, false)?;
+
+        let meta: JsonValue = open_json_from_path(&meta_path)?;
+        delete_file(&meta_path, false)?;
+        assert!(!Path::new("missing.csv.meta.json").exists());
+
+        assert_eq!(meta["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(meta["command"], "sample");
+        assert_eq!(meta["options"]["input"], input_path.as_str());
+        assert_eq!(meta["options"]["force"], "false");
+        assert_eq!(meta["seed"], 42);
+        assert!(meta["master_seed"].is_null());
+        assert_eq!(meta["inputs"][0]["path"], input_path.as_str());
+        assert_eq!(meta["inputs"][0]["size"], 9);
+        assert_eq!(
+            meta["inputs"][0]["blake3"],
+            hash_file(&input_path)?.1.as_str()
+        );
+        assert_eq!(meta["outputs"][1], "missing.csv");
+        assert!(meta["started"].as_str() <= meta["finished"].as_str());
+        assert_eq!(meta["success"], true);
+        assert!(meta["telemetry"].is_null());
+        Ok(())
+    }
+
+    #[test]
+    fn run_report_test() -> Result<()> {
+        let report_path: String = format!("{TEST_DATA}/report.md");
+        let args = Command::new("sample")
+            .arg(Arg::new("seed").long("seed").default_value("42"))
+            .arg(
+                Arg::new("telemetry")
+                    .long("telemetry")
+                    .action(ArgAction::SetTrue),
+            )
+            .try_get_matches_from(["sample", "--telemetry"])?;
+
+        let mut manifest = RunManifest::new("sample", &args);
+        manifest.add_inputs(&[&format!("{TEST_DATA}/input.csv")]);
+        manifest.add_count("projects processed", 2);
+        manifest.add_count("projects processed", 1);
+        for _ in 0..2 {
+            manifest.add_error("HTTP 404 Not Found");
+        }
+        manifest.add_error("interrupted download");
+        manifest.begin_stage("Loading tokens");
+        manifest.end_stage();
+        for i in 0..TOP_ERRORS {
+            manifest.add_error(&format!("rare error {i}"));
+        }
+
+        manifest.write_report(&report_path, Some("network unreachable"))?;
+        let report: String = std::fs::read_to_string(&report_path)?;
+        delete_file(&report_path, false)?;
+
+        assert!(report.starts_with("# scyros sample\n"));
+        assert!(report.contains("- **Status**: failed: network unreachable\n"));
+        assert!(report.contains("| seed | 42 |\n"));
+        assert!(report.contains(&format!("| {TEST_DATA}/input.csv | 9 |\n")));
+        assert!(report.contains("| projects processed | 3 |\n"));
+        // The most frequent categories come first and the rarest are summed up.
+        assert!(report.contains(
+            "| Category | Errors |\n| --- | --- |\n| HTTP 404 Not Found | 2 |\n| interrupted download | 1 |\n"
+        ));
+        assert!(report.contains("| 2 other categories | 2 |\n"));
+        assert!(
+            report.contains("| Stage | Resources |\n| --- | --- |\n| Loading tokens | wall time ")
+        );
+        assert!(report.contains("| Total | wall time "));
+        Ok(())
+    }
+}
@@ -0,0 +1,179 @@
+// Copyright 2026 Andrea Gilot
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Derivation of the seeds of the subcommands from a single master seed.
+//!
+//! With `--master-seed SEED`, the default value of the `--seed` option of every subcommand is replaced by a seed
+//! derived from the master seed and the name of the subcommand, so that a whole pipeline is reproducible from one number.
+//! The seeds take precedence in the following order: `--seed` given on the command line, then the seed derived from
+//! the master seed, then the seed of the configuration file, and finally the default seed of the subcommand.
+
+use std::ffi::OsString;
+
+use anyhow::{Context, Result};
+use clap::parser::ValueSource;
+use clap::{Arg, ArgMatches, Command};
+use tracing::info;
+
+/// Argument giving the master seed.
+pub fn master_seed_arg() -> Arg {
+    Arg::new("master-seed")
+        .long("master-seed")
+        .value_name("SEED")
+        .help("Master seed from which the seed of every subcommand is derived, unless --seed is given. \
+               The derived seed only depends on the master seed and the name of the subcommand.")
+        .value_parser(clap::value_parser!(u64))
+        .global(true)
+}
+
+/// Derives the seed of a subcommand from the master seed.
+/// The seed is made of the first 8 bytes of the BLAKE3 hash of the master seed and the name of the subcommand,
+/// so that the subcommands get independent seeds that do not change between versions.
+///
+/// # Arguments
+///
+/// * `master_seed` - The master seed.
+/// * `subcommand` - The name of the subcommand.
+pub fn derive_seed(master_seed: u64, subcommand: &str) -> u64 {
+    let hash: blake3::Hash = blake3::hash(format!("{master_seed}:{subcommand}").as_bytes());
+    // Safe unwrap: the hash is 32 bytes long.
+    u64::from_le_bytes(hash.as_bytes()[..8].try_into().unwrap())
+}
+
+/// Replaces the default seed of every subcommand with the seed derived from the master seed given with
+/// --master-seed in the command line arguments, if any.
+///
+/// # Arguments
+///
+/// * `cli` - The command whose subcommands are configured.
+/// * `args` - The command line arguments, which are not parsed yet.
+///
+/// # Returns
+///
+/// The configured command, or an error if the master seed is not a valid seed.
+pub fn apply_master_seed(
+    cli: Command,
+    args: impl IntoIterator<Item = OsString>,
+) -> Result<Command> {
+    let mut args = args.into_iter();
+    let mut master_seed: Option<String> = None;
+    while let Some(arg) = args.next() {
+        let arg: String = arg.to_string_lossy().to_string();
+        if arg == "--master-seed" {
+            master_seed = args.next().map(|s| s.to_string_lossy().to_string());
+        } else if let Some(s) = arg.strip_prefix("--master-seed=") {
+            master_seed = Some(s.to_string());
+        } else if arg == "--" {
+            break;
+        }
+    }
+    let Some(master_seed) = master_seed else {
+        return Ok(cli);
+    };
+    let master_seed: u64 = master_seed
+        .parse()
+        .with_context(|| format!("Invalid master seed '{master_seed}'"))?;
+
+    let subcommands: Vec<String> = cli
+        .get_subcommands()
+        .filter(|s| s.get_arguments().any(|a| a.get_id() == "seed"))
+        .map(|s| s.get_name().to_string())
+        .collect();
+    Ok(subcommands.iter().fold(cli, |cli, name| {
+        cli.mut_subcommand(name, |s| {
+            s.mut_arg("seed", |a| {
+                a.default_value(derive_seed(master_seed, name).to_string())
+            })
+        })
+    }))
+}
+
+/// Logs where the seed of a subcommand comes from when a master seed is given.
+///
+/// # Arguments
+///
+/// * `subcommand` - The name of the subcommand.
+/// * `args` - The parsed arguments of the subcommand.
+pub fn log_master_seed(subcommand: &str, args: &ArgMatches) {
+    let (Some(master_seed), Ok(Some(seed))) = (
+        args.get_one::<u64>("master-seed"),
+        args.try_get_one::<u64>("seed"),
+    ) else {
+        return;
+    };
+    if args.value_source("seed") == Some(ValueSource::CommandLine) {
+        info!("The seed {seed} given with --seed overrides the master seed {master_seed}.");
+    } else {
+        info!("Seed {seed} of {subcommand} derived from the master seed {master_seed}.");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_cli() -> Command {
+        Command::new("test")
+            .subcommand(
+                Command::new("download").arg(
+                    Arg::new("seed")
+                        .long("seed")
+                        .default_value("1")
+                        .value_parser(clap::value_parser!(u64)),
+                ),
+            )
+            .subcommand(Command::new("merge"))
+            .arg(master_seed_arg())
+    }
+
+    #[test]
+    fn master_seed_test() -> Result<()> {
+        assert_eq!(derive_seed(42, "download"), derive_seed(42, "download"));
+        assert_ne!(derive_seed(42, "download"), derive_seed(42, "parse"));
+        assert_ne!(derive_seed(42, "download"), derive_seed(43, "download"));
+
+        let seed = |args: &[&str]| -> Result<u64> {
+            let args: Vec<OsString> = args.iter().map(OsString::from).collect();
+            let matches: ArgMatches =
+                apply_master_seed(test_cli(), args.clone())?.try_get_matches_from(args)?;
+            Ok(*matches
+                .subcommand_matches("download")
+                .unwrap()
+                .get_one::<u64>("seed")
+                .unwrap())
+        };
+        assert_eq!(seed(&["test", "download"])?, 1);
+        assert_eq!(
+            seed(&["test", "--master-seed", "42", "download"])?,
+            derive_seed(42, "download")
+        );
+        assert_eq!(
+            seed(&["test", "download", "--master-seed=42"])?,
+            derive_seed(42, "download")
+        );
+        // An explicit seed overrides the master seed.
+        assert_eq!(
+            seed(&["test", "download", "--master-seed", "42", "--seed", "7"])?,
+            7
+        );
+        assert!(seed(&["test", "--master-seed", "x", "download"]).is_err());
+
+        // Subcommands without seed are left unchanged.
+        let args = ["test", "--master-seed", "42", "merge"].map(OsString::from);
+        assert!(apply_master_seed(test_cli(), args.clone())?
+            .try_get_matches_from(args)
+            .is_ok());
+        Ok(())
+    }
+}
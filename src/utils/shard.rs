@@ -0,0 +1,162 @@
+// Copyright 2026 Andrea Gilot
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Partitioning of the input of a subcommand into shards processed by independent runs, e.g. on different machines.
+//!
+//! A run with `--shard K/N` only processes the K-th of N disjoint parts of its input and writes its output files
+//! with the `.shard-K-of-N` suffix, so that the runs of all shards can share a directory.
+//! The outputs of the shards are combined afterwards with the `merge` subcommand.
+
+use std::fmt::{self, Display};
+
+use anyhow::{ensure, Context, Result};
+use clap::Arg;
+
+/// One of the disjoint parts of the input of a subcommand.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Shard {
+    /// Index of the shard, between 1 and `count`.
+    index: usize,
+    /// Number of shards.
+    count: usize,
+}
+
+impl Shard {
+    /// Creates a shard.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the shard, between 1 and `count`.
+    /// * `count` - The number of shards.
+    pub fn new(index: usize, count: usize) -> Result<Self> {
+        ensure!(count > 0, "The number of shards must be positive");
+        ensure!(
+            (1..=count).contains(&index),
+            "The shard index must be between 1 and {count}"
+        );
+        Ok(Self { index, count })
+    }
+
+    /// Parses a shard written as `K/N`.
+    pub fn parse(s: &str) -> Result<Self> {
+        let (index, count) = s
+            .split_once('/')
+            .with_context(|| format!("Invalid shard '{s}', expected K/N"))?;
+        Self::new(
+            index
+                .trim()
+                .parse()
+                .with_context(|| format!("Invalid shard index '{index}'"))?,
+            count
+                .trim()
+                .parse()
+                .with_context(|| format!("Invalid number of shards '{count}'"))?,
+        )
+    }
+
+    /// Whether the item with the given key belongs to the shard.
+    /// Consecutive keys are assigned to the shards in turn.
+    pub fn contains(&self, key: u64) -> bool {
+        key % self.count as u64 == (self.index - 1) as u64
+    }
+
+    /// Keeps the items of the shard, identified by their position in the list.
+    pub fn select<T>(&self, items: Vec<T>) -> Vec<T> {
+        items
+            .into_iter()
+            .enumerate()
+            .filter(|(position, _)| self.contains(*position as u64))
+            .map(|(_, item)| item)
+            .collect()
+    }
+
+    /// Returns the path of an output file of the shard.
+    /// The suffix `.shard-K-of-N` is inserted before the `.csv` extension, or appended if there is none.
+    pub fn path(&self, path: &str) -> String {
+        shard_path(path, self.index, self.count)
+    }
+}
+
+impl Display for Shard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.index, self.count)
+    }
+}
+
+/// Returns the path of an output file, with the suffix of the shard if there is one.
+///
+/// # Arguments
+///
+/// * `path` - The path of the output file without shards.
+/// * `shard` - The optional shard processed by the run.
+pub fn with_shard(path: &str, shard: Option<Shard>) -> String {
+    match shard {
+        Some(shard) => shard.path(path),
+        None => path.to_string(),
+    }
+}
+
+/// Returns the path of an output file written by the K-th of N shards.
+///
+/// # Arguments
+///
+/// * `path` - The path of the output file without shards.
+/// * `index` - The index of the shard, between 1 and `count`.
+/// * `count` - The number of shards.
+pub fn shard_path(path: &str, index: usize, count: usize) -> String {
+    let suffix: String = format!(".shard-{index}-of-{count}");
+    match path.strip_suffix(".csv") {
+        Some(stem) => format!("{stem}{suffix}.csv"),
+        None => format!("{path}{suffix}"),
+    }
+}
+
+/// Argument selecting the shard processed by a subcommand.
+pub fn shard_arg() -> Arg {
+    Arg::new("shard")
+        .long("shard")
+        .value_name("K/N")
+        .help("Only process the K-th of N disjoint parts of the input, with K between 1 and N, and add the suffix .shard-K-of-N to the output files. \
+               The outputs of all shards can be combined with the merge command.")
+        .value_parser(|s: &str| Shard::parse(s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shard_test() -> Result<()> {
+        let shard: Shard = Shard::parse("2/3")?;
+        assert_eq!(shard, Shard::new(2, 3)?);
+        assert_eq!(shard.select((0..8).collect()), vec![1, 4, 7]);
+
+        // Every item belongs to exactly one shard.
+        let shards: Vec<Shard> = (1..=3).map(|k| Shard::new(k, 3)).collect::<Result<_>>()?;
+        for key in 0..20 {
+            assert_eq!(shards.iter().filter(|s| s.contains(key)).count(), 1);
+        }
+
+        assert!(Shard::parse("0/3").is_err());
+        assert!(Shard::parse("4/3").is_err());
+        assert!(Shard::parse("1/0").is_err());
+        assert!(Shard::parse("3").is_err());
+
+        assert_eq!(shard.path("out/files.csv"), "out/files.shard-2-of-3.csv");
+        assert_eq!(shard.path("out/files"), "out/files.shard-2-of-3");
+        assert_eq!(with_shard("out/files.csv", None), "out/files.csv");
+        assert_eq!(shard.to_string(), "2/3");
+        Ok(())
+    }
+}
@@ -0,0 +1,242 @@
+// Copyright 2026 Andrea Gilot
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Resources used by a run of a subcommand, recorded with the global `--telemetry` option.
+//!
+//! The wall time, the CPU time of all the threads and the peak resident memory of the process are recorded for the
+//! whole run and for each of its stages, i.e. the tasks logged by the subcommand and the processing of its items.
+//! They are added to the run manifest and to the report, and logged when the run ends.
+//! The CPU time and the memory are only available on Unix.
+
+use std::time::{Duration, Instant};
+
+use clap::{Arg, ArgAction};
+use json::JsonValue;
+
+use crate::utils::report::Section;
+
+/// Argument enabling the telemetry.
+pub fn telemetry_arg() -> Arg {
+    Arg::new("telemetry")
+        .long("telemetry")
+        .help(
+            "Record the wall time, CPU time and peak memory of the run and of each of its stages \
+             in the run manifest and the report, and log them when the run ends.",
+        )
+        .action(ArgAction::SetTrue)
+        .global(true)
+}
+
+/// Resources used by the process so far.
+#[derive(Clone, Copy, Debug)]
+struct Usage {
+    /// When the usage was measured.
+    instant: Instant,
+    /// CPU time of all the threads, in user and system mode.
+    cpu_time: Option<Duration>,
+    /// Peak resident memory in bytes.
+    peak_rss: Option<u64>,
+}
+
+impl Usage {
+    /// Measures the resources used by the process so far.
+    fn now() -> Self {
+        let (cpu_time, peak_rss) = match rusage() {
+            Some((cpu_time, peak_rss)) => (Some(cpu_time), Some(peak_rss)),
+            None => (None, None),
+        };
+        Self {
+            instant: Instant::now(),
+            cpu_time,
+            peak_rss,
+        }
+    }
+}
+
+/// Returns the CPU time and the peak resident memory in bytes of the process.
+#[cfg(unix)]
+fn rusage() -> Option<(Duration, u64)> {
+    let mut usage = std::mem::MaybeUninit::<libc::rusage>::zeroed();
+    // SAFETY: getrusage only writes to the structure, which is initialized when it succeeds.
+    let usage: libc::rusage = unsafe {
+        if libc::getrusage(libc::RUSAGE_SELF, usage.as_mut_ptr()) != 0 {
+            return None;
+        }
+        usage.assume_init()
+    };
+    let time = |t: libc::timeval| {
+        Duration::from_secs(t.tv_sec as u64) + Duration::from_micros(t.tv_usec as u64)
+    };
+    // The peak memory is in bytes on macOS and in kilobytes on the other systems.
+    let unit: u64 = if cfg!(target_os = "macos") { 1 } else { 1024 };
+    Some((
+        time(usage.ru_utime) + time(usage.ru_stime),
+        usage.ru_maxrss as u64 * unit,
+    ))
+}
+
+#[cfg(not(unix))]
+fn rusage() -> Option<(Duration, u64)> {
+    None
+}
+
+/// Resources used by a stage of a run, or by the whole run.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stage {
+    /// Name of the stage.
+    pub name: String,
+    /// Wall time of the stage.
+    pub wall_time: Duration,
+    /// CPU time of all the threads during the stage, if available.
+    pub cpu_time: Option<Duration>,
+    /// Peak resident memory of the process in bytes at the end of the stage, if available.
+    pub peak_rss: Option<u64>,
+}
+
+impl Stage {
+    /// Returns the resources used between two measures.
+    fn between(name: &str, start: &Usage, end: &Usage) -> Self {
+        Self {
+            name: name.to_string(),
+            wall_time: end.instant - start.instant,
+            cpu_time: end.cpu_time.zip(start.cpu_time).map(|(e, s)| e - s),
+            peak_rss: end.peak_rss,
+        }
+    }
+
+    fn to_json(&self) -> JsonValue {
+        json::object! {
+            "name": self.name.as_str(),
+            "wall_time_s": self.wall_time.as_secs_f64(),
+            "cpu_time_s": self.cpu_time.map(|t| t.as_secs_f64()),
+            "peak_rss_bytes": self.peak_rss,
+        }
+    }
+
+    /// Returns the resources used as a short human-readable text.
+    pub fn summary(&self) -> String {
+        format!(
+            "wall time {:.1} s, CPU time {}, peak memory {}",
+            self.wall_time.as_secs_f64(),
+            self.cpu_time
+                .map_or_else(|| "-".to_string(), |t| format!("{:.1} s", t.as_secs_f64())),
+            self.peak_rss.map_or_else(
+                || "-".to_string(),
+                |b| format!("{:.1} MiB", b as f64 / (1024.0 * 1024.0))
+            )
+        )
+    }
+}
+
+/// Resources used by a run and by each of its stages.
+pub struct Telemetry {
+    /// Usage at the start of the run.
+    start: Usage,
+    /// Stages that ended, in order.
+    stages: Vec<Stage>,
+    /// Name and usage at the start of the current stage, if any.
+    current: Option<(String, Usage)>,
+}
+
+impl Telemetry {
+    /// Starts recording the resources used by a run.
+    pub fn new() -> Self {
+        Self {
+            start: Usage::now(),
+            stages: Vec::new(),
+            current: None,
+        }
+    }
+
+    /// Ends the current stage, if any, and starts a new one.
+    pub fn begin_stage(&mut self, name: &str) {
+        self.end_stage();
+        self.current = Some((name.to_string(), Usage::now()));
+    }
+
+    /// Ends the current stage, if any.
+    pub fn end_stage(&mut self) {
+        if let Some((name, start)) = self.current.take() {
+            self.stages
+                .push(Stage::between(&name, &start, &Usage::now()));
+        }
+    }
+
+    /// Returns the stages that ended, in order.
+    pub fn stages(&self) -> &[Stage] {
+        &self.stages
+    }
+
+    /// Returns the resources used by the whole run so far.
+    pub fn total(&self) -> Stage {
+        Stage::between("Total", &self.start, &Usage::now())
+    }
+
+    /// Returns the resources used by the run and its stages, as recorded in the run manifest.
+    pub fn to_json(&self) -> JsonValue {
+        let mut total: JsonValue = self.total().to_json();
+        total["stages"] = JsonValue::Array(self.stages.iter().map(Stage::to_json).collect());
+        total.remove("name");
+        total
+    }
+
+    /// Returns the section of the report listing the resources used by the run and its stages.
+    pub fn to_section(&self) -> Section {
+        Section::table("Telemetry", ["Stage", "Resources"]).rows(
+            self.stages
+                .iter()
+                .cloned()
+                .chain([self.total()])
+                .map(|stage| (stage.name.clone(), stage.summary())),
+        )
+    }
+}
+
+impl Default for Telemetry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn telemetry_test() {
+        let mut telemetry: Telemetry = Telemetry::new();
+        telemetry.begin_stage("Loading");
+        std::thread::sleep(Duration::from_millis(20));
+        // A new stage ends the previous one.
+        telemetry.begin_stage("Processing");
+        let _ = (0..1_000_000u64).sum::<u64>();
+        telemetry.end_stage();
+        telemetry.end_stage();
+
+        let names: Vec<&str> = telemetry.stages().iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["Loading", "Processing"]);
+        assert!(telemetry.stages()[0].wall_time >= Duration::from_millis(20));
+        let total: Stage = telemetry.total();
+        assert!(total.wall_time >= telemetry.stages()[0].wall_time);
+        if cfg!(unix) {
+            assert!(total.peak_rss.unwrap() > 0);
+            assert!(total.cpu_time.is_some());
+        }
+
+        let json: JsonValue = telemetry.to_json();
+        assert_eq!(json["stages"][1]["name"], "Processing");
+        assert!(json["wall_time_s"].as_f64().unwrap() >= 0.02);
+        assert!(!json.has_key("name"));
+    }
+}
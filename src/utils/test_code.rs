@@ -0,0 +1,119 @@
+// Copyright 2026 Andrea Gilot
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Heuristic detection of test code, so that functions from test files can be labelled or excluded.
+//!
+//! A file is considered a test file if:
+//! * one of its directories is a test directory (`test`, `tests`, `__tests__`, `testing` or `spec`),
+//! * its name follows a naming convention of test files (`*_test.*`, `test_*.py`, `*Test.java`, `*.spec.ts`, ...),
+//! * or it imports a test framework (JUnit, TestNG, ScalaTest, kotlin.test, pytest, unittest, Google Test, Catch2,
+//!   Boost.Test, xUnit, NUnit or the `testing` package of Go).
+
+use std::path::Path;
+use std::sync::LazyLock;
+
+use regex::bytes::Regex;
+
+/// Names of the directories containing tests.
+const TEST_DIRECTORIES: [&str; 5] = ["test", "tests", "__tests__", "testing", "spec"];
+
+/// Naming conventions of test files.
+static TEST_FILE_NAME: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(
+        r"^(test_.+\.py|.+_tests?\.[^.]+|.+_spec\.[^.]+|.+(Test|Tests|TestCase|Spec)\.[^.]+|.+\.(test|spec)\.[^.]+)$",
+    )
+    .unwrap()
+});
+
+/// Imports of test frameworks.
+static TEST_IMPORT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?m)^[ \t]*(import[ \t]+(static[ \t]+)?(org\.junit|org\.testng|org\.scalatest|kotlin\.test|pytest|unittest)\b|from[ \t]+(pytest|unittest)\b|#[ \t]*include[ \t]*[<"](gtest/|gmock/|catch2/|catch\.hpp|boost/test/)|using[ \t]+(Xunit|NUnit\.Framework)\b|(import[ \t]+)?"testing"[ \t]*$)"#,
+    )
+    .unwrap()
+});
+
+/// Checks whether the path of a file relative to its project follows the conventions of test files,
+/// i.e. whether one of its directories is a test directory or its name is the name of a test file.
+///
+/// # Arguments
+///
+/// * `relative_path` - The path of the file relative to its project.
+pub fn is_test_path(relative_path: &Path) -> bool {
+    let in_test_directory: bool = relative_path
+        .parent()
+        .into_iter()
+        .flat_map(|dir| dir.components())
+        .any(|c| TEST_DIRECTORIES.contains(&c.as_os_str().to_string_lossy().as_ref()));
+    in_test_directory || is_test_file_name(relative_path)
+}
+
+/// Checks whether the name of a file is the name of a test file, regardless of its directories.
+///
+/// # Arguments
+///
+/// * `path` - The path of the file.
+pub fn is_test_file_name(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| TEST_FILE_NAME.is_match(&name.to_string_lossy()))
+}
+
+/// Checks whether a source file imports a test framework.
+///
+/// # Arguments
+///
+/// * `content` - The content of the file, or a part of it.
+pub fn has_test_imports(content: &[u8]) -> bool {
+    TEST_IMPORT.is_match(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paths() {
+        assert!(is_test_path(Path::new("src/test/java/MainTest.java")));
+        assert!(is_test_path(Path::new("tests/util.c")));
+        assert!(is_test_path(Path::new("web/__tests__/app.js")));
+        assert!(is_test_path(Path::new("pkg/math_test.go")));
+        assert!(is_test_path(Path::new("test_math.py")));
+        assert!(is_test_path(Path::new("src/VectorTests.cs")));
+        assert!(is_test_path(Path::new("src/app.spec.ts")));
+        assert!(!is_test_path(Path::new("src/test.c")));
+        assert!(!is_test_path(Path::new("src/contest/latest.c")));
+        assert!(!is_test_path(Path::new("src/Testing.java")));
+        assert!(!is_test_file_name(Path::new("tests/util.c")));
+    }
+
+    #[test]
+    fn test_imports() {
+        assert!(has_test_imports(
+            b"package a;\nimport org.junit.jupiter.api.Test;\n"
+        ));
+        assert!(has_test_imports(b"import static org.junit.Assert.*;"));
+        assert!(has_test_imports(b"import os\nimport pytest\n"));
+        assert!(has_test_imports(b"from unittest import TestCase"));
+        assert!(has_test_imports(b"#include <gtest/gtest.h>\n"));
+        assert!(has_test_imports(
+            b"import (\n\t\"math\"\n\t\"testing\"\n)\n"
+        ));
+        assert!(has_test_imports(b"using Xunit;"));
+        assert!(!has_test_imports(b"import pytesting\n"));
+        assert!(!has_test_imports(b"// import org.junit is not needed\n"));
+        assert!(!has_test_imports(
+            b"#include <stdio.h>\nint main() { return 0; }\n"
+        ));
+    }
+}
@@ -0,0 +1,5 @@
+void copy(int n, const double *a, double *b) {
+    for (int i = 0; i < n; i++) {
+        b[i] = a[i];
+    }
+}
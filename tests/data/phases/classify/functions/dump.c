@@ -0,0 +1,7 @@
+void dump(const char *path, int n, const double *a) {
+    FILE *f = fopen(path, "w");
+    for (int i = 0; i < n; i++) {
+        fprintf(f, "%f\n", a[i]);
+    }
+    fclose(f);
+}
@@ -0,0 +1,11 @@
+#ifndef VECTOR_H
+#define VECTOR_H
+
+typedef struct {
+    double x;
+    double y;
+} vector;
+
+double norm(vector v);
+
+#endif
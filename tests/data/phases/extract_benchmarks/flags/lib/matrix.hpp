@@ -0,0 +1,5 @@
+#pragma once
+
+struct matrix {
+    double values[4];
+};
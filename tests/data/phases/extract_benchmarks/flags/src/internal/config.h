@@ -0,0 +1,8 @@
+#ifndef CONFIG_H
+#define CONFIG_H
+
+#ifndef VERSION
+#define VERSION 1
+#endif
+
+#endif
@@ -0,0 +1,7 @@
+#include "vector.h"
+#include "config.h"
+
+int main(void) {
+    vector v = {3.0, 4.0};
+    return norm(v) == 5.0 && VERSION == 2 ? 0 : 1;
+}
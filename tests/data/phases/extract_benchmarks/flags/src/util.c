@@ -0,0 +1,6 @@
+#include <math.h>
+#include "vector.h"
+
+double norm(vector v) {
+    return sqrt(v.x * v.x + v.y * v.y);
+}
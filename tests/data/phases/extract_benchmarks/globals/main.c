@@ -0,0 +1,10 @@
+#include <stdio.h>
+#include "smooth.h"
+
+int main(void) {
+    double values[5] = {1.0, 2.0, 4.0, 2.0, 1.0};
+    for (int i = 0; i < 5; i++) {
+        printf("%f\n", smooth(values, 5, i));
+    }
+    return 0;
+}
@@ -0,0 +1,15 @@
+#include "smooth.h"
+
+const double weights[3] = {0.25, 0.5, 0.25};
+
+static int calls;
+
+double smooth(const double *values, int n, int i) {
+    double sum = 0.0;
+    calls++;
+    for (int k = -1; k <= 1; k++) {
+        int j = i + k < 0 ? 0 : (i + k >= n ? n - 1 : i + k);
+        sum += weights[k + 1] * values[j];
+    }
+    return sum;
+}
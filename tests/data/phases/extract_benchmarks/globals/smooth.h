@@ -0,0 +1,8 @@
+#ifndef SMOOTH_H
+#define SMOOTH_H
+
+extern const double weights[3];
+
+double smooth(const double *values, int n, int i);
+
+#endif
@@ -0,0 +1,37 @@
+#include <stdio.h>
+
+static int square(int x);
+static int is_odd(unsigned n);
+
+struct range {
+    int start;
+    int end;
+};
+
+int sum_even_squares(struct range r) {
+    int sum = 0;
+    for (int i = r.start; i < r.end; i++) {
+        if (!is_odd(i)) {
+            sum += square(i);
+        }
+    }
+    return sum;
+}
+
+static int square(int x) {
+    return x * x;
+}
+
+static int is_even(unsigned n) {
+    return n == 0 ? 1 : is_odd(n - 1);
+}
+
+static int is_odd(unsigned n) {
+    return n == 0 ? 0 : is_even(n - 1);
+}
+
+int main(void) {
+    struct range r = {0, 10};
+    printf("%d\n", sum_even_squares(r));
+    return 0;
+}
@@ -0,0 +1,11 @@
+package com.example.util;
+
+public class Vector {
+    public double x;
+    public double y;
+
+    public Vector(double x, double y) {
+        this.x = x;
+        this.y = y;
+    }
+}
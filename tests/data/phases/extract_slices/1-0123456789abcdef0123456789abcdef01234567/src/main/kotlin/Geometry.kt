@@ -0,0 +1,15 @@
+import kotlin.math.PI
+
+data class Circle(val radius: Double)
+
+class Geometry {
+    val scale = 2.0
+
+    fun area(c: Circle): Double {
+        return PI * square(c.radius) * scale
+    }
+
+    fun square(x: Double): Double = x * x
+
+    fun perimeter(c: Circle): Double = 2 * PI * c.radius
+}
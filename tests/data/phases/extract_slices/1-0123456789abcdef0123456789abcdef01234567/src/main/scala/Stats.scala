@@ -0,0 +1,14 @@
+import scala.math.sqrt
+
+object Stats {
+  val epsilon: Double = 1e-9
+
+  def mean(xs: Seq[Double]): Double = xs.sum / xs.length
+
+  def stddev(xs: Seq[Double]): Double = {
+    val m = mean(xs)
+    sqrt(xs.map(x => (x - m) * (x - m)).sum / xs.length + epsilon)
+  }
+
+  def median(xs: Seq[Double]): Double = xs.sorted.apply(xs.length / 2)
+}
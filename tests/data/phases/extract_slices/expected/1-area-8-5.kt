@@ -0,0 +1,11 @@
+import kotlin.math.PI
+
+class Geometry {
+    val scale = 2.0
+    fun area(c: Circle): Double {
+        return PI * square(c.radius) * scale
+    }
+    fun square(x: Double): Double = x * x
+}
+
+data class Circle(val radius: Double)
@@ -0,0 +1,18 @@
+import java.util.List;
+
+class Physics {
+    private static final double G = 9.81;
+    private int steps;
+    public Physics(int steps) {
+        this.steps = steps;
+    }
+    public double energy(double mass, double height) {
+        return mass * G * height + kinetic(mass, 2.0);
+    }
+    private double kinetic(double mass, double speed) {
+        return 0.5 * mass * square(speed);
+    }
+    private static double square(double x) {
+        return x * x;
+    }
+}
@@ -0,0 +1,25 @@
+import java.util.List;
+
+class Physics {
+    private static final double G = 9.81;
+    private int steps;
+    public Physics(int steps) {
+        this.steps = steps;
+    }
+    private static double square(double x) {
+        return x * x;
+    }
+    public double norm(Vector v) {
+        return Math.sqrt(square(v.x) + square(v.y));
+    }
+}
+
+class Vector {
+    public double x;
+    public double y;
+
+    public Vector(double x, double y) {
+        this.x = x;
+        this.y = y;
+    }
+}
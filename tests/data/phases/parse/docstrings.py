@@ -0,0 +1,22 @@
+import math
+
+
+def hypotenuse(a: float, b: float) -> float:
+    """Length of the hypotenuse of a right triangle."""
+    return math.sqrt(a * a + b * b)
+
+
+# Mean of a list of values.
+def mean(values):
+    # Empty lists have a mean of zero.
+    if not values:
+        return 0.0
+    return float(sum(values)) / len(values)
+
+
+def clamp(x, low, high):
+    return max(low, min(high, float(x)))  # clamp to [low, high]
+
+
+def name():
+    return "docstrings"
@@ -0,0 +1,15 @@
+#include <stdio.h>
+
+/* Returns the average
+   of two floats. */
+float average(float a, float b) {
+    /* a float
+       comment */
+    double sum = a + b;
+    printf("double %f\n", sum);
+    return sum / 2;
+}
+
+int count(int n) {
+    return n + 1;
+}
@@ -0,0 +1,35 @@
+import kotlin.math.sqrt
+
+fun label(count: Int): String {
+    // No floating-point here, "Double" in a string is ignored
+    return "Double count: $count"
+}
+
+// Euclidean norm of a vector of doubles
+fun norm(values: DoubleArray): Double {
+    var sum = 0.0
+    for (v in values) {
+        sum += v * v
+    }
+    return sqrt(sum)
+}
+
+class Shape(val width: Double, val height: Double) {
+    fun area(): Double = width * height
+
+    fun describe(): String {
+        return when {
+            width == height -> "square"
+            else -> "rectangle"
+        }
+    }
+}
+
+fun clamp(x: Float, low: Float, high: Float): Float {
+    if (x < low) {
+        return low
+    } else if (x > high) {
+        return high
+    }
+    return x
+}
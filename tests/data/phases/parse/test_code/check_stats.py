@@ -0,0 +1,7 @@
+import pytest
+
+from stats import mean
+
+
+def test_mean():
+    assert mean([1, 2]) == pytest.approx(float(1.5))
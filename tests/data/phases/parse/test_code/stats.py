@@ -0,0 +1,2 @@
+def mean(values):
+    return float(sum(values)) / len(values)
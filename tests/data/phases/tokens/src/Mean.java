@@ -0,0 +1,10 @@
+public class Mean {
+    /* Average of an array. */
+    public static double mean(double[] values) {
+        double total = 0.0;
+        for (double v : values) {
+            total += v;
+        }
+        return total / values.length;
+    }
+}
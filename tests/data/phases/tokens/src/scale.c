@@ -0,0 +1,5 @@
+void scale(float *values, int n, float factor) {
+    for (int i = 0; i < n; i++) {
+        values[i] *= factor;
+    }
+}
@@ -0,0 +1,8 @@
+// Sums the elements of an array.
+float sum(float *values, int n) {
+    float total = 0.0f;
+    for (int i = 0; i < n; i++) {
+        total += values[i];
+    }
+    return total;
+}